@@ -0,0 +1,86 @@
+//! One ARM-export-sized pipeline, checked by a single worker and by all of them: the rules
+//! of a file this large, and the nodes they select, are evaluated in parallel.
+//!
+//! `cargo bench --bench large_file`
+
+use adf_guardian::Config;
+use adf_guardian::engine::{GuardRegistry, RuleSet};
+use criterion::{Criterion, criterion_group, criterion_main};
+use serde_json::json;
+use std::hint::black_box;
+use std::path::Path;
+use std::thread;
+
+const RULES: &str = r#"
+rules:
+  - id: "activity-naming"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].name", guard: "PatternMatch", params: { regex: "^(cp|lk|ex)_[a-z0-9_]+$" } }
+  - id: "retries"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].policy.retry", guard: "Range", params: { min: 1, max: 5 } }
+  - id: "timeout"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].policy.timeout", guard: "PatternMatch", params: { regex: "^0\\.(0[0-9]|1[0-2]):[0-5][0-9]:[0-5][0-9]$" } }
+  - id: "activity-types"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].type", guard: "AllowedValues", params: { values: ["Copy", "Lookup", "ExecutePipeline"] } }
+  - id: "no-secrets"
+    asset: "pipeline"
+    validate: { target: "$..password", guard: "Exists", params: { should_exist: false } }
+  - id: "described"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].description", guard: "StringLength", params: { min: 10 } }
+"#;
+
+/// A pipeline of `activities` activities, a few MB of JSON like a large ARM export. Nearly
+/// all of them comply, so the bench measures the rules rather than locating violations.
+fn document(activities: usize) -> String {
+    let activities: Vec<_> = (0..activities)
+        .map(|i| {
+            json!({
+                "name": if i % 997 == 0 { format!("Activity {}", i) } else { format!("cp_load_{}", i) },
+                "type": if i % 3 == 0 { "Lookup" } else { "Copy" },
+                "description": format!("Copies partition {} of the sales history", i),
+                "policy": { "timeout": "0.12:00:00", "retry": i % 5 + 1, "secureInput": false },
+                "typeProperties": {
+                    "source": { "type": "AzureSqlSource", "sqlReaderQuery": format!("SELECT * FROM sales WHERE partition = {}", i) },
+                    "sink": { "type": "ParquetSink", "storeSettings": { "type": "AzureBlobFSWriteSettings" } }
+                },
+                "inputs": [{ "referenceName": "ds_sql_sales", "type": "DatasetReference" }],
+                "outputs": [{ "referenceName": "ds_lake_sales", "type": "DatasetReference" }]
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&json!({
+        "name": "pl_export",
+        "properties": { "activities": activities }
+    }))
+    .unwrap()
+}
+
+fn large_file(c: &mut Criterion) {
+    let config = Config::from_yaml(RULES.as_bytes()).unwrap();
+    let rules = RuleSet::new(config, GuardRegistry::default());
+    let source = document(20_000);
+    let path = Path::new("pipeline/pl_export.json");
+
+    let mut group = c.benchmark_group("large_file");
+    group.sample_size(10);
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = vec![1, workers];
+    threads.dedup();
+    for threads in threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_function(format!("{} thread(s)", threads), |b| {
+            b.iter(|| pool.install(|| rules.check_source(path, black_box(&source)).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, large_file);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+//! A whole factory scanned the way the CLI does, and a single pipeline checked against
+//! regex-heavy rules, to validate changes to file discovery, parsing (`--features
+//! fast-parse`), JSONPath matching and the guards. `adf-guardian bench` splits the same work
+//! by phase and rule on a real project.
+//!
+//! `cargo bench --bench project`
+
+use adf_guardian::engine::{GuardRegistry, RuleSet};
+use adf_guardian::{Config, run_scan};
+use criterion::{Criterion, criterion_group, criterion_main};
+use serde_json::{Value, json};
+use std::fs;
+use std::hint::black_box;
+use std::path::Path;
+
+const RULES: &str = r#"
+rules:
+  - id: "pipeline-naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_(ingest|transform|export)_[a-z0-9_]+$" } }
+  - id: "activity-naming"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].name", guard: "PatternMatch", params: { regex: "^(cp|lk|ex|wt)_[a-z0-9_]+$" } }
+  - id: "retries"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].policy.retry", guard: "Range", params: { min: 1, max: 5 } }
+  - id: "copy-sinks"
+    asset: "pipeline"
+    when: { target: "$.properties.activities[*].type", guard: "AllowedValues", params: { values: ["Copy", "Lookup"] } }
+    validate: { target: "$..sink.type", guard: "AllowedValues", params: { values: ["ParquetSink", "DelimitedTextSink"] } }
+  - id: "folders"
+    asset: "pipeline"
+    validate: { target: "$.properties.folder.name", guard: "PatternMatch", params: { regex: "^(ingest|transform|export)(/[a-z]+)*$" } }
+  - id: "dataset-naming"
+    asset: "dataset"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^ds_(sql|lake|blob)_[a-z0-9_]+$" } }
+  - id: "linked-service-naming"
+    asset: "linkedService"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^ls_[a-z0-9_]+$" } }
+  - id: "no-passwords"
+    asset: "linkedService"
+    validate: { target: "$..password", guard: "Exists", params: { should_exist: false }, on_missing: pass }
+"#;
+
+fn pipeline(index: usize, activities: usize) -> Value {
+    let activities: Vec<_> = (0..activities)
+        .map(|i| {
+            json!({
+                "name": format!("cp_load_{}_{}", index, i),
+                "type": if i % 4 == 0 { "Lookup" } else { "Copy" },
+                "policy": { "timeout": "0.12:00:00", "retry": i % 5 + 1 },
+                "typeProperties": {
+                    "source": { "type": "AzureSqlSource" },
+                    "sink": { "type": "ParquetSink" }
+                },
+                "inputs": [{ "referenceName": format!("ds_sql_{}", index), "type": "DatasetReference" }],
+                "outputs": [{ "referenceName": format!("ds_lake_{}", index), "type": "DatasetReference" }]
+            })
+        })
+        .collect();
+    json!({
+        "name": format!("pl_ingest_{}", index),
+        "properties": { "activities": activities, "folder": { "name": "ingest/sales" } }
+    })
+}
+
+/// A factory of 200 pipelines, 400 datasets and 20 linked services.
+fn write_factory(root: &Path) {
+    for folder in ["pipeline", "dataset", "linkedService"] {
+        fs::create_dir_all(root.join(folder)).unwrap();
+    }
+    for i in 0..200 {
+        let path = root.join("pipeline").join(format!("pl_ingest_{}.json", i));
+        fs::write(path, pipeline(i, 12).to_string()).unwrap();
+        for kind in ["sql", "lake"] {
+            let dataset = json!({
+                "name": format!("ds_{}_{}", kind, i),
+                "properties": {
+                    "linkedServiceName": { "referenceName": format!("ls_{}_{}", kind, i % 10), "type": "LinkedServiceReference" },
+                    "type": "Parquet"
+                }
+            });
+            let path = root.join("dataset").join(format!("ds_{}_{}.json", kind, i));
+            fs::write(path, dataset.to_string()).unwrap();
+        }
+    }
+    for i in 0..20 {
+        let kind = if i % 2 == 0 { "sql" } else { "lake" };
+        let linked_service = json!({
+            "name": format!("ls_{}_{}", kind, i / 2),
+            "properties": { "type": "AzureSqlDatabase", "typeProperties": { "connectionString": "Server=tcp:sql" } }
+        });
+        let path = root.join("linkedService").join(format!("ls_{}.json", i));
+        fs::write(path, linked_service.to_string()).unwrap();
+    }
+}
+
+fn project(c: &mut Criterion) {
+    let config = Config::from_yaml(RULES.as_bytes()).unwrap();
+    let root = std::env::temp_dir().join(format!("adf-guardian-bench-{}", std::process::id()));
+    write_factory(&root);
+
+    let mut group = c.benchmark_group("project");
+    group.sample_size(20);
+    group.bench_function("scan", |b| {
+        b.iter(|| run_scan(&config, black_box(&root)).unwrap())
+    });
+
+    let rules = RuleSet::new(config.clone(), GuardRegistry::default());
+    let source = serde_json::to_string_pretty(&pipeline(0, 200)).unwrap();
+    let path = Path::new("pipeline/pl_ingest_0.json");
+    group.bench_function("check_source", |b| {
+        b.iter(|| rules.check_source(path, black_box(&source)).unwrap())
+    });
+    group.finish();
+    fs::remove_dir_all(&root).unwrap();
+}
+
+criterion_group!(benches, project);
+criterion_main!(benches);
@@ -0,0 +1,20 @@
+from typing import Any, Literal, Optional
+
+class Violation:
+    rule_id: str
+    file: str
+    message: str
+    severity: Literal["Error", "Warning"]
+    actual_value: Optional[str]
+    help_url: Optional[str]
+    line: Optional[int]
+    column: Optional[int]
+
+def scan(project_path: str, config_path: str) -> list[Violation]:
+    """Scans every JSON file under `project_path` with the rules of the config file at `config_path`."""
+
+def validate_json(obj: Any, rules: Any, *, asset: str = "pipeline") -> list[Violation]:
+    """Checks one document (a dict, or a JSON string) as if it were a file in the `asset` folder.
+
+    `rules` is a config: its YAML text, a dict, or just the list of rules.
+    """
@@ -0,0 +1,235 @@
+use crate::scanner::{self, WalkOptions};
+use anyhow::{Context, Result};
+use ignore::overrides::Override;
+use regex::Regex;
+use serde_json::{Value, json};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Data Factory resource types of an ARM template, lower-cased, by the folder Git
+/// integration stores them in.
+const ARM_TYPES: [(&str, &str); 7] = [
+    ("pipelines", "pipeline"),
+    ("datasets", "dataset"),
+    ("linkedservices", "linkedService"),
+    ("triggers", "trigger"),
+    ("dataflows", "dataflow"),
+    ("integrationruntimes", "integrationRuntime"),
+    ("credentials", "credential"),
+];
+const ARM_PREFIX: &str = "microsoft.datafactory/factories/";
+
+/// A JSON document read from an archive.
+pub struct Entry {
+    /// Under the path of the archive, e.g. `export.zip/pipeline/pl_load.json`.
+    pub path: PathBuf,
+    pub contents: Vec<u8>,
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// The JSON files of the zip archive at `path`, read without extracting it. The Data
+/// Factory resources of an ARM template, as in the package of ADF's "Export ARM template",
+/// are split into one document per asset at the path Git integration would store it at.
+/// `.gitignore` doesn't apply inside an archive; the other walk options do.
+pub fn read(path: &Path, options: &WalkOptions) -> Result<Vec<Entry>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    read_from(file, path, options)
+}
+
+/// Like [`read`], for an archive that isn't a file; its entries are placed under `path`.
+pub fn read_from(
+    reader: impl Read + Seek,
+    path: &Path,
+    options: &WalkOptions,
+) -> Result<Vec<Entry>> {
+    let mut archive = ZipArchive::new(reader)
+        .with_context(|| format!("Failed to open the zip archive {:?}", path))?;
+    let overrides = scanner::overrides(Path::new(""), options)?;
+
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        // Names that would escape the archive (`../`) are skipped.
+        let Some(name) = file.enclosed_name() else {
+            continue;
+        };
+        if file.is_dir()
+            || name.extension().is_none_or(|ext| ext != "json")
+            || !visited(&name, options, &overrides)
+        {
+            continue;
+        }
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .with_context(|| format!("Failed to read {:?} in {:?}", name, path))?;
+
+        let dir = path.join(name.parent().unwrap_or(Path::new("")));
+        match arm_assets(&dir, &contents) {
+            Some(assets) => entries.extend(assets),
+            None => entries.push(Entry {
+                path: path.join(&name),
+                contents,
+            }),
+        }
+    }
+    Ok(entries)
+}
+
+/// Whether the walk options let [`read`] visit the entry `name`.
+fn visited(name: &Path, options: &WalkOptions, overrides: &Override) -> bool {
+    if options
+        .max_depth
+        .is_some_and(|max| name.components().count() > max)
+    {
+        return false;
+    }
+    if !options.hidden
+        && name
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+    {
+        return false;
+    }
+    let ignored_dir = name
+        .ancestors()
+        .skip(1)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .any(|dir| overrides.matched(dir, true).is_ignore());
+    !ignored_dir && !overrides.matched(name, false).is_ignore()
+}
+
+/// The assets of an ARM template with Data Factory resources, under `dir`; `None` for any
+/// other document.
+fn arm_assets(dir: &Path, contents: &[u8]) -> Option<Vec<Entry>> {
+    let template: Value = serde_json::from_slice(contents).ok()?;
+    let resources = template["resources"].as_array()?;
+    let mut assets = Vec::new();
+    for resource in resources {
+        let Some(kind) = resource["type"]
+            .as_str()
+            .map(str::to_lowercase)
+            .and_then(|kind| kind.strip_prefix(ARM_PREFIX).map(str::to_string))
+        else {
+            continue;
+        };
+        let segments = arm_name(resource["name"].as_str().unwrap_or_default());
+        let folder = match (kind.as_str(), segments.as_slice()) {
+            ("managedvirtualnetworks/managedprivateendpoints", [network, _]) => {
+                Path::new("managedVirtualNetwork")
+                    .join(network)
+                    .join("managedPrivateEndpoint")
+            }
+            (kind, [_]) => match ARM_TYPES.iter().find(|(k, _)| *k == kind) {
+                Some((_, folder)) => PathBuf::from(folder),
+                None => continue,
+            },
+            _ => continue,
+        };
+        let name = segments.last().map(String::as_str).unwrap_or_default();
+        let document = json!({
+            "name": name,
+            "type": resource["type"],
+            "properties": resource["properties"],
+        });
+        assets.push(Entry {
+            path: dir.join(folder).join(format!("{}.json", name)),
+            contents: serde_json::to_vec_pretty(&document).ok()?,
+        });
+    }
+    (!assets.is_empty()).then_some(assets)
+}
+
+/// The segments after the factory in an ARM resource name, either an expression like
+/// `[concat(parameters('factoryName'), '/pl_load')]` or a literal like `adf-sales/pl_load`.
+fn arm_name(name: &str) -> Vec<String> {
+    let Some(expression) = name.strip_prefix('[') else {
+        return name.split('/').skip(1).map(str::to_string).collect();
+    };
+    let parameters = Regex::new(r"parameters\('[^']*'\)").expect("valid regex");
+    let literal = Regex::new(r"'([^']*)'").expect("valid regex");
+    let expression = parameters.replace_all(expression, "");
+    let joined: String = literal
+        .captures_iter(&expression)
+        .map(|captures| captures[1].to_string())
+        .collect();
+    joined
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_read() {
+        let path = std::env::temp_dir().join(format!("adf-guardian-{}.zip", std::process::id()));
+        let template = json!({
+            "$schema": "https://schema.management.azure.com/schemas/2019-04-01/deploymentTemplate.json#",
+            "resources": [
+                {
+                    "name": "[concat(parameters('factoryName'), '/pl_load')]",
+                    "type": "Microsoft.DataFactory/factories/pipelines",
+                    "properties": { "activities": [] }
+                },
+                {
+                    "name": "[concat(parameters('factoryName'), '/default/mpe_sql')]",
+                    "type": "Microsoft.DataFactory/factories/managedVirtualNetworks/managedPrivateEndpoints",
+                    "properties": {}
+                },
+                { "name": "adf-sales", "type": "Microsoft.DataFactory/factories" }
+            ]
+        });
+        let mut archive = zip::ZipWriter::new(File::create(&path).unwrap());
+        for (name, contents) in [
+            ("export/ARMTemplateForFactory.json", template.to_string()),
+            (
+                "export/ARMTemplateParametersForFactory.json",
+                "{}".to_string(),
+            ),
+            ("export/linkedTemplates/readme.md", String::new()),
+            ("samples/pipeline/pl_sample.json", "{}".to_string()),
+        ] {
+            archive
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            archive.write_all(contents.as_bytes()).unwrap();
+        }
+        archive.finish().unwrap();
+
+        let options = WalkOptions {
+            exclude: vec!["samples/".to_string()],
+            ..Default::default()
+        };
+        let entries = read(&path, &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let paths: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+        let export = path.join("export");
+        assert_eq!(
+            paths,
+            [
+                export.join("pipeline").join("pl_load.json"),
+                export.join("managedVirtualNetwork/default/managedPrivateEndpoint/mpe_sql.json"),
+                export.join("ARMTemplateParametersForFactory.json"),
+            ]
+        );
+        let pipeline: Value = serde_json::from_slice(&entries[0].contents).unwrap();
+        assert_eq!(pipeline["name"], "pl_load");
+        assert_eq!(pipeline["properties"], json!({ "activities": [] }));
+        assert_eq!(arm_name("adf-sales/tr_daily"), ["tr_daily"]);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::engine::Violation;
+use crate::{git, http};
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::env;
+use std::path::Path;
+
+const API_VERSION: &str = "7.1";
+const SERVICE: &str = "Azure DevOps";
+
+/// A pull request of an Azure Repos repository.
+pub struct PullRequest {
+    /// Organization name, or its URL (e.g. `$(System.CollectionUri)` on Azure DevOps Server).
+    pub organization: String,
+    pub project: String,
+    /// Repository name or id.
+    pub repository: String,
+    pub id: u64,
+}
+
+impl PullRequest {
+    fn threads_url(&self) -> String {
+        let base = if self.organization.starts_with("http") {
+            self.organization.trim_end_matches('/').to_string()
+        } else {
+            format!("https://dev.azure.com/{}", self.organization)
+        };
+        format!(
+            "{}/{}/_apis/git/repositories/{}/pullRequests/{}/threads?api-version={}",
+            base, self.project, self.repository, self.id, API_VERSION
+        )
+    }
+}
+
+/// How requests are authenticated: a personal access token, or a bearer token such as
+/// the pipeline's `System.AccessToken` or an Entra ID token obtained through OIDC.
+pub enum Auth {
+    Pat(String),
+    Bearer(String),
+}
+
+impl Auth {
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+        if let Some(pat) = var("AZURE_DEVOPS_EXT_PAT") {
+            return Ok(Auth::Pat(pat));
+        }
+        if let Some(token) = var("AZURE_DEVOPS_TOKEN").or_else(|| var("SYSTEM_ACCESSTOKEN")) {
+            return Ok(Auth::Bearer(token));
+        }
+        bail!(
+            "No Azure DevOps credentials: set AZURE_DEVOPS_EXT_PAT to a personal access token, \
+             or AZURE_DEVOPS_TOKEN (or SYSTEM_ACCESSTOKEN) to a bearer token"
+        )
+    }
+
+    fn header(&self) -> String {
+        match self {
+            Auth::Pat(pat) => format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(format!(":{}", pat))
+            ),
+            Auth::Bearer(token) => format!("Bearer {}", token),
+        }
+    }
+}
+
+/// Threads posted and skipped because an identical one was already on the pull request.
+pub struct Outcome {
+    pub posted: usize,
+    pub existing: usize,
+}
+
+/// Posts one active comment thread per violation, anchored to its file and line, on the
+/// pull request. Threads identical to an existing one (same file, line and text) are
+/// skipped, so re-running on every push doesn't repeat them. File paths are made relative
+/// to the root of the git work tree at `repo_root`.
+pub fn annotate(
+    pr: &PullRequest,
+    auth: &Auth,
+    violations: &[Violation],
+    repo_root: &Path,
+) -> Result<Outcome> {
+    let agent = http::agent();
+    let url = pr.threads_url();
+    let authorization = auth.header();
+
+    let body: Value = agent
+        .get(&url)
+        .header("Authorization", &authorization)
+        .call()
+        .map_err(anyhow::Error::from)
+        .and_then(|mut response| {
+            http::check_status(SERVICE, &mut response)?;
+            Ok(response.body_mut().read_json()?)
+        })
+        .context("Failed to list the pull request threads")?;
+    let mut existing: HashSet<String> = body["value"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|thread| thread["isDeleted"] != true)
+        .map(thread_key)
+        .collect();
+
+    let mut outcome = Outcome {
+        posted: 0,
+        existing: 0,
+    };
+    for violation in violations {
+        let thread = thread_for(violation, repo_root);
+        if !existing.insert(thread_key(&thread)) {
+            outcome.existing += 1;
+            continue;
+        }
+        agent
+            .post(&url)
+            .header("Authorization", &authorization)
+            .send_json(&thread)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut response| http::check_status(SERVICE, &mut response))
+            .with_context(|| {
+                format!(
+                    "Failed to post the thread for '{}' on {}",
+                    violation.rule_id, violation.file
+                )
+            })?;
+        outcome.posted += 1;
+    }
+    Ok(outcome)
+}
+
+/// The thread posted for `violation`.
+fn thread_for(violation: &Violation, repo_root: &Path) -> Value {
+    let mut content = format!(
+        "**{}** `{}`: {}",
+        violation.severity, violation.rule_id, violation.message
+    );
+    if let Some(path) = &violation.json_path {
+        content.push_str(&format!(" at `{}`", path));
+    }
+    if let Some(url) = &violation.help_url {
+        content.push_str(&format!(" ([more information]({}))", url));
+    }
+
+    let mut context = json!({ "filePath": repo_path(&violation.file, repo_root) });
+    if let Some(line) = violation.line {
+        let offset = violation.column.unwrap_or(1);
+        context["rightFileStart"] = json!({ "line": line, "offset": offset });
+        context["rightFileEnd"] = json!({ "line": line, "offset": offset + 1 });
+    }
+    json!({
+        "comments": [{ "parentCommentId": 0, "content": content, "commentType": "text" }],
+        "status": "active",
+        "threadContext": context,
+    })
+}
+
+/// What identifies a thread when looking for one posted by an earlier run.
+fn thread_key(thread: &Value) -> String {
+    let context = &thread["threadContext"];
+    format!(
+        "{}:{}:{}",
+        context["filePath"].as_str().unwrap_or_default(),
+        context["rightFileStart"]["line"],
+        thread["comments"][0]["content"]
+            .as_str()
+            .unwrap_or_default()
+    )
+}
+
+/// `file` as Azure Repos names it: from the repository root, with a leading slash.
+fn repo_path(file: &str, repo_root: &Path) -> String {
+    format!("/{}", git::repo_relative(file, repo_root))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+
+    #[test]
+    fn test_thread_for() {
+        let root = env::current_dir().unwrap();
+        let violation = Violation {
+            rule_id: "naming".to_string(),
+            file: "src/main.rs".to_string(),
+            message: "Pipelines start with pl_".to_string(),
+            severity: Severity::Warning,
+            line: Some(3),
+            column: Some(12),
+            ..Default::default()
+        };
+
+        let thread = thread_for(&violation, &root);
+        assert_eq!(
+            thread["comments"][0]["content"],
+            "**Warning** `naming`: Pipelines start with pl_"
+        );
+        assert_eq!(thread["threadContext"]["filePath"], "/src/main.rs");
+        assert_eq!(
+            thread["threadContext"]["rightFileStart"],
+            json!({ "line": 3, "offset": 12 })
+        );
+        assert_eq!(
+            thread_key(&thread),
+            "/src/main.rs:3:**Warning** `naming`: Pipelines start with pl_"
+        );
+
+        let moved = Violation {
+            file: "./factory/pipeline/pl_a.json".to_string(),
+            line: None,
+            ..violation
+        };
+        let thread = thread_for(&moved, &root);
+        assert_eq!(
+            thread["threadContext"],
+            json!({ "filePath": "/factory/pipeline/pl_a.json" })
+        );
+    }
+}
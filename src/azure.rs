@@ -0,0 +1,341 @@
+use crate::cli::FactoryArgs;
+use crate::http;
+use anyhow::{Context, Result, bail};
+use serde_json::{Value, json};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SERVICE: &str = "Azure";
+const API_VERSION: &str = "2018-06-01";
+/// Written to the folders [`download`] fills, so it only ever clears its own downloads.
+const DOWNLOAD_MARKER: &str = ".adf-guardian-download";
+
+/// Factory sub-resources, by their REST collection and the folder Git integration
+/// stores them in.
+pub const COLLECTIONS: [(&str, &str); 7] = [
+    ("pipelines", "pipeline"),
+    ("datasets", "dataset"),
+    ("linkedservices", "linkedService"),
+    ("triggers", "trigger"),
+    ("dataflows", "dataflow"),
+    ("integrationRuntimes", "integrationRuntime"),
+    ("credentials", "credential"),
+];
+
+/// A deployed data factory.
+pub struct Factory {
+    pub subscription: String,
+    pub resource_group: String,
+    pub name: String,
+}
+
+impl From<&FactoryArgs> for Factory {
+    fn from(args: &FactoryArgs) -> Self {
+        Factory {
+            subscription: args.subscription.clone(),
+            resource_group: args.resource_group.clone(),
+            name: args.factory.clone(),
+        }
+    }
+}
+
+impl Factory {
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/subscriptions/{}/resourceGroups/{}/providers/Microsoft.DataFactory/factories/{}/{}?api-version={}",
+            management_url(),
+            self.subscription,
+            self.resource_group,
+            self.name,
+            path,
+            API_VERSION
+        )
+    }
+}
+
+/// Azure Resource Manager endpoint; `AZURE_RESOURCE_MANAGER_URL` points it at a sovereign
+/// cloud, e.g. `https://management.chinacloudapi.cn`.
+fn management_url() -> String {
+    env::var("AZURE_RESOURCE_MANAGER_URL")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .map_or("https://management.azure.com".to_string(), |url| {
+            url.trim_end_matches('/').to_string()
+        })
+}
+
+/// A token for the Azure Resource Manager API, from the first of these that is set up:
+/// a token in `AZURE_ACCESS_TOKEN`; a service principal secret (`AZURE_TENANT_ID`,
+/// `AZURE_CLIENT_ID`, `AZURE_CLIENT_SECRET`); workload identity federation
+/// (`AZURE_FEDERATED_TOKEN_FILE` instead of the secret); the signed-in Azure CLI.
+pub fn access_token() -> Result<String> {
+    let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+    if let Some(token) = var("AZURE_ACCESS_TOKEN") {
+        return Ok(token);
+    }
+
+    let secret = match (
+        var("AZURE_FEDERATED_TOKEN_FILE"),
+        var("AZURE_CLIENT_SECRET"),
+    ) {
+        (Some(path), _) => Some(Secret::Assertion(
+            fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read the federated token {:?}", path))?,
+        )),
+        (None, Some(secret)) => Some(Secret::Password(secret)),
+        (None, None) => None,
+    };
+    if let (Some(tenant), Some(client), Some(secret)) =
+        (var("AZURE_TENANT_ID"), var("AZURE_CLIENT_ID"), secret)
+    {
+        let authority =
+            var("AZURE_AUTHORITY_HOST").unwrap_or("https://login.microsoftonline.com".to_string());
+        return service_principal_token(&authority, &tenant, &client, &secret);
+    }
+
+    let output = Command::new("az")
+        .args(["account", "get-access-token", "--resource"])
+        .arg(format!("{}/", management_url()))
+        .args(["--query", "accessToken", "--output", "tsv"])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        _ => bail!(
+            "No Azure credentials: set AZURE_ACCESS_TOKEN, the AZURE_TENANT_ID, AZURE_CLIENT_ID \
+             and AZURE_CLIENT_SECRET (or AZURE_FEDERATED_TOKEN_FILE) of a service principal, \
+             or sign in with `az login`"
+        ),
+    }
+}
+
+/// How a service principal proves its identity.
+enum Secret {
+    Password(String),
+    /// A federated token, e.g. from a Kubernetes or CI workload identity.
+    Assertion(String),
+}
+
+fn service_principal_token(
+    authority: &str,
+    tenant: &str,
+    client: &str,
+    secret: &Secret,
+) -> Result<String> {
+    let url = format!(
+        "{}/{}/oauth2/v2.0/token",
+        authority.trim_end_matches('/'),
+        tenant
+    );
+    let scope = format!("{}/.default", management_url());
+    let mut form = vec![
+        ("grant_type", "client_credentials"),
+        ("client_id", client),
+        ("scope", scope.as_str()),
+    ];
+    match secret {
+        Secret::Password(password) => form.push(("client_secret", password)),
+        Secret::Assertion(assertion) => form.extend([
+            (
+                "client_assertion_type",
+                "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+            ),
+            ("client_assertion", assertion.trim()),
+        ]),
+    }
+
+    let mut response = http::agent()
+        .post(&url)
+        .send_form(form)
+        .context("Failed to request an Azure access token")?;
+    http::check_status("Microsoft Entra ID", &mut response)?;
+    let body: Value = response.body_mut().read_json()?;
+    match body["access_token"].as_str() {
+        Some(token) => Ok(token.to_string()),
+        None => bail!("Microsoft Entra ID answered without an access token"),
+    }
+}
+
+/// The factory's pipelines, datasets, linked services, triggers, data flows, integration
+/// runtimes, credentials and managed private endpoints, each with the path Git integration
+/// would store it at (e.g. `pipeline/pl_load.json`).
+pub fn fetch(factory: &Factory, token: &str) -> Result<Vec<(PathBuf, Value)>> {
+    let mut assets: Vec<(PathBuf, Value)> = Vec::new();
+    for (collection, folder) in COLLECTIONS {
+        for resource in list(&factory.url(collection), token)? {
+            assets.push((
+                asset_path(Path::new(folder), &resource),
+                git_document(&resource),
+            ));
+        }
+    }
+    for network in list(&factory.url("managedVirtualNetworks"), token)? {
+        let network_name = network["name"].as_str().unwrap_or_default();
+        let endpoints = format!(
+            "managedVirtualNetworks/{}/managedPrivateEndpoints",
+            network_name
+        );
+        for endpoint in list(&factory.url(&endpoints), token)? {
+            let folder = Path::new("managedVirtualNetwork")
+                .join(file_name(network_name))
+                .join("managedPrivateEndpoint");
+            assets.push((asset_path(&folder, &endpoint), git_document(&endpoint)));
+        }
+    }
+    Ok(assets)
+}
+
+/// The top-level folders assets are stored in.
+pub fn asset_folders() -> impl Iterator<Item = &'static str> {
+    COLLECTIONS
+        .iter()
+        .map(|(_, folder)| *folder)
+        .chain(["managedVirtualNetwork"])
+}
+
+/// Writes the assets of [`fetch`] under `dir`, laid out like a Git-integrated factory so
+/// the same rules apply. Folders of earlier downloads are replaced, so assets deleted
+/// since then don't linger. Returns the number of assets.
+pub fn download(factory: &Factory, token: &str, dir: &Path) -> Result<usize> {
+    let assets = fetch(factory, token)?;
+    prepare_download_dir(dir)?;
+    for (path, document) in &assets {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create the folder {:?}", parent))?;
+        }
+        let source = serde_json::to_string_pretty(document)?;
+        fs::write(&path, source + "\n").with_context(|| format!("Failed to write {:?}", path))?;
+    }
+    Ok(assets.len())
+}
+
+/// Clears the asset folders of an earlier download in `dir`. Refuses a folder that isn't
+/// empty and wasn't filled by [`download`], e.g. a Git-integrated factory given by mistake.
+fn prepare_download_dir(dir: &Path) -> Result<()> {
+    let marker = dir.join(DOWNLOAD_MARKER);
+    let empty = match fs::read_dir(dir) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read the folder {:?}", dir)),
+    };
+    if !empty && !marker.is_file() {
+        bail!(
+            "{:?} is not empty and holds no earlier download; pick an empty or new --download-dir",
+            dir
+        );
+    }
+    for folder in asset_folders() {
+        let path = dir.join(folder);
+        if path.exists() {
+            fs::remove_dir_all(&path)
+                .with_context(|| format!("Failed to clear the earlier download in {:?}", path))?;
+        }
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create the folder {:?}", dir))?;
+    fs::write(&marker, "").with_context(|| format!("Failed to write {:?}", marker))
+}
+
+/// Every resource of a collection, following `nextLink` across pages.
+fn list(url: &str, token: &str) -> Result<Vec<Value>> {
+    let agent = http::agent();
+    let mut resources = Vec::new();
+    let mut next = Some(url.to_string());
+    while let Some(url) = next {
+        let mut response = agent
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .call()?;
+        http::check_status(SERVICE, &mut response)
+            .with_context(|| format!("Failed to list {}", url))?;
+        let mut page: Value = response.body_mut().read_json()?;
+        if let Some(values) = page["value"].as_array_mut() {
+            resources.append(values);
+        }
+        next = page["nextLink"].as_str().map(str::to_string);
+    }
+    Ok(resources)
+}
+
+/// A resource as Git integration stores it: without its ARM id and etag.
+fn git_document(resource: &Value) -> Value {
+    let mut document = json!({ "name": resource["name"], "properties": resource["properties"] });
+    if let Some(kind) = resource.get("type") {
+        document["type"] = kind.clone();
+    }
+    document
+}
+
+fn asset_path(folder: &Path, resource: &Value) -> PathBuf {
+    let name = resource["name"].as_str().unwrap_or_default();
+    folder.join(format!("{}.json", file_name(name)))
+}
+
+/// `name` made safe to use as a file name.
+fn file_name(name: &str) -> String {
+    name.replace(['/', '\\', ':'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_document() {
+        let resource = json!({
+            "id": "/subscriptions/s/resourceGroups/rg/providers/Microsoft.DataFactory/factories/f/pipelines/pl_a",
+            "name": "pl_a",
+            "type": "Microsoft.DataFactory/factories/pipelines",
+            "etag": "\"0100\"",
+            "properties": { "activities": [] }
+        });
+        assert_eq!(
+            git_document(&resource),
+            json!({
+                "name": "pl_a",
+                "type": "Microsoft.DataFactory/factories/pipelines",
+                "properties": { "activities": [] }
+            })
+        );
+        assert_eq!(
+            asset_path(Path::new("pipeline"), &resource),
+            Path::new("pipeline").join("pl_a.json")
+        );
+        assert_eq!(
+            Factory {
+                subscription: "s".to_string(),
+                resource_group: "rg".to_string(),
+                name: "f".to_string(),
+            }
+            .url("pipelines"),
+            "https://management.azure.com/subscriptions/s/resourceGroups/rg/providers/Microsoft.DataFactory/factories/f/pipelines?api-version=2018-06-01"
+        );
+    }
+
+    #[test]
+    fn test_prepare_download_dir() {
+        let dir = env::temp_dir().join(format!("adf-guardian-download-{}", std::process::id()));
+        let pipeline = dir.join("pipeline").join("pl_a.json");
+
+        prepare_download_dir(&dir).unwrap();
+        fs::create_dir_all(pipeline.parent().unwrap()).unwrap();
+        fs::write(&pipeline, "{}").unwrap();
+        prepare_download_dir(&dir).unwrap();
+        let cleared = !pipeline.exists();
+
+        fs::remove_file(dir.join(DOWNLOAD_MARKER)).unwrap();
+        fs::create_dir_all(pipeline.parent().unwrap()).unwrap();
+        fs::write(&pipeline, "{}").unwrap();
+        let refused = prepare_download_dir(&dir).is_err();
+        let kept = pipeline.exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(cleared);
+        assert!(refused);
+        assert!(kept);
+    }
+}
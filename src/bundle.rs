@@ -0,0 +1,227 @@
+use crate::config::{self, Config, Rule};
+use crate::oci;
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::signature::{ED25519, Ed25519KeyPair, UnparsedPublicKey};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// DER prefix of an Ed25519 public key in a `PUBLIC KEY` PEM (SubjectPublicKeyInfo), before
+/// the 32 bytes of the key.
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+#[derive(Serialize)]
+struct Bundle<'a> {
+    rules: &'a [Rule],
+}
+
+/// Merges the rules of the YAML files under `dir` into one config file, expanded so it
+/// needs nothing else to load. Rule ids must be unique across the files; the pack metadata
+/// (`pack.yaml`) is not a rule file.
+pub fn build(dir: &Path) -> Result<String> {
+    let mut files: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.is_file()
+                && path.file_name().is_some_and(|name| name != oci::PACK_FILE)
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        })
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        bail!("No YAML files found in {:?}", dir);
+    }
+    let mut rules: Vec<Rule> = Vec::new();
+    for file in &files {
+        let config = Config::load(file)?;
+        for rule in config.rules {
+            if rules.iter().any(|r| r.id == rule.id) {
+                bail!("Rule id '{}' of {:?} is already used", rule.id, file);
+            }
+            rules.push(rule);
+        }
+    }
+    serde_yaml::to_string(&Bundle { rules: &rules }).context("Failed to write the bundle")
+}
+
+/// The signature file of a bundle: `rules.yaml.sig` for `rules.yaml`.
+pub fn signature_path(bundle: &Path) -> PathBuf {
+    let mut path = bundle.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// The base64 Ed25519 signature of `contents` with the PKCS#8 private key in `key_pem`
+/// (e.g. from `openssl genpkey -algorithm ed25519`).
+pub fn sign(contents: &[u8], key_pem: &str) -> Result<String> {
+    let der = pem_decode(key_pem, "PRIVATE KEY")?;
+    let key = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+        .map_err(|e| anyhow!("Not an Ed25519 private key: {}", e))?;
+    Ok(BASE64.encode(key.sign(contents).as_ref()))
+}
+
+/// Checks the base64 `signature` of `contents` against the Ed25519 public key in `key_pem`
+/// (e.g. from `openssl pkey -pubout`).
+pub fn verify(contents: &[u8], signature: &str, key_pem: &str) -> Result<()> {
+    let der = pem_decode(key_pem, "PUBLIC KEY")?;
+    let key = der
+        .strip_prefix(&ED25519_SPKI_PREFIX)
+        .filter(|key| key.len() == 32)
+        .ok_or_else(|| anyhow!("Not an Ed25519 public key"))?;
+    let signature = BASE64
+        .decode(signature.trim())
+        .context("The signature is not valid base64")?;
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(contents, &signature)
+        .map_err(|_| anyhow!("The signature doesn't match the contents and the trusted key"))
+}
+
+/// Checks the signature next to the file `path` with the public key file `key`.
+pub fn verify_file(path: &Path, key: &Path) -> Result<()> {
+    let contents = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let signature_path = signature_path(path);
+    let signature = fs::read_to_string(&signature_path)
+        .with_context(|| format!("{:?} is not signed: no {:?}", path, signature_path))?;
+    let key_pem = fs::read_to_string(key)
+        .with_context(|| format!("Failed to read the trusted key {:?}", key))?;
+    verify(&contents, &signature, &key_pem)
+        .with_context(|| format!("Invalid signature of {:?}", path))
+}
+
+/// Signs the files the file params (lists, policies) of `config` name, writing
+/// `<file>.sig` next to each, as the bundle only holds their paths. Returns the files.
+pub fn sign_param_files(config: &Config, key_pem: &str) -> Result<Vec<PathBuf>> {
+    let files = param_files(config)?;
+    for file in &files {
+        let contents = fs::read(file).with_context(|| format!("Failed to read {:?}", file))?;
+        let signature_path = signature_path(file);
+        fs::write(&signature_path, sign(&contents, key_pem)? + "\n")
+            .with_context(|| format!("Failed to write {:?}", signature_path))?;
+    }
+    Ok(files)
+}
+
+/// Checks the signatures [`sign_param_files`] wrote, with the public key file `key`.
+pub fn verify_param_files(config: &Config, key: &Path) -> Result<()> {
+    param_files(config)?
+        .iter()
+        .try_for_each(|file| verify_file(file, key))
+}
+
+/// The files the file params of the rules of `config` name, including those of its
+/// `projects`, without the signatures in their folders. Lists at a URL can't be signed.
+fn param_files(config: &Config) -> Result<Vec<PathBuf>> {
+    let rules = || {
+        config
+            .rules
+            .iter()
+            .chain(config.projects.values().flat_map(|project| &project.rules))
+    };
+    if let Some(url) = config::file_params(rules()).find(|path| config::is_url(path)) {
+        bail!("{} can't be signed: only files can be, not URLs", url);
+    }
+    Ok(config::param_files(rules())
+        .into_iter()
+        .filter(|file| file.extension().is_none_or(|ext| ext != "sig"))
+        .collect())
+}
+
+/// The DER bytes of the `label` block of a PEM document.
+fn pem_decode(pem: &str, label: &str) -> Result<Vec<u8>> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+    let body = pem
+        .split_once(&begin)
+        .and_then(|(_, rest)| rest.split_once(&end))
+        .map(|(body, _)| body)
+        .ok_or_else(|| anyhow!("Expected a PEM file with a {} block", label))?;
+    let base64: String = body.split_whitespace().collect();
+    BASE64
+        .decode(base64)
+        .with_context(|| format!("Invalid base64 in the {} block", label))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::KeyPair;
+
+    /// A new Ed25519 key pair, as the private and the public key PEM.
+    pub(crate) fn key_pems() -> (String, String) {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let public = [&ED25519_SPKI_PREFIX[..], key.public_key().as_ref()].concat();
+        (
+            pem("PRIVATE KEY", pkcs8.as_ref()),
+            pem("PUBLIC KEY", &public),
+        )
+    }
+
+    fn pem(label: &str, der: &[u8]) -> String {
+        format!(
+            "-----BEGIN {}-----\n{}\n-----END {}-----\n",
+            label,
+            BASE64.encode(der),
+            label
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (private_pem, public_pem) = key_pems();
+
+        let bundle = b"rules:\n- id: naming\n";
+        let signature = sign(bundle, &private_pem).unwrap();
+        verify(bundle, &signature, &public_pem).unwrap();
+        assert!(verify(b"rules: []\n", &signature, &public_pem).is_err());
+        assert!(verify(bundle, &signature, &private_pem).is_err());
+
+        let dir = std::env::temp_dir().join(format!("adf-guardian-sign-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let list = dir.join("accounts.txt");
+        let key = dir.join("trusted-key.pem");
+        fs::write(&list, "stcontoso\n").unwrap();
+        fs::write(&key, &public_pem).unwrap();
+        let rules = |source: &str| {
+            Config::from_yaml(
+                format!(
+                    r#"
+rules:
+  - id: "accounts"
+    asset: "linkedService"
+    validate: {{ target: "$.name", guard: "ExternalList", params: {{ source: "{}" }} }}
+"#,
+                    source
+                )
+                .as_bytes(),
+            )
+            .unwrap()
+        };
+        let config = rules(&list.to_string_lossy());
+        let unsigned = verify_param_files(&config, &key).is_err();
+        assert_eq!(
+            sign_param_files(&config, &private_pem).unwrap(),
+            [dir.join("accounts.txt")]
+        );
+        let signed = verify_param_files(&config, &key).is_ok();
+        fs::write(&list, "stcontoso\nstattacker\n").unwrap();
+        let tampered = verify_param_files(&config, &key).is_err();
+        let url = verify_param_files(&rules("https://example.com/accounts.txt"), &key).is_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(unsigned);
+        assert!(signed);
+        assert!(tampered);
+        assert!(url);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::config::{Config, Rule};
+use adf_guardian::engine::lint::Finding;
+use adf_guardian::engine::suppress::{Audit, Status};
+use adf_guardian::engine::trace::{RuleTrace, ValidationTrace};
+use anyhow::{Result, anyhow};
+use colored::*;
+use std::io::{self, Write};
+
+/// Finds a configured rule by id for `explain`.
+pub fn find<'a>(config: &'a Config, rule_id: &str) -> Result<&'a Rule> {
+    config
+        .rules
+        .iter()
+        .find(|r| r.id == rule_id)
+        .ok_or_else(|| anyhow!("No rule with id '{}' in the configuration", rule_id))
+}
+
+/// Table of the configured rules for `list-rules`, in the order they are evaluated.
+pub fn write_rule_list(out: &mut dyn Write, rules: &[Rule]) -> io::Result<()> {
+    let rows: Vec<[String; 4]> = rules
+        .iter()
+        .map(|r| {
+            [
+                r.id.clone(),
+                r.asset.to_string(),
+                r.severity.to_string(),
+                r.tags.join(", "),
+            ]
+        })
+        .collect();
+    let headers = ["Rule", "Asset", "Severity", "Tags"];
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain([headers[i].len()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  Description",
+            headers[0],
+            headers[1],
+            headers[2],
+            headers[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        )
+        .bold()
+    )?;
+    for (row, rule) in rows.iter().zip(rules) {
+        writeln!(
+            out,
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            rule.description.as_deref().unwrap_or_default(),
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(out, "{} rule(s)", rules.len())
+}
+
+/// Everything `explain` knows about a rule: its metadata, then the full definition as YAML.
+pub fn write_rule(out: &mut dyn Write, rule: &Rule) -> io::Result<()> {
+    writeln!(out, "{}", rule.id.bold())?;
+    if let Some(description) = &rule.description {
+        writeln!(out, "  {}", description)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "  {:<10}{}", "Asset:", rule.asset)?;
+    writeln!(out, "  {:<10}{}", "Severity:", rule.severity)?;
+    writeln!(out, "  {:<10}{}", "Guard:", rule.validate.guard)?;
+    if !rule.tags.is_empty() {
+        writeln!(out, "  {:<10}{}", "Tags:", rule.tags.join(", "))?;
+    }
+    if let Some(url) = &rule.help_url {
+        writeln!(out, "  {:<10}{}", "Help:", url.underline())?;
+    }
+
+    let definition = serde_yaml::to_string(rule).map_err(io::Error::other)?;
+    writeln!(out)?;
+    writeln!(out, "{}", "Definition".bold())?;
+    for line in definition.lines() {
+        writeln!(out, "  {}", line)?;
+    }
+    Ok(())
+}
+
+/// The findings of `lint-config`, grouped by rule.
+pub fn write_findings(out: &mut dyn Write, findings: &[Finding]) -> io::Result<()> {
+    if findings.is_empty() {
+        return writeln!(out, "No problems found in the rules");
+    }
+    let mut previous = None;
+    for finding in findings {
+        if previous != Some(&finding.rule_id) {
+            writeln!(out, "{}", finding.rule_id.bold())?;
+            previous = Some(&finding.rule_id);
+        }
+        writeln!(out, "  {}", finding.message)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "{} problem(s)", findings.len())
+}
+
+pub fn write_suppressions(out: &mut dyn Write, audit: &[Audit]) -> io::Result<()> {
+    if audit.is_empty() {
+        return writeln!(out, "No suppressions configured");
+    }
+    for entry in audit {
+        let suppression = &entry.suppression;
+        let status = match entry.status {
+            Status::Active => format!("hides {} violation(s)", entry.violations).normal(),
+            Status::Expired => {
+                format!("expired, {} violation(s) reported again", entry.violations).red()
+            }
+            Status::Unused => "matches nothing, can be deleted".yellow(),
+            Status::Invalid => "invalid, skipped".red(),
+        };
+        writeln!(
+            out,
+            "{} on {}: {}",
+            suppression.rule.bold(),
+            suppression.file.as_deref().unwrap_or("every file"),
+            status
+        )?;
+        if let Some(path) = &suppression.path {
+            writeln!(out, "  Path: {}", path)?;
+        }
+        if let Some(reason) = &suppression.reason {
+            writeln!(out, "  Reason: {}", reason)?;
+        }
+        if let Some(added_by) = &suppression.added_by {
+            writeln!(out, "  Added by: {}", added_by)?;
+        }
+        if let Some(expires) = &suppression.expires {
+            writeln!(out, "  Expires: {}", expires)?;
+        }
+    }
+    let count = |status: Status| audit.iter().filter(|e| e.status == status).count();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} suppression(s): {} active, {} expired, {} unused, {} invalid",
+        audit.len(),
+        count(Status::Active),
+        count(Status::Expired),
+        count(Status::Unused),
+        count(Status::Invalid)
+    )
+}
+
+pub fn write_trace(out: &mut dyn Write, traces: &[RuleTrace]) -> io::Result<()> {
+    for trace in traces {
+        let outcome = match (trace.violations, &trace.skipped) {
+            (0, Some(_)) => "skipped".dimmed(),
+            (0, None) => "passes".green(),
+            (violations, _) => format!("{} violation(s)", violations).red(),
+        };
+        writeln!(out, "{}: {}", trace.rule_id.bold(), outcome)?;
+        for (clause, validation) in [("when", &trace.when), ("validate", &trace.validate)] {
+            if let Some(validation) = validation {
+                write_validation(out, clause, validation)?;
+            }
+        }
+        if let Some(skipped) = &trace.skipped {
+            writeln!(out, "  Skipped: {}", skipped)?;
+        }
+    }
+    let evaluated = traces.iter().filter(|t| t.validate.is_some()).count();
+    let violations: usize = traces.iter().map(|t| t.violations).sum();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} rule(s) considered, {} evaluated, {} violation(s)",
+        traces.len(),
+        evaluated,
+        violations
+    )
+}
+
+fn write_validation(out: &mut dyn Write, clause: &str, trace: &ValidationTrace) -> io::Result<()> {
+    let params = trace
+        .params
+        .as_ref()
+        .map_or_else(|| "?".to_string(), |params| params.to_string());
+    writeln!(
+        out,
+        "  {}: {} {} {}",
+        clause.bold(),
+        trace.target,
+        trace.guard,
+        params
+    )?;
+    for node in &trace.nodes {
+        let result = if node.passed {
+            "pass".green()
+        } else {
+            "fail".red()
+        };
+        write!(out, "    {} {}", result, node.path)?;
+        if let Some(value) = &node.value {
+            write!(out, " = {}", value)?;
+        }
+        if let Some(message) = &node.message {
+            write!(out, " ({})", message)?;
+        }
+        writeln!(out)?;
+    }
+    let reason = if trace.passed {
+        trace.reason.normal()
+    } else {
+        trace.reason.yellow()
+    };
+    writeln!(out, "    {}", reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_and_explain() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "retries"
+    asset: ["pipeline", "dataflow"]
+    description: "Too many retries"
+    severity: Warning
+    tags: ["reliability"]
+    help_url: "https://wiki.example.com/retries"
+    validate:
+      target: "$.properties.retries"
+      guard: "Range"
+      params: { max: 3 }
+"#,
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        write_rule_list(&mut out, &config.rules).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert!(table.contains("Rule     Asset               Severity  Tags         Description"));
+        assert!(
+            table.contains("retries  pipeline, dataflow  Warning   reliability  Too many retries")
+        );
+
+        let mut out = Vec::new();
+        write_rule(&mut out, find(&config, "retries").unwrap()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("Severity: Warning"));
+        assert!(text.contains("https://wiki.example.com/retries"));
+        assert!(text.contains("    guard: Range"));
+        assert!(text.contains("      max: 3"));
+
+        assert!(find(&config, "missing").is_err());
+    }
+}
@@ -1,18 +1,642 @@
-use clap::Parser;
-use std::path::PathBuf;
+use crate::config::Severity;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// Path to the ADF project to scan
-    #[arg(short, long, default_value = ".")]
-    pub project_path: PathBuf,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path to the ADF project to scan, a folder or a zip archive (repeatable, to scan several
+    /// factories as one run)
+    #[arg(
+        short,
+        long = "project-path",
+        value_name = "PROJECT_PATH",
+        default_value = "."
+    )]
+    pub project_paths: Vec<PathBuf>,
+
+    /// Scan the factory roots found under each project path (folders with a
+    /// `publish_config.json` or the ADF asset folders) rather than the paths themselves
+    #[arg(long, default_value_t = false, conflicts_with = "stdin")]
+    pub auto_detect: bool,
+
+    /// Only evaluate rules on JSON files changed relative to `--base` (uses git)
+    #[arg(long, default_value_t = false)]
+    pub changed: bool,
+
+    /// Scan the files of this branch, tag or commit (e.g. `adf_publish`) read from git,
+    /// without checking it out
+    #[arg(long, value_name = "REF", conflicts_with_all = ["changed", "stdin"])]
+    pub git_ref: Option<String>,
+
+    /// Only evaluate rules on the files listed one per line (or NUL-separated, as from
+    /// `git diff -z`) in this file, or on stdin with `-` (e.g. `git diff --name-only |
+    /// adf-guardian --files-from -`)
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["changed", "stdin"])]
+    pub files_from: Option<PathBuf>,
+
+    /// Check a single document read from stdin instead of scanning the project
+    #[arg(long, default_value_t = false, requires = "stdin_filename", conflicts_with_all = ["changed", "stream", "stats"])]
+    pub stdin: bool,
+
+    /// Path the stdin document is reported as; its folder decides which rules apply
+    /// (e.g. `pipeline/pl_x.json`)
+    #[arg(long, value_name = "PATH", requires = "stdin")]
+    pub stdin_filename: Option<PathBuf>,
+
+    /// Git ref that `--changed` compares against
+    #[arg(long, value_name = "REF", default_value = "origin/main")]
+    pub base: String,
+
+    /// Number of files checked in parallel (default: one per CPU)
+    #[arg(short, long, value_name = "N")]
+    pub jobs: Option<NonZeroUsize>,
+
+    /// Only scan the files matching this glob, relative to the project path (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Don't scan the files and folders matching this glob, e.g. `**/ARMTemplate*/**`
+    /// (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Also scan files ignored by `.gitignore` and the git excludes, e.g. generated JSON
+    #[arg(long, default_value_t = false)]
+    pub no_gitignore: bool,
+
+    /// Also scan hidden files and folders
+    #[arg(long, default_value_t = false)]
+    pub hidden: bool,
+
+    /// Follow symbolic links, e.g. to asset folders shared between factories
+    #[arg(long, default_value_t = false)]
+    pub follow_links: bool,
+
+    /// Descend at most this many levels below the project path (1: only the files directly in it)
+    #[arg(long, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Accept comments and trailing commas in JSON files (JSONC)
+    #[arg(long, default_value_t = false)]
+    pub jsonc: bool,
+
+    /// Skip files larger than this, e.g. `50MB` (units: B, KB, MB, GB; 1 KB = 1024 bytes)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Cut actual values longer than this many characters in reports
+    #[arg(long, value_name = "N")]
+    pub max_value_length: Option<usize>,
+
+    /// Reuse the results of files unchanged since an earlier run with the same rules
+    #[arg(long, default_value_t = false)]
+    pub cache: bool,
+
+    /// Where `--cache` keeps results (default: `.adf-guardian/cache` in the project folder)
+    #[arg(long, value_name = "DIR", requires = "cache")]
+    pub cache_dir: Option<PathBuf>,
 
     /// Path to the configuration file
     #[arg(short, long, default_value = "guards.yaml")]
     pub config: PathBuf,
 
-    /// Output results in JSON format
-    #[arg(long, default_value_t = false)]
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Human, global = true)]
+    pub format: OutputFormat,
+
+    /// Output results in JSON format (same as `--format json`)
+    #[arg(long, default_value_t = false, global = true)]
     pub json: bool,
+
+    /// Write the JSON report in the pre-v2 shape: a flat array of violations
+    #[arg(long, default_value_t = false)]
+    pub json_compat: bool,
+
+    /// Tera template used by `--format template` (and `--report template=PATH`)
+    #[arg(long, value_name = "PATH")]
+    pub template: Option<PathBuf>,
+
+    /// Write the report to a file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// How to group violations in the human report
+    #[arg(long, value_enum, default_value_t = GroupBy::File)]
+    pub group_by: GroupBy,
+
+    /// Sort violations (and groups) in the human report instead of keeping scan order
+    #[arg(long, value_enum)]
+    pub sort: Option<SortBy>,
+
+    /// Also list files that passed and the rules evaluated against each file
+    #[arg(short, long, default_value_t = false, conflicts_with_all = ["summary", "quiet", "group_by"])]
+    pub verbose: bool,
+
+    /// Only run rules of at least this severity
+    #[arg(long, value_enum)]
+    pub min_severity: Option<Severity>,
+
+    /// Only run rules whose id matches one of these patterns (`*` is a wildcard)
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',')]
+    pub only_rules: Vec<String>,
+
+    /// Skip rules whose id matches one of these patterns (`*` is a wildcard)
+    #[arg(long, value_name = "PATTERNS", value_delimiter = ',')]
+    pub skip_rules: Vec<String>,
+
+    /// Compare with an earlier JSON report and print new, fixed and persisting violations
+    /// instead of the report; only new violations fail the run
+    #[arg(long, value_name = "REPORT", conflicts_with = "stream")]
+    pub compare_to: Option<PathBuf>,
+
+    /// Fail (exit code 1) when there are more than N warnings
+    #[arg(long, value_name = "N", global = true)]
+    pub max_warnings: Option<usize>,
+
+    /// Print only per-rule and total counts in the human report
+    #[arg(long, default_value_t = false, conflicts_with = "quiet")]
+    pub summary: bool,
+
+    /// Print nothing to stdout and rely on the exit code (`--report` files are still written)
+    #[arg(short, long, default_value_t = false)]
+    pub quiet: bool,
+
+    /// Also write a report to a file, as FORMAT=PATH (repeatable, e.g. `--report json=out.json`)
+    #[arg(long, value_name = "FORMAT=PATH", value_parser = parse_report_target)]
+    pub report: Vec<ReportTarget>,
+
+    /// Publish the violations as a check run with annotations on the pull request's commit,
+    /// through the GitHub API (in GitHub Actions, authenticated with GITHUB_TOKEN)
+    #[arg(long, default_value_t = false, conflicts_with = "stdin")]
+    pub github_pr: bool,
+
+    /// Add the owners the repository's CODEOWNERS file gives each violation's file to the
+    /// report (`code_owners` in JSON, NDJSON and templates)
+    #[arg(long, default_value_t = false)]
+    pub codeowners: bool,
+
+    /// Post a summary to a Slack, Teams or generic webhook when errors are found
+    /// (repeatable; see `notifications:` in the config for more options)
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Vec<String>,
+
+    /// Export metrics and a trace of the scan to an OpenTelemetry collector over OTLP/HTTP,
+    /// e.g. `http://localhost:4318` (headers are read from OTEL_EXPORTER_OTLP_HEADERS)
+    #[arg(long, value_name = "URL", env = "OTEL_EXPORTER_OTLP_ENDPOINT")]
+    pub otel_endpoint: Option<String>,
+
+    /// Append the run's summary and violations to a history database, as `sqlite://PATH`
+    /// (see the `trends` subcommand)
+    #[arg(long, value_name = "sqlite://PATH", value_parser = parse_history, global = true)]
+    pub history: Option<PathBuf>,
+
+    /// Print each file's violations as soon as it has been checked instead of at the end
+    #[arg(long, default_value_t = false, conflicts_with_all = ["output", "quiet", "summary", "sort", "group_by"])]
+    pub stream: bool,
+
+    /// Report unreadable files and rules that can't be compiled as errors instead of only
+    /// logging them (see the `strict` config section)
+    #[arg(long, default_value_t = false)]
+    pub strict: bool,
+
+    /// Only warn about unknown guards and invalid guard params, skipping the checks they
+    /// break, instead of refusing to run
+    #[arg(long, default_value_t = false)]
+    pub lenient: bool,
+
+    /// Refuse to load a config file, or a list or policy file its rules read, without a
+    /// valid signature from `--trusted-key` next to it (`<file>.sig`, written by
+    /// `pack build --sign`)
+    #[arg(long, default_value_t = false, requires = "trusted_key", global = true)]
+    pub require_signed: bool,
+
+    /// Ed25519 public key (PEM) that signs the trusted rule bundles
+    #[arg(
+        long,
+        value_name = "PEM",
+        env = "ADF_GUARDIAN_TRUSTED_KEY",
+        global = true
+    )]
+    pub trusted_key: Option<PathBuf>,
+
+    /// Append per-rule evaluation counts and timings to the report
+    #[arg(long, default_value_t = false)]
+    pub stats: bool,
+
+    /// Instead of scanning, show how every rule is evaluated on this file: whether its
+    /// asset matches, whether its `when` holds, the nodes its target selects and the guard
+    /// result on each
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stdin", "changed", "files_from"])]
+    pub explain: Option<PathBuf>,
+
+    /// Print the man page (roff) to stdout, for packaging
+    #[arg(long, default_value_t = false, hide = true)]
+    pub man: bool,
+
+    /// When to use colors in the human report (`auto` honours `NO_COLOR` and non-TTY stdout)
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, global = true)]
+    pub color: ColorChoice,
+
+    /// Least severe log messages to print on stderr
+    #[arg(long, value_enum, default_value_t = LogLevel::Warn, global = true)]
+    pub log_level: LogLevel,
+
+    /// Format of the log messages printed on stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text, global = true)]
+    pub log_format: LogFormat,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Compare two JSON reports and show new, fixed and persisting violations
+    Diff {
+        /// Report from the earlier run (e.g. the target branch)
+        old: PathBuf,
+        /// Report from the later run
+        new: PathBuf,
+    },
+    /// Run a Language Server Protocol server on stdin/stdout for editor diagnostics
+    Lsp,
+    /// List the configured rules (including rule packs) with their asset, severity and tags
+    ListRules,
+    /// Show everything about one rule: description, help URL and its full definition
+    Explain {
+        /// Id of the rule, as shown in reports
+        rule_id: String,
+    },
+    /// Find dead policy in the rules: assets that match no file of the project, `when`
+    /// clauses that can never hold, bounds like min > max, empty allow-lists and invalid params
+    LintConfig,
+    /// Build, sign and publish rule bundles for central distribution, and extend the config
+    /// from a registry
+    Pack {
+        #[command(subcommand)]
+        command: PackCommand,
+    },
+    /// Convert governance defined elsewhere into rules, printed as YAML or appended to the
+    /// config
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+    /// List the suppressions of the config with who added them and the violations they hide,
+    /// flagging expired ones and ones that no longer match anything. Exits with 1 when one expired
+    Suppressions,
+    /// Check one rule on a JSON document given inline, as if it were a file of the rule's
+    /// asset type, without a project on disk. Exits like a scan
+    CheckSnippet {
+        /// Id of the rule, as shown in reports
+        #[arg(long = "rule", value_name = "ID")]
+        rule_id: String,
+        /// The document, e.g. '{"name":"pl_x"}', or `-` to read it from stdin (`--json`
+        /// is the output format)
+        #[arg(value_name = "JSON")]
+        document: String,
+    },
+    /// Print the nodes a JSONPath selects in JSON files, with their normalized paths and
+    /// lines, using the JSONPath engine of the rules. Exits with 1 when nothing is selected
+    Query {
+        /// JSONPath, as in the `target` of a rule, e.g. '$..activities[?(@.type=="Copy")].name'
+        expression: String,
+        /// Files to query
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Build a rule step by step: the asset type, a target JSONPath previewed on a file of
+    /// the project, the guard and its params; then append it to the config
+    NewRule {
+        /// File to preview the target and the rule on (default: the first file of the asset type)
+        #[arg(long, value_name = "FILE")]
+        sample: Option<PathBuf>,
+    },
+    /// Run only the given rules against the project and count the files and violations they
+    /// would report at each severity, e.g. before enforcing a new Error-level rule
+    Preview {
+        /// Id of a configured rule, or a YAML file of (not yet committed) rules; repeatable
+        #[arg(long = "rule", value_name = "ID|FILE", required = true)]
+        rules: Vec<String>,
+    },
+    /// Print a shell completion script (e.g. `adf-guardian completions bash > /etc/bash_completion.d/adf-guardian`)
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
+    },
+    /// Install a git pre-commit hook that checks the staged JSON files of the project
+    InstallHook {
+        /// Print an entry for the pre-commit framework instead of writing `.git/hooks/pre-commit`
+        #[arg(long, default_value_t = false)]
+        pre_commit: bool,
+        /// Replace an existing pre-commit hook that wasn't installed by adf-guardian
+        #[arg(long, default_value_t = false, conflicts_with = "pre_commit")]
+        force: bool,
+    },
+    /// Generate one documentation page per configured rule, plus an index
+    /// (Markdown by default, HTML with `--format html`)
+    Docs {
+        /// Folder the pages are written to
+        #[arg(short, long, default_value = "docs")]
+        output: PathBuf,
+    },
+    /// Post the violations as comment threads on the lines of an Azure Repos pull request
+    /// (authenticated with AZURE_DEVOPS_EXT_PAT, or a bearer token in AZURE_DEVOPS_TOKEN
+    /// or SYSTEM_ACCESSTOKEN)
+    AnnotateAzdo {
+        /// Organization name, or its URL
+        #[arg(long, env = "SYSTEM_COLLECTIONURI")]
+        org: String,
+        /// Project the repository belongs to
+        #[arg(long, env = "SYSTEM_TEAMPROJECT")]
+        project: String,
+        /// Repository name or id (default: the project's repository of the same name)
+        #[arg(long, env = "BUILD_REPOSITORY_ID")]
+        repository: Option<String>,
+        /// Id of the pull request
+        #[arg(long, env = "SYSTEM_PULLREQUEST_PULLREQUESTID")]
+        pr: u64,
+        /// Post the violations of an earlier JSON report instead of scanning the project
+        #[arg(long)]
+        from_report: Option<PathBuf>,
+    },
+    /// Download a deployed factory through the Azure Resource Manager API and scan it like
+    /// a project, to catch what differs from Git (authenticated with AZURE_ACCESS_TOKEN, a
+    /// service principal in AZURE_TENANT_ID/AZURE_CLIENT_ID/AZURE_CLIENT_SECRET, or `az login`)
+    ScanAzure {
+        #[command(flatten)]
+        factory: FactoryArgs,
+        /// Folder the factory is downloaded to, empty or holding an earlier download
+        /// (default: .adf-guardian/azure/<factory>)
+        #[arg(long)]
+        download_dir: Option<PathBuf>,
+    },
+    /// List the assets that differ between the project and the deployed factory, exist on
+    /// one side only, or were modified in the factory after their last commit (same
+    /// credentials as `scan-azure`)
+    Drift {
+        #[command(flatten)]
+        factory: FactoryArgs,
+    },
+    /// Show violation counts per rule over the runs recorded with --history
+    Trends {
+        /// Only show this rule
+        #[arg(long, value_name = "ID")]
+        rule: Option<String>,
+        /// Only count runs on this branch
+        #[arg(long)]
+        branch: Option<String>,
+        /// Number of most recent runs to show
+        #[arg(long, default_value_t = 10)]
+        runs: usize,
+    },
+    /// Evaluate the rules on the project several times, one file after the other, and print
+    /// how long each phase (scan, parse, match, guard, locate) and each rule takes; `--json`
+    /// prints it for scripts comparing runs, e.g. before and after a change
+    Bench {
+        /// Project to benchmark (default: the project path)
+        #[arg(long, value_name = "PATH")]
+        project: Option<PathBuf>,
+        /// Number of timed runs
+        #[arg(long, value_name = "N", default_value = "10")]
+        iterations: NonZeroUsize,
+    },
+    /// Serve an HTTP API that validates submitted assets (`POST /validate`) and zipped
+    /// factories (`POST /scan`) with the rules of the config
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Address to listen on (use 0.0.0.0 to accept connections from other machines)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+}
+
+/// A data factory deployed in Azure.
+#[derive(Args, Debug, Clone)]
+pub struct FactoryArgs {
+    /// Name of the data factory
+    #[arg(long)]
+    pub factory: String,
+    /// Resource group of the factory
+    #[arg(long)]
+    pub resource_group: String,
+    /// Subscription of the factory
+    #[arg(long, env = "AZURE_SUBSCRIPTION_ID")]
+    pub subscription: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportTarget {
+    pub format: OutputFormat,
+    pub path: PathBuf,
+}
+
+fn parse_report_target(value: &str) -> Result<ReportTarget, String> {
+    let (format, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected FORMAT=PATH, got '{}'", value))?;
+    if path.is_empty() {
+        return Err(format!("missing file path for format '{}'", format));
+    }
+
+    Ok(ReportTarget {
+        format: OutputFormat::from_str(format, true)?,
+        path: PathBuf::from(path),
+    })
+}
+
+fn parse_history(value: &str) -> Result<PathBuf, String> {
+    match value.strip_prefix("sqlite://") {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Err(format!("expected sqlite://PATH, got '{}'", value)),
+    }
+}
+
+fn parse_size(value: &str) -> Result<u64, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: u64 = match upper[digits.len()..].trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        unit => return Err(format!("unknown size unit '{}'", unit)),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("expected a size such as 50MB, got '{}'", value))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size '{}' is too large", value))
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored report for terminals
+    Human,
+    /// JSON array of violations
+    Json,
+    /// Checkstyle XML
+    Checkstyle,
+    /// GitLab Code Quality (Code Climate JSON)
+    Gitlab,
+    /// Standalone HTML page
+    Html,
+    /// Markdown summary for pull request comments
+    Markdown,
+    /// One JSON object per violation and line, streamed as files are checked
+    Ndjson,
+    /// Custom Tera template given with `--template`
+    Template,
+    /// TeamCity service messages (inspections)
+    Teamcity,
+    /// Test Anything Protocol, one test point per file
+    Tap,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum PackCommand {
+    /// Merge the rules of the YAML files in a folder into one config file, optionally signed.
+    /// The name and version of the pack are read from `pack.yaml` in the folder
+    Build {
+        /// Folder of the rule files
+        dir: PathBuf,
+        /// Bundle to write, or folder of the OCI image layout with `--oci`
+        #[arg(short, long, value_name = "PATH")]
+        output: PathBuf,
+        /// Sign the bundle with this Ed25519 private key (PEM), writing `<FILE>.sig`
+        #[arg(long, value_name = "PEM")]
+        sign: Option<PathBuf>,
+        /// Write a versioned OCI image layout, for `pack publish`
+        #[arg(long, default_value_t = false)]
+        oci: bool,
+        /// Version of the pack, instead of the one in `pack.yaml`
+        #[arg(long = "version", value_name = "VERSION")]
+        pack_version: Option<String>,
+    },
+    /// Push a pack built with `pack build --oci` to an OCI registry (credentials from
+    /// ADF_GUARDIAN_REGISTRY_TOKEN, or ADF_GUARDIAN_REGISTRY_USERNAME and _PASSWORD)
+    Publish {
+        /// Folder of the OCI image layout
+        layout: PathBuf,
+        /// Where to push it, e.g. oci://ghcr.io/contoso/adf-rules:1.2.0
+        reference: String,
+    },
+    /// Pull a pack from an OCI registry into the pack cache next to the config and add it to
+    /// the `extends` of the config
+    Add {
+        /// The pack, e.g. oci://ghcr.io/contoso/adf-rules:1.2.0
+        reference: String,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ImportCommand {
+    /// Convert Azure Policy definitions that deny or audit Data Factory resources by name or
+    /// tag into rules. Tags are checked on annotations like `CostCenter:4100`
+    AzurePolicy {
+        /// A definition, a list of them, or the output of `az policy definition list`
+        #[arg(required_unless_present = "preset", value_name = "POLICY.json")]
+        policy: Option<PathBuf>,
+        /// Also convert the definitions of a built-in naming standard
+        #[arg(long, value_enum)]
+        preset: Option<PolicyPreset>,
+        /// Append the rules to the config instead of printing them
+        #[arg(long, default_value_t = false)]
+        append: bool,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyPreset {
+    /// Cloud Adoption Framework abbreviations: adf- factories, pl_ pipelines, ds_ datasets,
+    /// ls_ linked services, df_ data flows, tr_ triggers, ir- integration runtimes and pep-
+    /// managed private endpoints
+    CafNaming,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    File,
+    Rule,
+    Severity,
+    /// The team owning the file, from the `owners` of the config
+    Owner,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    File,
+    Rule,
+    Severity,
+}
+
+impl Cli {
+    /// The first `--project-path`, which subcommands and git integration work in.
+    pub fn project_path(&self) -> &Path {
+        &self.project_paths[0]
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            self.format
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report_target() {
+        let target = parse_report_target("gitlab=reports/gl.json").unwrap();
+        assert_eq!(target.format, OutputFormat::Gitlab);
+        assert_eq!(target.path, PathBuf::from("reports/gl.json"));
+
+        assert!(parse_report_target("gitlab").is_err());
+        assert!(parse_report_target("json=").is_err());
+        assert!(parse_report_target("yaml=out.yaml").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("50MB").unwrap(), 50 * 1024 * 1024);
+        assert_eq!(parse_size("2g").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("10 KB").unwrap(), 10 * 1024);
+
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("5TB").is_err());
+    }
 }
@@ -0,0 +1,122 @@
+use crate::git;
+use adf_guardian::engine::Violation;
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where GitHub, GitLab and Azure Repos look for the file, in their order.
+const LOCATIONS: [&str; 4] = [
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// The rules of a CODEOWNERS file: a gitignore-style pattern and its owners per line, the
+/// last matching line winning. A line without owners leaves its files unowned.
+pub struct CodeOwners {
+    repo_root: PathBuf,
+    rules: Vec<(GlobSet, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// The CODEOWNERS file of the git repository `project_path` is in, if it has one.
+    pub fn discover(project_path: &Path) -> Result<Option<Self>> {
+        let repo_root = git::top_level(project_path)?;
+        let Some(path) = LOCATIONS
+            .iter()
+            .map(|location| repo_root.join(location))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        let source =
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        Ok(Some(CodeOwners::parse(&source, repo_root)))
+    }
+
+    /// Parses the file; lines with an invalid pattern are skipped.
+    pub fn parse(source: &str, repo_root: PathBuf) -> Self {
+        let rules = source
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                let pattern = words.next().filter(|word| !word.starts_with('#'))?;
+                let owners = words
+                    .take_while(|word| !word.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                match globs(pattern) {
+                    Ok(globs) => Some((globs, owners)),
+                    Err(e) => {
+                        tracing::warn!("Skipping CODEOWNERS pattern '{}': {}", pattern, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        CodeOwners { repo_root, rules }
+    }
+
+    /// The owners of `file`, relative to the repository root.
+    pub fn owners_of(&self, file: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(globs, _)| globs.is_match(file))
+            .map_or(&[], |(_, owners)| owners)
+    }
+
+    /// Sets the `code_owners` of each of `violations`.
+    pub fn assign<'a>(&self, violations: impl IntoIterator<Item = &'a mut Violation>) {
+        for violation in violations {
+            let file = git::repo_relative(&violation.file, &self.repo_root);
+            violation.code_owners = self.owners_of(&file).to_vec();
+        }
+    }
+}
+
+/// Globs of the files a pattern covers: a leading `/` or an inner `/` anchors it at the
+/// root, otherwise it matches at any depth, and a folder covers everything in it.
+fn globs(pattern: &str) -> Result<GlobSet, globset::Error> {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    let pattern = if anchored {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+    let mut globs = GlobSetBuilder::new();
+    for glob in [pattern.clone(), format!("{}/**", pattern)] {
+        globs.add(GlobBuilder::new(&glob).literal_separator(true).build()?);
+    }
+    globs.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_owners() {
+        let code_owners = CodeOwners::parse(
+            r#"
+# Default owners
+*                       @org/data-platform
+/pipeline/              @org/ingestion @alice
+pipeline/sales_*.json   @org/sales # revenue pipelines
+*.md
+linkedService           @org/security
+"#,
+            PathBuf::from("/repo"),
+        );
+        let owners = |file: &str| code_owners.owners_of(file).join(" ");
+        assert_eq!(owners("factory/dataset/ds_a.json"), "@org/data-platform");
+        assert_eq!(owners("pipeline/pl_a.json"), "@org/ingestion @alice");
+        assert_eq!(owners("pipeline/sales_daily.json"), "@org/sales");
+        assert_eq!(owners("factory/pipeline/pl_a.json"), "@org/data-platform");
+        assert_eq!(owners("README.md"), "");
+        assert_eq!(owners("factory/linkedService/ls_sql.json"), "@org/security");
+    }
+}
@@ -1,25 +1,371 @@
-use anyhow::{Context, Result};
+use crate::packs;
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value as YamlValue};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
-use std::fs::File;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Guard params naming a file (or a URL), by guard. Relative paths are resolved from the
+/// folder of the config file.
+pub const FILE_PARAMS: [(&str, &str); 2] = [("ExternalList", "source"), ("Opa", "policy")];
+
+/// Whether a file param is fetched rather than read from disk.
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("https://") || path.starts_with("http://")
+}
+
+/// The values of the [`FILE_PARAMS`] of `rules`, paths or URLs.
+pub fn file_params<'a>(rules: impl IntoIterator<Item = &'a Rule>) -> impl Iterator<Item = &'a str> {
+    rules
+        .into_iter()
+        .flat_map(|rule| rule.when.iter().chain([&rule.validate]))
+        .filter_map(|validation| {
+            let (_, key) = FILE_PARAMS
+                .iter()
+                .find(|(guard, _)| *guard == validation.guard)?;
+            let cases = validation.params_by.iter().flat_map(|s| s.cases.values());
+            Some(
+                [&validation.params]
+                    .into_iter()
+                    .chain(cases)
+                    .filter_map(move |params| params.get(*key).and_then(|v| v.as_str())),
+            )
+        })
+        .flatten()
+}
+
+/// The files the [`FILE_PARAMS`] of `rules` name, with the files under those that are
+/// folders, sorted. URLs are left out.
+pub fn param_files<'a>(rules: impl IntoIterator<Item = &'a Rule>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = file_params(rules)
+        .filter(|path| !is_url(path))
+        .flat_map(|path| {
+            WalkDir::new(path)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.into_path())
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// A rules file: its own rules, the packs it extends, project checks and policies.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
+    /// Rule packs whose rules come before the config's: built-in ones like `adf:copy`, or
+    /// ones published to a registry like `oci://ghcr.io/contoso/adf-rules:1.2.0`.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub extends: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Validation blocks shared by rules, by id. A `when` or `validate` of `{ use: <id> }`
+    /// stands for the definition; the other keys of the block override its keys.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub definitions: BTreeMap<String, YamlValue>,
+    #[serde(default)]
+    pub project: ProjectChecks,
+    /// Assets that must exist in the project, e.g. the Key Vault linked service.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require: Vec<Requirement>,
+    #[serde(default)]
+    pub policy: Policy,
+    #[serde(default)]
+    pub strict: StrictChecks,
+    #[serde(default)]
+    pub limits: Limits,
+    /// Violations accepted for a while, e.g. on a legacy pipeline being reworked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressions: Vec<Suppression>,
+    /// Teams owning the assets, so violations can be routed to them. The first match wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<Owner>,
+    /// Which folders of a file tell its asset type.
+    #[serde(default)]
+    pub folder_matching: FolderMatching,
+    /// Ways to recognise asset types besides their folder, by type: globs of their paths or
+    /// JSONPaths their documents match, e.g. `pipeline: ["**/pipelines/**"]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub asset_mapping: BTreeMap<String, Vec<String>>,
+    /// Globs of the files to scan, relative to the project path; every JSON file when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Globs of the files and folders not to scan, e.g. `**/ARMTemplate*/**`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<Notification>,
+    /// Factories of a monorepo by name, each scanned and reported on its own.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<String, ProjectConfig>,
+    /// Where the config was loaded from; violations of rules that can't be compiled point at it.
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// SHA-256 of the config file, so reports can tell which rule set produced them.
+    #[serde(skip)]
+    pub hash: String,
+}
+
+/// Checks that run across all assets after the per-file pass.
+/// Each check is enabled by giving it a severity.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ProjectChecks {
+    pub unreferenced_pipelines: Option<Severity>,
+    pub duplicate_names: Option<Severity>,
+}
+
+impl ProjectChecks {
+    pub fn any_enabled(&self) -> bool {
+        self.unreferenced_pipelines.is_some() || self.duplicate_names.is_some()
+    }
+}
+
+/// Assets of a type that must exist, reported with `id` when fewer than `min` are found.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Requirement {
+    pub id: String,
+    /// Asset type, e.g. `linkedService`.
+    pub asset: String,
+    /// Name of the required asset; any asset of the type counts when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default = "one")]
+    pub min: usize,
+    /// Checks the requirement for each asset of a type instead of once for the project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per: Option<RequirementScope>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help_url: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+/// The assets a requirement is checked for; only the required assets that reference one
+/// count for it, e.g. the triggers of a pipeline.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RequirementScope {
+    pub asset: String,
+    /// Only the assets with this annotation, e.g. `scheduled`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+}
+
+fn one() -> usize {
+    1
+}
+
+/// A list, or a single value standing for a list of one.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Turns problems that keep files or rules from being checked into violations, instead
+/// of only logging them. Each is enabled by giving it a severity; `--strict` enables both.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct StrictChecks {
+    /// Files that can't be read or aren't valid JSON.
+    pub unreadable_files: Option<Severity>,
+    /// Rules with an invalid JSONPath or an unknown guard.
+    pub invalid_rules: Option<Severity>,
+}
+
+/// Bounds on the work of rules, so a slow rule can't hold up a scan.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Limits {
+    /// Total time a rule may spend on the files of a scan, e.g. `30s`. A rule that used it up
+    /// is skipped on the files left and reported as a diagnostic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule_timeout: Option<String>,
+}
+
+/// A team and the assets it owns, by path or by annotation.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Owner {
+    pub team: String,
+    /// Globs of the files, relative to the project path, e.g. `pipeline/sales_*.json`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+    /// Labels of `properties.annotations`, e.g. `team:sales`, compared ignoring case.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<String>,
+}
+
+/// Hides the violations of a rule on some files, or on one node, from the counts and the
+/// reports; the JSON report still lists them as `suppressed`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Suppression {
+    /// Id of the rule.
+    pub rule: String,
+    /// Glob of the files, relative to the project path; every file when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// JSONPath of the failing node as reported, e.g. `$.properties.activities[2].policy`;
+    /// every node when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Who accepted the violations, for the `suppressions` report.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_by: Option<String>,
+    /// Last day the suppression applies, e.g. `2026-12-31`. After it, the violations are
+    /// reported again and the expired suppression fails the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+}
+
+/// A factory in a subfolder of the project path, with its own rule overrides.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ProjectConfig {
+    /// Folder of the factory, relative to the project path.
+    pub path: PathBuf,
+    /// Rules for this factory only; one with the id of a shared rule replaces it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub rules: Vec<Rule>,
+    /// Ids of shared rules that don't apply to this factory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<String>,
+    /// Severities of shared rules for this factory, by rule id.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub severity: HashMap<String, Severity>,
+}
+
+/// A webhook that gets a summary of the run, e.g. to alert the owning team of a nightly scan.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Notification {
+    /// `${NAME}` is replaced by the environment variable, to keep the secret out of the file.
+    pub url: String,
+    /// Payload shape; guessed from the URL when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<NotificationKind>,
+    #[serde(default)]
+    pub on: NotifyOn,
+    /// Tera template of the message, rendered with the model of the JSON report.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    Slack,
+    Teams,
+    /// The message and the summary of the JSON report, as JSON.
+    Generic,
+}
+
+/// Which runs a notification is sent for.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOn {
+    #[default]
+    Errors,
+    /// Errors or warnings.
+    Violations,
+    Always,
+}
+
+/// Factory-wide policies, expanded into regular rules when the config is loaded.
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Policy {
+    pub linked_services: Option<ConnectorPolicy>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ConnectorPolicy {
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub require_integration_runtime: Vec<String>,
+}
+
+/// A check on one JSON node (or set of nodes) of the assets in the `asset` folders.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Rule {
     pub id: String,
     pub asset: AssetMatcher,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help_url: Option<String>,
     #[serde(default)]
     pub severity: Severity,
+    /// A date (`2026-07-01`) or adf-guardian release (`v0.4.0`) from which a `Warning` rule
+    /// reports errors, so a new policy gets a grace period.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promote: Option<String>,
+    /// Free-form labels for grouping rules, e.g. `security`; shown by `list-rules`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "RuleScope::is_file")]
+    pub scope: RuleScope,
+    /// `false` leaves the actual value out of the violations, e.g. for rules on secrets.
+    #[serde(default = "yes", skip_serializing_if = "is_true")]
+    pub report_value: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub when: Option<Validation>,
     pub validate: Validation,
 }
 
+/// What a rule's JSONPaths query.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleScope {
+    /// Each document of the `asset` types on its own.
+    #[default]
+    File,
+    /// One document with every asset of the `asset` types, by type and name, e.g.
+    /// `$.trigger.tr_daily`; evaluated once after the files are scanned.
+    Project,
+}
+
+impl RuleScope {
+    fn is_file(&self) -> bool {
+        *self == RuleScope::File
+    }
+}
+
+/// Which folders of a file are compared with the `asset` of rules.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FolderMatching {
+    /// Any folder below the project path, so `pipeline/ingest/pl_x.json` is a pipeline.
+    #[default]
+    Recursive,
+    /// Only the folder the file is directly in.
+    Parent,
+}
+
+/// The asset folder (e.g. `pipeline`) or folders a rule applies to, compared ignoring case.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
 pub enum AssetMatcher {
@@ -27,13 +373,32 @@ pub enum AssetMatcher {
     List(Vec<String>),
 }
 
-#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
 pub enum Severity {
     #[default]
     Error,
     Warning,
 }
 
+impl AssetMatcher {
+    pub fn names(&self) -> &[String] {
+        match self {
+            AssetMatcher::Single(asset) => std::slice::from_ref(asset),
+            AssetMatcher::List(list) => list,
+        }
+    }
+}
+
+impl fmt::Display for AssetMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetMatcher::Single(s) => write!(f, "{}", s),
+            AssetMatcher::List(list) => write!(f, "{}", list.join(", ")),
+        }
+    }
+}
+
 impl fmt::Display for Severity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -43,21 +408,327 @@ impl fmt::Display for Severity {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+/// A guard applied to the nodes selected by the `target` JSONPath.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Validation {
     pub target: String,
     pub guard: String,
     pub params: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params_by: Option<ParamsSelector>,
+    /// What a `validate` whose `target` selects nothing reports; ignored in `when`.
+    #[serde(default, skip_serializing_if = "OnMissing::is_pass")]
+    pub on_missing: OnMissing,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnMissing {
+    /// Nothing to check, so nothing to report.
+    #[default]
+    Pass,
+    /// A violation with the severity of the rule.
+    Fail,
+    /// A warning, whatever the severity of the rule.
+    Warn,
+}
+
+impl OnMissing {
+    fn is_pass(&self) -> bool {
+        *self == OnMissing::Pass
+    }
+}
+
+/// Picks the guard params from a value read elsewhere in the document,
+/// e.g. a different threshold per `sla:*` annotation.
+/// The first node selected by `target` that matches a case wins; `params` is the fallback.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ParamsSelector {
+    pub target: String,
+    pub cases: HashMap<String, serde_json::Value>,
 }
 
 impl Config {
+    /// Reads a config file, with its packs and policies expanded into rules.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(path.as_ref())
+        let source = fs::read(path.as_ref())
             .with_context(|| format!("Failed to open config file: {:?}", path.as_ref()))?;
 
-        let config: Config =
-            serde_yaml::from_reader(file).context("Failed to parse configuration file")?;
+        let mut config = Config::parse(&source, path.as_ref().parent())?;
+        config.path = path.as_ref().to_path_buf();
+        Ok(config)
+    }
+
+    /// Parses a config that isn't on disk, with its packs and policies expanded into rules.
+    /// Packs from a registry can't be used, as they are kept next to the config file.
+    pub fn from_yaml(source: &[u8]) -> Result<Self> {
+        Config::parse(source, None)
+    }
+
+    /// Parses a config whose `oci://` packs are kept in `config_dir` (see `packs::load`).
+    fn parse(source: &[u8], config_dir: Option<&Path>) -> Result<Self> {
+        let mut document: YamlValue =
+            serde_yaml::from_slice(source).context("Failed to parse configuration file")?;
+        // Configs without `use` are parsed from the source, so errors keep their line.
+        let mut config: Config = if expand_definitions(&mut document)? {
+            serde_yaml::from_value(document)
+        } else {
+            serde_yaml::from_slice(source)
+        }
+        .context("Failed to parse configuration file")?;
+        config.hash = format!("{:x}", Sha256::digest(source));
+
+        check_rule_ids(&config.rules, "the config")?;
+        for (name, project) in &config.projects {
+            check_rule_ids(&project.rules, &format!("project '{}'", name))?;
+        }
+
+        config.resolve_packs(config_dir)?;
+        config.expand_policies()?;
+        if let Some(config_dir) = config_dir {
+            config.resolve_file_params(config_dir);
+        }
 
         Ok(config)
     }
+
+    /// The config the factory `project` of `projects` is scanned with: the shared rules
+    /// with its own rules merged in, its disabled rules removed and its severities applied.
+    pub fn for_project(&self, project: &ProjectConfig) -> Config {
+        let mut config = self.clone();
+        config.projects.clear();
+        merge_rules(&mut config.rules, project.rules.iter().cloned());
+        config
+            .rules
+            .retain(|rule| !project.disable.contains(&rule.id));
+        for rule in &mut config.rules {
+            if let Some(severity) = project.severity.get(&rule.id) {
+                rule.severity = *severity;
+            }
+        }
+        config
+    }
+
+    /// Prepends the rules of every pack listed in `extends`.
+    /// A rule defined in the config with the same id as a pack rule replaces it.
+    fn resolve_packs(&mut self, config_dir: Option<&Path>) -> Result<()> {
+        let mut rules = Vec::new();
+        for name in &self.extends {
+            rules.extend(packs::load(name, config_dir)?);
+        }
+        merge_rules(&mut rules, std::mem::take(&mut self.rules));
+        self.rules = rules;
+        Ok(())
+    }
+
+    /// Makes the relative paths of the [`FILE_PARAMS`] of rules, including those of
+    /// `projects`, relative to `config_dir`, so the files can sit next to the config file.
+    fn resolve_file_params(&mut self, config_dir: &Path) {
+        let rules = self
+            .rules
+            .iter_mut()
+            .chain(self.projects.values_mut().flat_map(|p| p.rules.iter_mut()));
+        for rule in rules {
+            for validation in rule.when.iter_mut().chain([&mut rule.validate]) {
+                let Some(&(_, key)) = FILE_PARAMS
+                    .iter()
+                    .find(|(guard, _)| *guard == validation.guard)
+                else {
+                    continue;
+                };
+                let cases = validation
+                    .params_by
+                    .iter_mut()
+                    .flat_map(|selector| selector.cases.values_mut());
+                for params in [&mut validation.params].into_iter().chain(cases) {
+                    if let Some(serde_json::Value::String(path)) = params.get_mut(key)
+                        && !is_url(path)
+                        && Path::new(path.as_str()).is_relative()
+                    {
+                        *path = config_dir.join(&*path).to_string_lossy().into_owned();
+                    }
+                }
+            }
+        }
+    }
+
+    fn expand_policies(&mut self) -> Result<()> {
+        if let Some(policy) = &self.policy.linked_services {
+            self.rules.push(Rule {
+                id: "policy-linked-service-types".to_string(),
+                asset: AssetMatcher::Single("linkedService".to_string()),
+                description: Some(
+                    "Linked service connector type is not approved for this factory".to_string(),
+                ),
+                help_url: None,
+                severity: policy.severity,
+                promote: None,
+                tags: vec![],
+                scope: RuleScope::File,
+                report_value: true,
+                when: None,
+                validate: Validation {
+                    target: "$".to_string(),
+                    guard: "ConnectorPolicy".to_string(),
+                    params: serde_json::to_value(policy)
+                        .context("Failed to expand the linked service policy")?,
+                    params_by: None,
+                    on_missing: OnMissing::Pass,
+                },
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Two rules with one id would be reported and filtered as one, so that is a config error.
+/// A rule with the id of a pack rule is an override, not a duplicate.
+fn check_rule_ids(rules: &[Rule], place: &str) -> Result<()> {
+    let mut seen = HashSet::new();
+    for rule in rules {
+        if !seen.insert(&rule.id) {
+            bail!("Duplicate rule id '{}' in {}", rule.id, place);
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the `use` blocks of the rules in `document`, including those of `projects`,
+/// with their definitions. Returns whether there were any.
+fn expand_definitions(document: &mut YamlValue) -> Result<bool> {
+    let definitions = match document.get("definitions") {
+        Some(YamlValue::Mapping(definitions)) => definitions.clone(),
+        _ => Mapping::new(),
+    };
+    let mut rule_lists: Vec<&mut YamlValue> = Vec::new();
+    let Some(root) = document.as_mapping_mut() else {
+        return Ok(false);
+    };
+    for (key, value) in root.iter_mut() {
+        match key.as_str() {
+            Some("rules") => rule_lists.push(value),
+            Some("projects") => {
+                if let Some(projects) = value.as_mapping_mut() {
+                    rule_lists.extend(projects.values_mut().filter_map(|p| p.get_mut("rules")));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut expanded = false;
+    for rule in rule_lists
+        .into_iter()
+        .filter_map(|rules| rules.as_sequence_mut())
+        .flatten()
+    {
+        let id = rule
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        for key in ["when", "validate"] {
+            if let Some(block) = rule.get_mut(key)
+                && block.get("use").is_some()
+            {
+                *block = resolve_use(block, &definitions, &mut Vec::new())
+                    .with_context(|| format!("Rule '{}'", id))?;
+                expanded = true;
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// The definition a `use` block names, merged with the block's other keys. Definitions may
+/// `use` other definitions; `stack` holds the ids being resolved, to catch cycles.
+fn resolve_use(
+    block: &YamlValue,
+    definitions: &Mapping,
+    stack: &mut Vec<String>,
+) -> Result<YamlValue> {
+    let Some(id) = block.get("use") else {
+        return Ok(block.clone());
+    };
+    let Some(id) = id.as_str() else {
+        bail!("'use' must be the id of a definition");
+    };
+    if stack.iter().any(|seen| seen == id) {
+        bail!("Definition '{}' uses itself", id);
+    }
+    let Some(definition) = definitions.get(id) else {
+        bail!("Unknown definition '{}' in 'use'", id);
+    };
+    stack.push(id.to_string());
+    let mut resolved = resolve_use(definition, definitions, stack)?;
+    stack.pop();
+
+    let (Some(merged), Some(overrides)) = (resolved.as_mapping_mut(), block.as_mapping()) else {
+        bail!("Definition '{}' must be a mapping", id);
+    };
+    for (key, value) in overrides {
+        if key.as_str() != Some("use") {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    Ok(resolved)
+}
+
+/// Adds `overrides` to `rules`, each replacing the rule with the same id if there is one.
+fn merge_rules(rules: &mut Vec<Rule>, overrides: impl IntoIterator<Item = Rule>) {
+    for rule in overrides {
+        match rules.iter_mut().find(|r| r.id == rule.id) {
+            Some(existing) => *existing = rule,
+            None => rules.push(rule),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions_and_duplicate_ids() {
+        let config = Config::from_yaml(
+            br#"
+definitions:
+  snake-case: { target: "$.name", guard: PatternMatch, params: { regex: "^[a-z_]+$" } }
+  activity-names: { use: snake-case, target: "$.properties.activities[*].name" }
+rules:
+  - id: pipeline-naming
+    asset: pipeline
+    validate: { use: snake-case }
+  - id: activity-naming
+    asset: pipeline
+    when: { use: snake-case }
+    validate: { use: activity-names }
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.rules[0].validate.target, "$.name");
+        assert_eq!(config.rules[0].validate.guard, "PatternMatch");
+        assert_eq!(config.rules[1].when.as_ref().unwrap().target, "$.name");
+        assert_eq!(
+            config.rules[1].validate.target,
+            "$.properties.activities[*].name"
+        );
+        assert_eq!(config.rules[1].validate.params["regex"], "^[a-z_]+$");
+
+        let error =
+            |source: &str| format!("{:#}", Config::from_yaml(source.as_bytes()).unwrap_err());
+        assert!(
+            error("rules: [{ id: a, asset: pipeline, validate: { use: missing } }]")
+                .contains("Unknown definition 'missing'")
+        );
+        assert!(
+            error("definitions: { x: { use: x } }\nrules: [{ id: a, asset: pipeline, validate: { use: x } }]")
+                .contains("uses itself")
+        );
+        let rule = "{ id: a, asset: pipeline, validate: { target: $, guard: Exists, params: {} } }";
+        assert!(
+            error(&format!("rules: [{}, {}]", rule, rule))
+                .contains("Duplicate rule id 'a' in the config")
+        );
+    }
 }
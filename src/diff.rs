@@ -0,0 +1,161 @@
+use crate::engine::{FileResult, Violation};
+use crate::reporter::display_path;
+use anyhow::{Context, Result, bail};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// How the violations of two scans relate. Violations are matched on rule, file and
+/// actual value, not on line numbers, which move whenever the file is edited.
+#[derive(Debug, Serialize)]
+pub struct Diff<'a> {
+    pub new: Vec<&'a Violation>,
+    pub fixed: Vec<&'a Violation>,
+    pub persisting: Vec<&'a Violation>,
+}
+
+type Key = (String, String, Option<String>);
+
+fn key(v: &Violation) -> Key {
+    (
+        v.rule_id.clone(),
+        display_path(&v.file),
+        v.actual_value.clone(),
+    )
+}
+
+pub fn compare<'a>(old: &'a [Violation], new: &'a [Violation]) -> Diff<'a> {
+    // Multisets, so two identical violations in one file are matched one to one.
+    let unmatched = |from: &'a [Violation], against: &[Violation]| {
+        let mut counts: HashMap<Key, usize> = HashMap::new();
+        for v in against {
+            *counts.entry(key(v)).or_default() += 1;
+        }
+        from.iter()
+            .partition::<Vec<&Violation>, _>(|v| match counts.get_mut(&key(v)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            })
+    };
+
+    let (added, persisting) = unmatched(new, old);
+    let (fixed, _) = unmatched(old, new);
+    Diff {
+        new: added,
+        fixed,
+        persisting,
+    }
+}
+
+/// Reads the violations of a JSON report, in the v2 envelope or the `--json-compat` shape.
+pub fn load_report(path: &Path) -> Result<Vec<Violation>> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("Failed to read report {:?}", path))?;
+    parse_report(&source).with_context(|| format!("Failed to parse report {:?}", path))
+}
+
+fn parse_report(source: &str) -> Result<Vec<Violation>> {
+    let json: Value = serde_json::from_str(source)?;
+    if json.is_array() {
+        return Ok(Vec::deserialize(json)?);
+    }
+    if json.get("schema_version").is_none() {
+        bail!("not an adf-guardian JSON report");
+    }
+    let files = Vec::<FileResult>::deserialize(&json["files"])?;
+    Ok(files.into_iter().flat_map(|f| f.violations).collect())
+}
+
+pub fn write_human(out: &mut dyn Write, diff: &Diff) -> io::Result<()> {
+    let sections = [
+        ("New violations", "+", &diff.new, Color::BrightRed),
+        ("Fixed violations", "-", &diff.fixed, Color::Green),
+    ];
+    for (title, marker, violations, color) in sections {
+        writeln!(out, "{} ({})", title.bold(), violations.len())?;
+        for v in violations.iter() {
+            let value = v
+                .actual_value
+                .as_deref()
+                .map(|value| format!(" {}", value.dimmed()))
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "  {} [{}] {}{}",
+                marker.color(color),
+                v.rule_id.color(color),
+                display_path(&v.file),
+                value
+            )?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(
+        out,
+        "{} {} violation(s)",
+        "Persisting:".bold(),
+        diff.persisting.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+
+    fn violation(rule_id: &str, file: &str, value: &str, line: usize) -> Violation {
+        Violation {
+            rule_id: rule_id.to_string(),
+            file: file.to_string(),
+            severity: Severity::Error,
+            actual_value: Some(value.to_string()),
+            line: Some(line),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let old = vec![
+            violation("naming", "./pipeline/a.json", "\"a\"", 2),
+            violation("retries", "./pipeline/a.json", "10", 8),
+            violation("retries", "./pipeline/a.json", "10", 12),
+        ];
+        let new = vec![
+            // Moved down a line, still the same violation.
+            violation("naming", "pipeline/a.json", "\"a\"", 3),
+            violation("retries", "pipeline/a.json", "10", 9),
+            violation("https", "pipeline/b.json", "\"web\"", 4),
+        ];
+
+        let diff = compare(&old, &new);
+        assert_eq!(diff.new.len(), 1);
+        assert_eq!(diff.new[0].rule_id, "https");
+        assert_eq!(diff.fixed.len(), 1);
+        assert_eq!(diff.fixed[0].rule_id, "retries");
+        assert_eq!(diff.persisting.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let compat = r#"[{"rule_id": "naming", "file": "a.json", "message": "m", "severity": "Warning", "actual_value": null}]"#;
+        assert_eq!(parse_report(compat).unwrap()[0].severity, Severity::Warning);
+
+        let envelope = r#"{"schema_version": 2, "files": [
+            {"file": "a.json", "violations": [{"rule_id": "naming", "file": "a.json", "message": "m", "severity": "Error", "actual_value": "\"x\"", "line": 3}]},
+            {"file": "b.json", "violations": []}
+        ]}"#;
+        let violations = parse_report(envelope).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].line, Some(3));
+
+        assert!(parse_report(r#"{"error": "Config file not found"}"#).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::config::{Config, Rule, Validation};
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageFormat {
+    Markdown,
+    Html,
+}
+
+impl PageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            PageFormat::Markdown => "md",
+            PageFormat::Html => "html",
+        }
+    }
+}
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Roboto, sans-serif; margin: 2rem auto; max-width: 60rem; color: #1f2328; }
+table { border-collapse: collapse; }
+td, th { text-align: left; padding: 0.4rem 0.75rem; border-top: 1px solid #d0d7de; vertical-align: top; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 0.75rem; overflow-x: auto; }
+code { font-size: 0.85rem; }
+"#;
+
+/// Writes an index and one page per configured rule into `dir`, for publishing the
+/// policy to a wiki. Returns the number of rule pages written.
+pub fn generate(config: &Config, dir: &Path, format: PageFormat) -> Result<usize> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create documentation folder {:?}", dir))?;
+
+    let write_page = |name: &str, write: &dyn Fn(&mut dyn Write) -> io::Result<()>| {
+        let path = dir.join(format!("{}.{}", name, format.extension()));
+        let file =
+            File::create(&path).with_context(|| format!("Failed to create page {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)
+            .and_then(|_| writer.flush())
+            .with_context(|| format!("Failed to write page {:?}", path))
+    };
+
+    write_page("index", &|out| match format {
+        PageFormat::Markdown => write_markdown_index(out, &config.rules),
+        PageFormat::Html => write_html_index(out, &config.rules),
+    })?;
+    for rule in &config.rules {
+        write_page(&page_name(&rule.id), &|out| match format {
+            PageFormat::Markdown => write_markdown_page(out, rule),
+            PageFormat::Html => write_html_page(out, rule),
+        })?;
+    }
+    Ok(config.rules.len())
+}
+
+/// Rule ids are free-form, so keep only characters that are safe in file names and URLs.
+fn page_name(rule_id: &str) -> String {
+    rule_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Label and value rows shown at the top of a rule page.
+fn metadata(rule: &Rule) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Asset", rule.asset.to_string()),
+        ("Severity", rule.severity.to_string()),
+        ("Guard", rule.validate.guard.clone()),
+    ];
+    if !rule.tags.is_empty() {
+        rows.push(("Tags", rule.tags.join(", ")));
+    }
+    rows
+}
+
+fn pretty_json(value: &serde_json::Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn write_markdown_index(out: &mut dyn Write, rules: &[Rule]) -> io::Result<()> {
+    writeln!(out, "# Rules")?;
+    writeln!(out)?;
+    writeln!(out, "| Rule | Asset | Severity | Description |")?;
+    writeln!(out, "|------|-------|----------|-------------|")?;
+    for rule in rules {
+        writeln!(
+            out,
+            "| [{}]({}.md) | {} | {} | {} |",
+            escape_cell(&rule.id),
+            page_name(&rule.id),
+            rule.asset,
+            rule.severity,
+            escape_cell(rule.description.as_deref().unwrap_or_default())
+        )?;
+    }
+    Ok(())
+}
+
+fn write_markdown_page(out: &mut dyn Write, rule: &Rule) -> io::Result<()> {
+    writeln!(out, "# {}", rule.id)?;
+    writeln!(out)?;
+    if let Some(description) = &rule.description {
+        writeln!(out, "{}", description)?;
+        writeln!(out)?;
+    }
+
+    writeln!(out, "| | |")?;
+    writeln!(out, "|---|---|")?;
+    for (label, value) in metadata(rule) {
+        writeln!(out, "| **{}** | {} |", label, escape_cell(&value))?;
+    }
+    if let Some(url) = &rule.help_url {
+        writeln!(out, "| **More information** | <{}> |", url)?;
+    }
+
+    if let Some(when) = &rule.when {
+        writeln!(out)?;
+        writeln!(out, "## Applies when")?;
+        write_markdown_validation(out, when)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "## Validation")?;
+    write_markdown_validation(out, &rule.validate)
+}
+
+fn write_markdown_validation(out: &mut dyn Write, validation: &Validation) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "Guard `{}` on:", validation.guard)?;
+    writeln!(out)?;
+    writeln!(out, "```")?;
+    writeln!(out, "{}", validation.target)?;
+    writeln!(out, "```")?;
+    writeln!(out)?;
+    writeln!(out, "Parameters:")?;
+    writeln!(out)?;
+    writeln!(out, "```json")?;
+    writeln!(out, "{}", pretty_json(&validation.params))?;
+    writeln!(out, "```")?;
+
+    if let Some(selector) = &validation.params_by {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Parameters by the value of `{}` (the parameters above are the fallback):",
+            selector.target
+        )?;
+        let mut cases: Vec<_> = selector.cases.iter().collect();
+        cases.sort_by_key(|(case, _)| *case);
+        for (case, params) in cases {
+            writeln!(out)?;
+            writeln!(out, "- `{}`:", case)?;
+            writeln!(out)?;
+            writeln!(out, "  ```json")?;
+            for line in pretty_json(params).lines() {
+                writeln!(out, "  {}", line)?;
+            }
+            writeln!(out, "  ```")?;
+        }
+    }
+    Ok(())
+}
+
+fn write_html_start(out: &mut dyn Write, title: &str) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, r#"<html lang="en"><head><meta charset="utf-8">"#)?;
+    writeln!(out, "<title>{}</title>", escape_html(title))?;
+    writeln!(out, "<style>{}</style></head><body>", STYLE)?;
+    writeln!(out, "<h1>{}</h1>", escape_html(title))
+}
+
+fn write_html_index(out: &mut dyn Write, rules: &[Rule]) -> io::Result<()> {
+    write_html_start(out, "Rules")?;
+    writeln!(
+        out,
+        "<table><tr><th>Rule</th><th>Asset</th><th>Severity</th><th>Description</th></tr>"
+    )?;
+    for rule in rules {
+        writeln!(
+            out,
+            r#"<tr><td><a href="{}.html">{}</a></td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            page_name(&rule.id),
+            escape_html(&rule.id),
+            escape_html(&rule.asset.to_string()),
+            rule.severity,
+            escape_html(rule.description.as_deref().unwrap_or_default())
+        )?;
+    }
+    writeln!(out, "</table></body></html>")
+}
+
+fn write_html_page(out: &mut dyn Write, rule: &Rule) -> io::Result<()> {
+    write_html_start(out, &rule.id)?;
+    if let Some(description) = &rule.description {
+        writeln!(out, "<p>{}</p>", escape_html(description))?;
+    }
+
+    writeln!(out, "<table>")?;
+    for (label, value) in metadata(rule) {
+        writeln!(
+            out,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            label,
+            escape_html(&value)
+        )?;
+    }
+    if let Some(url) = &rule.help_url {
+        writeln!(
+            out,
+            r#"<tr><th>More information</th><td><a href="{0}">{0}</a></td></tr>"#,
+            escape_html(url)
+        )?;
+    }
+    writeln!(out, "</table>")?;
+
+    if let Some(when) = &rule.when {
+        writeln!(out, "<h2>Applies when</h2>")?;
+        write_html_validation(out, when)?;
+    }
+    writeln!(out, "<h2>Validation</h2>")?;
+    write_html_validation(out, &rule.validate)?;
+    writeln!(out, "</body></html>")
+}
+
+fn write_html_validation(out: &mut dyn Write, validation: &Validation) -> io::Result<()> {
+    writeln!(
+        out,
+        "<p>Guard <code>{}</code> on:</p><pre><code>{}</code></pre>",
+        escape_html(&validation.guard),
+        escape_html(&validation.target)
+    )?;
+    writeln!(
+        out,
+        "<p>Parameters:</p><pre><code>{}</code></pre>",
+        escape_html(&pretty_json(&validation.params))
+    )?;
+
+    if let Some(selector) = &validation.params_by {
+        writeln!(
+            out,
+            "<p>Parameters by the value of <code>{}</code> (the parameters above are the fallback):</p><ul>",
+            escape_html(&selector.target)
+        )?;
+        let mut cases: Vec<_> = selector.cases.iter().collect();
+        cases.sort_by_key(|(case, _)| *case);
+        for (case, params) in cases {
+            writeln!(
+                out,
+                "<li><code>{}</code>:<pre><code>{}</code></pre></li>",
+                escape_html(case),
+                escape_html(&pretty_json(params))
+            )?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+    Ok(())
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_markdown() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming/pipelines"
+    asset: "pipeline"
+    description: "Names must start with pl_"
+    tags: ["naming"]
+    validate:
+      target: "$.name"
+      guard: "Regex"
+      params: { pattern: "^pl_" }
+"#,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("adf-guardian-docs-{}", std::process::id()));
+
+        let pages = generate(&config, &dir, PageFormat::Markdown).unwrap();
+        let index = fs::read_to_string(dir.join("index.md")).unwrap();
+        let page = fs::read_to_string(dir.join("naming_pipelines.md")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pages, 1);
+        assert!(index.contains(
+            "| [naming/pipelines](naming_pipelines.md) | pipeline | Error | Names must start with pl_ |"
+        ));
+        assert!(page.starts_with("# naming/pipelines\n\nNames must start with pl_\n"));
+        assert!(page.contains("| **Tags** | naming |"));
+        assert!(page.contains("Guard `Regex` on:\n\n```\n$.name\n```"));
+        assert!(page.contains("\"pattern\": \"^pl_\""));
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{azure, git};
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Most differing values listed per asset.
+const MAX_CHANGES: usize = 10;
+
+/// How an asset differs between Git and the deployed factory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// In Git but not deployed, e.g. not published yet.
+    OnlyInGit,
+    /// Deployed but not in Git, e.g. created in the portal.
+    OnlyInFactory,
+    /// In both, with different content.
+    Differs,
+    /// Different, and published after the last commit of the Git file: most likely edited
+    /// directly in the portal.
+    ModifiedInFactory,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Drift {
+    /// Path of the asset in the Git repository, e.g. `pipeline/pl_load.json`.
+    pub asset: String,
+    pub status: Status,
+    /// JSON pointers of the first values that differ.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<String>,
+}
+
+/// An asset document and its path relative to the factory root.
+pub struct Asset {
+    pub path: PathBuf,
+    pub document: Value,
+}
+
+impl Asset {
+    /// Matches the same asset on both sides: its folder and name, ignoring case like ADF.
+    fn key(&self) -> String {
+        let folder = self.path.parent().unwrap_or(Path::new(""));
+        let name = match self.document["name"].as_str() {
+            Some(name) => name.to_string(),
+            None => self
+                .path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        format!("{}/{}", display(folder), name).to_lowercase()
+    }
+}
+
+/// Compares the factory at `root` with the deployed one.
+pub fn detect(root: &Path, factory: &azure::Factory, token: &str) -> Result<Vec<Drift>> {
+    let local = local_assets(root)?;
+    let deployed: Vec<Asset> = azure::fetch(factory, token)?
+        .into_iter()
+        .map(|(path, document)| Asset { path, document })
+        .collect();
+
+    let mut drifts = compare(&local, &deployed);
+    let local_keys: HashMap<String, String> = local
+        .iter()
+        .map(|asset| (display(&asset.path), asset.key()))
+        .collect();
+    let published: HashMap<String, &Value> = deployed
+        .iter()
+        .map(|asset| (asset.key(), &asset.document))
+        .collect();
+    for drift in drifts.iter_mut().filter(|d| d.status == Status::Differs) {
+        let published_at = local_keys
+            .get(&drift.asset)
+            .and_then(|key| published.get(key))
+            .and_then(|d| d["properties"]["lastPublishTime"].as_str())
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        let committed_at = git::last_commit_date(&root.join(&drift.asset))
+            .ok()
+            .flatten()
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok());
+        if let (Some(published_at), Some(committed_at)) = (published_at, committed_at)
+            && published_at > committed_at
+        {
+            drift.status = Status::ModifiedInFactory;
+        }
+    }
+    Ok(drifts)
+}
+
+/// The assets under the asset folders of the factory at `root`.
+fn local_assets(root: &Path) -> Result<Vec<Asset>> {
+    let mut assets = Vec::new();
+    for folder in azure::asset_folders() {
+        for entry in WalkDir::new(root.join(folder)).sort_by_file_name() {
+            let Ok(entry) = entry else { continue };
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let source = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+            let document = serde_json::from_slice(&source)
+                .with_context(|| format!("Failed to parse {:?}", path))?;
+            assets.push(Asset {
+                path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+                document,
+            });
+        }
+    }
+    Ok(assets)
+}
+
+/// Assets missing on one side or with different content, sorted by path. Volatile fields
+/// that deployments change on their own (`id`, `etag`, `type`, `lastPublishTime`) are
+/// ignored.
+pub fn compare(local: &[Asset], deployed: &[Asset]) -> Vec<Drift> {
+    let deployed_by_key: HashMap<String, &Asset> =
+        deployed.iter().map(|asset| (asset.key(), asset)).collect();
+    let local_keys: BTreeSet<String> = local.iter().map(Asset::key).collect();
+
+    let mut drifts = Vec::new();
+    for asset in local {
+        let status_and_changes = match deployed_by_key.get(&asset.key()) {
+            None => Some((Status::OnlyInGit, vec![])),
+            Some(other) => {
+                let mut changes = Vec::new();
+                differences(
+                    &normalize(&asset.document),
+                    &normalize(&other.document),
+                    "",
+                    &mut changes,
+                );
+                (!changes.is_empty()).then_some((Status::Differs, changes))
+            }
+        };
+        if let Some((status, mut changes)) = status_and_changes {
+            changes.truncate(MAX_CHANGES);
+            drifts.push(Drift {
+                asset: display(&asset.path),
+                status,
+                changes,
+            });
+        }
+    }
+    for asset in deployed {
+        if !local_keys.contains(&asset.key()) {
+            drifts.push(Drift {
+                asset: display(&asset.path),
+                status: Status::OnlyInFactory,
+                changes: vec![],
+            });
+        }
+    }
+    drifts.sort_by(|a, b| a.asset.cmp(&b.asset));
+    drifts
+}
+
+fn normalize(document: &Value) -> Value {
+    let mut document = document.clone();
+    if let Some(map) = document.as_object_mut() {
+        for field in ["id", "etag", "type"] {
+            map.remove(field);
+        }
+    }
+    if let Some(properties) = document["properties"].as_object_mut() {
+        properties.remove("lastPublishTime");
+    }
+    document
+}
+
+/// Collects the JSON pointers under `pointer` where `a` and `b` differ.
+fn differences(a: &Value, b: &Value, pointer: &str, out: &mut Vec<String>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let child = format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => differences(a, b, &child, out),
+                    _ => out.push(child),
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                differences(a, b, &format!("{}/{}", pointer, i), out);
+            }
+        }
+        _ if a != b => out.push(if pointer.is_empty() { "/" } else { pointer }.to_string()),
+        _ => {}
+    }
+}
+
+fn display(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+pub fn write_human(out: &mut dyn Write, factory: &str, drifts: &[Drift]) -> io::Result<()> {
+    if drifts.is_empty() {
+        return writeln!(out, "No drift between Git and the factory '{}'", factory);
+    }
+    writeln!(
+        out,
+        "{} asset(s) differ between Git and the factory '{}':",
+        drifts.len(),
+        factory
+    )?;
+    for drift in drifts {
+        let status = match drift.status {
+            Status::OnlyInGit => "only in Git",
+            Status::OnlyInFactory => "only in factory",
+            Status::Differs => "differs",
+            Status::ModifiedInFactory => "modified in factory",
+        };
+        writeln!(out, "  {:<20} {}", status, drift.asset)?;
+        for change in &drift.changes {
+            writeln!(out, "  {:<20}   {}", "", change)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compare() {
+        let asset = |path: &str, document: Value| Asset {
+            path: PathBuf::from(path),
+            document,
+        };
+        let local = [
+            asset(
+                "pipeline/pl_a.json",
+                json!({ "name": "pl_a", "properties": { "activities": [{ "name": "copy", "timeout": "1:00:00" }] } }),
+            ),
+            asset(
+                "pipeline/pl_b.json",
+                json!({ "name": "pl_b", "properties": { "activities": [] } }),
+            ),
+            asset("dataset/ds_new.json", json!({ "name": "ds_new" })),
+        ];
+        let deployed = [
+            asset(
+                "pipeline/PL_A.json",
+                json!({
+                    "name": "PL_A",
+                    "etag": "\"0a00\"",
+                    "properties": { "activities": [{ "name": "copy", "timeout": "2:00:00" }] }
+                }),
+            ),
+            asset(
+                "pipeline/pl_b.json",
+                json!({
+                    "name": "pl_b",
+                    "id": "/subscriptions/s/resourceGroups/rg/providers/Microsoft.DataFactory/factories/f/pipelines/pl_b",
+                    "type": "Microsoft.DataFactory/factories/pipelines",
+                    "properties": { "activities": [], "lastPublishTime": "2026-01-01T00:00:00Z" }
+                }),
+            ),
+            asset("trigger/tr_portal.json", json!({ "name": "tr_portal" })),
+        ];
+
+        let drifts = compare(&local, &deployed);
+        let summary: Vec<(&str, Status, &[String])> = drifts
+            .iter()
+            .map(|d| (d.asset.as_str(), d.status, d.changes.as_slice()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("dataset/ds_new.json", Status::OnlyInGit, &[][..]),
+                (
+                    "pipeline/pl_a.json",
+                    Status::Differs,
+                    &[
+                        "/name".to_string(),
+                        "/properties/activities/0/timeout".to_string()
+                    ][..]
+                ),
+                ("trigger/tr_portal.json", Status::OnlyInFactory, &[][..]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use super::{CompiledRule, Violation, check_rule, locator};
+use crate::config::RuleScope;
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// An asset kept for the rules with `scope: project`.
+pub(super) struct Document {
+    /// The asset types of those rules it is of.
+    pub types: Vec<String>,
+    pub file: String,
+    pub source: String,
+    pub json: Value,
+}
+
+impl Document {
+    /// The `name` of the asset, or the name of its file without the extension.
+    fn name(&self) -> String {
+        match self.json.get("name").and_then(|v| v.as_str()) {
+            Some(name) => name.to_string(),
+            None => Path::new(&self.file)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// The document `scope: project` rules query: every kept asset, by type and name.
+pub(super) fn merge(documents: &[Document]) -> Value {
+    let mut merged = Map::new();
+    for document in documents {
+        for asset in &document.types {
+            if let Value::Object(assets) = merged
+                .entry(asset.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                assets.insert(document.name(), document.json.clone());
+            }
+        }
+    }
+    Value::Object(merged)
+}
+
+/// Evaluates the rules with `scope: project` once on the merged document. A violation on a
+/// node inside an asset is reported on the asset's file, at that node; others, such as a
+/// `Count` of `$.trigger`, on `config_file`.
+pub(super) fn check(
+    rules: &[CompiledRule],
+    documents: &[Document],
+    config_file: &str,
+) -> (Vec<Violation>, Vec<(usize, Duration, usize)>) {
+    let rules: Vec<&CompiledRule> = rules
+        .iter()
+        .filter(|compiled| compiled.rule.scope == RuleScope::Project)
+        .collect();
+    if rules.is_empty() {
+        return (vec![], vec![]);
+    }
+    let merged = merge(documents);
+
+    let mut timings = Vec::new();
+    let mut violations = Vec::new();
+    for compiled in rules {
+        let started = Instant::now();
+        let found = check_rule(compiled, &merged, Path::new(config_file));
+        timings.push((compiled.index, started.elapsed(), found.len()));
+        violations.extend(
+            found
+                .into_iter()
+                .map(|violation| place(violation, documents)),
+        );
+    }
+    (violations, timings)
+}
+
+/// Moves a violation found in the merged document to the asset its node is in.
+fn place(mut violation: Violation, documents: &[Document]) -> Violation {
+    let Some(pointer) = violation.pointer.clone() else {
+        return violation;
+    };
+    let mut segments = pointer.splitn(4, '/').skip(1);
+    let (Some(asset), Some(name)) = (segments.next(), segments.next()) else {
+        return violation;
+    };
+    let unescape = |segment: &str| segment.replace("~1", "/").replace("~0", "~");
+    let (asset, name) = (unescape(asset), unescape(name));
+    let Some(document) = documents
+        .iter()
+        .rev()
+        .find(|d| d.types.contains(&asset) && d.name() == name)
+    else {
+        return violation;
+    };
+
+    let inner = segments
+        .next()
+        .map(|rest| format!("/{}", rest))
+        .unwrap_or_default();
+    violation.file = document.file.clone();
+    if let Some(location) = Some(&inner)
+        .filter(|pointer| !pointer.is_empty())
+        .and_then(|pointer| locator::locate(&document.source, pointer))
+    {
+        violation.line = Some(location.line);
+        violation.column = Some(location.column);
+    }
+    violation.json_path = locator::json_path(&document.json, &inner);
+    violation.pointer = Some(inner);
+    violation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::engine::{GuardRegistry, compile};
+    use serde_json::json;
+
+    #[test]
+    fn test_project_scope() {
+        let config = Config::from_yaml(
+            br#"
+rules:
+  - id: max-triggers
+    asset: trigger
+    scope: project
+    validate: { target: "$.trigger", guard: Count, params: { max: 1 } }
+  - id: distinct-schedules
+    asset: trigger
+    scope: project
+    validate:
+      target: "$.trigger"
+      guard: Unique
+      params: { key: "$.properties.typeProperties.recurrence.schedule" }
+  - id: started
+    asset: trigger
+    scope: project
+    validate: { target: "$.trigger.*.properties.runtimeState", guard: AllowedValues, params: { values: [Started] } }
+"#,
+        )
+        .unwrap();
+        let guards = GuardRegistry::default();
+        let (rules, _) = compile(&config, &guards);
+
+        let trigger = |name: &str, state: &str| {
+            let json = json!({ "name": name, "properties": {
+                "runtimeState": state,
+                "typeProperties": { "recurrence": { "schedule": { "minutes": [0] } } }
+            } });
+            Document {
+                types: vec!["trigger".to_string()],
+                file: format!("trigger/{}.json", name),
+                source: serde_json::to_string_pretty(&json).unwrap(),
+                json,
+            }
+        };
+        let documents = [trigger("tr_a", "Started"), trigger("tr/b", "Stopped")];
+        assert_eq!(merge(&documents)["trigger"]["tr/b"]["name"], "tr/b");
+
+        let (violations, timings) = check(&rules, &documents, "guardian.yaml");
+        assert_eq!(timings.len(), 3);
+        let found: Vec<(&str, &str, Option<usize>)> = violations
+            .iter()
+            .map(|v| (v.rule_id.as_str(), v.file.as_str(), v.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("max-triggers", "guardian.yaml", None),
+                ("distinct-schedules", "guardian.yaml", None),
+                ("started", "trigger/tr/b.json", Some(4)),
+            ]
+        );
+        assert_eq!(
+            violations[2].pointer.as_deref(),
+            Some("/properties/runtimeState")
+        );
+        assert_eq!(
+            violations[2].json_path.as_deref(),
+            Some("$.properties.runtimeState")
+        );
+    }
+}
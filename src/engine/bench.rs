@@ -0,0 +1,268 @@
+pub use super::Phase;
+use super::{FileTiming, RuleTiming, ScanObserver, ScanOptions};
+use crate::config::{Config, RuleScope};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const PHASES: [Phase; 5] = [
+    Phase::Scan,
+    Phase::Parse,
+    Phase::Match,
+    Phase::Guard,
+    Phase::Locate,
+];
+
+/// How long something took over the iterations, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Timing {
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Timing {
+    fn new(samples: impl Iterator<Item = Duration>) -> Self {
+        let samples: Vec<Duration> = samples.collect();
+        let (Some(min), Some(max)) = (samples.iter().min(), samples.iter().max()) else {
+            return Timing::default();
+        };
+        Timing {
+            mean_ms: ms(samples.iter().sum::<Duration>() / samples.len() as u32),
+            min_ms: ms(*min),
+            max_ms: ms(*max),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PhaseTiming {
+    pub phase: Phase,
+    #[serde(flatten)]
+    pub timing: Timing,
+}
+
+/// What one rule costs in an iteration, on average.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleCost {
+    pub rule_id: String,
+    pub files: usize,
+    /// Nodes its `validate` target selected.
+    pub nodes: usize,
+    pub violations: usize,
+    pub match_ms: f64,
+    pub guard_ms: f64,
+}
+
+impl RuleCost {
+    pub fn total_ms(&self) -> f64 {
+        self.match_ms + self.guard_ms
+    }
+}
+
+/// The result of `bench`: per-phase timings over the iterations and per-rule costs.
+#[derive(Debug, Clone, Serialize)]
+pub struct Benchmark {
+    pub iterations: usize,
+    pub files: usize,
+    pub bytes: u64,
+    pub phases: Vec<PhaseTiming>,
+    pub total: Timing,
+    /// In the order of the config.
+    pub rules: Vec<RuleCost>,
+}
+
+/// Scans the project at `root` with the file-scoped rules of `config` `iterations` times,
+/// with the timings [`super::run`] reports to its observer, after one untimed run that
+/// warms the file cache and the lazily compiled regexes. The scans run on a single worker,
+/// so the phases add up to the run time and runs can be compared across machines with
+/// different core counts. Rules with `scope: project` and the project checks are left out,
+/// and `options` should have no cache.
+pub fn run(
+    config: &Config,
+    root: &Path,
+    options: &ScanOptions,
+    iterations: usize,
+) -> Result<Benchmark> {
+    let mut config = config.clone();
+    config.rules.retain(|rule| rule.scope == RuleScope::File);
+    config.project = Default::default();
+    config.require.clear();
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build()?;
+    let iterate = || {
+        let profile = Profile(Mutex::new(Iteration::new(config.rules.len())));
+        pool.install(|| super::run(&config, root, options, &profile))?;
+        Ok(profile.0.into_inner().unwrap_or_else(|e| e.into_inner()))
+    };
+
+    iterate()?;
+    let samples = (0..iterations)
+        .map(|_| iterate())
+        .collect::<Result<Vec<Iteration>>>()?;
+
+    let phases = PHASES
+        .iter()
+        .map(|&phase| PhaseTiming {
+            phase,
+            timing: Timing::new(samples.iter().map(|sample| sample.phases[phase as usize])),
+        })
+        .collect();
+    let total = Timing::new(samples.iter().map(|sample| sample.phases.iter().sum()));
+    let per_iteration = iterations.max(1);
+    let rules = config
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let sum = samples.iter().map(|sample| &sample.rules[index]);
+            let mean = |f: fn(&Cost) -> Duration| {
+                ms(sum.clone().map(f).sum::<Duration>() / per_iteration as u32)
+            };
+            let count = |f: fn(&Cost) -> usize| sum.clone().map(f).sum::<usize>() / per_iteration;
+            RuleCost {
+                rule_id: rule.id.clone(),
+                files: count(|cost| cost.files),
+                nodes: count(|cost| cost.nodes),
+                violations: count(|cost| cost.violations),
+                match_ms: mean(|cost| cost.matching),
+                guard_ms: mean(|cost| cost.guard),
+            }
+        })
+        .collect();
+    let last = samples.last();
+    Ok(Benchmark {
+        iterations,
+        files: last.map_or(0, |sample| sample.files),
+        bytes: last.map_or(0, |sample| sample.bytes),
+        phases,
+        total,
+        rules,
+    })
+}
+
+/// Milliseconds, to the microsecond.
+fn ms(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
+
+/// Collects the timings of one scan.
+struct Profile(Mutex<Iteration>);
+
+impl ScanObserver for Profile {
+    fn walked(&self, elapsed: Duration) {
+        let mut iteration = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        iteration.phases[Phase::Scan as usize] += elapsed;
+    }
+
+    fn file_timed(&self, timing: &FileTiming) {
+        let mut iteration = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        iteration.files += 1;
+        iteration.bytes += timing.bytes as u64;
+        iteration.phases[Phase::Parse as usize] += timing.parse;
+        iteration.phases[Phase::Match as usize] += timing.matching;
+        iteration.phases[Phase::Guard as usize] += timing.guard;
+        iteration.phases[Phase::Locate as usize] += timing.locate;
+        for rule in &timing.rules {
+            iteration.rules[rule.index].add(rule);
+        }
+    }
+}
+
+/// What one scan spent, by phase and by rule.
+struct Iteration {
+    phases: [Duration; PHASES.len()],
+    /// Indexed like `config.rules`.
+    rules: Vec<Cost>,
+    files: usize,
+    bytes: u64,
+}
+
+impl Iteration {
+    fn new(rule_count: usize) -> Self {
+        Iteration {
+            phases: [Duration::ZERO; PHASES.len()],
+            rules: (0..rule_count).map(|_| Cost::default()).collect(),
+            files: 0,
+            bytes: 0,
+        }
+    }
+}
+
+/// Time spent on one rule, by phase, and what it found.
+#[derive(Default)]
+struct Cost {
+    matching: Duration,
+    guard: Duration,
+    files: usize,
+    nodes: usize,
+    violations: usize,
+}
+
+impl Cost {
+    fn add(&mut self, timing: &RuleTiming) {
+        self.matching += timing.matching;
+        self.guard += timing.guard;
+        self.files += 1;
+        self.nodes += timing.nodes;
+        self.violations += timing.violations;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_bench() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-bench-{}", std::process::id()));
+        fs::create_dir_all(dir.join("pipeline")).unwrap();
+        fs::create_dir_all(dir.join("dataset")).unwrap();
+        for (file, name) in [("pl_a.json", "pl_a"), ("load.json", "load")] {
+            let json = serde_json::json!({
+                "name": name,
+                "properties": { "activities": [{ "name": "a", "type": "Copy" }, { "name": "b", "type": "Wait" }] }
+            });
+            fs::write(dir.join("pipeline").join(file), json.to_string()).unwrap();
+        }
+        fs::write(dir.join("dataset/ds.json"), r#"{ "name": "ds" }"#).unwrap();
+        let config = Config::from_yaml(
+            br#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+  - id: "types"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].type", guard: "AllowedValues", params: { values: ["Copy"] } }
+  - id: "copies"
+    asset: "pipeline"
+    when: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+    validate: { target: "$..parallelCopies", guard: "Exists", params: { should_exist: true }, on_missing: pass }
+"#,
+        )
+        .unwrap();
+
+        let benchmark = run(&config, &dir, &ScanOptions::default(), 3).unwrap();
+        assert_eq!(benchmark.iterations, 3);
+        // No rule applies to the dataset, so it isn't read.
+        assert_eq!(benchmark.files, 2);
+        let phases: Vec<Phase> = benchmark.phases.iter().map(|p| p.phase).collect();
+        assert_eq!(phases, PHASES);
+        let timing = &benchmark.phases[0].timing;
+        assert!(timing.min_ms <= timing.mean_ms && timing.mean_ms <= timing.max_ms);
+
+        let costs: Vec<_> = benchmark
+            .rules
+            .iter()
+            .map(|r| (r.rule_id.as_str(), r.files, r.nodes, r.violations))
+            .collect();
+        assert_eq!(
+            costs,
+            [("naming", 2, 2, 1), ("types", 2, 4, 2), ("copies", 2, 0, 0)]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+use super::Violation;
+use super::project::Asset;
+use crate::config::{Config, file_params, is_url, param_files};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// On-disk store of per-file results, so files that haven't changed since an earlier
+/// run are neither parsed nor evaluated again.
+///
+/// Entries are keyed by the tool version, the effective config (after packs and rule
+/// filters, so asset mapping, folder matching and limits too) with the files its guards
+/// read, like lists and policies, the file path (which decides the asset type) and the
+/// file content, so any change to one of them is a cache miss. So is a rule reaching its
+/// `promote` date, since cached violations carry the promoted severity, and a change to a
+/// list at a URL, which is fetched when the cache is opened. Old entries are never read
+/// again; delete the folder to reclaim the space.
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+    fingerprint: String,
+}
+
+/// What a cached file contributes to a scan.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct Entry {
+    pub violations: Vec<Violation>,
+    pub rules_checked: Vec<String>,
+    pub asset: Asset,
+}
+
+impl Cache {
+    pub fn open(dir: &Path, config: &Config) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create the cache folder {:?}", dir))?;
+        Ok(Cache {
+            dir: dir.to_path_buf(),
+            fingerprint: fingerprint(config, Utc::now())?,
+        })
+    }
+
+    fn entry_path(&self, file_path: &Path, source: &str) -> PathBuf {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.fingerprint);
+        hasher.update([0]);
+        hasher.update(file_path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(source);
+        // Not `.json`, so a cache folder inside the project is never scanned itself.
+        self.dir.join(format!("{:x}.entry", hasher.finalize()))
+    }
+
+    /// An unreadable or corrupt entry counts as a miss.
+    pub(super) fn get(&self, file_path: &Path, source: &str) -> Option<Entry> {
+        let bytes = fs::read(self.entry_path(file_path, source)).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub(super) fn put(&self, file_path: &Path, source: &str, entry: &Entry) {
+        let path = self.entry_path(file_path, source);
+        // Write then rename, so a concurrent run never reads a half-written entry.
+        let temp = path.with_extension(format!("tmp{}", std::process::id()));
+        let written = serde_json::to_vec(entry)
+            .map_err(std::io::Error::from)
+            .and_then(|bytes| fs::write(&temp, bytes))
+            .and_then(|_| fs::rename(&temp, &path));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&temp);
+            tracing::warn!(
+                "Could not write cache entry for {}: {}",
+                file_path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// What every entry key of a run under `config` at `now` shares.
+fn fingerprint(config: &Config, now: DateTime<Utc>) -> Result<String> {
+    // Through a `Value`, whose keys are sorted, since the config holds `HashMap`s.
+    let effective = serde_json::to_value(config)
+        .and_then(|value| serde_json::to_vec(&value))
+        .context("Failed to hash the config")?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update([0]);
+    hasher.update(effective);
+    // A `promote` date changes the severity of cached violations without changing the config.
+    for rule in &config.rules {
+        let severity = super::promoted_severity(rule, now, &mut Vec::new());
+        hasher.update([0]);
+        hasher.update(format!("{:?}", severity));
+    }
+    for file in param_files(&config.rules) {
+        hasher.update([0]);
+        hasher.update(fs::read(file).unwrap_or_default());
+    }
+    // A list that can't be fetched hashes as empty; its rules then fail on every file.
+    for url in file_params(&config.rules).filter(|path| is_url(path)) {
+        hasher.update([0]);
+        hasher.update(super::external::fetch(url).unwrap_or_default());
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-cache-{}", std::process::id()));
+        let mut config: Config = serde_yaml::from_str("rules: []").unwrap();
+        let path = Path::new("pipeline/pl_a.json");
+        let entry = Entry {
+            violations: vec![Violation {
+                rule_id: "naming".to_string(),
+                line: Some(3),
+                ..Default::default()
+            }],
+            rules_checked: vec!["naming".to_string()],
+            asset: Asset::from_document(path, &serde_json::json!({ "name": "pl_a" })),
+        };
+
+        let cache = Cache::open(&dir, &config).unwrap();
+        cache.put(path, "{}", &entry);
+        let hit = cache.get(path, "{}");
+        let edited = cache.get(path, "{ }");
+        let moved = cache.get(Path::new("dataset/pl_a.json"), "{}");
+
+        config.rules = serde_yaml::from_str(
+            r#"
+- id: "naming"
+  asset: "pipeline"
+  validate: { target: "$.name", guard: "Regex", params: { pattern: "^pl_" } }
+"#,
+        )
+        .unwrap();
+        let other_rules = Cache::open(&dir, &config).unwrap().get(path, "{}");
+
+        let mut config: Config = serde_yaml::from_str("rules: []").unwrap();
+        config
+            .asset_mapping
+            .insert("pipeline".to_string(), vec!["**/flows/**".to_string()]);
+        let other_mapping = Cache::open(&dir, &config).unwrap().get(path, "{}");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let hit = hit.unwrap();
+        assert_eq!(hit.violations[0].line, Some(3));
+        assert_eq!(hit.asset.name.as_deref(), Some("pl_a"));
+        assert!(edited.is_none());
+        assert!(moved.is_none());
+        assert!(other_rules.is_none());
+        assert!(other_mapping.is_none());
+    }
+
+    #[test]
+    fn test_fingerprint_follows_promotion() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    severity: Warning
+    promote: "2026-07-01"
+    validate: { target: "$.name", guard: "Exists", params: {} }
+"#,
+        )
+        .unwrap();
+        let at = |now: &str| fingerprint(&config, now.parse().unwrap()).unwrap();
+        assert_eq!(at("2026-05-01T00:00:00Z"), at("2026-06-01T00:00:00Z"));
+        assert_ne!(at("2026-06-01T00:00:00Z"), at("2026-07-02T00:00:00Z"));
+    }
+
+    #[cfg(feature = "remote-lists")]
+    #[test]
+    fn test_fingerprint_follows_remote_lists() {
+        use std::io::{BufRead, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            for body in ["sa_one", "sa_one", "sa_one\nsa_two"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                // The request headers end with a blank line.
+                let mut request = std::io::BufReader::new(&stream);
+                let mut line = String::new();
+                while request.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let config: Config = serde_yaml::from_str(&format!(
+            r#"
+rules:
+  - id: "approved-accounts"
+    asset: "linkedService"
+    validate:
+      target: "$.properties.typeProperties.accountName"
+      guard: "ExternalList"
+      params: {{ source: "http://127.0.0.1:{}/accounts.txt" }}
+"#,
+            port
+        ))
+        .unwrap();
+        let now = Utc::now();
+        let first = fingerprint(&config, now).unwrap();
+        let unchanged = fingerprint(&config, now).unwrap();
+        let edited = fingerprint(&config, now).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(first, unchanged);
+        assert_ne!(first, edited);
+    }
+}
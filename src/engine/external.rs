@@ -0,0 +1,306 @@
+use super::registry::{Guard, GuardResult, Params};
+use super::schema::{self, ParamSpec};
+use crate::config::is_url;
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The name rules give the guard.
+pub(super) const NAME: &str = "ExternalList";
+
+/// `ExternalList`, which checks the node against the values of a file or URL, like
+/// `AllowedValues` with a `values` kept outside the config. Each list is read the first
+/// time a rule needs it, then kept for the life of the registry, i.e. one scan, or a few
+/// minutes of `serve` and `lsp`.
+#[derive(Default)]
+pub(super) struct ExternalList {
+    lists: Mutex<HashMap<(String, Option<String>), Loaded>>,
+}
+
+/// A list, or why it couldn't be read.
+type Loaded = Arc<Result<List, String>>;
+
+#[derive(Debug, PartialEq)]
+struct List {
+    values: HashSet<String>,
+    /// The values in lower case, for `case_sensitive: false`.
+    lowercase: HashSet<String>,
+}
+
+impl List {
+    fn new(values: Vec<String>) -> Self {
+        List {
+            lowercase: values.iter().map(|v| v.to_lowercase()).collect(),
+            values: values.into_iter().collect(),
+        }
+    }
+}
+
+impl ExternalList {
+    fn list(&self, source: &str, column: Option<&str>) -> Loaded {
+        let key = (source.to_string(), column.map(str::to_string));
+        // Held while loading, so concurrent files wait for one read of the list.
+        let mut lists = self.lists.lock().unwrap_or_else(|e| e.into_inner());
+        lists
+            .entry(key)
+            .or_insert_with(|| {
+                let list = load(source, column).map(List::new).map_err(|e| {
+                    tracing::warn!("Could not load the list {}: {:#}", source, e);
+                    format!("{:#}", e)
+                });
+                Arc::new(list)
+            })
+            .clone()
+    }
+}
+
+impl Guard for ExternalList {
+    fn check(&self, node: &Value, params: &Params) -> GuardResult {
+        let Some(source) = params.get("source").and_then(|v| v.as_str()) else {
+            return GuardResult::Fail;
+        };
+        let column = params.get("column").and_then(|v| v.as_str());
+        let deny = params.get("mode").and_then(|v| v.as_str()) == Some("Deny");
+        let case_sensitive = params
+            .get("case_sensitive")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let Some(text) = scalar(node) else {
+            return GuardResult::Fail;
+        };
+
+        let list = self.list(source, column);
+        let list = match list.as_ref() {
+            Ok(list) => list,
+            Err(e) => {
+                return GuardResult::FailWith(format!("Could not load the list {}: {}", source, e));
+            }
+        };
+        let found = if case_sensitive {
+            list.values.contains(text.as_str())
+        } else {
+            list.lowercase.contains(&text.to_lowercase())
+        };
+        (found != deny).into()
+    }
+
+    fn check_params(&self, params: &Value) -> Vec<String> {
+        schema::check(NAME, params)
+    }
+
+    fn params(&self) -> Vec<ParamSpec> {
+        schema::specs(NAME)
+    }
+}
+
+/// Strings, and numbers as written, e.g. a numeric cost center.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// The values of the list at `source`, in the format its extension tells: CSV, JSON,
+/// YAML, or one value per line for anything else.
+fn load(source: &str, column: Option<&str>) -> Result<Vec<String>> {
+    let text = if is_url(source) {
+        fetch(source)?
+    } else {
+        fs::read_to_string(source).with_context(|| format!("Failed to read {:?}", source))?
+    };
+    let path = source.split(['?', '#']).next().unwrap_or(source);
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("csv") => parse_csv(&text, column),
+        Some("json") => {
+            let json: Value = serde_json::from_str(&text).context("Invalid JSON")?;
+            from_items(&json, column)
+        }
+        Some("yaml" | "yml") => {
+            let json: Value = serde_yaml::from_str(&text).context("Invalid YAML")?;
+            from_items(&json, column)
+        }
+        _ => Ok(text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect()),
+    }
+}
+
+#[cfg(feature = "remote-lists")]
+pub(super) fn fetch(url: &str) -> Result<String> {
+    ureq::get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .with_context(|| format!("Failed to fetch {}", url))
+}
+
+#[cfg(not(feature = "remote-lists"))]
+pub(super) fn fetch(_url: &str) -> Result<String> {
+    bail!("lists at a URL need the `remote-lists` feature")
+}
+
+/// The values of a JSON or YAML list of strings and numbers, or of objects whose `column`
+/// property is the value.
+fn from_items(json: &Value, column: Option<&str>) -> Result<Vec<String>> {
+    let items = json
+        .as_array()
+        .ok_or_else(|| anyhow!("Expected a list of values"))?;
+    items
+        .iter()
+        .map(|item| {
+            let value = match column {
+                Some(column) => item.get(column),
+                None => Some(item),
+            };
+            value.and_then(scalar).ok_or_else(|| match column {
+                Some(column) => anyhow!("Expected objects with a '{}' property", column),
+                None => anyhow!("Expected strings or numbers, found {}", item),
+            })
+        })
+        .collect()
+}
+
+/// The values of a CSV document: the `column` named in the header row, or the first field
+/// of every row without one.
+fn parse_csv(text: &str, column: Option<&str>) -> Result<Vec<String>> {
+    let mut rows = csv_rows(text).into_iter();
+    let index = match column {
+        Some(column) => {
+            let header = rows.next().unwrap_or_default();
+            match header.iter().position(|name| name == column) {
+                Some(index) => index,
+                None => bail!("No column '{}' in the header row", column),
+            }
+        }
+        None => 0,
+    };
+    Ok(rows
+        .filter_map(|mut row| (index < row.len()).then(|| row.swap_remove(index)))
+        .filter(|value| !value.is_empty())
+        .collect())
+}
+
+/// The rows of a CSV document, with quoted fields (`"a, b"`, `""` for a quote) and blank
+/// lines skipped. Fields are trimmed.
+fn csv_rows(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field).trim().to_string()),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field).trim().to_string());
+                let row = std::mem::take(&mut row);
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(row);
+                }
+            }
+            '\r' if !quoted => {}
+            c => field.push(c),
+        }
+    }
+    row.push(field.trim().to_string());
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::engine::{GuardRegistry, check_source};
+
+    #[test]
+    fn test_external_list() {
+        assert_eq!(
+            parse_csv(
+                "id,name\n\"sa,1\",\"Main \"\"prod\"\"\"\r\n\nsa2,Backup\n",
+                Some("name")
+            )
+            .unwrap(),
+            ["Main \"prod\"", "Backup"]
+        );
+        assert_eq!(parse_csv("sa1\nsa2", None).unwrap(), ["sa1", "sa2"]);
+        assert!(parse_csv("id\nsa1", Some("name")).is_err());
+        assert_eq!(
+            from_items(&serde_json::json!([{ "code": 4100 }]), Some("code")).unwrap(),
+            ["4100"]
+        );
+
+        let dir = std::env::temp_dir().join(format!("adf-guardian-lists-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("accounts.csv"),
+            "account,owner\nsaprod,data\nsadev,web\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("guards.yaml"),
+            r#"
+rules:
+  - id: "storage-accounts"
+    asset: "linkedService"
+    validate:
+      target: "$.properties.typeProperties.accountName"
+      guard: "ExternalList"
+      params: { source: "accounts.csv", column: "account", case_sensitive: false }
+  - id: "missing-list"
+    asset: "linkedService"
+    validate: { target: "$.name", guard: "ExternalList", params: { source: "missing.txt" } }
+"#,
+        )
+        .unwrap();
+        let config = Config::load(dir.join("guards.yaml")).unwrap();
+        assert_eq!(
+            config.rules[0].validate.params["source"],
+            dir.join("accounts.csv").to_string_lossy().as_ref()
+        );
+
+        let guards = GuardRegistry::default();
+        let check = |account: &str| {
+            let source = serde_json::json!({
+                "name": "ls_blob",
+                "properties": { "typeProperties": { "accountName": account } }
+            })
+            .to_string();
+            check_source(
+                &config,
+                &guards,
+                Path::new("linkedService/ls.json"),
+                &source,
+            )
+            .unwrap()
+            .violations
+            .into_iter()
+            .map(|v| (v.rule_id, v.message))
+            .collect::<Vec<_>>()
+        };
+        let violations = check("SAPROD");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, "missing-list");
+        assert!(violations[0].1.starts_with("Could not load the list"));
+        assert_eq!(check("satest")[0].0, "storage-accounts");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
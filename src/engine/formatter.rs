@@ -1,22 +1,99 @@
+use regex::Regex;
 use serde_json::Value;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+/// Guards that validate a whole activity or asset, reported by name.
+const NAMED_NODE_GUARDS: &[&str] = &[
+    "CopyActivity",
+    "WebActivity",
+    "ComputeCluster",
+    "TriggerPolicy",
+    "CredentialUsage",
+    "PrivateEndpoint",
+    "KeyVaultSecrets",
+];
 
 /// Formats the `actual_value` for display based on the guard type.
-/// For most guards, it stringifies the JSON value. For `Count`, it returns the array length.
+/// For most guards, it stringifies the JSON value. For `Count` and `Unique`, it returns the
+/// number of items, activity/asset guards return the node name instead of the whole object,
+/// `ConnectorPolicy` returns the linked service type and `SecretLikeness` only shows the start
+/// of a suspected secret.
 pub fn format_actual_value(guard: &str, actual_value: &Value) -> String {
     match guard {
-        "Count" => {
+        "ConnectorPolicy" => match actual_value.pointer("/properties/type") {
+            Some(service_type) => service_type.to_string(),
+            None => actual_value.to_string(),
+        },
+        g if NAMED_NODE_GUARDS.contains(&g) => match actual_value.get("name") {
+            Some(name) => name.to_string(),
+            None => actual_value.to_string(),
+        },
+        "Count" | "Unique" => {
             if let Some(arr) = actual_value.as_array() {
                 arr.len().to_string()
+            } else if let Some(map) = actual_value.as_object() {
+                map.len().to_string()
             } else {
                 // Fallback for non-array values, though `check_count` should prevent this path on success.
                 "0".to_string()
             }
         }
+        "SecretLikeness" => match actual_value.as_str() {
+            Some(text) if text.chars().count() > 4 => {
+                let start: String = text.chars().take(4).collect();
+                format!("\"{start}…\" ({} chars)", text.chars().count())
+            }
+            Some(_) => "\"…\"".to_string(),
+            None => "<redacted>".to_string(),
+        },
         // Default behavior for all other guards
         _ => actual_value.to_string(),
     }
 }
 
+/// Secrets that are hidden in actual values: `key=value` settings of connection strings,
+/// string members named like a password or key, and JSON web tokens.
+static SECRETS: LazyLock<[(Regex, &str); 3]> = LazyLock::new(|| {
+    [
+        (
+            Regex::new(r#"(?i)\b(password|pwd|accountkey|sharedaccesskey|sig|client_?secret|api_?key|access_?token)=[^;&"\s\\]+"#)
+                .expect("valid regex"),
+            "$1=***",
+        ),
+        (
+            Regex::new(r#"(?i)"([^"]*(?:password|pwd|secret|accountkey|principalkey|api_?key|token)[^"]*)":"[^"]*""#)
+                .expect("valid regex"),
+            r#""$1":"***""#,
+        ),
+        (
+            Regex::new(r"eyJ[\w-]{8,}\.[\w-]{8,}\.[\w-]*").expect("valid regex"),
+            "***",
+        ),
+    ]
+});
+
+/// `text` with anything that looks like a secret replaced by `***`.
+pub fn redact_secrets(text: &str) -> Cow<'_, str> {
+    let mut redacted = Cow::Borrowed(text);
+    for (pattern, replacement) in SECRETS.iter() {
+        if let Cow::Owned(replaced) = pattern.replace_all(&redacted, *replacement) {
+            redacted = Cow::Owned(replaced);
+        }
+    }
+    redacted
+}
+
+/// The first `max` characters of `text`, followed by how long it was when cut.
+pub fn truncate(text: &str, max: usize) -> Cow<'_, str> {
+    let length = text.chars().count();
+    if length <= max {
+        return Cow::Borrowed(text);
+    }
+    let start: String = text.chars().take(max).collect();
+    Cow::Owned(format!("{}… ({} chars)", start, length))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,6 +113,61 @@ mod tests {
         assert_eq!(format_actual_value("Count", &value), "0");
     }
 
+    #[test]
+    fn test_format_secret_likeness_guard() {
+        let value = json!("hK9#vQ2$mZ7!pL4x");
+        assert_eq!(
+            format_actual_value("SecretLikeness", &value),
+            "\"hK9#…\" (16 chars)"
+        );
+        assert_eq!(
+            format_actual_value("SecretLikeness", &json!({ "password": "x" })),
+            "<redacted>"
+        );
+    }
+
+    #[test]
+    fn test_redact_secrets() {
+        let value = json!({
+            "connectionString": "Server=tcp:sql1;Database=sales;Password=hunter2;",
+            "servicePrincipalKey": "abc",
+            "userName": "loader",
+        });
+        assert_eq!(
+            redact_secrets(&value.to_string()),
+            r#"{"connectionString":"Server=tcp:sql1;Database=sales;Password=***;","servicePrincipalKey":"***","userName":"loader"}"#
+        );
+        assert_eq!(
+            redact_secrets(r#"{"accessToken":"eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxMjM0In0.sig"}"#),
+            r#"{"accessToken":"***"}"#
+        );
+        assert_eq!(
+            redact_secrets(r#""https://st.blob.core.windows.net/raw?sv=2022&sig=abc%3D""#),
+            r#""https://st.blob.core.windows.net/raw?sv=2022&sig=***""#
+        );
+        assert!(matches!(redact_secrets("\"pl_sales\""), Cow::Borrowed(_)));
+        assert_eq!(truncate("abcdef", 4), "abcd… (6 chars)");
+        assert_eq!(truncate("abcd", 4), "abcd");
+    }
+
+    #[test]
+    fn test_format_activity_guard() {
+        let activity = json!({ "name": "copy_sales", "type": "Copy" });
+        assert_eq!(
+            format_actual_value("CopyActivity", &activity),
+            "\"copy_sales\""
+        );
+    }
+
+    #[test]
+    fn test_format_connector_policy_guard() {
+        let linked_service = json!({ "name": "ls_ftp", "properties": { "type": "FtpServer" } });
+        assert_eq!(
+            format_actual_value("ConnectorPolicy", &linked_service),
+            "\"FtpServer\""
+        );
+    }
+
     #[test]
     fn test_format_default_guards() {
         let value_str = json!("a_string");
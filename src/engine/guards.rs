@@ -1,7 +1,59 @@
-use regex::Regex;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, Utc};
+use regex::{Regex, RegexBuilder};
 use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
 
-pub fn check_pattern_match(node: &Value, params: &Value) -> bool {
+/// The regexes named in a rule's guard params (every `*regex` key, including the
+/// `params_by` cases), compiled once when the rule is compiled instead of for every node.
+#[derive(Debug, Default)]
+pub struct Regexes(HashMap<String, Regex>);
+
+/// Compiles a regex from a rule, within limits that keep a huge or deeply nested pattern
+/// from taking the memory and time of a scan.
+pub(super) fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .size_limit(1 << 20)
+        .dfa_size_limit(1 << 20)
+        .nest_limit(64)
+        .build()
+}
+
+impl Regexes {
+    pub fn compile<'a>(params: impl IntoIterator<Item = &'a Value>) -> Self {
+        let mut regexes = HashMap::new();
+        for params in params {
+            let Some(map) = params.as_object() else {
+                continue;
+            };
+            for (key, value) in map {
+                if let (true, Some(pattern)) = (key.ends_with("regex"), value.as_str())
+                    && let Ok(re) = build_regex(pattern)
+                {
+                    regexes.insert(pattern.to_string(), re);
+                }
+                // The params of the inner guard of `DeepMatch`.
+                if key == "params" && value.is_object() {
+                    regexes.extend(Regexes::compile([value]).0);
+                }
+            }
+        }
+        Regexes(regexes)
+    }
+
+    /// `None` if the pattern is invalid. Patterns that weren't compiled up front are
+    /// compiled on the spot.
+    pub(super) fn get(&self, pattern: &str) -> Option<Cow<'_, Regex>> {
+        match self.0.get(pattern) {
+            Some(re) => Some(Cow::Borrowed(re)),
+            None => build_regex(pattern).ok().map(Cow::Owned),
+        }
+    }
+}
+
+pub fn check_pattern_match(node: &Value, params: &Value, regexes: &Regexes) -> bool {
     let regex_str = params.get("regex").and_then(|v| v.as_str());
     let negative = params
         .get("negative")
@@ -9,7 +61,7 @@ pub fn check_pattern_match(node: &Value, params: &Value) -> bool {
         .unwrap_or(false);
 
     if let (Some(text), Some(pattern)) = (node.as_str(), regex_str) {
-        if let Ok(re) = Regex::new(pattern) {
+        if let Some(re) = regexes.get(pattern) {
             let is_match = re.is_match(text);
             if negative { !is_match } else { is_match }
         } else {
@@ -69,6 +121,40 @@ pub fn check_exists(node: &Value, params: &Value) -> bool {
     }
 }
 
+/// How many of the `fields` params are properties of the node that aren't null.
+fn count_set_fields(node: &Value, params: &Value) -> (usize, usize) {
+    let fields: Vec<&str> = params
+        .get("fields")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .collect();
+    let set = fields
+        .iter()
+        .filter(|field| node.get(**field).is_some_and(|v| !v.is_null()))
+        .count();
+    (set, fields.len())
+}
+
+/// At most one of the `fields` properties may be set, exactly one with `required: true`.
+pub fn check_mutually_exclusive(node: &Value, params: &Value) -> bool {
+    let required = params
+        .get("required")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    match count_set_fields(node, params).0 {
+        0 => !required,
+        set => set == 1,
+    }
+}
+
+/// The `fields` properties are either all set or none of them is.
+pub fn check_required_together(node: &Value, params: &Value) -> bool {
+    let (set, total) = count_set_fields(node, params);
+    set == 0 || set == total
+}
+
 pub fn check_range(node: &Value, params: &Value) -> bool {
     let min = params.get("min").and_then(|v| v.as_f64());
     let max = params.get("max").and_then(|v| v.as_f64());
@@ -82,18 +168,44 @@ pub fn check_range(node: &Value, params: &Value) -> bool {
     }
 }
 
+/// Counts the items of an array or the members of an object, e.g. the assets of a type in
+/// the document of a `scope: project` rule.
 pub fn check_count(node: &Value, params: &Value) -> bool {
     let min = params.get("min").and_then(|v| v.as_u64());
     let max = params.get("max").and_then(|v| v.as_u64());
 
-    if let Some(arr) = node.as_array() {
-        let len = arr.len() as u64;
-        let min_ok = min.is_none_or(|m| len >= m);
-        let max_ok = max.is_none_or(|m| len <= m);
-        min_ok && max_ok
-    } else {
-        false // Not an array
-    }
+    let len = match node {
+        Value::Array(list) => list.len(),
+        Value::Object(map) => map.len(),
+        _ => return false, // Not a collection
+    } as u64;
+    min.is_none_or(|m| len >= m) && max.is_none_or(|m| len <= m)
+}
+
+/// Fails when two items of an array, or members of an object, are equal; with `key`, a
+/// JSONPath into each item, only the first node it selects is compared and items without
+/// one are ignored.
+pub fn check_unique(node: &Value, params: &Value) -> bool {
+    let items: Vec<&Value> = match node {
+        Value::Array(list) => list.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => return false,
+    };
+    let key = match params.get("key").and_then(|v| v.as_str()) {
+        Some(path) => match JsonPath::parse(path) {
+            Ok(path) => Some(path),
+            Err(_) => return false,
+        },
+        None => None,
+    };
+    let mut seen = HashSet::new();
+    items
+        .into_iter()
+        .filter_map(|item| match &key {
+            Some(path) => path.query(item).first(),
+            None => Some(item),
+        })
+        .all(|value| seen.insert(value.to_string()))
 }
 
 pub fn check_string_length(node: &Value, params: &Value) -> bool {
@@ -109,3 +221,1110 @@ pub fn check_string_length(node: &Value, params: &Value) -> bool {
         false // Not a string
     }
 }
+
+/// Validates a Copy activity node, correlating its source and sink connector types.
+/// Every constraint present in `params` must hold. Other activities, such as a `Wait`
+/// selected by a broad target, pass.
+///
+/// Factory JSON records no region for datasets or linked services, so a copy can't be
+/// recognized as cross-region here: `staging_required_for_types` lists the source and sink
+/// types (e.g. `SqlDWSink`) whose copies must be staged.
+pub fn check_copy_activity(node: &Value, params: &Value) -> bool {
+    if node.get("type").is_some_and(|t| t != "Copy") {
+        return true;
+    }
+    let Some(type_props) = node.get("typeProperties") else {
+        return true; // Nothing to check
+    };
+
+    let source_type = type_props.pointer("/source/type").and_then(|v| v.as_str());
+    let sink_type = type_props.pointer("/sink/type").and_then(|v| v.as_str());
+    let flag = |key: &str| {
+        type_props
+            .get(key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    };
+
+    let staging_ok = params
+        .get("staging_required_for_types")
+        .and_then(|v| v.as_array())
+        .is_none_or(|types| {
+            let requires_staging = types
+                .iter()
+                .filter_map(|t| t.as_str())
+                .any(|t| source_type == Some(t) || sink_type == Some(t));
+            !requires_staging || flag("enableStaging")
+        });
+
+    let skip_rows_ok = params
+        .get("skip_incompatible_row")
+        .and_then(|v| v.as_bool())
+        .is_none_or(|expected| flag("enableSkipIncompatibleRow") == expected);
+
+    // Values set through dynamic expressions can't be checked statically and are accepted.
+    let within_max = |key: &str, param: &str| {
+        let max = params.get(param).and_then(|v| v.as_u64());
+        let value = type_props.get(key).and_then(|v| v.as_u64());
+        match (max, value) {
+            (Some(max), Some(value)) => value <= max,
+            _ => true,
+        }
+    };
+    let parallel_ok = within_max("parallelCopies", "max_parallel_copies");
+    let diu_ok = within_max("dataIntegrationUnits", "max_data_integration_units");
+
+    let write_behavior = type_props
+        .pointer("/sink/writeBehavior")
+        .and_then(|v| v.as_str());
+    let write_ok = match (params.get("allowed_write_behaviors"), write_behavior) {
+        (Some(Value::Array(list)), Some(behavior)) => list.iter().any(|v| v == behavior),
+        (Some(Value::Object(by_sink)), Some(behavior)) => sink_type
+            .and_then(|t| by_sink.get(t))
+            .and_then(|v| v.as_array())
+            .is_none_or(|list| list.iter().any(|v| v == behavior)),
+        _ => true,
+    };
+
+    staging_ok && skip_rows_ok && parallel_ok && diu_ok && write_ok
+}
+
+/// Validates a Web or WebHook activity node: URL scheme and host, authentication type
+/// and literal secrets in request headers. Every constraint present in `params` must hold.
+pub fn check_web_activity(node: &Value, params: &Value, regexes: &Regexes) -> bool {
+    let Some(type_props) = node.get("typeProperties") else {
+        return false; // Not a Web activity
+    };
+
+    // URLs built through dynamic expressions can't be checked statically and are accepted.
+    let url = type_props
+        .get("url")
+        .and_then(|v| v.as_str())
+        .filter(|u| !u.starts_with('@'));
+
+    let https_ok = params
+        .get("https_only")
+        .and_then(|v| v.as_bool())
+        .is_none_or(|https_only| {
+            !https_only || url.is_none_or(|u| u.to_ascii_lowercase().starts_with("https://"))
+        });
+
+    let host_ok = match (params.get("allowed_hosts").and_then(|v| v.as_array()), url) {
+        (Some(hosts), Some(u)) => {
+            let host = url_host(u);
+            hosts
+                .iter()
+                .filter_map(|h| h.as_str())
+                .any(|pattern| host_matches(pattern, &host))
+        }
+        _ => true,
+    };
+
+    let auth = type_props.get("authentication");
+    let auth_type = auth
+        .and_then(|a| a.get("type"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("None");
+
+    let auth_ok = params
+        .get("allowed_authentication")
+        .and_then(|v| v.as_array())
+        .is_none_or(|list| {
+            list.iter()
+                .filter_map(|v| v.as_str())
+                .any(|t| t.eq_ignore_ascii_case(auth_type))
+        });
+
+    let inline_password = auth
+        .and_then(|a| a.pointer("/password/type"))
+        .and_then(|v| v.as_str())
+        .is_some_and(|t| t == "SecureString");
+    let password_ok = params
+        .get("forbid_inline_password")
+        .and_then(|v| v.as_bool())
+        .is_none_or(|forbid| !forbid || !inline_password);
+
+    let headers_ok = check_header_secrets(type_props.get("headers"), params, regexes);
+
+    https_ok && host_ok && auth_ok && password_ok && headers_ok
+}
+
+fn check_header_secrets(headers: Option<&Value>, params: &Value, regexes: &Regexes) -> bool {
+    let secret_names: Vec<&str> = params
+        .get("secret_header_names")
+        .and_then(|v| v.as_array())
+        .map(|list| list.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let secret_regex = params
+        .get("secret_value_regex")
+        .and_then(|v| v.as_str())
+        .and_then(|r| regexes.get(r));
+
+    // Headers are either an object of name/value pairs or a list of `{ name, value }` entries.
+    let pairs: Vec<(&str, &Value)> = match headers {
+        Some(Value::Object(map)) => map.iter().map(|(k, v)| (k.as_str(), v)).collect(),
+        Some(Value::Array(list)) => list
+            .iter()
+            .filter_map(|h| Some((h.get("name")?.as_str()?, h.get("value")?)))
+            .collect(),
+        _ => vec![],
+    };
+
+    pairs.iter().all(|(name, value)| {
+        // Only literal values are secrets; expressions and Key Vault references are fine.
+        let Some(literal) = value.as_str().filter(|v| !v.starts_with('@')) else {
+            return true;
+        };
+        let secret_name = secret_names.iter().any(|n| n.eq_ignore_ascii_case(name));
+        let secret_value = secret_regex.as_ref().is_some_and(|re| re.is_match(literal));
+        !secret_name && !secret_value
+    })
+}
+
+fn url_host(url: &str) -> String {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = without_scheme
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    host.split(':')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Matches a host against an allowlist entry, where `*.example.com` covers any subdomain.
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host
+            .strip_suffix(suffix)
+            .is_some_and(|rest| rest.ends_with('.')),
+        None => pattern == host,
+    }
+}
+
+/// Validates the cluster configuration of a Databricks or HDInsight linked service document.
+/// Every constraint present in `params` must hold.
+pub fn check_compute_cluster(node: &Value, params: &Value) -> bool {
+    let Some(properties) = node.get("properties") else {
+        return false; // Not a linked service
+    };
+    let Some(type_props) = properties.get("typeProperties") else {
+        return false;
+    };
+    let service_type = properties
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let flag = |key: &str| params.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    let is_set = |key: &str| type_props.get(key).is_some_and(|v| !v.is_null());
+
+    // Only Databricks tells an interactive cluster apart: a bring-your-own HDInsight
+    // service may well point at a job cluster.
+    let uses_existing_cluster = service_type == "AzureDatabricks" && is_set("existingClusterId");
+    let existing_ok = !flag("forbid_existing_cluster") || !uses_existing_cluster;
+
+    // Pools only apply to the job clusters Databricks creates from the `newCluster*` settings.
+    let creates_cluster = type_props
+        .as_object()
+        .is_some_and(|props| props.keys().any(|key| key.starts_with("newCluster")));
+    let pool_ok = !flag("require_instance_pool")
+        || service_type != "AzureDatabricks"
+        || !creates_cluster
+        || is_set("instancePoolId");
+
+    let node_type_keys: &[&str] = match service_type {
+        "AzureDatabricks" => &["newClusterNodeType", "newClusterDriverNodeType"],
+        "HDInsightOnDemand" => &["headNodeSize", "dataNodeSize", "zookeeperNodeSize"],
+        _ => &[],
+    };
+    let node_types_ok = params
+        .get("allowed_node_types")
+        .and_then(|v| v.as_array())
+        .is_none_or(|allowed| {
+            node_type_keys
+                .iter()
+                .filter_map(|key| type_props.get(*key).and_then(|v| v.as_str()))
+                .all(|node_type| allowed.iter().any(|a| a == node_type))
+        });
+
+    // Databricks uses "N" or "min:max" (autoscale) worker counts; HDInsight a plain cluster size.
+    let workers = match service_type {
+        "AzureDatabricks" => type_props
+            .get("newClusterNumOfWorker")
+            .and_then(|v| v.as_str())
+            .and_then(parse_worker_range),
+        "HDInsightOnDemand" => type_props
+            .get("clusterSize")
+            .and_then(|v| v.as_u64())
+            .map(|n| (n, n)),
+        _ => None,
+    };
+    let min = params.get("min_workers").and_then(|v| v.as_u64());
+    let max = params.get("max_workers").and_then(|v| v.as_u64());
+    let workers_ok = workers
+        .is_none_or(|(low, high)| min.is_none_or(|m| low >= m) && max.is_none_or(|m| high <= m));
+
+    existing_ok && pool_ok && node_types_ok && workers_ok
+}
+
+fn parse_worker_range(value: &str) -> Option<(u64, u64)> {
+    match value.split_once(':') {
+        Some((low, high)) => Some((low.trim().parse().ok()?, high.trim().parse().ok()?)),
+        None => {
+            let n = value.trim().parse().ok()?;
+            Some((n, n))
+        }
+    }
+}
+
+/// Validates a trigger document against the governance settings present in `params`.
+/// Settings that don't apply to the trigger type are ignored.
+pub fn check_trigger_policy(node: &Value, params: &Value) -> bool {
+    let Some(properties) = node.get("properties") else {
+        return false; // Not a trigger
+    };
+    let trigger_type = properties
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    let type_props = properties.get("typeProperties").unwrap_or(&Value::Null);
+    let flag = |key: &str| params.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    let allowed = |key: &str, value: Option<&str>| {
+        params
+            .get(key)
+            .and_then(|v| v.as_array())
+            .is_none_or(|list| {
+                value.is_some_and(|value| {
+                    list.iter()
+                        .filter_map(|v| v.as_str())
+                        .any(|a| a.eq_ignore_ascii_case(value))
+                })
+            })
+    };
+
+    let started = properties.get("runtimeState").and_then(|v| v.as_str()) == Some("Started");
+    let state_ok = !flag("forbid_started") || !started;
+
+    let type_ok = match trigger_type {
+        "TumblingWindowTrigger" => {
+            let retry_ok = !flag("require_retry_policy")
+                || type_props
+                    .pointer("/retryPolicy/count")
+                    .is_some_and(|v| !v.is_null());
+            let concurrency_ok = params
+                .get("max_concurrency")
+                .and_then(|v| v.as_u64())
+                .is_none_or(|max| {
+                    type_props
+                        .get("maxConcurrency")
+                        .and_then(|v| v.as_u64())
+                        .is_some_and(|c| c <= max)
+                });
+            retry_ok && concurrency_ok
+        }
+        "ScheduleTrigger" => {
+            let time_zone = type_props
+                .pointer("/recurrence/timeZone")
+                .and_then(|v| v.as_str());
+            allowed("allowed_time_zones", time_zone)
+        }
+        "BlobEventsTrigger" => {
+            let account = type_props
+                .get("scope")
+                .and_then(|v| v.as_str())
+                .and_then(|scope| scope.rsplit_once("/storageAccounts/"))
+                .map(|(_, name)| name.trim_end_matches('/'));
+            allowed("allowed_storage_accounts", account)
+        }
+        _ => true,
+    };
+
+    state_ok && type_ok
+}
+
+/// Validates how a linked service document authenticates: inline service principal secrets
+/// and the `credential` reference. Every constraint present in `params` must hold.
+pub fn check_credential_usage(node: &Value, params: &Value) -> bool {
+    let Some(type_props) = node.pointer("/properties/typeProperties") else {
+        return false; // Not a linked service
+    };
+
+    let forbid_inline = params
+        .get("forbid_inline_keys")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let has_inline_key = [
+        "servicePrincipalKey",
+        "servicePrincipalCredential",
+        "servicePrincipalEmbeddedCert",
+    ]
+    .iter()
+    .any(|key| {
+        type_props
+            .get(*key)
+            .and_then(|v| v.get("type"))
+            .and_then(|v| v.as_str())
+            == Some("SecureString")
+    });
+    let inline_ok = !forbid_inline || !has_inline_key;
+
+    let credential = type_props
+        .get("credential")
+        .filter(|c| c.get("type").and_then(|v| v.as_str()) == Some("CredentialReference"))
+        .and_then(|c| c.get("referenceName"))
+        .and_then(|v| v.as_str());
+
+    let required_ok = !params
+        .get("require_credential")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+        || credential.is_some();
+
+    let allowed_ok = params
+        .get("allowed_credentials")
+        .and_then(|v| v.as_array())
+        .is_none_or(|list| credential.is_none_or(|name| list.iter().any(|v| v == name)));
+
+    inline_ok && required_ok && allowed_ok
+}
+
+/// Validates a managed private endpoint document: target subscription, `groupId` and an
+/// approval annotation. Every constraint present in `params` must hold.
+pub fn check_private_endpoint(node: &Value, params: &Value, regexes: &Regexes) -> bool {
+    let Some(properties) = node.get("properties") else {
+        return false; // Not a managed private endpoint
+    };
+
+    let subscription = properties
+        .get("privateLinkResourceId")
+        .and_then(|v| v.as_str())
+        .and_then(|id| resource_id_segment(id, "subscriptions"));
+    let subscription_ok = params
+        .get("allowed_subscriptions")
+        .and_then(|v| v.as_array())
+        .is_none_or(|list| {
+            subscription.is_some_and(|sub| {
+                list.iter()
+                    .filter_map(|v| v.as_str())
+                    .any(|a| a.eq_ignore_ascii_case(sub))
+            })
+        });
+
+    let group_id = properties.get("groupId").and_then(|v| v.as_str());
+    let group_ok = params
+        .get("allowed_group_ids")
+        .and_then(|v| v.as_array())
+        .is_none_or(|list| group_id.is_some_and(|g| list.iter().any(|v| v == g)));
+
+    let annotation_ok = match params
+        .get("ticket_annotation_regex")
+        .and_then(|v| v.as_str())
+    {
+        Some(pattern) => match regexes.get(pattern) {
+            Some(re) => properties
+                .get("annotations")
+                .and_then(|v| v.as_array())
+                .is_some_and(|list| {
+                    list.iter()
+                        .filter_map(|v| v.as_str())
+                        .any(|a| re.is_match(a))
+                }),
+            None => false,
+        },
+        None => true,
+    };
+
+    subscription_ok && group_ok && annotation_ok
+}
+
+/// Returns the value following `key` in an Azure resource ID, e.g. the subscription id.
+fn resource_id_segment<'a>(resource_id: &'a str, key: &str) -> Option<&'a str> {
+    let mut segments = resource_id.split('/').filter(|s| !s.is_empty());
+    segments.find(|s| s.eq_ignore_ascii_case(key))?;
+    segments.next()
+}
+
+/// Applies `check` (the inner guard of `DeepMatch`) to every string and number under the
+/// node, and to object keys too with `keys: true`. `include_keys` only checks values with one
+/// of these property names on their way from the node, `exclude_keys` skips the values
+/// under them; both ignore case.
+pub fn check_deep_match(node: &Value, params: &Value, check: impl Fn(&Value) -> bool) -> bool {
+    let names = |key: &str| -> Vec<String> {
+        params
+            .get(key)
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (include, exclude) = (names("include_keys"), names("exclude_keys"));
+    let keys = params
+        .get("keys")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let mut stack = vec![(node, include.is_empty())];
+    while let Some((current, included)) = stack.pop() {
+        match current {
+            Value::String(_) | Value::Number(_) if included && !check(current) => return false,
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let key_lower = key.to_lowercase();
+                    if exclude.contains(&key_lower) {
+                        continue;
+                    }
+                    let included = included || include.contains(&key_lower);
+                    if keys && included && !check(&Value::String(key.clone())) {
+                        return false;
+                    }
+                    stack.push((value, included));
+                }
+            }
+            Value::Array(list) => stack.extend(list.iter().map(|v| (v, included))),
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Words that mark the value after them as a secret for `SecretLikeness`.
+const SECRET_KEYWORDS: [&str; 7] = [
+    "password",
+    "passwd",
+    "pwd",
+    "secret",
+    "token",
+    "key",
+    "signature",
+];
+
+/// A word of a string checked by `SecretLikeness`; URLs are kept whole so they can be skipped.
+static SECRET_TOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"[^\s;,&"'=:()\[\]{}<>]+(?:://\S*)?"#).expect("valid regex"));
+
+/// Fails when a string in the node looks like a hard-coded secret. A token is suspicious when
+/// it is long and random enough (Shannon entropy, in bits per character), with lower
+/// thresholds when a keyword such as `password` comes at most `proximity` characters before
+/// it or names the property holding it. `sensitivity` (`low`, `medium` or `high`) picks the
+/// thresholds; `min_entropy` and `min_length` override them. Expressions (`@...`), URLs and
+/// GUIDs are not secrets.
+pub fn check_secret_likeness(node: &Value, params: &Value) -> bool {
+    let (default_entropy, default_length) = match params.get("sensitivity").and_then(|v| v.as_str())
+    {
+        Some("low") => (4.5, 24),
+        Some("high") => (3.5, 16),
+        _ => (4.0, 20),
+    };
+    let min_entropy = params
+        .get("min_entropy")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(default_entropy);
+    let min_length = params
+        .get("min_length")
+        .and_then(|v| v.as_u64())
+        .map_or(default_length, |n| n as usize);
+    let proximity = params
+        .get("proximity")
+        .and_then(|v| v.as_u64())
+        .map_or(20, |n| n as usize);
+    let keywords: Vec<String> = match params.get("keywords").and_then(|v| v.as_array()) {
+        Some(list) => list
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(str::to_lowercase)
+            .collect(),
+        None => SECRET_KEYWORDS.iter().map(|k| k.to_string()).collect(),
+    };
+    let has_keyword = |text: &str| {
+        let text = text.to_lowercase();
+        keywords.iter().any(|k| text.contains(k.as_str()))
+    };
+
+    let looks_secret = |text: &str, named_secret: bool| {
+        if text.starts_with('@') {
+            return false;
+        }
+        SECRET_TOKEN.find_iter(text).any(|token| {
+            let (start, token) = (token.start(), token.as_str());
+            if token.contains("://") || is_uuid(token) {
+                return false;
+            }
+            let before = &text[text[..start]
+                .char_indices()
+                .rev()
+                .take(proximity)
+                .last()
+                .map_or(start, |(i, _)| i)..start];
+            let near = named_secret || has_keyword(before);
+            let (entropy, length) = if near {
+                (min_entropy - 1.0, min_length / 2)
+            } else {
+                (min_entropy, min_length)
+            };
+            token.chars().count() >= length.max(8) && shannon_entropy(token) >= entropy
+        })
+    };
+
+    let mut stack = vec![(node, false)];
+    while let Some((current, named_secret)) = stack.pop() {
+        match current {
+            Value::String(text) if looks_secret(text, named_secret) => return false,
+            Value::Object(map) => stack.extend(map.iter().map(|(k, v)| (v, has_keyword(k)))),
+            Value::Array(list) => stack.extend(list.iter().map(|v| (v, named_secret))),
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Bits per character of `text`.
+fn shannon_entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let len = text.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Validates a semantic version (`MAJOR.MINOR.PATCH`, with optional `-pre-release` and
+/// `+build` parts) after removing `prefix`, e.g. `version:` from an annotation. `range` is a
+/// list of comparisons that must all hold, like `>=1.2.0, <2.0.0`, and pre-release versions
+/// fail with `allow_prerelease: false`.
+pub fn check_sem_ver(node: &Value, params: &Value) -> bool {
+    let Some(text) = node.as_str() else {
+        return false;
+    };
+    let prefix = params.get("prefix").and_then(|v| v.as_str()).unwrap_or("");
+    let Some(version) = text.strip_prefix(prefix).and_then(SemVer::parse) else {
+        return false;
+    };
+    let allow_prerelease = params
+        .get("allow_prerelease")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    if !allow_prerelease && !version.pre.is_empty() {
+        return false;
+    }
+    match params.get("range").and_then(|v| v.as_str()) {
+        Some(range) => parse_sem_ver_range(range)
+            .is_some_and(|comparisons| comparisons.iter().all(|(op, bound)| op(&version, bound))),
+        None => true,
+    }
+}
+
+/// Validates a GUID such as a tenant or subscription id: `8-4-4-4-12` hex digits, lower-case
+/// with `lowercase`, and not all zeros with `forbid_nil`. Expressions (`@...`) can't be
+/// checked statically and pass.
+pub fn check_uuid(node: &Value, params: &Value) -> bool {
+    let Some(text) = node.as_str() else {
+        return false;
+    };
+    if text.starts_with('@') {
+        return true;
+    }
+    let flag = |key: &str| params.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+    is_uuid(text)
+        && (!flag("lowercase") || !text.chars().any(|c| c.is_ascii_uppercase()))
+        && (!flag("forbid_nil") || text.chars().any(|c| c != '0' && c != '-'))
+}
+
+fn is_uuid(text: &str) -> bool {
+    let groups: Vec<&str> = text.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The parts of an Azure resource id,
+/// `/subscriptions/<id>/resourceGroups/<group>/providers/<namespace>/<type>/<name>[/<type>/<name>]`.
+struct ResourceId<'a> {
+    subscription: &'a str,
+    resource_group: Option<&'a str>,
+    /// The namespace and types, e.g. `Microsoft.Sql/servers/databases`.
+    resource_type: Option<String>,
+    name: Option<&'a str>,
+}
+
+fn parse_resource_id(text: &str) -> Option<ResourceId<'_>> {
+    let segments: Vec<&str> = text.strip_prefix('/')?.split('/').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return None;
+    }
+    let mut rest = segments.as_slice();
+    let [key, subscription, after @ ..] = rest else {
+        return None;
+    };
+    if !key.eq_ignore_ascii_case("subscriptions") || !is_uuid(subscription) {
+        return None;
+    }
+    rest = after;
+    let mut resource_group = None;
+    if let [key, group, after @ ..] = rest
+        && key.eq_ignore_ascii_case("resourceGroups")
+    {
+        resource_group = Some(*group);
+        rest = after;
+    }
+    let mut id = ResourceId {
+        subscription,
+        resource_group,
+        resource_type: None,
+        name: None,
+    };
+    match rest {
+        [] => Some(id),
+        [key, namespace, pairs @ ..]
+            if key.eq_ignore_ascii_case("providers")
+                && !pairs.is_empty()
+                && pairs.len().is_multiple_of(2) =>
+        {
+            let mut resource_type = namespace.to_string();
+            for pair in pairs.chunks(2) {
+                resource_type.push('/');
+                resource_type.push_str(pair[0]);
+            }
+            id.resource_type = Some(resource_type);
+            id.name = pairs.last().copied();
+            Some(id)
+        }
+        _ => None,
+    }
+}
+
+/// Validates an Azure resource id, as in linked services and managed private endpoints: it
+/// must be well formed, in an `allowed_subscriptions`, of an `allowed_resource_types` (e.g.
+/// `Microsoft.Storage/storageAccounts`) and have a name matching `name_regex`. Expressions
+/// (`@...`) can't be checked statically and pass.
+pub fn check_azure_resource_id(node: &Value, params: &Value, regexes: &Regexes) -> bool {
+    let Some(text) = node.as_str() else {
+        return false;
+    };
+    if text.starts_with('@') {
+        return true;
+    }
+    let Some(id) = parse_resource_id(text) else {
+        return false;
+    };
+    let listed = |key: &str, value: Option<&str>| {
+        params
+            .get(key)
+            .and_then(|v| v.as_array())
+            .is_none_or(|list| {
+                value.is_some_and(|value| {
+                    list.iter()
+                        .filter_map(|v| v.as_str())
+                        .any(|allowed| allowed.eq_ignore_ascii_case(value))
+                })
+            })
+    };
+    let name_ok = match params.get("name_regex").and_then(|v| v.as_str()) {
+        Some(pattern) => match (regexes.get(pattern), id.name) {
+            (Some(re), Some(name)) => re.is_match(name),
+            _ => false,
+        },
+        None => true,
+    };
+    listed("allowed_subscriptions", Some(id.subscription))
+        && listed("allowed_resource_groups", id.resource_group)
+        && listed("allowed_resource_types", id.resource_type.as_deref())
+        && name_ok
+}
+
+/// Walks the whole node validating every `AzureKeyVaultSecret` reference (vault linked service
+/// and secret name) and checking that secret-bearing properties are Key Vault references.
+pub fn check_key_vault_secrets(node: &Value, params: &Value, regexes: &Regexes) -> bool {
+    let allowed_vaults = params.get("allowed_vaults").and_then(|v| v.as_array());
+    let secret_name_re = match params.get("secret_name_regex").and_then(|v| v.as_str()) {
+        Some(pattern) => match regexes.get(pattern) {
+            Some(re) => Some(re),
+            None => return false,
+        },
+        None => None,
+    };
+    let secret_properties: Vec<&str> = params
+        .get("secret_properties")
+        .and_then(|v| v.as_array())
+        .map(|list| list.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+
+    let reference_ok = |secret: &Value| {
+        let vault = secret
+            .pointer("/store/referenceName")
+            .and_then(|v| v.as_str());
+        let vault_ok = allowed_vaults
+            .is_none_or(|list| vault.is_some_and(|name| list.iter().any(|v| v == name)));
+
+        // Secret names set through expressions can't be checked statically and are accepted.
+        let name_ok = match (&secret_name_re, secret.get("secretName")) {
+            (Some(re), Some(Value::String(name))) => re.is_match(name),
+            _ => true,
+        };
+        vault_ok && name_ok
+    };
+
+    let is_key_vault_ref =
+        |value: &Value| value.get("type").and_then(|v| v.as_str()) == Some("AzureKeyVaultSecret");
+    let is_literal = |value: &Value| match value {
+        Value::String(s) => !s.starts_with('@'),
+        Value::Object(_) => value.get("type").and_then(|v| v.as_str()) == Some("SecureString"),
+        _ => false,
+    };
+
+    let mut stack = vec![node];
+    while let Some(current) = stack.pop() {
+        match current {
+            Value::Object(map) => {
+                if is_key_vault_ref(current) && !reference_ok(current) {
+                    return false;
+                }
+                for (key, value) in map {
+                    let secret_property = secret_properties
+                        .iter()
+                        .any(|p| p.eq_ignore_ascii_case(key));
+                    if secret_property && !is_key_vault_ref(value) && is_literal(value) {
+                        return false;
+                    }
+                    stack.push(value);
+                }
+            }
+            Value::Array(list) => stack.extend(list),
+            _ => {}
+        }
+    }
+
+    true
+}
+
+/// Units of the `Numeric` guard with their dimension and size in bytes or seconds. Sizes
+/// are powers of 1024, as Azure reports them.
+pub(super) const UNITS: [(&str, Dimension, f64); 11] = [
+    ("B", Dimension::Size, 1.0),
+    ("KB", Dimension::Size, 1024.0),
+    ("MB", Dimension::Size, 1024.0 * 1024.0),
+    ("GB", Dimension::Size, 1024.0 * 1024.0 * 1024.0),
+    ("TB", Dimension::Size, 1024.0 * 1024.0 * 1024.0 * 1024.0),
+    ("ms", Dimension::Duration, 0.001),
+    ("s", Dimension::Duration, 1.0),
+    ("m", Dimension::Duration, 60.0),
+    ("min", Dimension::Duration, 60.0),
+    ("h", Dimension::Duration, 3600.0),
+    ("d", Dimension::Duration, 86400.0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Dimension {
+    /// A plain number, such as a DIU or core count.
+    Count,
+    Size,
+    Duration,
+}
+
+/// A number in base units (bytes or seconds) and what it measures.
+type Quantity = (f64, Dimension);
+
+/// Validates a number against `min` and `max`, which may have units like `4GB` or `30s`.
+/// `unit` is what the target stores plain numbers in, e.g. `MB`. Strings in the target may
+/// carry their own unit, or be a timespan like `0.12:00:00` as in activity timeouts.
+/// Values and bounds of different dimensions fail.
+pub fn check_numeric(node: &Value, params: &Value) -> bool {
+    let unit = match params.get("unit").and_then(|v| v.as_str()) {
+        Some(unit) => match find_unit(unit) {
+            Some(unit) => unit,
+            None => return false,
+        },
+        None => (1.0, Dimension::Count),
+    };
+    let value = match node {
+        Value::String(text) => parse_timespan(text).or_else(|| parse_quantity(text, unit)),
+        other => other.as_f64().map(|n| (n * unit.0, unit.1)),
+    };
+    let Some((value, dimension)) = value else {
+        return false;
+    };
+    let within = |key: &str, ok: fn(f64, f64) -> bool| match params.get(key) {
+        None => true,
+        Some(bound) => {
+            let bound = match bound {
+                Value::String(text) => parse_quantity(text, unit),
+                other => other.as_f64().map(|n| (n * unit.0, unit.1)),
+            };
+            bound.is_some_and(|(bound, bound_dimension)| {
+                bound_dimension == dimension && ok(value, bound)
+            })
+        }
+    };
+    within("min", |value, min| value >= min) && within("max", |value, max| value <= max)
+}
+
+/// Sizes are matched ignoring case (`4gb`), durations exactly, so `m` is minutes.
+fn find_unit(name: &str) -> Option<(f64, Dimension)> {
+    UNITS
+        .iter()
+        .find(|(unit, dimension, _)| {
+            *unit == name || (*dimension == Dimension::Size && unit.eq_ignore_ascii_case(name))
+        })
+        .map(|(_, dimension, factor)| (*factor, *dimension))
+}
+
+/// A number with an optional unit, e.g. `4GB`, `1.5 h` or `32`; plain numbers are in
+/// `default`.
+pub(super) fn parse_quantity(text: &str, default: (f64, Dimension)) -> Option<Quantity> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let (factor, dimension) = match unit.trim() {
+        "" => default,
+        unit => find_unit(unit)?,
+    };
+    Some((number * factor, dimension))
+}
+
+/// Seconds in a .NET timespan as ADF writes timeouts and intervals: `[d.]hh:mm:ss`.
+fn parse_timespan(text: &str) -> Option<Quantity> {
+    let (days, time) = match text.split_once('.') {
+        Some((days, time)) if time.contains(':') => (days.parse::<f64>().ok()?, time),
+        _ => (0.0, text),
+    };
+    let parts: Vec<&str> = time.split(':').collect();
+    let [hours, minutes, seconds] = parts.as_slice() else {
+        return None;
+    };
+    let hours: f64 = hours.parse().ok()?;
+    let minutes: f64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    Some((
+        days * 86400.0 + hours * 3600.0 + minutes * 60.0 + seconds,
+        Dimension::Duration,
+    ))
+}
+
+/// Validates an ISO-8601 date and time, e.g. a trigger's `startTime`: it must parse, carry
+/// a UTC offset with `require_offset`, and fall within `not_before` and `not_after`. Values
+/// without an offset are compared as UTC.
+pub fn check_date_time(node: &Value, params: &Value) -> bool {
+    let Some(text) = node.as_str() else {
+        return false;
+    };
+    let Some((value, has_offset)) = parse_date_time(text) else {
+        return false;
+    };
+    let bound = |key: &str| {
+        params
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|v| parse_bound(v).ok_or(()))
+            .transpose()
+    };
+    let (Ok(not_before), Ok(not_after)) = (bound("not_before"), bound("not_after")) else {
+        return false;
+    };
+
+    let require_offset = params
+        .get("require_offset")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    (has_offset || !require_offset)
+        && not_before.is_none_or(|min| value >= min)
+        && not_after.is_none_or(|max| value <= max)
+}
+
+/// The instant of an ISO-8601 date or date and time, and whether it had an offset.
+fn parse_date_time(text: &str) -> Option<(DateTime<Utc>, bool)> {
+    if let Ok(value) = DateTime::parse_from_rfc3339(text) {
+        return Some((value.with_timezone(&Utc), true));
+    }
+    let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map(|date| date.and_time(Default::default()))
+        })
+        .ok()?;
+    Some((naive.and_utc(), false))
+}
+
+/// A `DateTime` bound: a date and time, or `now` optionally shifted by a number of
+/// seconds, minutes, hours or days, e.g. `now-1d`.
+pub(super) fn parse_bound(text: &str) -> Option<DateTime<Utc>> {
+    let Some(shift) = text.strip_prefix("now") else {
+        return parse_date_time(text).map(|(value, _)| value);
+    };
+    if shift.is_empty() {
+        return Some(Utc::now());
+    }
+    let (sign, amount) = match shift.strip_prefix('+') {
+        Some(amount) => (1, amount),
+        None => (-1, shift.strip_prefix('-')?),
+    };
+    // Split on characters, as a typo can put a multibyte one at either end.
+    let unit = amount.chars().last()?;
+    let number: i64 = amount[..amount.len() - unit.len_utf8()].parse().ok()?;
+    let delta = match unit {
+        's' => TimeDelta::try_seconds(number),
+        'm' => TimeDelta::try_minutes(number),
+        'h' => TimeDelta::try_hours(number),
+        'd' => TimeDelta::try_days(number),
+        _ => None,
+    }?;
+    Utc::now().checked_add_signed(delta * sign)
+}
+
+/// A semantic version, ordered by precedence: build metadata is ignored and a pre-release
+/// comes before its release.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct SemVer {
+    core: [u64; 3],
+    pre: Vec<String>,
+}
+
+impl SemVer {
+    fn parse(text: &str) -> Option<Self> {
+        let text = match text.split_once('+') {
+            Some((_, "")) => return None,
+            Some((version, _build)) => version,
+            None => text,
+        };
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+        let numeric = |part: &str| {
+            let leading_zero = part.len() > 1 && part.starts_with('0');
+            (!leading_zero && part.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| part.parse::<u64>().ok())
+                .flatten()
+        };
+        let mut parts = core.split('.');
+        let core = [
+            numeric(parts.next()?)?,
+            numeric(parts.next()?)?,
+            numeric(parts.next()?)?,
+        ];
+        if parts.next().is_some() {
+            return None;
+        }
+        let pre: Vec<String> = match pre {
+            Some(pre) => pre.split('.').map(str::to_string).collect(),
+            None => vec![],
+        };
+        let valid_identifier = |id: &String| {
+            !id.is_empty() && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        };
+        pre.iter()
+            .all(valid_identifier)
+            .then_some(SemVer { core, pre })
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
+        let identifier = |a: &String, b: &String| match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+        self.core
+            .cmp(&other.core)
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self
+                    .pre
+                    .iter()
+                    .zip(&other.pre)
+                    .map(|(a, b)| identifier(a, b))
+                    .find(|o| o.is_ne())
+                    .unwrap_or_else(|| self.pre.len().cmp(&other.pre.len())),
+            })
+    }
+}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Whether a `promote` date or release (`v` optional) is reached at `now`, running
+/// `release`. `None` if it is neither.
+pub(super) fn promotion_due(promote: &str, now: DateTime<Utc>, release: &str) -> Option<bool> {
+    if let Some((date, _)) = parse_date_time(promote) {
+        return Some(now >= date);
+    }
+    let version = SemVer::parse(promote.strip_prefix('v').unwrap_or(promote))?;
+    Some(SemVer::parse(release)? >= version)
+}
+
+type SemVerComparison = (fn(&SemVer, &SemVer) -> bool, SemVer);
+
+/// A `SemVer` range: comparisons separated by commas or spaces, each one of `>=`, `>`, `<=`,
+/// `<` or `=` (the default) followed by a version. `None` if any part is invalid.
+pub(super) fn parse_sem_ver_range(range: &str) -> Option<Vec<SemVerComparison>> {
+    let comparisons: Vec<SemVerComparison> = range
+        .split([',', ' '])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (op, version): (fn(&SemVer, &SemVer) -> bool, &str) =
+                if let Some(v) = part.strip_prefix(">=") {
+                    (|a, b| a >= b, v)
+                } else if let Some(v) = part.strip_prefix("<=") {
+                    (|a, b| a <= b, v)
+                } else if let Some(v) = part.strip_prefix('>') {
+                    (|a, b| a > b, v)
+                } else if let Some(v) = part.strip_prefix('<') {
+                    (|a, b| a < b, v)
+                } else {
+                    (|a, b| a == b, part.strip_prefix('=').unwrap_or(part))
+                };
+            Some((op, SemVer::parse(version)?))
+        })
+        .collect::<Option<_>>()?;
+    (!comparisons.is_empty()).then_some(comparisons)
+}
+
+/// Validates the connector `type` of a linked service document against allow/deny lists, and
+/// that connectors listed in `require_integration_runtime` run through an integration runtime.
+pub fn check_connector_policy(node: &Value, params: &Value) -> bool {
+    let Some(properties) = node.get("properties") else {
+        return false; // Not a linked service
+    };
+    let Some(service_type) = properties.get("type").and_then(|v| v.as_str()) else {
+        return false;
+    };
+    let listed = |key: &str| {
+        params.get(key).and_then(|v| v.as_array()).map(|list| {
+            list.iter()
+                .filter_map(|v| v.as_str())
+                .any(|t| t.eq_ignore_ascii_case(service_type))
+        })
+    };
+
+    let allowed_ok = listed("allowed_types").unwrap_or(true);
+    let denied_ok = !listed("denied_types").unwrap_or(false);
+    let runtime_ok = !listed("require_integration_runtime").unwrap_or(false)
+        || properties
+            .pointer("/connectVia/referenceName")
+            .is_some_and(|v| !v.is_null());
+
+    allowed_ok && denied_ok && runtime_ok
+}
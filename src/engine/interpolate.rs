@@ -0,0 +1,164 @@
+use regex::Regex;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::borrow::Cow;
+use std::sync::LazyLock;
+
+/// `{{ <JSONPath> }}` in a guard param, e.g. `^{{ $.properties.folder.name }}_`.
+static PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{\{\s*(\$.*?)\s*\}\}").expect("valid regex"));
+
+/// The placeholders of a rule's params, filled in from each document it checks.
+#[derive(Default)]
+pub struct Template {
+    paths: Vec<(String, JsonPath)>,
+}
+
+impl Template {
+    /// Parses the JSONPaths of the placeholders in `params`; the invalid ones are returned,
+    /// and left as they are.
+    pub fn compile<'a>(params: impl IntoIterator<Item = &'a Value>) -> (Self, Vec<String>) {
+        let mut strings = Vec::new();
+        params
+            .into_iter()
+            .for_each(|p| collect_strings(p, &mut strings));
+        let mut template = Template::default();
+        let mut problems = Vec::new();
+        for string in strings {
+            for captures in PLACEHOLDER.captures_iter(string) {
+                let path = &captures[1];
+                if template.paths.iter().any(|(p, _)| p == path) {
+                    continue;
+                }
+                match JsonPath::parse(path) {
+                    Ok(parsed) => template.paths.push((path.to_string(), parsed)),
+                    Err(e) => problems.push(format!(
+                        "Could not parse JSONPath '{}' in params, it is not filled in: {}",
+                        path, e
+                    )),
+                }
+            }
+        }
+        (template, problems)
+    }
+
+    /// `params` with its placeholders replaced by the first node they select in `root`. A
+    /// param that is only a placeholder takes the node as it is, e.g. a number; elsewhere
+    /// strings are inserted as they are, regex-escaped in `*regex` params. `None` when a
+    /// placeholder selects nothing.
+    pub fn fill<'a>(&self, params: &'a Value, root: &Value) -> Option<Cow<'a, Value>> {
+        if self.paths.is_empty() {
+            return Some(Cow::Borrowed(params));
+        }
+        self.fill_value(params, "", root).map(Cow::Owned)
+    }
+
+    fn fill_value(&self, value: &Value, key: &str, root: &Value) -> Option<Value> {
+        Some(match value {
+            Value::String(s) => self.fill_string(s, key, root)?,
+            Value::Array(list) => Value::Array(
+                list.iter()
+                    .map(|item| self.fill_value(item, key, root))
+                    .collect::<Option<_>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| Some((k.clone(), self.fill_value(v, k, root)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            other => other.clone(),
+        })
+    }
+
+    fn fill_string(&self, s: &str, key: &str, root: &Value) -> Option<Value> {
+        let lookup = |path: &str| {
+            let (_, compiled) = self.paths.iter().find(|(p, _)| p == path)?;
+            compiled.query(root).first().cloned()
+        };
+        if let Some(captures) = PLACEHOLDER.captures(s)
+            && captures[0].len() == s.len()
+            && self.paths.iter().any(|(p, _)| p == &captures[1])
+        {
+            return lookup(&captures[1]);
+        }
+
+        let mut filled = String::with_capacity(s.len());
+        let mut last = 0;
+        for captures in PLACEHOLDER.captures_iter(s) {
+            let whole = captures.get(0).expect("group 0");
+            if !self.paths.iter().any(|(p, _)| p == &captures[1]) {
+                continue;
+            }
+            let text = match lookup(&captures[1])? {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            filled.push_str(&s[last..whole.start()]);
+            if key.ends_with("regex") {
+                filled.push_str(&regex::escape(&text));
+            } else {
+                filled.push_str(&text);
+            }
+            last = whole.end();
+        }
+        filled.push_str(&s[last..]);
+        Some(Value::String(filled))
+    }
+}
+
+/// Whether `value` is a string that is only a placeholder, which can stand for a param of
+/// any type.
+pub fn is_placeholder(value: &Value) -> bool {
+    value.as_str().is_some_and(|s| {
+        PLACEHOLDER
+            .find(s)
+            .is_some_and(|m| m.start() == 0 && m.end() == s.len())
+    })
+}
+
+/// `pattern` without its placeholders, to check the rest of a regex param.
+pub fn without_placeholders(pattern: &str) -> Cow<'_, str> {
+    PLACEHOLDER.replace_all(pattern, "")
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => strings.push(s),
+        Value::Array(list) => list.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Object(map) => map.values().for_each(|v| collect_strings(v, strings)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fill() {
+        let params = json!({
+            "regex": "^{{ $.properties.folder.name }}_",
+            "max": "{{$.properties.parameters.limit.defaultValue}}",
+            "values": ["fixed", "{{ $.name }}"],
+        });
+        let (template, problems) = Template::compile([&params, &json!("{{ $[ }}")]);
+        assert_eq!(problems.len(), 1);
+
+        let document = json!({
+            "name": "pl_load",
+            "properties": {
+                "folder": { "name": "fin.ops" },
+                "parameters": { "limit": { "defaultValue": 3 } }
+            }
+        });
+        assert_eq!(
+            template.fill(&params, &document).unwrap().into_owned(),
+            json!({ "regex": "^fin\\.ops_", "max": 3, "values": ["fixed", "pl_load"] })
+        );
+        assert!(template.fill(&params, &json!({ "name": "x" })).is_none());
+        assert!(is_placeholder(&json!("{{ $.a }}")));
+        assert!(!is_placeholder(&json!("x{{ $.a }}")));
+        assert_eq!(without_placeholders("^{{ $.a }}_[0-9]+$"), "^_[0-9]+$");
+    }
+}
@@ -0,0 +1,276 @@
+use super::taxonomy::Taxonomy;
+use super::{Diagnostic, GuardRegistry, guards, interpolate, parse_document, source};
+use crate::config::{Config, Rule, Validation};
+use crate::scanner::{self, WalkOptions};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// A rule that can't do what its author meant, found by `lint-config`.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Finding {
+    pub rule_id: String,
+    pub message: String,
+}
+
+/// Dead policy in the rules of `config`: rules whose asset matches no file under `root`,
+/// `when` clauses that can never hold, validations that always fail, and the invalid
+/// JSONPaths and params a scan would only warn about.
+pub fn lint(
+    config: &Config,
+    guards: &GuardRegistry,
+    root: &Path,
+    walk: &WalkOptions,
+) -> Result<Vec<Finding>> {
+    let (_, diagnostics) = super::compile(config, guards);
+    let mut findings: Vec<Finding> = diagnostics
+        .into_iter()
+        .filter_map(|d| {
+            Some(Finding {
+                rule_id: d.rule_id?,
+                message: d.message,
+            })
+        })
+        .collect();
+
+    let mut walk = walk.clone();
+    walk.include.extend(config.include.iter().cloned());
+    walk.exclude.extend(config.exclude.iter().cloned());
+    let taxonomy = Taxonomy::new(config, &mut Vec::<Diagnostic>::new());
+    let mut unmatched: Vec<&Rule> = config.rules.iter().collect();
+    for path in scanner::find_json_files(root, &walk)? {
+        if unmatched.is_empty() {
+            break;
+        }
+        let asset_path = path.strip_prefix(root).unwrap_or(&path);
+        let json = fs::read(&path)
+            .ok()
+            .and_then(|bytes| source::decode(bytes).ok())
+            .and_then(|source| parse_document(&source::strip_jsonc(&source)).ok());
+        unmatched.retain(|rule| !taxonomy.matches(&rule.asset, asset_path, json.as_ref()));
+    }
+    for rule in unmatched {
+        findings.push(Finding {
+            rule_id: rule.id.clone(),
+            message: format!("No file of the project is a {}", rule.asset),
+        });
+    }
+
+    for rule in &config.rules {
+        if let Some(when) = &rule.when {
+            for problem in never_passes(when) {
+                findings.push(Finding {
+                    rule_id: rule.id.clone(),
+                    message: format!("The when clause can never hold: {}", problem),
+                });
+            }
+            if let Some(problem) = contradicts(when, &rule.validate) {
+                findings.push(Finding {
+                    rule_id: rule.id.clone(),
+                    message: problem,
+                });
+            }
+        }
+        for problem in never_passes(&rule.validate) {
+            findings.push(Finding {
+                rule_id: rule.id.clone(),
+                message: format!("The validation always fails: {}", problem),
+            });
+        }
+    }
+    Ok(findings)
+}
+
+/// Params of a validation that no node can pass: empty allow-lists and bounds that
+/// exclude each other. Params filled in from the document are left alone.
+fn never_passes(validation: &Validation) -> Vec<String> {
+    let mut problems = check_params(&validation.guard, &validation.params);
+    if let Some(selector) = &validation.params_by {
+        let mut cases: Vec<_> = selector.cases.iter().collect();
+        cases.sort_by_key(|(case, _)| *case);
+        for (case, params) in cases {
+            problems.extend(
+                check_params(&validation.guard, params)
+                    .into_iter()
+                    .map(|problem| format!("{} (params_by case '{}')", problem, case)),
+            );
+        }
+    }
+    problems
+}
+
+fn check_params(guard: &str, params: &Value) -> Vec<String> {
+    let known = |key: &str| params.get(key).filter(|v| !interpolate::is_placeholder(v));
+    let empty_list = |key: &str| {
+        known(key)
+            .and_then(|v| v.as_array())
+            .is_some_and(Vec::is_empty)
+    };
+    let mut problems = Vec::new();
+
+    let bounds = match guard {
+        "Range" | "Count" | "StringLength" => known("min")
+            .and_then(|v| v.as_f64())
+            .zip(known("max").and_then(|v| v.as_f64())),
+        "Numeric" => {
+            let quantity = |key: &str| match known(key)? {
+                Value::String(text) => {
+                    guards::parse_quantity(text, (1.0, guards::Dimension::Count))
+                        .map(|(value, _)| value)
+                }
+                other => other.as_f64(),
+            };
+            quantity("min").zip(quantity("max"))
+        }
+        _ => None,
+    };
+    if let Some((min, max)) = bounds
+        && min > max
+    {
+        problems.push(format!("min ({}) is greater than max ({})", min, max));
+    }
+    if guard == "DateTime"
+        && let (Some(not_before), Some(not_after)) = (
+            known("not_before").and_then(|v| v.as_str()),
+            known("not_after").and_then(|v| v.as_str()),
+        )
+        && guards::parse_bound(not_before) > guards::parse_bound(not_after)
+    {
+        problems.push(format!(
+            "not_before ({}) is later than not_after ({})",
+            not_before, not_after
+        ));
+    }
+
+    let allow_lists: &[&str] = match guard {
+        "AllowedValues" if known("mode").and_then(|v| v.as_str()) != Some("Deny") => &["values"],
+        "ConnectorPolicy" => &["allowed_types"],
+        "AzureResourceId" => &[
+            "allowed_subscriptions",
+            "allowed_resource_groups",
+            "allowed_resource_types",
+        ],
+        _ => &[],
+    };
+    for key in allow_lists {
+        if empty_list(key) {
+            problems.push(format!("'{}' is empty, so nothing is allowed", key));
+        }
+    }
+    if guard == "MutuallyExclusive"
+        && known("required").and_then(|v| v.as_bool()) == Some(true)
+        && empty_list("fields")
+    {
+        problems.push("'fields' is empty, so none can be set".to_string());
+    }
+    if guard == "DeepMatch"
+        && let (Some(inner), Some(inner_params)) =
+            (known("guard").and_then(|v| v.as_str()), known("params"))
+    {
+        problems.extend(check_params(inner, inner_params));
+    }
+    problems
+}
+
+/// A `when` that only lets through values its `validate` rejects: both list the allowed
+/// values of the same target, with nothing in common (even ignoring case).
+fn contradicts(when: &Validation, validate: &Validation) -> Option<String> {
+    if when.target != validate.target {
+        return None;
+    }
+    let (when_values, validate_values) = (allowed_values(when)?, allowed_values(validate)?);
+    let same = |a: &Value, b: &Value| match (a.as_str(), b.as_str()) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => a == b,
+    };
+    if when_values.is_empty()
+        || when_values
+            .iter()
+            .any(|a| validate_values.iter().any(|b| same(a, b)))
+    {
+        return None;
+    }
+    Some(format!(
+        "The validation always fails when the rule applies: the when clause only lets through values of '{}' that aren't allowed",
+        when.target
+    ))
+}
+
+/// The `values` of an `AllowedValues` validation in `Allow` mode.
+fn allowed_values(validation: &Validation) -> Option<&Vec<Value>> {
+    let params = &validation.params;
+    if validation.guard != "AllowedValues"
+        || params.get("mode").and_then(|v| v.as_str()) == Some("Deny")
+    {
+        return None;
+    }
+    params.get("values")?.as_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let root = std::env::temp_dir().join(format!("adf-guardian-lint-{}", std::process::id()));
+        fs::create_dir_all(root.join("pipeline")).unwrap();
+        fs::write(root.join("pipeline/pl_a.json"), r#"{ "name": "pl_a" }"#).unwrap();
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+  - id: "dataflows"
+    asset: "dataflow"
+    validate: { target: "$.name", guard: "Exists", params: {} }
+  - id: "parallelism"
+    asset: "pipeline"
+    validate: { target: "$.properties.concurrency", guard: "Range", params: { min: 10, max: 4 } }
+  - id: "copy-only"
+    asset: "pipeline"
+    when: { target: "$.properties.activities[*].type", guard: "AllowedValues", params: { values: ["Copy"] } }
+    validate: { target: "$.properties.activities[*].type", guard: "AllowedValues", params: { values: ["Wait"] } }
+  - id: "no-connectors"
+    asset: "pipeline"
+    when: { target: "$.name", guard: "AllowedValues", params: { values: [] } }
+    validate: { target: "$", guard: "Exists", params: {} }
+"#,
+        )
+        .unwrap();
+        let findings = lint(
+            &config,
+            &GuardRegistry::default(),
+            &root,
+            &WalkOptions::default(),
+        )
+        .unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let found: Vec<(&str, &str)> = findings
+            .iter()
+            .map(|f| (f.rule_id.as_str(), f.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("dataflows", "No file of the project is a dataflow"),
+                (
+                    "parallelism",
+                    "The validation always fails: min (10) is greater than max (4)"
+                ),
+                (
+                    "copy-only",
+                    "The validation always fails when the rule applies: the when clause only lets through values of '$.properties.activities[*].type' that aren't allowed"
+                ),
+                (
+                    "no-connectors",
+                    "The when clause can never hold: 'values' is empty, so nothing is allowed"
+                ),
+            ]
+        );
+    }
+}
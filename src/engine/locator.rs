@@ -0,0 +1,376 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Returns the 1-based line and column where the value at `pointer` (RFC 6901) starts.
+/// `serde_json::Value` keeps no spans, so the source is re-scanned just far enough to find it.
+pub fn locate(source: &str, pointer: &str) -> Option<Location> {
+    let scanner = seek(source, pointer)?;
+    Some(position(source, scanner.pos))
+}
+
+/// Like `locate`, but also returns where the value ends (exclusive), e.g. for editor ranges.
+pub fn locate_span(source: &str, pointer: &str) -> Option<(Location, Location)> {
+    let mut scanner = seek(source, pointer)?;
+    let start = position(source, scanner.pos);
+    scanner.skip_value()?;
+    Some((start, position(source, scanner.pos)))
+}
+
+/// Like [`locate`] for many pointers, reading the source once for all of them rather than
+/// from its start for each. The locations are in the order of `pointers`.
+pub fn locate_all(source: &str, pointers: &[&str]) -> Vec<Option<Location>> {
+    // The values on the way to a pointer are entered, every other one skipped.
+    let mut wanted: HashSet<&str> = HashSet::new();
+    for pointer in pointers {
+        wanted.extend(pointer.match_indices('/').map(|(i, _)| &pointer[..i]));
+        wanted.insert(pointer);
+    }
+    let mut scanner = Scanner {
+        bytes: source.as_bytes(),
+        pos: 0,
+    };
+    scanner.skip_ws();
+    let mut offsets = HashMap::new();
+    // A source that stops making sense keeps the values found before.
+    let _ = scanner.find(&mut String::new(), &wanted, &mut offsets);
+
+    let mut found: Vec<(usize, usize)> = pointers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, pointer)| Some((*offsets.get(*pointer)?, i)))
+        .collect();
+    found.sort_unstable();
+    let mut locations = vec![None; pointers.len()];
+    let (mut line, mut line_start, mut counted) = (1, 0, 0);
+    for (offset, i) in found {
+        let between = &source[counted..offset];
+        line += between.matches('\n').count();
+        if let Some(newline) = between.rfind('\n') {
+            line_start = counted + newline + 1;
+        }
+        counted = offset;
+        locations[i] = Some(Location {
+            line,
+            column: source[line_start..offset].chars().count() + 1,
+        });
+    }
+    locations
+}
+
+fn seek<'a>(source: &'a str, pointer: &str) -> Option<Scanner<'a>> {
+    let mut scanner = Scanner {
+        bytes: source.as_bytes(),
+        pos: 0,
+    };
+    scanner.skip_ws();
+
+    if !pointer.is_empty() {
+        for token in pointer.strip_prefix('/')?.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            match scanner.peek()? {
+                b'{' => scanner.enter_member(&token)?,
+                b'[' => scanner.enter_index(token.parse().ok()?)?,
+                _ => return None,
+            }
+        }
+    }
+    Some(scanner)
+}
+
+fn position(source: &str, offset: usize) -> Location {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Location {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// The normalized JSONPath of the value at `pointer` in `root`, with dots for plain member
+/// names, e.g. `$.properties.activities[3].name`. `None` if nothing is there.
+pub fn json_path(root: &Value, pointer: &str) -> Option<String> {
+    let mut path = String::from("$");
+    let mut node = root;
+    if pointer.is_empty() {
+        return Some(path);
+    }
+    for token in pointer.strip_prefix('/')?.split('/') {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match node {
+            Value::Object(map) => {
+                node = map.get(&token)?;
+                let mut chars = token.chars();
+                let plain = chars
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if plain {
+                    path.push('.');
+                    path.push_str(&token);
+                } else {
+                    let escaped = token.replace('\\', "\\\\").replace('\'', "\\'");
+                    path.push_str(&format!("['{}']", escaped));
+                }
+            }
+            Value::Array(list) => {
+                let index: usize = token.parse().ok()?;
+                node = list.get(index)?;
+                path.push_str(&format!("[{}]", index));
+            }
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_ws();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    /// Moves to the value of `key` in the object starting at the current position.
+    fn enter_member(&mut self, key: &str) -> Option<()> {
+        self.expect(b'{')?;
+        loop {
+            self.skip_ws();
+            let start = self.pos;
+            self.skip_string()?;
+            let name: String = serde_json::from_slice(&self.bytes[start..self.pos]).ok()?;
+            self.expect(b':')?;
+            self.skip_ws();
+            if name == key {
+                return Some(());
+            }
+            self.skip_value()?;
+            self.expect(b',')?;
+        }
+    }
+
+    /// Moves to the element at `index` in the array starting at the current position.
+    fn enter_index(&mut self, index: usize) -> Option<()> {
+        self.expect(b'[')?;
+        for _ in 0..index {
+            self.skip_ws();
+            self.skip_value()?;
+            self.expect(b',')?;
+        }
+        self.skip_ws();
+        (self.peek()? != b']').then_some(())
+    }
+
+    /// Records where the value at the current position, `path`, and the values under it
+    /// that are `wanted` start, skipping the others.
+    fn find(
+        &mut self,
+        path: &mut String,
+        wanted: &HashSet<&str>,
+        offsets: &mut HashMap<String, usize>,
+    ) -> Option<()> {
+        offsets.entry(path.clone()).or_insert(self.pos);
+        let close = match self.peek()? {
+            b'{' => b'}',
+            b'[' => b']',
+            _ => return self.skip_value(),
+        };
+        self.pos += 1;
+        let mut index = 0;
+        loop {
+            self.skip_ws();
+            if self.peek()? == close {
+                self.pos += 1;
+                return Some(());
+            }
+            let len = path.len();
+            path.push('/');
+            if close == b'}' {
+                let start = self.pos;
+                self.skip_string()?;
+                let name: String = serde_json::from_slice(&self.bytes[start..self.pos]).ok()?;
+                path.push_str(&name.replace('~', "~0").replace('/', "~1"));
+                self.expect(b':')?;
+                self.skip_ws();
+            } else {
+                path.push_str(&index.to_string());
+                index += 1;
+            }
+            if wanted.contains(path.as_str()) {
+                self.find(path, wanted, offsets)?;
+            } else {
+                self.skip_value()?;
+            }
+            path.truncate(len);
+            self.skip_ws();
+            if self.peek()? == b',' {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_string(&mut self) -> Option<()> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => self.skip_string(),
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                loop {
+                    self.skip_ws();
+                    match self.peek()? {
+                        b if b == close => {
+                            self.pos += 1;
+                            return Some(());
+                        }
+                        b',' | b':' => self.pos += 1,
+                        _ => self.skip_value()?,
+                    }
+                }
+            }
+            _ => {
+                // Numbers, true, false and null
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+                Some(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"{
+    "name": "pl_a",
+    "properties": {
+        "description": "uses \"quotes\" and a/slash",
+        "activities": [
+            { "name": "first", "type": "Wait" },
+            {
+                "name": "second",
+                "a/b": 1
+            }
+        ]
+    }
+}"#;
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(locate(SOURCE, ""), Some(Location { line: 1, column: 1 }));
+        assert_eq!(
+            locate(SOURCE, "/name"),
+            Some(Location {
+                line: 2,
+                column: 13
+            })
+        );
+        assert_eq!(
+            locate(SOURCE, "/properties/activities/0/type"),
+            Some(Location {
+                line: 6,
+                column: 40
+            })
+        );
+        assert_eq!(
+            locate(SOURCE, "/properties/activities/1/name"),
+            Some(Location {
+                line: 8,
+                column: 25
+            })
+        );
+        assert_eq!(
+            locate(SOURCE, "/properties/activities/1/a~1b"),
+            Some(Location {
+                line: 9,
+                column: 24
+            })
+        );
+    }
+
+    #[test]
+    fn test_locate_all() {
+        let pointers = [
+            "/properties/activities/1/a~1b",
+            "/name",
+            "/missing",
+            "/properties/activities/0/type",
+            "/name",
+        ];
+        let expected: Vec<_> = pointers
+            .iter()
+            .map(|pointer| locate(SOURCE, pointer))
+            .collect();
+        assert_eq!(locate_all(SOURCE, &pointers), expected);
+        assert_eq!(expected[2], None);
+    }
+
+    #[test]
+    fn test_json_path() {
+        let root: Value = serde_json::from_str(SOURCE).unwrap();
+        assert_eq!(json_path(&root, "").as_deref(), Some("$"));
+        assert_eq!(
+            json_path(&root, "/properties/activities/1/name").as_deref(),
+            Some("$.properties.activities[1].name")
+        );
+        assert_eq!(
+            json_path(&root, "/properties/activities/1/a~1b").as_deref(),
+            Some("$.properties.activities[1]['a/b']")
+        );
+        assert_eq!(json_path(&root, "/properties/activities/7"), None);
+    }
+
+    #[test]
+    fn test_locate_span() {
+        let (start, end) = locate_span(SOURCE, "/properties/activities/0").unwrap();
+        assert_eq!((start.line, start.column), (6, 13));
+        assert_eq!((end.line, end.column), (6, 48));
+    }
+
+    #[test]
+    fn test_locate_missing() {
+        assert_eq!(locate(SOURCE, "/missing"), None);
+        assert_eq!(locate(SOURCE, "/properties/activities/2"), None);
+        assert_eq!(locate(SOURCE, "/name/0"), None);
+    }
+}
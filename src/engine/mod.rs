@@ -1,191 +1,1342 @@
+#[cfg(feature = "scan")]
+mod aggregate;
+#[cfg(feature = "scan")]
+pub mod bench;
+#[cfg(feature = "scan")]
+mod cache;
+mod external;
 mod formatter;
 mod guards;
+mod interpolate;
+#[cfg(feature = "scan")]
+pub mod lint;
+pub mod locator;
+#[cfg(feature = "opa")]
+mod opa;
+#[cfg(feature = "scan")]
+mod owners;
+pub mod project;
+mod registry;
+#[cfg(feature = "scan")]
+mod ruleset;
+mod schema;
+#[cfg(feature = "scan")]
+mod source;
+#[cfg(feature = "scan")]
+pub mod suppress;
+mod taxonomy;
+pub mod trace;
 
-use crate::config::{AssetMatcher, Config, Rule, Severity, Validation};
+#[cfg(feature = "scan")]
+pub use cache::Cache;
+pub use registry::{Guard, GuardRegistry, GuardResult, Params};
+#[cfg(feature = "scan")]
+pub use ruleset::{RuleSet, SharedRuleSet};
+pub use schema::ParamSpec;
+#[cfg(feature = "scan")]
+pub use source::{decode, strip_jsonc};
+
+#[cfg(feature = "scan")]
+use crate::archive;
+use crate::config::{Config, OnMissing, Rule, RuleScope, Severity, Validation};
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "scan")]
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use serde_json_path::JsonPath;
-use std::fs::File;
-use std::path::Path;
+use std::borrow::Cow;
+#[cfg(feature = "scan")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+#[cfg(feature = "scan")]
+use std::fs;
+#[cfg(feature = "scan")]
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use taxonomy::Taxonomy;
+
+/// Reported with `strict.unreadable_files` on files that can't be read or parsed.
+pub const UNREADABLE_FILE_RULE: &str = "strict-unreadable-file";
+/// Reported with `strict.invalid_rules` on the config file, for rules that can't be compiled.
+pub const INVALID_RULE_RULE: &str = "strict-invalid-rule";
 
-#[derive(Debug, Serialize)]
+/// A node that failed a rule (or a project check).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Violation {
     pub rule_id: String,
     pub file: String,
     pub message: String,
     pub severity: Severity,
     pub actual_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub help_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Normalized JSONPath of the failing node, e.g. `$.properties.activities[3].name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_path: Option<String>,
+    /// JSON pointer of the failing node, used to find its line and column.
+    #[serde(skip)]
+    pub pointer: Option<String>,
+    /// Name of the factory of a multi-project scan the file belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Team owning the file, from the `owners` of the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Owners of the file in the repository's CODEOWNERS file, with `--codeowners`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub code_owners: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+/// The violations found in one evaluated file; files without any are listed too.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FileResult {
     pub file: String,
     pub violations: Vec<Violation>,
+    /// Ids of the rules evaluated against the file, listed by `--verbose`.
+    #[serde(skip)]
+    pub rules_checked: Vec<String>,
+    /// Name of the factory of a multi-project scan the file belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// The text that was checked, kept when it isn't what `file` holds on disk (a Git
+    /// revision, a zip archive, stdin) and there are violations, for report snippets.
+    #[serde(skip)]
+    pub source: Option<Arc<str>>,
 }
 
-pub fn run(config: &Config, root: &Path) -> Result<Vec<FileResult>> {
-    let files = crate::scanner::find_json_files(root);
-
-    let results = files
-        .par_bridge()
-        .map(|file_path| {
-            let file_str = file_path.to_string_lossy().to_string();
-            let file_res = match File::open(&file_path) {
-                Ok(file) => file,
-                Err(e) => {
-                    eprintln!("[Warning] Could not open file {}: {}", file_str, e);
-                    return FileResult {
-                        file: file_str,
-                        violations: vec![],
-                    };
-                }
-            };
+/// Everything a scan produced: per-file results, per-rule statistics and the problems
+/// that kept files or rules from being checked.
+#[derive(Debug)]
+pub struct ScanReport {
+    pub results: Vec<FileResult>,
+    /// Violations hidden by the `suppressions` of the config.
+    pub suppressed: Vec<Violation>,
+    pub rule_stats: Vec<RuleStats>,
+    pub diagnostics: Vec<Diagnostic>,
+}
 
-            let json: Value = match serde_json::from_reader(file_res) {
-                Ok(j) => j,
-                Err(e) => {
-                    eprintln!(
-                        "[Warning] Could not parse JSON from file {}: {}",
-                        file_str, e
-                    );
-                    return FileResult {
-                        file: file_str,
-                        violations: vec![],
-                    };
-                }
-            };
+/// A file or rule that couldn't be checked, such as unreadable JSON or an invalid JSONPath.
+/// Logged as a warning when found, and listed in the JSON report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    pub message: String,
+}
 
-            let violations = config
-                .rules
-                .iter()
-                .filter(|rule| matches_asset_type(&rule.asset, &file_path))
-                .flat_map(|rule| check_rule(rule, &json, &file_path))
-                .collect::<Vec<_>>();
+impl Diagnostic {
+    #[cfg(feature = "scan")]
+    fn for_file(file: &str, message: String) -> Self {
+        Diagnostic {
+            file: Some(file.to_string()),
+            rule_id: None,
+            message,
+        }
+        .logged()
+    }
+
+    fn for_config(message: String) -> Self {
+        Diagnostic {
+            file: None,
+            rule_id: None,
+            message,
+        }
+        .logged()
+    }
+
+    fn for_rule(rule_id: &str, message: String) -> Self {
+        Diagnostic {
+            file: None,
+            rule_id: Some(rule_id.to_string()),
+            message,
+        }
+        .logged()
+    }
+
+    fn logged(self) -> Self {
+        tracing::warn!("{}", self);
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, &self.rule_id) {
+            (Some(file), _) => write!(f, "{}: {}", file, self.message),
+            (None, Some(rule_id)) => write!(f, "Rule '{}': {}", rule_id, self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// How often a rule was evaluated, how many violations it found and how long it took.
+#[derive(Debug, Clone, Default)]
+pub struct RuleStats {
+    pub rule_id: String,
+    pub files_checked: usize,
+    pub violations: usize,
+    pub elapsed: Duration,
+}
+
+/// A step of evaluating the rules on a project, timed separately by `bench`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// Walking the project (or reading the archive) for the files rules apply to.
+    Scan,
+    /// Reading, decoding and parsing the files.
+    Parse,
+    /// Matching asset types and selecting the nodes of `when` and `validate` targets.
+    Match,
+    /// Running the guards on the selected nodes and building the violations.
+    Guard,
+    /// Finding the line and column of each violation.
+    Locate,
+}
 
-            FileResult {
-                file: file_str,
-                violations,
+impl Phase {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Phase::Scan => "scan",
+            Phase::Parse => "parse",
+            Phase::Match => "match",
+            Phase::Guard => "guard",
+            Phase::Locate => "locate",
+        }
+    }
+}
+
+/// What checking a file from disk took, by [`Phase`], and what each rule took on it.
+#[derive(Debug, Clone, Default)]
+pub struct FileTiming {
+    pub bytes: usize,
+    pub parse: Duration,
+    /// Matching the asset types of the rules, and their own [`RuleTiming::matching`].
+    pub matching: Duration,
+    pub guard: Duration,
+    pub locate: Duration,
+    pub rules: Vec<RuleTiming>,
+}
+
+/// What a rule took on a file and what it found.
+#[derive(Debug, Clone, Default)]
+pub struct RuleTiming {
+    /// Position in `config.rules`.
+    pub index: usize,
+    pub matching: Duration,
+    pub guard: Duration,
+    /// Nodes its `validate` target selected.
+    pub nodes: usize,
+    pub violations: usize,
+}
+
+/// Which files a scan evaluates.
+#[derive(Debug, Default)]
+pub struct ScanOptions {
+    /// Only evaluate rules on these files (canonical paths), e.g. the files changed in a
+    /// pull request. The other files are still read for the project-level checks.
+    pub only_files: Option<HashSet<PathBuf>>,
+    /// Files larger than this many bytes are skipped with a warning instead of being parsed.
+    pub max_file_size: Option<u64>,
+    /// Reuse the results of files that haven't changed since an earlier run.
+    #[cfg(feature = "scan")]
+    pub cache: Option<Cache>,
+    /// Globs of the files to visit and skip, on top of the `include` and `exclude` of the
+    /// config.
+    #[cfg(feature = "scan")]
+    pub walk: crate::scanner::WalkOptions,
+    /// A zip archive to read the files from instead of `root`, e.g. a Git revision. They are
+    /// reported under `root`.
+    #[cfg(feature = "scan")]
+    pub archive: Option<Vec<u8>>,
+    /// Accept comments and trailing commas in files.
+    pub jsonc: bool,
+    /// Actual values longer than this many characters are cut short.
+    pub max_value_length: Option<usize>,
+    /// The guards rules can name.
+    pub guards: GuardRegistry,
+}
+
+/// A file found under the root of a scan.
+#[cfg(feature = "scan")]
+struct FoundFile {
+    path: PathBuf,
+    /// Relative to the root; decides the asset type.
+    asset_path: PathBuf,
+    /// Already read, for files of an archive.
+    contents: Option<Vec<u8>>,
+    /// `false` for files only read for the project-level checks.
+    evaluate: bool,
+}
+
+/// What a worker returns for one file.
+#[cfg(feature = "scan")]
+struct FileScan {
+    /// `None` for files that were only read for the project-level checks.
+    result: Option<FileResult>,
+    asset: Option<project::Asset>,
+    /// Kept when rules with `scope: project` apply to the file.
+    document: Option<aggregate::Document>,
+    /// `None` for files that weren't evaluated, or came from the cache.
+    timing: Option<FileTiming>,
+    diagnostic: Option<Diagnostic>,
+}
+
+/// Notified while a scan runs, e.g. to drive a progress bar or stream results.
+/// Methods are called from the worker threads, in completion order.
+pub trait ScanObserver: Sync {
+    fn started(&self, _total_files: usize) {}
+
+    fn file_finished(&self, _result: &FileResult) {}
+
+    /// Called once every file has been checked, with the project-level violations per file.
+    fn project_checked(&self, _results: &[FileResult]) {}
+
+    /// Called once the files to check are found, with how long walking the project took.
+    fn walked(&self, _elapsed: Duration) {}
+
+    /// Called after a file was evaluated from its content, with what each step took.
+    fn file_timed(&self, _timing: &FileTiming) {}
+}
+
+/// Checks every JSON file under `root`, a folder or a zip archive, in parallel.
+#[cfg(feature = "scan")]
+pub fn run(
+    config: &Config,
+    root: &Path,
+    options: &ScanOptions,
+    observer: &dyn ScanObserver,
+) -> Result<ScanReport> {
+    let (rules, mut diagnostics) = compile(config, &options.guards);
+    let taxonomy = Taxonomy::new(config, &mut diagnostics);
+    // Files no rule applies to are never opened, unless the project checks need every asset.
+    let read_all = config.project.any_enabled() || !config.require.is_empty();
+    let mut project_assets: Vec<&str> = rules
+        .iter()
+        .filter(|compiled| compiled.rule.scope == RuleScope::Project)
+        .flat_map(|compiled| compiled.rule.asset.names())
+        .map(String::as_str)
+        .collect();
+    project_assets.sort_unstable();
+    project_assets.dedup();
+
+    let mut walk = options.walk.clone();
+    walk.include.extend(config.include.iter().cloned());
+    walk.exclude.extend(config.exclude.iter().cloned());
+    let walk_started = Instant::now();
+    // Archives are read up front, files on disk by the workers.
+    let entries = match &options.archive {
+        Some(bytes) => Some(archive::read_from(Cursor::new(bytes), root, &walk)?),
+        None if archive::is_archive(root) => Some(archive::read(root, &walk)?),
+        None => None,
+    };
+    let found: Vec<(PathBuf, Option<Vec<u8>>)> = if let Some(entries) = entries {
+        entries
+            .into_iter()
+            .map(|entry| (entry.path, Some(entry.contents)))
+            .collect()
+    } else {
+        crate::scanner::find_json_files(root, &walk)?
+            .map(|path| (path, None))
+            .collect()
+    };
+    let files: Vec<FoundFile> = found
+        .into_iter()
+        .filter_map(|(path, contents)| {
+            let asset_path = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            let applies = || {
+                rules
+                    .iter()
+                    .any(|compiled| taxonomy.matches(&compiled.rule.asset, &asset_path, None))
+            };
+            if !read_all && !applies() {
+                return None;
             }
+            let evaluate = options.only_files.as_ref().is_none_or(|only| {
+                fs::canonicalize(&path).is_ok_and(|canonical| only.contains(&canonical))
+            });
+            Some(FoundFile {
+                path,
+                asset_path,
+                contents,
+                evaluate,
+            })
         })
         .collect();
+    tracing::debug!(
+        "Scanning {} file(s) with {} rule(s)",
+        files.len(),
+        rules.len()
+    );
+    observer.walked(walk_started.elapsed());
+    observer.started(files.iter().filter(|file| file.evaluate).count());
 
-    Ok(results)
-}
+    // When only some files are evaluated, the files the project-level checks tie to them are
+    // evaluated too, in a second pass (see `project::dependents`).
+    let mut unevaluated: HashMap<PathBuf, FoundFile> = if read_all && options.only_files.is_some() {
+        files
+            .iter()
+            .filter(|file| !file.evaluate)
+            .map(|file| {
+                let found = FoundFile {
+                    path: file.path.clone(),
+                    asset_path: file.asset_path.clone(),
+                    contents: file.contents.clone(),
+                    evaluate: true,
+                };
+                (file.path.clone(), found)
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+    let scan = |file: FoundFile| {
+        let mut scan = scan_file(
+            &rules,
+            &taxonomy,
+            &project_assets,
+            file,
+            options,
+            config.strict.unreadable_files,
+        );
+        if let Some(timing) = &scan.timing {
+            observer.file_timed(timing);
+        }
+        if let Some(result) = &mut scan.result {
+            limit_values(&mut result.violations, options.max_value_length);
+            observer.file_finished(result);
+        }
+        scan
+    };
+    let scans: Vec<FileScan> = files.into_par_iter().map(scan).collect();
+
+    let mut rule_stats: Vec<RuleStats> = config
+        .rules
+        .iter()
+        .map(|rule| RuleStats {
+            rule_id: rule.id.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut results = Vec::with_capacity(scans.len());
+    let mut assets = Vec::new();
+    let mut documents = Vec::new();
+    let add_stats = |rule_stats: &mut Vec<RuleStats>, timing: Option<FileTiming>| {
+        for rule in timing.into_iter().flat_map(|timing| timing.rules) {
+            let stats = &mut rule_stats[rule.index];
+            stats.files_checked += 1;
+            stats.violations += rule.violations;
+            stats.elapsed += rule.matching + rule.guard;
+        }
+    };
+    for scan in scans {
+        add_stats(&mut rule_stats, scan.timing);
+        results.extend(scan.result);
+        assets.extend(scan.asset);
+        documents.extend(scan.document);
+        diagnostics.extend(scan.diagnostic);
+    }
+    if !unevaluated.is_empty() {
+        let evaluated: HashSet<&str> = results.iter().map(|r| r.file.as_str()).collect();
+        let dependents: Vec<FoundFile> = assets
+            .iter()
+            .filter(|asset| evaluated.contains(asset.file.as_str()))
+            .flat_map(|asset| project::dependents(&assets, asset))
+            .filter_map(|file| unevaluated.remove(Path::new(file)))
+            .collect();
+        tracing::debug!(
+            "Evaluating {} file(s) that depend on the selected ones",
+            dependents.len()
+        );
+        // Their asset and document were kept by the first pass.
+        let scans: Vec<FileScan> = dependents.into_par_iter().map(scan).collect();
+        for scan in scans {
+            add_stats(&mut rule_stats, scan.timing);
+            results.extend(scan.result);
+        }
+    }
+    for compiled in &rules {
+        let skipped = compiled.skipped.load(Ordering::Relaxed);
+        if skipped > 0 {
+            diagnostics.push(Diagnostic::for_rule(
+                &compiled.rule.id,
+                format!(
+                    "Used up limits.rule_timeout ({}) and was skipped on {} file(s)",
+                    config.limits.rule_timeout.as_deref().unwrap_or_default(),
+                    skipped
+                ),
+            ));
+        }
+    }
+
+    let config_file = config.path.to_string_lossy().to_string();
+    let (aggregate_violations, timings) = aggregate::check(&rules, &documents, &config_file);
+    for (index, elapsed, violations) in timings {
+        let stats = &mut rule_stats[index];
+        stats.files_checked += documents.len();
+        stats.violations += violations;
+        stats.elapsed += elapsed;
+    }
 
-fn matches_asset_type(matcher: &AssetMatcher, file_path: &Path) -> bool {
-    let parent = file_path
-        .parent()
-        .and_then(|p| p.file_name())
-        .and_then(|s| s.to_str());
+    // Assets missing from the whole project are reported on the config file.
+    let (mut config_violations, violations): (Vec<Violation>, Vec<Violation>) =
+        project::check(&config.project, &assets)
+            .into_iter()
+            .chain(aggregate_violations)
+            .chain(project::check_requirements(
+                &config.require,
+                &assets,
+                &config_file,
+            ))
+            .partition(|v| v.file == config_file);
+    let mut project_results: Vec<FileResult> = Vec::new();
+    for violation in violations {
+        match project_results
+            .iter_mut()
+            .find(|r| r.file == violation.file)
+        {
+            Some(result) => result.violations.push(violation),
+            None => project_results.push(FileResult {
+                file: violation.file.clone(),
+                violations: vec![violation],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            }),
+        }
+    }
+    project_results.retain(|p| results.iter().any(|r| r.file == p.file));
+    for result in &mut project_results {
+        limit_values(&mut result.violations, options.max_value_length);
+    }
+    observer.project_checked(&project_results);
+    for project_result in project_results {
+        if let Some(result) = results.iter_mut().find(|r| r.file == project_result.file) {
+            result.violations.extend(project_result.violations);
+        }
+    }
+
+    owners::Owners::new(&config.owners, &mut diagnostics).assign(root, &mut results, &assets);
+
+    let suppressions = suppress::Suppressions::new(
+        &config.suppressions,
+        Utc::now().date_naive(),
+        &mut diagnostics,
+    );
+    let suppressed = suppressions.apply(root, &mut results);
+    config_violations.extend(suppressions.expired_violations(&config_file));
 
-    if let Some(folder_name) = parent {
-        match matcher {
-            AssetMatcher::Single(s) => folder_name.eq_ignore_ascii_case(s),
-            AssetMatcher::List(list) => list.iter().any(|s| folder_name.eq_ignore_ascii_case(s)),
+    if let Some(severity) = config.strict.invalid_rules {
+        config_violations.extend(
+            diagnostics
+                .iter()
+                .filter_map(|d| Some((d.rule_id.as_deref()?, &d.message)))
+                .map(|(rule_id, message)| Violation {
+                    rule_id: INVALID_RULE_RULE.to_string(),
+                    file: config_file.clone(),
+                    message: format!("Rule '{}': {}", rule_id, message),
+                    severity,
+                    ..Default::default()
+                }),
+        );
+    }
+    if !config_violations.is_empty() {
+        limit_values(&mut config_violations, options.max_value_length);
+        let result = FileResult {
+            file: config_file,
+            violations: config_violations,
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        };
+        observer.file_finished(&result);
+        results.push(result);
+    }
+
+    // Only files with violations need their source for snippets.
+    for result in &mut results {
+        if result.violations.is_empty() {
+            result.source = None;
         }
-    } else {
-        false
     }
+
+    // Workers finish in any order and directory listings aren't sorted, so order the
+    // results for reports that can be diffed between runs.
+    results.sort_by(|a, b| a.file.cmp(&b.file));
+    for result in &mut results {
+        result.violations.sort_by(|a, b| {
+            (&a.rule_id, a.line, a.column, &a.pointer)
+                .cmp(&(&b.rule_id, b.line, b.column, &b.pointer))
+        });
+    }
+
+    diagnostics.sort_by(|a, b| (&a.file, &a.rule_id).cmp(&(&b.file, &b.rule_id)));
+
+    Ok(ScanReport {
+        results,
+        suppressed,
+        rule_stats,
+        diagnostics,
+    })
 }
 
-fn check_rule(rule: &Rule, root: &Value, file_path: &Path) -> Vec<Violation> {
-    // evaluate 'when' clause if present
-    if let Some(when) = &rule.when
-        && !evaluate_condition(when, root)
+/// Cuts the actual values of `violations` to `max` characters.
+pub fn limit_values(violations: &mut [Violation], max: Option<usize>) {
+    let Some(max) = max else {
+        return;
+    };
+    for violation in violations {
+        if let Some(value) = &mut violation.actual_value
+            && let Cow::Owned(truncated) = formatter::truncate(value, max)
+        {
+            *value = truncated;
+        }
+    }
+}
+
+#[cfg(feature = "scan")]
+fn scan_file(
+    rules: &[CompiledRule],
+    taxonomy: &Taxonomy,
+    // Asset types of the rules with `scope: project`.
+    project_assets: &[&str],
+    file: FoundFile,
+    options: &ScanOptions,
+    strict: Option<Severity>,
+) -> FileScan {
+    let FoundFile {
+        path: file_path,
+        asset_path,
+        contents,
+        evaluate,
+    } = file;
+    let file_path = file_path.as_path();
+    let file_str = file_path.to_string_lossy().to_string();
+    // `severity` is `None` for files skipped on purpose, which are never violations.
+    let unreadable = |message: String, severity: Option<Severity>| FileScan {
+        result: evaluate.then(|| FileResult {
+            file: file_str.clone(),
+            violations: severity
+                .map(|severity| Violation {
+                    rule_id: UNREADABLE_FILE_RULE.to_string(),
+                    file: file_str.clone(),
+                    message: message.clone(),
+                    severity,
+                    ..Default::default()
+                })
+                .into_iter()
+                .collect(),
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }),
+        asset: None,
+        document: None,
+        timing: None,
+        diagnostic: Some(Diagnostic::for_file(&file_str, message)),
+    };
+    let keep = |json: Value, source: String| {
+        let types: Vec<String> = project_assets
+            .iter()
+            .filter(|asset| taxonomy.is(asset, &asset_path, Some(&json)))
+            .map(|asset| asset.to_string())
+            .collect();
+        (!types.is_empty()).then(|| aggregate::Document {
+            types,
+            file: file_str.clone(),
+            source,
+            json,
+        })
+    };
+
+    let size = match &contents {
+        Some(contents) => Some(contents.len() as u64),
+        None => fs::metadata(file_path).ok().map(|metadata| metadata.len()),
+    };
+    if let Some(max) = options.max_file_size
+        && let Some(size) = size
+        && size > max
     {
-        return vec![]; // Condition not met, skip rule
+        return unreadable(
+            format!("Skipped, {} bytes is over --max-file-size", size),
+            None,
+        );
     }
 
-    // evaluate 'validate' clause
-    let path = match JsonPath::parse(&rule.validate.target) {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!(
-                "[Warning] Could not parse JSONPath '{}' for rule '{}': {}",
-                &rule.validate.target, &rule.id, e
-            );
-            return vec![];
+    // Reports read snippets of files on disk themselves, but not of an archive's.
+    let from_archive = contents.is_some();
+    let parse_started = Instant::now();
+    let bytes = match contents.map_or_else(|| fs::read(file_path), Ok) {
+        Ok(bytes) => bytes,
+        Err(e) => return unreadable(format!("Could not read file: {}", e), strict),
+    };
+    let source = match source::decode(bytes) {
+        Ok(source) if options.jsonc => source::strip_jsonc(&source),
+        Ok(source) => source,
+        Err(e) => return unreadable(format!("Could not read file: {}", e), strict),
+    };
+    let kept_source = from_archive.then(|| Arc::<str>::from(source.as_str()));
+
+    if let Some(entry) = options
+        .cache
+        .as_ref()
+        .and_then(|cache| cache.get(file_path, &source))
+    {
+        tracing::debug!("{}: unchanged, using the cached result", file_str);
+        return FileScan {
+            result: evaluate.then(|| FileResult {
+                file: file_str.clone(),
+                violations: entry.violations,
+                rules_checked: entry.rules_checked,
+                project: None,
+                source: kept_source,
+            }),
+            asset: Some(entry.asset),
+            // Cached entries don't keep the document, so it is parsed again when needed.
+            document: if project_assets.is_empty() {
+                None
+            } else {
+                parse_document(&source)
+                    .ok()
+                    .and_then(|json| keep(json, source))
+            },
+            timing: None,
+            diagnostic: None,
+        };
+    }
+
+    let json: Value = match parse_document(&source) {
+        Ok(j) => j,
+        Err(e) => return unreadable(format!("Could not parse JSON: {}", e), strict),
+    };
+
+    let asset = project::Asset::from_document(file_path, &json);
+    if !evaluate {
+        return FileScan {
+            result: None,
+            asset: Some(asset),
+            document: keep(json, source),
+            timing: None,
+            diagnostic: None,
+        };
+    }
+    let parsed = parse_started.elapsed();
+
+    let (mut result, mut timing, complete) = evaluate_document(
+        rules,
+        taxonomy,
+        file_path,
+        &asset_path,
+        &source,
+        &json,
+        true,
+    );
+    result.source = kept_source;
+    timing.parse = parsed;
+    timing.bytes = source.len();
+    let asset = match &options.cache {
+        // A rule out of its time budget would be missing from the entry for good.
+        Some(cache) if complete => {
+            let entry = cache::Entry {
+                violations: result.violations.clone(),
+                rules_checked: result.rules_checked.clone(),
+                asset,
+            };
+            cache.put(file_path, &source, &entry);
+            entry.asset
         }
+        _ => asset,
     };
+    FileScan {
+        result: Some(result),
+        asset: Some(asset),
+        document: keep(json, source),
+        timing: Some(timing),
+        diagnostic: None,
+    }
+}
+
+#[cfg(all(feature = "scan", not(feature = "fast-parse")))]
+fn parse_document(source: &str) -> Result<Value> {
+    Ok(serde_json::from_str(source)?)
+}
 
-    let nodes = path.query(root);
+/// simd-json parses in place, so it works on a copy; the source is still needed to
+/// locate violations.
+#[cfg(feature = "fast-parse")]
+fn parse_document(source: &str) -> Result<Value> {
+    let mut bytes = source.as_bytes().to_vec();
+    Ok(simd_json::serde::from_slice(&mut bytes)?)
+}
+
+/// Evaluates the rules on a document that is not on disk, such as an editor buffer.
+/// `file_path` only decides which rules apply; project-level checks are not run.
+pub fn check_source(
+    config: &Config,
+    guards: &GuardRegistry,
+    file_path: &Path,
+    source: &str,
+) -> serde_json::Result<FileResult> {
+    let json: Value = serde_json::from_str(source)?;
+    let (rules, mut diagnostics) = compile(config, guards);
+    let taxonomy = Taxonomy::new(config, &mut diagnostics);
+    Ok(evaluate_document(
+        &rules, &taxonomy, file_path, file_path, source, &json, false,
+    )
+    .0)
+}
 
-    nodes
+fn evaluate_document(
+    rules: &[CompiledRule],
+    taxonomy: &Taxonomy,
+    file_path: &Path,
+    // Relative to the root of the scan.
+    asset_path: &Path,
+    source: &str,
+    json: &Value,
+    // `Instant` isn't available in the browser, so only scans from disk are timed.
+    timed: bool,
+) -> (FileResult, FileTiming, bool) {
+    let mut watch = Stopwatch::new(timed);
+    let (applicable, out_of_time): (Vec<&CompiledRule>, Vec<_>) = rules
         .iter()
-        .filter(|node| !check_guard(node, &rule.validate.guard, &rule.validate.params))
-        .map(|node| {
-            let formatted_value = formatter::format_actual_value(&rule.validate.guard, node);
-            Violation {
-                rule_id: rule.id.clone(),
-                file: file_path.to_string_lossy().to_string(),
-                message: rule
-                    .description
-                    .clone()
-                    .unwrap_or_else(|| "Rule violation".to_string()),
-                severity: rule.severity,
-                actual_value: Some(formatted_value),
-            }
+        .filter(|compiled| compiled.rule.scope == RuleScope::File)
+        .filter(|compiled| taxonomy.matches(&compiled.rule.asset, asset_path, Some(json)))
+        .partition(|compiled| !compiled.out_of_time());
+    let mut timing = FileTiming {
+        matching: watch.lap(),
+        ..Default::default()
+    };
+    let evaluate = |compiled: &CompiledRule| {
+        let started = timed.then(Instant::now);
+        // What is left of the budget, so a rule also stops in the middle of a large file.
+        let deadline = started.zip(compiled.budget).map(|(started, budget)| {
+            let spent = Duration::from_micros(compiled.spent.load(Ordering::Relaxed));
+            started + budget.saturating_sub(spent)
+        });
+        let mut rule_timing = RuleTiming {
+            index: compiled.index,
+            ..Default::default()
+        };
+        let violations = check_rule_until(
+            compiled,
+            json,
+            file_path,
+            deadline,
+            timed.then_some(&mut rule_timing),
+        );
+        rule_timing.violations = violations.as_ref().map_or(0, Vec::len);
+        if violations.is_none() {
+            compiled.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        let elapsed = started.map(|s| s.elapsed()).unwrap_or_default();
+        compiled
+            .spent
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        (rule_timing, violations)
+    };
+
+    // Files are already checked in parallel, so only a large one gets the workers to itself.
+    let parallel = source.len() >= PARALLEL_FILE_SIZE;
+    let evaluated = par_map(applicable.clone(), parallel, evaluate);
+    let complete = out_of_time.is_empty() && evaluated.iter().all(|(_, v)| v.is_some());
+    let rules_checked = applicable
+        .iter()
+        .zip(&evaluated)
+        .filter(|(_, (_, violations))| violations.is_some())
+        .map(|(compiled, _)| compiled.rule.id.clone())
+        .collect();
+    let mut violations = Vec::new();
+    for (rule_timing, rule_violations) in evaluated {
+        timing.matching += rule_timing.matching;
+        timing.guard += rule_timing.guard;
+        timing.rules.push(rule_timing);
+        violations.extend(rule_violations.unwrap_or_default());
+    }
+    watch.lap();
+    let pointers: Vec<&str> = violations
+        .iter()
+        .map(|violation| violation.pointer.as_deref().unwrap_or_default())
+        .collect();
+    let locations = locator::locate_all(source, &pointers);
+    for (violation, location) in violations.iter_mut().zip(locations) {
+        // Whole-document matches have no useful location.
+        if let Some(location) =
+            location.filter(|_| violation.pointer.as_deref().is_some_and(|p| !p.is_empty()))
+        {
+            violation.line = Some(location.line);
+            violation.column = Some(location.column);
+        }
+    }
+    timing.locate = watch.lap();
+
+    let result = FileResult {
+        file: file_path.to_string_lossy().to_string(),
+        violations,
+        rules_checked,
+        project: None,
+        source: None,
+    };
+    (result, timing, complete)
+}
+
+/// Files from this many bytes have their rules, and the violations of those, evaluated in
+/// parallel, so a single huge ARM export doesn't leave the other workers idle.
+const PARALLEL_FILE_SIZE: usize = 4 * 1024 * 1024;
+/// Rules that select this many nodes check them in parallel.
+const PARALLEL_NODES: usize = 1024;
+
+/// `items` mapped in order, by the rayon workers when `parallel`.
+#[cfg(feature = "scan")]
+fn par_map<T: Send, R: Send>(
+    items: Vec<T>,
+    parallel: bool,
+    f: impl Fn(T) -> R + Send + Sync,
+) -> Vec<R> {
+    if parallel {
+        items.into_par_iter().map(f).collect()
+    } else {
+        items.into_iter().map(f).collect()
+    }
+}
+
+/// Without threads, e.g. in the browser, everything is mapped in order.
+#[cfg(not(feature = "scan"))]
+fn par_map<T, R>(items: Vec<T>, _parallel: bool, f: impl Fn(T) -> R) -> Vec<R> {
+    items.into_iter().map(f).collect()
+}
+
+/// A rule ready to evaluate: JSONPaths parsed, guard looked up and regexes compiled once,
+/// then shared by the workers.
+pub struct CompiledRule<'a> {
+    /// Position in `config.rules`, for the per-rule statistics.
+    index: usize,
+    rule: &'a Rule,
+    /// The rule's severity, or `Error` once its `promote` date or release is reached.
+    severity: Severity,
+    when: Option<CompiledValidation<'a>>,
+    validate: CompiledValidation<'a>,
+    /// `limits.rule_timeout`: how long the rule may take over all files.
+    budget: Option<Duration>,
+    /// Microseconds spent so far, by every worker.
+    spent: AtomicU64,
+    /// Files the rule wasn't evaluated on, or stopped on, because its budget ran out.
+    skipped: AtomicUsize,
+}
+
+struct CompiledValidation<'a> {
+    validation: &'a Validation,
+    path: JsonPath,
+    params_by: Option<JsonPath>,
+    /// `None` for unknown guards, which pass.
+    guard: Option<&'a dyn Guard>,
+    regexes: guards::Regexes,
+    /// `{{ <JSONPath> }}` placeholders of the params.
+    template: interpolate::Template,
+}
+
+/// Unknown guards and params that don't fit their guard, one message per problem. `compile`
+/// only reports these as diagnostics; the CLI refuses to run with them unless `--lenient`.
+pub fn check_guards(config: &Config, guards: &GuardRegistry) -> Vec<String> {
+    config
+        .rules
+        .iter()
+        .flat_map(|rule| {
+            rule.when
+                .iter()
+                .chain([&rule.validate])
+                .flat_map(|validation| match guards.get(&validation.guard) {
+                    Some(guard) => param_problems(validation, guard),
+                    None => vec![format!("Unknown guard '{}'", validation.guard)],
+                })
+                .map(move |problem| format!("Rule '{}': {}", rule.id, problem))
         })
         .collect()
 }
 
-fn evaluate_condition(validation: &Validation, root: &Value) -> bool {
-    let path = match JsonPath::parse(&validation.target) {
-        Ok(p) => p,
-        Err(_) => return false,
+/// Problems with the params of a validation, including its `params_by` cases.
+fn param_problems(validation: &Validation, guard: &dyn Guard) -> Vec<String> {
+    let mut problems = guard.check_params(&validation.params);
+    if let Some(selector) = &validation.params_by {
+        let mut cases: Vec<_> = selector.cases.iter().collect();
+        cases.sort_by_key(|(case, _)| *case);
+        for (case, params) in cases {
+            problems.extend(
+                guard
+                    .check_params(params)
+                    .into_iter()
+                    .map(|problem| format!("{} (params_by case '{}')", problem, case)),
+            );
+        }
+    }
+    problems
+}
+
+/// Compiles the rules of `config`. Rules that can't be compiled are left out; what went
+/// wrong is returned alongside.
+pub fn compile<'a>(
+    config: &'a Config,
+    guards: &'a GuardRegistry,
+) -> (Vec<CompiledRule<'a>>, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+    let budget = config.limits.rule_timeout.as_deref().and_then(|timeout| {
+        match guards::parse_quantity(timeout, (1.0, guards::Dimension::Duration)) {
+            Some((seconds, guards::Dimension::Duration)) if seconds > 0.0 => {
+                // Out of range for a `Duration` when huge.
+                Duration::try_from_secs_f64(seconds).ok()
+            }
+            _ => None,
+        }
+        .or_else(|| {
+            diagnostics.push(Diagnostic::for_config(format!(
+                "Invalid limits.rule_timeout '{}', expected a duration like '30s'; rules are not timed",
+                timeout
+            )));
+            None
+        })
+    });
+    let rules = config
+        .rules
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| CompiledRule::new(index, rule, guards, &mut diagnostics))
+        .map(|compiled| CompiledRule { budget, ..compiled })
+        .collect();
+    (rules, diagnostics)
+}
+
+impl<'a> CompiledRule<'a> {
+    fn new(
+        index: usize,
+        rule: &'a Rule,
+        guards: &'a GuardRegistry,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<Self> {
+        let severity = promoted_severity(rule, Utc::now(), diagnostics);
+        let mut compile = |validation: &'a Validation| {
+            CompiledValidation::new(validation, rule, guards, diagnostics).map_err(|e| {
+                diagnostics.push(Diagnostic::for_rule(
+                    &rule.id,
+                    format!(
+                        "Could not parse JSONPath '{}', the rule is skipped: {}",
+                        validation.target, e
+                    ),
+                ));
+            })
+        };
+        let when = match &rule.when {
+            Some(when) => Some(compile(when).ok()?),
+            None => None,
+        };
+        Some(CompiledRule {
+            index,
+            rule,
+            severity,
+            when,
+            validate: compile(&rule.validate).ok()?,
+            budget: None,
+            spent: AtomicU64::new(0),
+            skipped: AtomicUsize::new(0),
+        })
+    }
+
+    /// Whether the rule used up its time budget, counting the file it isn't evaluated on.
+    fn out_of_time(&self) -> bool {
+        let Some(budget) = self.budget else {
+            return false;
+        };
+        let out = self.spent.load(Ordering::Relaxed) >= budget.as_micros() as u64;
+        if out {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        out
+    }
+}
+
+/// `Error` for a `Warning` rule whose `promote` date or adf-guardian release is reached at
+/// `now`, the rule's own severity otherwise.
+fn promoted_severity(
+    rule: &Rule,
+    now: DateTime<Utc>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Severity {
+    let Some(promote) = &rule.promote else {
+        return rule.severity;
+    };
+    match guards::promotion_due(promote, now, env!("CARGO_PKG_VERSION")) {
+        Some(true) => Severity::Error,
+        Some(false) => rule.severity,
+        None => {
+            diagnostics.push(Diagnostic::for_rule(
+                &rule.id,
+                format!(
+                    "Invalid promote '{}', expected a date like '2026-07-01' or a release like 'v0.4.0'; the severity is kept",
+                    promote
+                ),
+            ));
+            rule.severity
+        }
+    }
+}
+
+impl<'a> CompiledValidation<'a> {
+    fn new(
+        validation: &'a Validation,
+        rule: &Rule,
+        guards: &'a GuardRegistry,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<Self, serde_json_path::ParseError> {
+        let params_by = validation.params_by.as_ref().and_then(|selector| {
+            JsonPath::parse(&selector.target)
+                .map_err(|e| {
+                    diagnostics.push(Diagnostic::for_rule(
+                        &rule.id,
+                        format!(
+                            "Could not parse JSONPath '{}' in params_by, the default params are used: {}",
+                            selector.target, e
+                        ),
+                    ));
+                })
+                .ok()
+        });
+        let guard = guards.get(&validation.guard);
+        match guard {
+            Some(guard) => {
+                for problem in param_problems(validation, guard) {
+                    diagnostics.push(Diagnostic::for_rule(&rule.id, problem));
+                }
+            }
+            None => diagnostics.push(Diagnostic::for_rule(
+                &rule.id,
+                format!("Unknown guard '{}', the check is skipped", validation.guard),
+            )),
+        }
+        let cases = || validation.params_by.iter().flat_map(|s| s.cases.values());
+        let (template, problems) =
+            interpolate::Template::compile([&validation.params].into_iter().chain(cases()));
+        for problem in problems {
+            diagnostics.push(Diagnostic::for_rule(&rule.id, problem));
+        }
+
+        Ok(CompiledValidation {
+            validation,
+            path: JsonPath::parse(&validation.target)?,
+            params_by,
+            guard,
+            regexes: guards::Regexes::compile([&validation.params].into_iter().chain(cases())),
+            template,
+        })
+    }
+
+    /// Checks `node`, selected in the document `root`.
+    fn check(&self, node: &Value, params: &Value, root: &Value) -> GuardResult {
+        match self.guard {
+            Some(guard) => guard.check(node, &Params::new(params, &self.regexes, root)),
+            None => GuardResult::Pass,
+        }
+    }
+
+    /// Returns the params selected by `params_by`, or the validation's own params, with
+    /// their placeholders filled in from `root`. `None` when a placeholder selects nothing.
+    fn resolve_params(&self, root: &Value) -> Option<Cow<'a, Value>> {
+        let validation = self.validation;
+        let params = match (&validation.params_by, &self.params_by) {
+            (Some(selector), Some(path)) => path
+                .query(root)
+                .iter()
+                .find_map(|node| {
+                    let key = match node {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    selector.cases.get(&key)
+                })
+                .unwrap_or(&validation.params),
+            _ => &validation.params,
+        };
+        self.template.fill(params, root)
+    }
+}
+
+fn check_rule(compiled: &CompiledRule, root: &Value, file_path: &Path) -> Vec<Violation> {
+    check_rule_until(compiled, root, file_path, None, None).unwrap_or_default()
+}
+
+/// Like [`check_rule`], giving up with `None` once `deadline` passes, between two nodes.
+/// With `timing`, the time spent selecting nodes and running the guard is added to it.
+fn check_rule_until(
+    compiled: &CompiledRule,
+    root: &Value,
+    file_path: &Path,
+    deadline: Option<Instant>,
+    timing: Option<&mut RuleTiming>,
+) -> Option<Vec<Violation>> {
+    let rule = compiled.rule;
+    let mut watch = Stopwatch::new(timing.is_some());
+    let mut untimed = RuleTiming::default();
+    let timing = timing.unwrap_or(&mut untimed);
+
+    // evaluate 'when' clause if present
+    if let Some(when) = &compiled.when
+        && !evaluate_condition(when, root, &mut watch, timing)
+    {
+        return Some(vec![]); // Condition not met, skip rule
+    }
+
+    // evaluate 'validate' clause
+    let validate = &compiled.validate;
+    let nodes = validate.path.query_located(root);
+    timing.nodes = nodes.len();
+    timing.matching += watch.lap();
+    if nodes.is_empty() {
+        let severity = match rule.validate.on_missing {
+            OnMissing::Pass => return Some(vec![]),
+            OnMissing::Fail => compiled.severity,
+            OnMissing::Warn => Severity::Warning,
+        };
+        return Some(vec![Violation {
+            rule_id: rule.id.clone(),
+            file: file_path.to_string_lossy().to_string(),
+            message: rule
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Nothing matches '{}'", rule.validate.target)),
+            severity,
+            help_url: rule.help_url.clone(),
+            ..Default::default()
+        }]);
+    }
+    // A placeholder that selects nothing leaves nothing to compare with.
+    let params = validate.resolve_params(root);
+    timing.matching += watch.lap();
+    let Some(params) = params else {
+        return Some(vec![]);
     };
 
-    let nodes = path.query(root);
+    let nodes: Vec<_> = nodes.iter().collect();
+    let parallel = nodes.len() >= PARALLEL_NODES;
+    let stopped = AtomicBool::new(false);
+    let violations = par_map(nodes, parallel, |node| {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            stopped.store(true, Ordering::Relaxed);
+            return None;
+        }
+        let message = match validate.check(node.node(), &params, root) {
+            GuardResult::Pass => return None,
+            GuardResult::Fail => rule
+                .description
+                .clone()
+                .unwrap_or_else(|| "Rule violation".to_string()),
+            GuardResult::FailWith(message) => message,
+        };
+        let actual_value = rule.report_value.then(|| {
+            let formatted = formatter::format_actual_value(&rule.validate.guard, node.node());
+            formatter::redact_secrets(&formatted).into_owned()
+        });
+        let pointer = node.location().to_json_pointer();
+        Some(Violation {
+            rule_id: rule.id.clone(),
+            file: file_path.to_string_lossy().to_string(),
+            message,
+            severity: compiled.severity,
+            actual_value,
+            help_url: rule.help_url.clone(),
+            json_path: locator::json_path(root, &pointer),
+            pointer: Some(pointer),
+            ..Default::default()
+        })
+    });
+    timing.guard += watch.lap();
+    (!stopped.into_inner()).then(|| violations.into_iter().flatten().collect())
+}
+
+fn evaluate_condition(
+    when: &CompiledValidation,
+    root: &Value,
+    watch: &mut Stopwatch,
+    timing: &mut RuleTiming,
+) -> bool {
+    let nodes = when.path.query(root);
+    let params = when.resolve_params(root);
+    timing.matching += watch.lap();
 
     if nodes.is_empty() {
         return false;
     }
 
-    nodes
+    let Some(params) = params else {
+        return false;
+    };
+    let holds = nodes
         .iter()
-        .all(|node| check_guard(node, &validation.guard, &validation.params))
-}
-
-fn check_guard(node: &Value, guard: &str, params: &Value) -> bool {
-    match guard {
-        "PatternMatch" => guards::check_pattern_match(node, params),
-        "AllowedValues" => guards::check_allowed_values(node, params),
-        "Exists" => guards::check_exists(node, params),
-        "Range" => guards::check_range(node, params),
-        "Count" => guards::check_count(node, params),
-        "StringLength" => guards::check_string_length(node, params),
-        other => {
-            eprintln!(
-                "[Warning] Unknown guard '{}', the check will be skipped.",
-                other
-            );
-            true // Unknown guard, pass
-        }
+        .all(|node| when.check(node, &params, root) == GuardResult::Pass);
+    timing.guard += watch.lap();
+    holds
+}
+
+/// Splits the time spent on a file or a rule between its steps, when timed.
+struct Stopwatch(Option<Instant>);
+
+impl Stopwatch {
+    fn new(timed: bool) -> Self {
+        Stopwatch(timed.then(Instant::now))
+    }
+
+    /// The time since the previous lap, or since the start; zero when not timed.
+    fn lap(&mut self) -> Duration {
+        let Some(last) = &mut self.0 else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let lap = now - *last;
+        *last = now;
+        lap
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{AssetMatcher, Validation};
+    use crate::config::{AssetMatcher, OnMissing, RuleScope, Validation};
+    use guards::Regexes;
     use serde_json::json;
 
     #[test]
     fn test_pattern_match() {
         let params = json!({ "regex": "^test" });
-        assert!(guards::check_pattern_match(&json!("test_value"), &params));
-        assert!(!guards::check_pattern_match(&json!("value_test"), &params));
+        assert!(guards::check_pattern_match(
+            &json!("test_value"),
+            &params,
+            &Regexes::default()
+        ));
+        assert!(!guards::check_pattern_match(
+            &json!("value_test"),
+            &params,
+            &Regexes::default()
+        ));
 
         let params_negative = json!({ "regex": "^test", "negative": true });
         assert!(!guards::check_pattern_match(
             &json!("test_value"),
-            &params_negative
+            &params_negative,
+            &Regexes::default()
         ));
         assert!(guards::check_pattern_match(
             &json!("value_test"),
-            &params_negative
+            &params_negative,
+            &Regexes::default()
         ));
     }
 
@@ -269,27 +1420,900 @@ mod tests {
     }
 
     #[test]
-    fn test_check_rule_when_clause_met() {
-        let rule = Rule {
-            id: "test-when-met".to_string(),
-            asset: AssetMatcher::Single("pipeline".to_string()),
-            description: None,
-            severity: Severity::Error,
-            when: Some(Validation {
-                target: "$.properties.type".to_string(),
-                guard: "AllowedValues".to_string(),
-                params: json!({ "values": ["MappingDataFlow"] }),
-            }),
-            validate: Validation {
-                target: "$.name".to_string(),
-                guard: "PatternMatch".to_string(),
+    fn test_copy_activity() {
+        let activity = json!({
+            "name": "copy_to_dw",
+            "type": "Copy",
+            "typeProperties": {
+                "source": { "type": "AzureSqlSource" },
+                "sink": { "type": "SqlDWSink", "writeBehavior": "insert" },
+                "enableStaging": false,
+                "parallelCopies": 8,
+                "dataIntegrationUnits": 128
+            }
+        });
+
+        let params_staging = json!({ "staging_required_for_types": ["SqlDWSink"] });
+        assert!(!guards::check_copy_activity(&activity, &params_staging));
+        let params_other_sink = json!({ "staging_required_for_types": ["SnowflakeSink"] });
+        assert!(guards::check_copy_activity(&activity, &params_other_sink));
+
+        let params_skip_rows = json!({ "skip_incompatible_row": false });
+        assert!(guards::check_copy_activity(&activity, &params_skip_rows));
+
+        let params_limits = json!({ "max_parallel_copies": 16, "max_data_integration_units": 64 });
+        assert!(!guards::check_copy_activity(&activity, &params_limits));
+        let params_parallel = json!({ "max_parallel_copies": 16 });
+        assert!(guards::check_copy_activity(&activity, &params_parallel));
+
+        let params_write = json!({ "allowed_write_behaviors": { "SqlDWSink": ["upsert"] } });
+        assert!(!guards::check_copy_activity(&activity, &params_write));
+        let params_write_other =
+            json!({ "allowed_write_behaviors": { "AzureSqlSink": ["upsert"] } });
+        assert!(guards::check_copy_activity(&activity, &params_write_other));
+        let params_write_list = json!({ "allowed_write_behaviors": ["insert", "upsert"] });
+        assert!(guards::check_copy_activity(&activity, &params_write_list));
+
+        // Activities that aren't copies have nothing to check.
+        let wait = json!({ "name": "wait", "type": "Wait" });
+        assert!(guards::check_copy_activity(&wait, &params_staging));
+        assert!(guards::check_copy_activity(
+            &json!("not an activity"),
+            &params_skip_rows
+        ));
+    }
+
+    #[test]
+    fn test_web_activity() {
+        let activity = json!({
+            "name": "call_api",
+            "type": "WebActivity",
+            "typeProperties": {
+                "url": "http://api.contoso.com:8080/v1/run",
+                "method": "POST",
+                "headers": { "Content-Type": "application/json", "x-api-key": "abc123" },
+                "authentication": {
+                    "type": "Basic",
+                    "username": "svc",
+                    "password": { "type": "SecureString", "value": "**********" }
+                }
+            }
+        });
+
+        assert!(!guards::check_web_activity(
+            &activity,
+            &json!({ "https_only": true }),
+            &Regexes::default()
+        ));
+        assert!(guards::check_web_activity(
+            &activity,
+            &json!({ "allowed_hosts": ["*.contoso.com"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_web_activity(
+            &activity,
+            &json!({ "allowed_hosts": ["contoso.com"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_web_activity(
+            &activity,
+            &json!({ "allowed_authentication": ["MSI", "ClientCertificate"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_web_activity(
+            &activity,
+            &json!({ "forbid_inline_password": true }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_web_activity(
+            &activity,
+            &json!({ "secret_header_names": ["X-API-Key"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_web_activity(
+            &activity,
+            &json!({ "secret_value_regex": "^[a-z]+[0-9]+$" }),
+            &Regexes::default()
+        ));
+
+        let expression_url = json!({
+            "type": "WebHook",
+            "typeProperties": {
+                "url": { "value": "@pipeline().parameters.url", "type": "Expression" },
+                "authentication": { "type": "MSI", "resource": "https://management.azure.com" },
+                "headers": [{ "name": "Authorization", "value": "@variables('token')" }]
+            }
+        });
+        let params = json!({
+            "https_only": true,
+            "allowed_hosts": ["*.azure.com"],
+            "allowed_authentication": ["MSI"],
+            "forbid_inline_password": true,
+            "secret_header_names": ["Authorization"]
+        });
+        assert!(guards::check_web_activity(
+            &expression_url,
+            &params,
+            &Regexes::default()
+        ));
+    }
+
+    #[test]
+    fn test_compute_cluster() {
+        let databricks = json!({
+            "name": "ls_databricks",
+            "properties": {
+                "type": "AzureDatabricks",
+                "typeProperties": {
+                    "domain": "https://adb-123.azuredatabricks.net",
+                    "newClusterNodeType": "Standard_DS3_v2",
+                    "newClusterNumOfWorker": "2:20"
+                }
+            }
+        });
+
+        assert!(guards::check_compute_cluster(
+            &databricks,
+            &json!({ "forbid_existing_cluster": true })
+        ));
+        assert!(!guards::check_compute_cluster(
+            &databricks,
+            &json!({ "require_instance_pool": true })
+        ));
+        assert!(guards::check_compute_cluster(
+            &databricks,
+            &json!({ "allowed_node_types": ["Standard_DS3_v2"] })
+        ));
+        assert!(!guards::check_compute_cluster(
+            &databricks,
+            &json!({ "allowed_node_types": ["Standard_DS4_v2"] })
+        ));
+        assert!(guards::check_compute_cluster(
+            &databricks,
+            &json!({ "min_workers": 1, "max_workers": 20 })
+        ));
+        assert!(!guards::check_compute_cluster(
+            &databricks,
+            &json!({ "max_workers": 10 })
+        ));
+
+        let interactive = json!({
+            "properties": {
+                "type": "AzureDatabricks",
+                "typeProperties": { "existingClusterId": "0101-123456-abc123" }
+            }
+        });
+        assert!(!guards::check_compute_cluster(
+            &interactive,
+            &json!({ "forbid_existing_cluster": true })
+        ));
+        // An existing cluster has no pool to take its nodes from.
+        assert!(guards::check_compute_cluster(
+            &interactive,
+            &json!({ "require_instance_pool": true })
+        ));
+        let pooled = json!({
+            "properties": {
+                "type": "AzureDatabricks",
+                "typeProperties": {
+                    "newClusterVersion": "13.3.x-scala2.12",
+                    "instancePoolId": "0101-123456-pool1"
+                }
+            }
+        });
+        assert!(guards::check_compute_cluster(
+            &pooled,
+            &json!({ "require_instance_pool": true })
+        ));
+
+        // A bring-your-own HDInsight cluster isn't necessarily interactive.
+        let byo_hdinsight = json!({
+            "properties": {
+                "type": "HDInsight",
+                "typeProperties": { "clusterUri": "https://contoso.azurehdinsight.net" }
+            }
+        });
+        assert!(guards::check_compute_cluster(
+            &byo_hdinsight,
+            &json!({ "forbid_existing_cluster": true })
+        ));
+
+        let hdinsight = json!({
+            "properties": {
+                "type": "HDInsightOnDemand",
+                "typeProperties": { "clusterSize": 16, "dataNodeSize": "Standard_D4_v2" }
+            }
+        });
+        assert!(!guards::check_compute_cluster(
+            &hdinsight,
+            &json!({ "max_workers": 8 })
+        ));
+        assert!(!guards::check_compute_cluster(&json!({}), &json!({})));
+    }
+
+    #[test]
+    fn test_trigger_policy() {
+        let tumbling = json!({
+            "name": "tr_hourly",
+            "properties": {
+                "type": "TumblingWindowTrigger",
+                "runtimeState": "Started",
+                "typeProperties": { "frequency": "Hour", "interval": 1, "maxConcurrency": 10 }
+            }
+        });
+        assert!(!guards::check_trigger_policy(
+            &tumbling,
+            &json!({ "forbid_started": true })
+        ));
+        assert!(!guards::check_trigger_policy(
+            &tumbling,
+            &json!({ "require_retry_policy": true })
+        ));
+        assert!(guards::check_trigger_policy(
+            &tumbling,
+            &json!({ "max_concurrency": 10 })
+        ));
+        assert!(!guards::check_trigger_policy(
+            &tumbling,
+            &json!({ "max_concurrency": 5 })
+        ));
+
+        let schedule = json!({
+            "properties": {
+                "type": "ScheduleTrigger",
+                "runtimeState": "Stopped",
+                "typeProperties": { "recurrence": { "frequency": "Day", "timeZone": "UTC" } }
+            }
+        });
+        let params_tz = json!({ "allowed_time_zones": ["utc"], "forbid_started": true });
+        assert!(guards::check_trigger_policy(&schedule, &params_tz));
+        assert!(!guards::check_trigger_policy(
+            &schedule,
+            &json!({ "allowed_time_zones": ["E. South America Standard Time"] })
+        ));
+
+        let events = json!({
+            "properties": {
+                "type": "BlobEventsTrigger",
+                "typeProperties": {
+                    "scope": "/subscriptions/0000/resourceGroups/rg/providers/Microsoft.Storage/storageAccounts/stlanding"
+                }
+            }
+        });
+        assert!(guards::check_trigger_policy(
+            &events,
+            &json!({ "allowed_storage_accounts": ["stlanding"] })
+        ));
+        assert!(!guards::check_trigger_policy(
+            &events,
+            &json!({ "allowed_storage_accounts": ["stother"] })
+        ));
+
+        // Settings for other trigger types don't apply
+        assert!(guards::check_trigger_policy(&events, &params_tz));
+        assert!(!guards::check_trigger_policy(
+            &json!("not a trigger"),
+            &params_tz
+        ));
+    }
+
+    #[test]
+    fn test_credential_usage() {
+        let inline = json!({
+            "name": "ls_adls",
+            "properties": {
+                "type": "AzureBlobFS",
+                "typeProperties": {
+                    "url": "https://stlake.dfs.core.windows.net",
+                    "servicePrincipalId": "00000000-0000-0000-0000-000000000000",
+                    "servicePrincipalKey": { "type": "SecureString", "value": "**********" }
+                }
+            }
+        });
+        assert!(!guards::check_credential_usage(
+            &inline,
+            &json!({ "forbid_inline_keys": true })
+        ));
+        // Like every constraint, inline keys are only refused when asked.
+        assert!(guards::check_credential_usage(&inline, &json!({})));
+        assert!(guards::check_credential_usage(
+            &inline,
+            &json!({ "allowed_credentials": ["cred_uami"] })
+        ));
+
+        let with_credential = json!({
+            "properties": {
+                "type": "AzureBlobFS",
+                "typeProperties": {
+                    "url": "https://stlake.dfs.core.windows.net",
+                    "credential": { "referenceName": "cred_uami", "type": "CredentialReference" }
+                }
+            }
+        });
+        assert!(guards::check_credential_usage(
+            &with_credential,
+            &json!({ "require_credential": true })
+        ));
+        assert!(!guards::check_credential_usage(
+            &inline,
+            &json!({ "require_credential": true })
+        ));
+        assert!(guards::check_credential_usage(
+            &with_credential,
+            &json!({ "allowed_credentials": ["cred_uami"] })
+        ));
+        assert!(!guards::check_credential_usage(
+            &with_credential,
+            &json!({ "allowed_credentials": ["cred_other"] })
+        ));
+    }
+
+    #[test]
+    fn test_private_endpoint() {
+        let endpoint = json!({
+            "name": "mpe_storage",
+            "properties": {
+                "privateLinkResourceId": "/subscriptions/1111/resourceGroups/rg/providers/Microsoft.Storage/storageAccounts/stlake",
+                "groupId": "blob",
+                "annotations": ["approval:CHG-1234"]
+            }
+        });
+
+        assert!(guards::check_private_endpoint(
+            &endpoint,
+            &json!({ "allowed_subscriptions": ["1111"], "allowed_group_ids": ["blob", "dfs"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_private_endpoint(
+            &endpoint,
+            &json!({ "allowed_subscriptions": ["2222"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_private_endpoint(
+            &endpoint,
+            &json!({ "allowed_group_ids": ["sqlServer"] }),
+            &Regexes::default()
+        ));
+        assert!(guards::check_private_endpoint(
+            &endpoint,
+            &json!({ "ticket_annotation_regex": "^approval:CHG-[0-9]+$" }),
+            &Regexes::default()
+        ));
+
+        let unannotated = json!({ "properties": { "groupId": "blob" } });
+        assert!(!guards::check_private_endpoint(
+            &unannotated,
+            &json!({ "ticket_annotation_regex": "^approval:" }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_private_endpoint(
+            &unannotated,
+            &json!({ "allowed_subscriptions": ["1111"] }),
+            &Regexes::default()
+        ));
+    }
+
+    #[test]
+    fn test_key_vault_secrets() {
+        let linked_service = json!({
+            "name": "ls_sql",
+            "properties": {
+                "type": "AzureSqlDatabase",
+                "typeProperties": {
+                    "connectionString": "Server=tcp:sql.database.windows.net;Database=db",
+                    "password": {
+                        "type": "AzureKeyVaultSecret",
+                        "store": { "referenceName": "ls_keyvault", "type": "LinkedServiceReference" },
+                        "secretName": "sql-password"
+                    }
+                }
+            }
+        });
+        let params = json!({
+            "allowed_vaults": ["ls_keyvault"],
+            "secret_name_regex": "^[a-z0-9-]+$",
+            "secret_properties": ["password", "accountKey", "sasUri"]
+        });
+        assert!(guards::check_key_vault_secrets(
+            &linked_service,
+            &params,
+            &Regexes::default()
+        ));
+        assert!(!guards::check_key_vault_secrets(
+            &linked_service,
+            &json!({ "allowed_vaults": ["ls_keyvault_prod"] }),
+            &Regexes::default()
+        ));
+        assert!(!guards::check_key_vault_secrets(
+            &linked_service,
+            &json!({ "secret_name_regex": "^sql_" }),
+            &Regexes::default()
+        ));
+
+        let literal = json!({
+            "properties": {
+                "typeProperties": {
+                    "accountKey": { "type": "SecureString", "value": "**********" },
+                    "sasUri": "@linkedService().sasUri"
+                }
+            }
+        });
+        assert!(!guards::check_key_vault_secrets(
+            &literal,
+            &params,
+            &Regexes::default()
+        ));
+
+        let expression_only =
+            json!({ "properties": { "typeProperties": { "sasUri": "@linkedService().sasUri" } } });
+        assert!(guards::check_key_vault_secrets(
+            &expression_only,
+            &params,
+            &Regexes::default()
+        ));
+    }
+
+    #[test]
+    fn test_connector_policy() {
+        let sftp = json!({
+            "name": "ls_sftp",
+            "properties": { "type": "Sftp", "typeProperties": { "host": "sftp.contoso.com" } }
+        });
+        assert!(guards::check_connector_policy(
+            &sftp,
+            &json!({ "allowed_types": ["sftp", "AzureBlobFS"] })
+        ));
+        assert!(!guards::check_connector_policy(
+            &sftp,
+            &json!({ "allowed_types": ["AzureBlobFS"] })
+        ));
+        assert!(!guards::check_connector_policy(
+            &sftp,
+            &json!({ "denied_types": ["Sftp"] })
+        ));
+        assert!(!guards::check_connector_policy(
+            &sftp,
+            &json!({ "require_integration_runtime": ["Sftp"] })
+        ));
+
+        let sftp_with_ir = json!({
+            "properties": {
+                "type": "Sftp",
+                "connectVia": { "referenceName": "ir_selfhosted", "type": "IntegrationRuntimeReference" }
+            }
+        });
+        assert!(guards::check_connector_policy(
+            &sftp_with_ir,
+            &json!({ "require_integration_runtime": ["Sftp"] })
+        ));
+    }
+
+    #[test]
+    fn test_numeric() {
+        let timeout = json!("0.01:30:00");
+        assert!(guards::check_numeric(&timeout, &json!({ "max": "2h" })));
+        assert!(!guards::check_numeric(&timeout, &json!({ "max": "1h" })));
+        assert!(!guards::check_numeric(&timeout, &json!({ "max": "4GB" })));
+        assert!(guards::check_numeric(
+            &json!(2048),
+            &json!({ "unit": "MB", "min": "1GB", "max": "4gb" })
+        ));
+        assert!(!guards::check_numeric(
+            &json!(2048),
+            &json!({ "unit": "MB", "max": 1024 })
+        ));
+        assert!(guards::check_numeric(
+            &json!("90s"),
+            &json!({ "min": "1m" })
+        ));
+        assert!(guards::check_numeric(&json!(16), &json!({ "max": 32 })));
+        assert!(!guards::check_numeric(&json!(16), &json!({ "max": "32s" })));
+        assert!(!guards::check_numeric(
+            &json!("@pipeline().parameters.diu"),
+            &json!({ "max": 32 })
+        ));
+    }
+
+    #[test]
+    fn test_uuid_and_azure_resource_id() {
+        let tenant = json!("72f988bf-86f1-41af-91ab-2d7cd011db47");
+        assert!(guards::check_uuid(&tenant, &json!({ "lowercase": true })));
+        assert!(!guards::check_uuid(
+            &json!("72F988BF-86F1-41AF-91AB-2D7CD011DB47"),
+            &json!({ "lowercase": true })
+        ));
+        assert!(!guards::check_uuid(
+            &json!("00000000-0000-0000-0000-000000000000"),
+            &json!({ "forbid_nil": true })
+        ));
+        assert!(!guards::check_uuid(
+            &json!("72f988bf86f141af91ab2d7cd011db47"),
+            &json!({})
+        ));
+        assert!(guards::check_uuid(
+            &json!("@linkedService().tenantId"),
+            &json!({})
+        ));
+
+        let storage = json!(
+            "/subscriptions/72f988bf-86f1-41af-91ab-2d7cd011db47/resourceGroups/rg-data/providers/Microsoft.Storage/storageAccounts/stdatalake"
+        );
+        let params = json!({
+            "allowed_subscriptions": ["72F988BF-86F1-41AF-91AB-2D7CD011DB47"],
+            "allowed_resource_types": ["microsoft.storage/storageaccounts"],
+            "name_regex": "^st",
+        });
+        let check = |node: &Value, params: &Value| {
+            guards::check_azure_resource_id(node, params, &Regexes::default())
+        };
+        assert!(check(&storage, &params));
+        assert!(!check(
+            &storage,
+            &json!({ "allowed_resource_types": ["Microsoft.Sql/servers"] })
+        ));
+        assert!(!check(
+            &storage,
+            &json!({ "allowed_resource_groups": ["rg-core"] })
+        ));
+        assert!(!check(&storage, &json!({ "name_regex": "^sa" })));
+        let database = json!(
+            "/subscriptions/72f988bf-86f1-41af-91ab-2d7cd011db47/resourceGroups/rg/providers/Microsoft.Sql/servers/sql1/databases/db1"
+        );
+        assert!(check(
+            &database,
+            &json!({ "allowed_resource_types": ["Microsoft.Sql/servers/databases"], "name_regex": "^db" })
+        ));
+        assert!(!check(
+            &json!("/subscriptions/x/resourceGroups/rg"),
+            &json!({})
+        ));
+        assert!(!check(
+            &json!(
+                "/subscriptions/72f988bf-86f1-41af-91ab-2d7cd011db47/providers/Microsoft.Sql/servers"
+            ),
+            &json!({})
+        ));
+    }
+
+    #[test]
+    fn test_sem_ver() {
+        let check = |version: &str, params: Value| guards::check_sem_ver(&json!(version), &params);
+        assert!(check("1.4.0", json!({})));
+        assert!(check("1.4.0-rc.1+build.7", json!({})));
+        for invalid in ["1.4", "1.4.0.1", "01.4.0", "1.4.0-", "1.4.0+", "v1.4.0"] {
+            assert!(!check(invalid, json!({})), "{}", invalid);
+        }
+        assert!(check("version:1.4.0", json!({ "prefix": "version:" })));
+        assert!(!check("1.4.0", json!({ "prefix": "version:" })));
+
+        let range = json!({ "range": ">=1.2.0, <2.0.0" });
+        assert!(check("1.10.0", range.clone()));
+        assert!(!check("2.0.0", range.clone()));
+        assert!(!check("2.0.0-alpha", json!({ "range": ">2.0.0-alpha.1" })));
+        assert!(check(
+            "2.0.0-alpha.10",
+            json!({ "range": ">2.0.0-alpha.9" })
+        ));
+        assert!(check(
+            "2.0.0-beta",
+            json!({ "range": ">2.0.0-alpha <2.0.0" })
+        ));
+        assert!(check("1.2.0+build", json!({ "range": "1.2.0" })));
+        assert!(!check("1.2.0", json!({ "range": "~1.2.0" })));
+        assert!(!check(
+            "1.5.0-rc.1",
+            json!({ "range": "<2.0.0", "allow_prerelease": false })
+        ));
+    }
+
+    #[test]
+    fn test_field_combinations() {
+        let type_properties = json!({
+            "connectionString": "DefaultEndpointsProtocol=https",
+            "sasUri": null,
+            "startTime": "2024-01-01T00:00:00",
+        });
+        let exclusive = json!({ "fields": ["connectionString", "sasUri", "serviceEndpoint"] });
+        assert!(guards::check_mutually_exclusive(
+            &type_properties,
+            &exclusive
+        ));
+        assert!(!guards::check_mutually_exclusive(
+            &json!({ "connectionString": "x", "serviceEndpoint": "y" }),
+            &exclusive
+        ));
+        assert!(guards::check_mutually_exclusive(&json!({}), &exclusive));
+        assert!(!guards::check_mutually_exclusive(
+            &json!({}),
+            &json!({ "fields": ["connectionString", "sasUri"], "required": true })
+        ));
+
+        let together = json!({ "fields": ["startTime", "timeZone"] });
+        assert!(!guards::check_required_together(
+            &type_properties,
+            &together
+        ));
+        assert!(guards::check_required_together(
+            &json!({ "startTime": "2024-01-01T00:00:00", "timeZone": "UTC" }),
+            &together
+        ));
+        assert!(guards::check_required_together(&json!({}), &together));
+    }
+
+    #[test]
+    fn test_deep_match() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "no-account-keys"
+    asset: "linkedService"
+    description: "No account keys in typeProperties"
+    validate:
+      target: "$.properties.typeProperties"
+      guard: "DeepMatch"
+      params:
+        guard: "PatternMatch"
+        params: { regex: "AccountKey=", negative: true }
+        exclude_keys: ["encryptedCredential"]
+"#,
+        )
+        .unwrap();
+        let check = |type_properties: Value| {
+            let source = json!({ "properties": { "typeProperties": type_properties } });
+            check_source(
+                &config,
+                &GuardRegistry::default(),
+                Path::new("linkedService/ls.json"),
+                &source.to_string(),
+            )
+            .unwrap()
+            .violations
+            .len()
+        };
+        assert_eq!(
+            check(json!({ "connectionString": "DefaultEndpointsProtocol=https" })),
+            0
+        );
+        assert_eq!(
+            check(json!({ "nested": [{ "value": "AccountName=st;AccountKey=abc" }] })),
+            1
+        );
+        assert_eq!(check(json!({ "encryptedCredential": "AccountKey=abc" })), 0);
+
+        let lengths = json!({ "guard": "StringLength", "params": { "max": 5 }, "keys": true });
+        let check = |node: &Value, params: &Value| {
+            guards::check_deep_match(node, params, |v| {
+                guards::check_string_length(v, &params["params"])
+            })
+        };
+        assert!(check(&json!({ "a": ["short", { "b": "tiny" }] }), &lengths));
+        assert!(!check(&json!({ "long_key": "x" }), &lengths));
+        let mut only_b = lengths.clone();
+        only_b["include_keys"] = json!(["B"]);
+        only_b["keys"] = json!(false);
+        assert!(check(&json!({ "a": "too long", "b": ["short"] }), &only_b));
+        assert!(!check(&json!({ "b": { "c": "too long" } }), &only_b));
+    }
+
+    #[test]
+    fn test_secret_likeness() {
+        let check = guards::check_secret_likeness;
+        let connection = json!({
+            "type": "AzureSqlDatabase",
+            "typeProperties": {
+                "connectionString": "Server=tcp:sql1.database.windows.net;Database=sales;Password=Xq7#vR2m!Lp9zK4w;"
+            }
+        });
+        assert!(!check(&connection, &json!({})));
+        assert!(check(&connection, &json!({ "keywords": ["credential"] })));
+        assert!(!check(&json!({ "apiToken": "aZ3kP9qW2x" }), &json!({})));
+        assert!(check(
+            &json!({ "apiToken": "aZ3kP9qW2x" }),
+            &json!({ "sensitivity": "low" })
+        ));
+        assert!(!check(
+            &json!("Mz4rT8wQ1xK6vN3pY7bH2jL5"),
+            &json!({ "sensitivity": "medium" })
+        ));
+        assert!(check(
+            &json!("Mz4rT8wQ1xK6vN3pY7bH2jL5"),
+            &json!({ "min_entropy": 5.0 })
+        ));
+        assert!(check(
+            &json!({
+                "password": "@linkedService().password",
+                "url": "https://adls01.dfs.core.windows.net/raw/sales/2024?sv=latest",
+                "tenant": "72f988bf-86f1-41af-91ab-2d7cd011db47",
+                "description": "Loads the daily sales extract into the curated zone"
+            }),
+            &json!({})
+        ));
+    }
+
+    #[test]
+    fn test_date_time() {
+        let start = json!("2024-03-01T06:00:00Z");
+        assert!(guards::check_date_time(&start, &json!({})));
+        assert!(guards::check_date_time(
+            &start,
+            &json!({ "require_offset": true, "not_before": "2024-01-01", "not_after": "2024-12-31T23:59:59+01:00" })
+        ));
+        assert!(!guards::check_date_time(
+            &start,
+            &json!({ "not_before": "now-1d" })
+        ));
+        assert!(guards::check_date_time(
+            &json!("2999-01-01T00:00:00"),
+            &json!({ "not_before": "now" })
+        ));
+        assert!(!guards::check_date_time(
+            &json!("2999-01-01T00:00:00"),
+            &json!({ "require_offset": true })
+        ));
+        assert!(!guards::check_date_time(&json!("01/03/2024"), &json!({})));
+        assert!(!guards::check_date_time(&json!(1709272800), &json!({})));
+        assert!(guards::parse_bound("now+2h").is_some());
+        assert!(guards::parse_bound("now2h").is_none());
+        for typo in ["now+5é", "nowé5d", "now-é", "now+", "é5d"] {
+            assert!(guards::parse_bound(typo).is_none(), "{}", typo);
+        }
+    }
+
+    #[test]
+    fn test_reported_values() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "sql-server"
+    asset: "linkedService"
+    validate: { target: "$.properties.typeProperties.connectionString", guard: "PatternMatch", params: { regex: "sql2" } }
+  - id: "no-inline-connection"
+    asset: "linkedService"
+    report_value: false
+    validate: { target: "$.properties.typeProperties.connectionString", guard: "Exists", params: { should_exist: false } }
+"#,
+        )
+        .unwrap();
+        let source = json!({ "properties": { "typeProperties": {
+            "connectionString": "Server=tcp:sql1;Database=sales;Password=hunter2;"
+        } } });
+        let mut result = check_source(
+            &config,
+            &GuardRegistry::default(),
+            Path::new("linkedService/ls.json"),
+            &source.to_string(),
+        )
+        .unwrap();
+        let values: Vec<Option<&str>> = result
+            .violations
+            .iter()
+            .map(|v| v.actual_value.as_deref())
+            .collect();
+        assert_eq!(
+            values,
+            [
+                Some(r#""Server=tcp:sql1;Database=sales;Password=***;""#),
+                None
+            ]
+        );
+
+        limit_values(&mut result.violations, Some(10));
+        assert_eq!(
+            result.violations[0].actual_value.as_deref(),
+            Some(r#""Server=tc… (46 chars)"#)
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut config: Config = serde_yaml::from_str(
+            r#"
+limits: { rule_timeout: "2s" }
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+        let registry = GuardRegistry::default();
+        let (rules, diagnostics) = compile(&config, &registry);
+        assert!(diagnostics.is_empty());
+        assert_eq!(rules[0].budget, Some(Duration::from_secs(2)));
+
+        let json = json!({ "name": "a" });
+        let evaluate = |rules: &[CompiledRule]| {
+            let taxonomy = Taxonomy::new(&config, &mut vec![]);
+            let path = Path::new("pipeline/a.json");
+            let (result, _, complete) =
+                evaluate_document(rules, &taxonomy, path, path, "", &json, true);
+            (result.violations.len(), complete)
+        };
+        assert_eq!(evaluate(&rules), (1, true));
+        rules[0].spent.store(2_000_000, Ordering::Relaxed);
+        assert_eq!(evaluate(&rules), (0, false));
+        assert_eq!(rules[0].skipped.load(Ordering::Relaxed), 1);
+
+        // A budget smaller than one file's evaluation stops the rule in the middle of it.
+        let tight: Config = serde_yaml::from_str(
+            r#"
+limits: { rule_timeout: "0.000001s" }
+rules:
+  - id: "activity-naming"
+    asset: "pipeline"
+    validate: { target: "$.activities[*].name", guard: "PatternMatch", params: { regex: "^cp_" } }
+"#,
+        )
+        .unwrap();
+        let (rules, _) = compile(&tight, &registry);
+        let activities: Vec<Value> = (0..20_000)
+            .map(|i| json!({ "name": i.to_string() }))
+            .collect();
+        let json = json!({ "name": "pl_a", "activities": activities });
+        let path = Path::new("pipeline/pl_a.json");
+        let (result, _, complete) = evaluate_document(
+            &rules,
+            &Taxonomy::new(&tight, &mut vec![]),
+            path,
+            path,
+            "",
+            &json,
+            true,
+        );
+        assert!(!complete);
+        assert!(result.violations.is_empty());
+        assert!(result.rules_checked.is_empty());
+        assert_eq!(rules[0].skipped.load(Ordering::Relaxed), 1);
+
+        for timeout in ["2GB".to_string(), format!("1{}d", "0".repeat(30))] {
+            config.limits.rule_timeout = Some(timeout.clone());
+            let (rules, diagnostics) = compile(&config, &registry);
+            assert_eq!(rules[0].budget, None);
+            assert!(
+                diagnostics[0]
+                    .message
+                    .starts_with(&format!("Invalid limits.rule_timeout '{}'", timeout))
+            );
+        }
+
+        assert!(guards::build_regex("^pl_[a-z]+$").is_ok());
+        assert!(guards::build_regex("(a{100}){100}{100}").is_err());
+        assert!(guards::build_regex(&format!("{}a{}", "(".repeat(100), ")".repeat(100))).is_err());
+    }
+
+    #[test]
+    fn test_check_rule_when_clause_met() {
+        let rule = Rule {
+            id: "test-when-met".to_string(),
+            asset: AssetMatcher::Single("pipeline".to_string()),
+            description: None,
+            help_url: None,
+            severity: Severity::Error,
+            promote: None,
+            tags: vec![],
+            scope: RuleScope::File,
+            report_value: true,
+            when: Some(Validation {
+                target: "$.properties.type".to_string(),
+                guard: "AllowedValues".to_string(),
+                params: json!({ "values": ["MappingDataFlow"] }),
+                params_by: None,
+                on_missing: OnMissing::Pass,
+            }),
+            validate: Validation {
+                target: "$.name".to_string(),
+                guard: "PatternMatch".to_string(),
                 params: json!({ "regex": "^pl_" }),
+                params_by: None,
+                on_missing: OnMissing::Pass,
             },
         };
+        let guards = GuardRegistry::default();
+        let compiled = CompiledRule::new(0, &rule, &guards, &mut vec![]).unwrap();
 
         let json = json!({ "properties": { "type": "MappingDataFlow" }, "name": "wrong_name" });
         assert_eq!(
-            check_rule(&rule, &json, Path::new("pipeline/test.json")).len(),
+            check_rule(&compiled, &json, Path::new("pipeline/test.json")).len(),
             1
         );
     }
@@ -300,21 +2324,32 @@ mod tests {
             id: "test-when-not-met".to_string(),
             asset: AssetMatcher::Single("pipeline".to_string()),
             description: None,
+            help_url: None,
             severity: Severity::Error,
+            promote: None,
+            tags: vec![],
+            scope: RuleScope::File,
+            report_value: true,
             when: Some(Validation {
                 target: "$.properties.type".to_string(),
                 guard: "AllowedValues".to_string(),
                 params: json!({ "values": ["MappingDataFlow"] }),
+                params_by: None,
+                on_missing: OnMissing::Pass,
             }),
             validate: Validation {
                 target: "$.name".to_string(),
                 guard: "PatternMatch".to_string(),
                 params: json!({ "regex": "^pl_" }),
+                params_by: None,
+                on_missing: OnMissing::Pass,
             },
         };
+        let guards = GuardRegistry::default();
+        let compiled = CompiledRule::new(0, &rule, &guards, &mut vec![]).unwrap();
 
         let json = json!({ "properties": { "type": "ExecutePipeline" }, "name": "wrong_name" });
-        assert!(check_rule(&rule, &json, Path::new("pipeline/test.json")).is_empty());
+        assert!(check_rule(&compiled, &json, Path::new("pipeline/test.json")).is_empty());
     }
 
     #[test]
@@ -323,27 +2358,212 @@ mod tests {
             id: "test-no-when".to_string(),
             asset: AssetMatcher::Single("pipeline".to_string()),
             description: None,
+            help_url: None,
             severity: Severity::Error,
+            promote: None,
+            tags: vec![],
+            scope: RuleScope::File,
+            report_value: true,
             when: None,
             validate: Validation {
                 target: "$.name".to_string(),
                 guard: "PatternMatch".to_string(),
                 params: json!({ "regex": "^pl_" }),
+                params_by: None,
+                on_missing: OnMissing::Pass,
             },
         };
+        let guards = GuardRegistry::default();
+        let compiled = CompiledRule::new(0, &rule, &guards, &mut vec![]).unwrap();
 
         let json = json!({ "name": "wrong_name" });
         assert_eq!(
-            check_rule(&rule, &json, Path::new("pipeline/test.json")).len(),
+            check_rule(&compiled, &json, Path::new("pipeline/test.json")).len(),
             1
         );
 
         let json_ok = json!({ "name": "pl_correct_name" });
-        assert!(check_rule(&rule, &json_ok, Path::new("pipeline/test.json")).is_empty());
+        assert!(check_rule(&compiled, &json_ok, Path::new("pipeline/test.json")).is_empty());
+    }
+
+    #[test]
+    fn test_check_rule_params_by() {
+        let rule = Rule {
+            id: "test-params-by".to_string(),
+            asset: AssetMatcher::Single("pipeline".to_string()),
+            description: None,
+            help_url: None,
+            severity: Severity::Error,
+            promote: None,
+            tags: vec![],
+            scope: RuleScope::File,
+            report_value: true,
+            when: None,
+            validate: Validation {
+                target: "$.properties.concurrency".to_string(),
+                guard: "Range".to_string(),
+                params: json!({ "min": 1, "max": 10 }),
+                params_by: Some(crate::config::ParamsSelector {
+                    target: "$.properties.annotations[*]".to_string(),
+                    cases: [("sla:gold".to_string(), json!({ "min": 1, "max": 1 }))]
+                        .into_iter()
+                        .collect(),
+                }),
+                on_missing: OnMissing::Warn,
+            },
+        };
+        let guards = GuardRegistry::default();
+        let compiled = CompiledRule::new(0, &rule, &guards, &mut vec![]).unwrap();
+
+        let gold =
+            json!({ "properties": { "concurrency": 4, "annotations": ["team:data", "sla:gold"] } });
+        assert_eq!(
+            check_rule(&compiled, &gold, Path::new("pipeline/test.json")).len(),
+            1
+        );
+
+        let default = json!({ "properties": { "concurrency": 4, "annotations": ["team:data"] } });
+        assert!(check_rule(&compiled, &default, Path::new("pipeline/test.json")).is_empty());
+
+        let missing = json!({ "properties": { "annotations": ["sla:gold"] } });
+        let violations = check_rule(&compiled, &missing, Path::new("pipeline/test.json"));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_parallel_evaluation() {
+        let config = Config::from_yaml(
+            br#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].name", guard: "PatternMatch", params: { regex: "^cp_" } }
+  - id: "retries"
+    asset: "pipeline"
+    validate: { target: "$.properties.activities[*].policy.retry", guard: "Range", params: { max: 3 } }
+"#,
+        )
+        .unwrap();
+        let padding = "x".repeat(400);
+        let activities: Vec<Value> = (0..12_000)
+            .map(|i| {
+                serde_json::json!({
+                    "name": if i % 1000 == 999 { format!("Copy {}", i) } else { format!("cp_{}", i) },
+                    "description": padding,
+                    "policy": { "retry": if i == 5 { 9 } else { 1 } }
+                })
+            })
+            .collect();
+        let source = serde_json::json!({ "properties": { "activities": activities } }).to_string();
+        assert!(source.len() >= PARALLEL_FILE_SIZE);
+
+        let path = Path::new("pipeline/pl_export.json");
+        let result = check_source(&config, &GuardRegistry::default(), path, &source).unwrap();
+        // In the order of the rules, then of the nodes.
+        let found: Vec<(&str, &str)> = result
+            .violations
+            .iter()
+            .map(|v| (v.rule_id.as_str(), v.pointer.as_deref().unwrap()))
+            .collect();
+        let mut expected: Vec<(&str, String)> = (0..12)
+            .map(|i| {
+                (
+                    "naming",
+                    format!("/properties/activities/{}/name", i * 1000 + 999),
+                )
+            })
+            .collect();
+        expected.push((
+            "retries",
+            "/properties/activities/5/policy/retry".to_string(),
+        ));
+        assert_eq!(
+            found,
+            expected
+                .iter()
+                .map(|(rule, pointer)| (*rule, pointer.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert!(result.violations.iter().all(|v| v.line.is_some()));
+        assert_eq!(result.rules_checked, ["naming", "retries"]);
+    }
+
+    #[test]
+    fn test_promoted_severity() {
+        let rule = |promote: &str| -> Rule {
+            serde_yaml::from_str(&format!(
+                r#"
+id: "new-policy"
+asset: "pipeline"
+severity: Warning
+promote: "{}"
+validate: {{ target: "$.name", guard: "Exists", params: {{}} }}
+"#,
+                promote
+            ))
+            .unwrap()
+        };
+        let now = "2026-07-01T12:00:00Z".parse().unwrap();
+        let severity = |promote: &str, diagnostics: &mut Vec<Diagnostic>| {
+            promoted_severity(&rule(promote), now, diagnostics)
+        };
+        let mut diagnostics = vec![];
+        assert_eq!(severity("2026-07-01", &mut diagnostics), Severity::Error);
+        assert_eq!(severity("2026-07-02", &mut diagnostics), Severity::Warning);
+        assert_eq!(severity("v0.1.0", &mut diagnostics), Severity::Error);
+        assert_eq!(severity("99.0.0", &mut diagnostics), Severity::Warning);
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            severity("next quarter", &mut diagnostics),
+            Severity::Warning
+        );
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_compile() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "bad-path"
+    asset: "pipeline"
+    validate: { target: "$.[", guard: "Exists", params: {} }
+  - id: "unknown-guard"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "NoSuchGuard", params: {} }
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+
+        let guards = GuardRegistry::default();
+        let (rules, diagnostics) = compile(&config, &guards);
+        let ids: Vec<&str> = rules.iter().map(|r| r.rule.id.as_str()).collect();
+        assert_eq!(ids, ["unknown-guard", "naming"]);
+        assert_eq!(rules[1].index, 2);
+        let flagged: Vec<_> = diagnostics.iter().map(|d| d.rule_id.as_deref()).collect();
+        assert_eq!(flagged, [Some("bad-path"), Some("unknown-guard")]);
+
+        let json = json!({ "name": "wrong_name" });
+        let path = Path::new("pipeline/a.json");
+        let (result, ..) =
+            evaluate_document(&rules, &Taxonomy::default(), path, path, "{}", &json, false);
+        assert_eq!(result.rules_checked, ["unknown-guard", "naming"]);
+        assert_eq!(result.violations.len(), 1);
+
+        assert_eq!(
+            check_guards(&config, &guards),
+            ["Rule 'unknown-guard': Unknown guard 'NoSuchGuard'"]
+        );
     }
 
     #[test]
     fn test_matches_asset_type() {
+        let taxonomy = Taxonomy::default();
+        let matches_asset_type = |matcher, path| taxonomy.matches(matcher, path, None);
         let matcher = AssetMatcher::Single("pipeline".to_string());
         assert!(matches_asset_type(
             &matcher,
@@ -368,4 +2588,260 @@ mod tests {
             Path::new("./trigger/test.json")
         ));
     }
+
+    #[cfg(feature = "scan")]
+    struct Silent;
+    #[cfg(feature = "scan")]
+    impl ScanObserver for Silent {}
+
+    /// A project in the temp folder, named after `name`, with `files` (path, content) in it.
+    #[cfg(feature = "scan")]
+    fn project<'a>(name: &str, files: impl IntoIterator<Item = (&'a str, String)>) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("adf-guardian-{}-{}", name, std::process::id()));
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    #[cfg(feature = "scan")]
+    fn test_run_skips_files_without_rules() {
+        let dir = project(
+            "skip",
+            [
+                ("pipeline/pl_a.json", r#"{ "name": "a" }"#.to_string()),
+                ("fixtures/broken.json", "{ not json".to_string()),
+            ],
+        );
+        let mut config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: "Pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+
+        let scan = run(&config, &dir, &ScanOptions::default(), &Silent).unwrap();
+        config.project.duplicate_names = Some(Severity::Warning);
+        let with_project_checks = run(&config, &dir, &ScanOptions::default(), &Silent).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(scan.results.len(), 1);
+        assert!(scan.results[0].file.ends_with("pl_a.json"));
+        assert_eq!(with_project_checks.results.len(), 2);
+        // The broken file is only read when the project checks need it.
+        assert!(scan.diagnostics.is_empty());
+        let diagnostic = &with_project_checks.diagnostics[0];
+        assert!(diagnostic.file.as_deref().unwrap().ends_with("broken.json"));
+        assert!(diagnostic.message.starts_with("Could not parse JSON"));
+    }
+
+    #[test]
+    #[cfg(feature = "scan")]
+    fn test_run_evaluates_dependents() {
+        let dir = project(
+            "deps",
+            [
+                ("pipeline/pl_a.json", r#"{ "name": "pl_a" }"#.to_string()),
+                ("pipeline/pl_b.json", r#"{ "name": "pl_b" }"#.to_string()),
+                (
+                    "trigger/tr_daily.json",
+                    r#"{ "name": "tr_daily", "properties": { "pipelines": [{
+                "pipelineReference": { "referenceName": "pl_a", "type": "PipelineReference" }
+            }] } }"#
+                        .to_string(),
+                ),
+            ],
+        );
+        let mut config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: ["pipeline", "trigger"]
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^(pl|tr)_" } }
+"#,
+        )
+        .unwrap();
+        let options = ScanOptions {
+            only_files: Some(HashSet::from([fs::canonicalize(
+                dir.join("trigger/tr_daily.json"),
+            )
+            .unwrap()])),
+            ..Default::default()
+        };
+        let files = |scan: ScanReport| -> Vec<String> {
+            scan.results
+                .iter()
+                .map(|r| {
+                    Path::new(&r.file)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        let scan = run(&config, &dir, &options, &Silent).unwrap();
+        assert_eq!(files(scan), ["tr_daily.json"]);
+        // The pipeline the trigger references may no longer be unreferenced.
+        config.project.unreferenced_pipelines = Some(Severity::Warning);
+        let scan = run(&config, &dir, &options, &Silent).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(scan.results.iter().all(|r| r.violations.is_empty()));
+        assert_eq!(files(scan), ["pl_a.json", "tr_daily.json"]);
+    }
+
+    #[test]
+    #[cfg(feature = "scan")]
+    fn test_run_archive_keeps_sources() {
+        use std::io::Write;
+
+        // A Git revision whose files differ from the working tree.
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in [
+            ("pipeline/pl_a.json", "{\n  \"name\": \"pl_a\"\n}"),
+            ("pipeline/load.json", "{\n  \"name\": \"load\"\n}"),
+        ] {
+            zip.start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        let options = ScanOptions {
+            archive: Some(zip.finish().unwrap().into_inner()),
+            ..Default::default()
+        };
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+
+        let scan = run(&config, Path::new("factory"), &options, &Silent).unwrap();
+        let sources: Vec<_> = scan
+            .results
+            .iter()
+            .map(|r| (r.file.as_str(), r.source.as_deref()))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                (
+                    "factory/pipeline/load.json",
+                    Some("{\n  \"name\": \"load\"\n}")
+                ),
+                ("factory/pipeline/pl_a.json", None),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "scan")]
+    fn test_run_strict() {
+        let dir = project(
+            "strict",
+            [
+                ("pipeline/pl_a.json", r#"{ "name": "pl_a" }"#.to_string()),
+                ("pipeline/broken.json", "{ not json".to_string()),
+            ],
+        );
+        let mut config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "typo"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PattenMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+        config.path = PathBuf::from("guards.yaml");
+
+        let lenient = run(&config, &dir, &ScanOptions::default(), &Silent).unwrap();
+        config.strict.unreadable_files = Some(Severity::Error);
+        config.strict.invalid_rules = Some(Severity::Warning);
+        let strict = run(&config, &dir, &ScanOptions::default(), &Silent).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(lenient.results.iter().all(|r| r.violations.is_empty()));
+        assert_eq!(lenient.diagnostics.len(), 2);
+
+        let violations: Vec<(&str, &str, Severity)> = strict
+            .results
+            .iter()
+            .flat_map(|r| &r.violations)
+            .map(|v| {
+                (
+                    Path::new(&v.file).file_name().unwrap().to_str().unwrap(),
+                    v.rule_id.as_str(),
+                    v.severity,
+                )
+            })
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                ("broken.json", UNREADABLE_FILE_RULE, Severity::Error),
+                ("guards.yaml", INVALID_RULE_RULE, Severity::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "scan")]
+    fn test_run_orders_results() {
+        let files = [
+            ("pipeline/c.json", "c"),
+            ("pipeline/a.json", "a"),
+            ("pipeline/d.json", "d"),
+            ("pipeline/b.json", "b"),
+        ];
+        let dir = project(
+            "order",
+            files.map(|(path, name)| {
+                let source = format!(
+                    r#"{{ "name": "{}", "properties": {{ "retries": 9 }} }}"#,
+                    name
+                );
+                (path, source)
+            }),
+        );
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "z-retries"
+    asset: "pipeline"
+    validate: { target: "$.properties.retries", guard: "Range", params: { max: 3 } }
+  - id: "a-naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+
+        let scan = run(&config, &dir, &ScanOptions::default(), &Silent).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let files: Vec<&str> = scan
+            .results
+            .iter()
+            .map(|r| Path::new(&r.file).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(files, ["a.json", "b.json", "c.json", "d.json"]);
+        let rules: Vec<&str> = scan.results[0]
+            .violations
+            .iter()
+            .map(|v| v.rule_id.as_str())
+            .collect();
+        assert_eq!(rules, ["a-naming", "z-retries"]);
+    }
 }
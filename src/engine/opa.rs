@@ -0,0 +1,166 @@
+use super::registry::{Guard, GuardResult, Params};
+use super::schema::{self, ParamSpec};
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// The name rules give the guard.
+pub(super) const NAME: &str = "Opa";
+
+/// `Opa`, which evaluates a query of Rego policies with `opa eval`, the node (or, with
+/// `input: document`, the whole document) being the `input`. The query passes when it is
+/// `true` or an empty set, and fails with the messages of a non-empty set, as the `deny`
+/// rules of Conftest policies. Results are kept by input for the life of the registry,
+/// i.e. one scan, or a few minutes of `serve` and `lsp`.
+#[derive(Default)]
+pub(super) struct Opa {
+    results: Mutex<HashMap<(String, String, String), GuardResult>>,
+}
+
+impl Guard for Opa {
+    fn check(&self, node: &Value, params: &Params) -> GuardResult {
+        let policy = params.get("policy").and_then(|v| v.as_str());
+        let query = params.get("query").and_then(|v| v.as_str());
+        let (Some(policy), Some(query)) = (policy, query) else {
+            return GuardResult::Fail;
+        };
+        let input = match params.get("input").and_then(|v| v.as_str()) {
+            Some("document") => params.document(),
+            _ => node,
+        };
+
+        let key = (policy.to_string(), query.to_string(), input.to_string());
+        if let Some(result) = self.lock().get(&key) {
+            return result.clone();
+        }
+        let result = match eval(policy, query, &key.2) {
+            Ok(value) => outcome(query, value.as_ref()),
+            Err(e) => GuardResult::FailWith(format!("Could not evaluate {}: {:#}", query, e)),
+        };
+        self.lock().insert(key, result.clone());
+        result
+    }
+
+    fn check_params(&self, params: &Value) -> Vec<String> {
+        schema::check(NAME, params)
+    }
+
+    fn params(&self) -> Vec<ParamSpec> {
+        schema::specs(NAME)
+    }
+}
+
+impl Opa {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, String, String), GuardResult>> {
+        self.results.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Runs `opa eval` (or the executable `ADF_GUARDIAN_OPA` names) on the policy file or
+/// folder `policy`, with `input` as JSON. `None` when the query is undefined.
+fn eval(policy: &str, query: &str, input: &str) -> Result<Option<Value>> {
+    let opa = env::var("ADF_GUARDIAN_OPA").unwrap_or_else(|_| "opa".to_string());
+    let mut child = Command::new(&opa)
+        .args([
+            "eval",
+            "--format",
+            "json",
+            "--stdin-input",
+            "--data",
+            policy,
+            query,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", opa))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .with_context(|| format!("Failed to write the input of {}", opa))?;
+    }
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run {}", opa))?;
+
+    let json: Option<Value> = serde_json::from_slice(&output.stdout).ok();
+    if let Some(errors) = json.as_ref().and_then(|json| json["errors"].as_array()) {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|error| error["message"].as_str())
+            .collect();
+        bail!("{}", messages.join("; "));
+    }
+    match json {
+        Some(json) if output.status.success() => {
+            Ok(json["result"][0]["expressions"][0].get("value").cloned())
+        }
+        _ => bail!(
+            "{} exited with {}: {}",
+            opa,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+    }
+}
+
+/// What the `value` of `query` means for the node.
+fn outcome(query: &str, value: Option<&Value>) -> GuardResult {
+    match value {
+        None => GuardResult::FailWith(format!("The query {} is undefined", query)),
+        Some(Value::Bool(passed)) => (*passed).into(),
+        Some(Value::Array(messages)) if messages.is_empty() => GuardResult::Pass,
+        Some(Value::Array(messages)) => {
+            let messages: Vec<String> = messages
+                .iter()
+                .map(|message| match message {
+                    Value::String(text) => text.clone(),
+                    // Conftest allows `{ "msg": ... }` results.
+                    other => match other["msg"].as_str() {
+                        Some(text) => text.to_string(),
+                        None => other.to_string(),
+                    },
+                })
+                .collect();
+            GuardResult::FailWith(messages.join("; "))
+        }
+        Some(other) => GuardResult::FailWith(format!(
+            "The query {} gave {}, expected true, false or a set of messages",
+            query, other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_outcome() {
+        let query = "data.adf.deny";
+        assert_eq!(outcome(query, Some(&json!(true))), GuardResult::Pass);
+        assert_eq!(outcome(query, Some(&json!(false))), GuardResult::Fail);
+        assert_eq!(outcome(query, Some(&json!([]))), GuardResult::Pass);
+        assert_eq!(
+            outcome(
+                query,
+                Some(&json!(["No owner annotation", { "msg": "Too many retries" }]))
+            ),
+            GuardResult::FailWith("No owner annotation; Too many retries".to_string())
+        );
+        assert_eq!(
+            outcome(query, None),
+            GuardResult::FailWith("The query data.adf.deny is undefined".to_string())
+        );
+        assert!(matches!(
+            outcome(query, Some(&json!(3))),
+            GuardResult::FailWith(_)
+        ));
+    }
+}
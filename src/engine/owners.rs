@@ -0,0 +1,116 @@
+use super::project::Asset;
+use super::{Diagnostic, FileResult};
+use crate::config::Owner;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The `owners` of a config, ready to match files.
+pub(super) struct Owners<'a> {
+    entries: Vec<(&'a Owner, GlobSet)>,
+}
+
+impl<'a> Owners<'a> {
+    /// Invalid globs are skipped with a diagnostic.
+    pub(super) fn new(owners: &'a [Owner], diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let entries = owners
+            .iter()
+            .map(|owner| {
+                let mut globs = GlobSetBuilder::new();
+                for path in &owner.paths {
+                    match GlobBuilder::new(path).literal_separator(true).build() {
+                        Ok(glob) => {
+                            globs.add(glob);
+                        }
+                        Err(e) => diagnostics.push(Diagnostic::for_config(format!(
+                            "Invalid path '{}' of the owner '{}', it is skipped: {}",
+                            path, owner.team, e
+                        ))),
+                    }
+                }
+                (owner, globs.build().unwrap_or_else(|_| GlobSet::empty()))
+            })
+            .collect();
+        Owners { entries }
+    }
+
+    /// The team owning the file at `path`, relative to the project path, with `annotations`.
+    fn owner(&self, path: &Path, annotations: &[String]) -> Option<&'a str> {
+        self.entries
+            .iter()
+            .find(|(owner, globs)| {
+                globs.is_match(path)
+                    || owner
+                        .annotations
+                        .iter()
+                        .any(|a| annotations.iter().any(|b| a.eq_ignore_ascii_case(b)))
+            })
+            .map(|(owner, _)| owner.team.as_str())
+    }
+
+    /// Sets the owner of the violations of `results`, files under `root` described by `assets`.
+    pub(super) fn assign(&self, root: &Path, results: &mut [FileResult], assets: &[Asset]) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let annotations: HashMap<&str, &[String]> = assets
+            .iter()
+            .map(|asset| (asset.file.as_str(), asset.annotations.as_slice()))
+            .collect();
+        for result in results {
+            let file = Path::new(&result.file);
+            let owner = self.owner(
+                file.strip_prefix(root).unwrap_or(file),
+                annotations
+                    .get(result.file.as_str())
+                    .copied()
+                    .unwrap_or(&[]),
+            );
+            for violation in &mut result.violations {
+                violation.owner = owner.map(str::to_string);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Violation;
+    use serde_json::json;
+
+    #[test]
+    fn test_owners() {
+        let owners: Vec<Owner> = serde_yaml::from_str(
+            r#"
+- team: "sales"
+  paths: ["pipeline/sales_*.json"]
+- team: "finance"
+  annotations: ["team:finance"]
+"#,
+        )
+        .unwrap();
+        let owners = Owners::new(&owners, &mut vec![]);
+
+        let root = Path::new("/factory");
+        let result = |name: &str| FileResult {
+            file: format!("/factory/pipeline/{}.json", name),
+            violations: vec![Violation::default()],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        };
+        let mut results = vec![result("sales_load"), result("pl_ledger"), result("pl_misc")];
+        let assets = [Asset::from_document(
+            Path::new("/factory/pipeline/pl_ledger.json"),
+            &json!({ "name": "pl_ledger", "properties": { "annotations": ["Team:Finance"] } }),
+        )];
+        owners.assign(root, &mut results, &assets);
+
+        let assigned: Vec<Option<&str>> = results
+            .iter()
+            .map(|r| r.violations[0].owner.as_deref())
+            .collect();
+        assert_eq!(assigned, [Some("sales"), Some("finance"), None]);
+    }
+}
@@ -0,0 +1,434 @@
+use super::Violation;
+use crate::config::{ProjectChecks, Requirement, Severity};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub const UNREFERENCED_PIPELINE_RULE: &str = "project-unreferenced-pipeline";
+pub const DUPLICATE_NAME_RULE: &str = "project-duplicate-name";
+
+/// What the project-level checks need to know about a scanned file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub file: String,
+    pub kind: Option<String>,
+    pub name: Option<String>,
+    /// Labels of `properties.annotations`.
+    pub annotations: Vec<String>,
+    pub references: Vec<Reference>,
+}
+
+/// An asset another one points at, e.g. the pipeline of a `PipelineReference`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Reference {
+    /// Asset type, from the reference type: `pipeline`, `linkedService`, `dataset`...
+    pub kind: String,
+    pub name: String,
+}
+
+impl Asset {
+    pub fn from_document(file_path: &Path, json: &Value) -> Self {
+        let mut references = Vec::new();
+        collect_references(json, &mut references);
+
+        Asset {
+            file: file_path.to_string_lossy().to_string(),
+            kind: file_path
+                .parent()
+                .and_then(|p| p.file_name())
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string()),
+            name: json
+                .get("name")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            annotations: json
+                .pointer("/properties/annotations")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            references,
+        }
+    }
+
+    fn is_pipeline(&self) -> bool {
+        self.is("pipeline")
+    }
+
+    fn is(&self, kind: &str) -> bool {
+        self.kind
+            .as_deref()
+            .is_some_and(|k| k.eq_ignore_ascii_case(kind))
+    }
+
+    fn references(&self, kind: &str, name: &str) -> bool {
+        self.references
+            .iter()
+            .any(|r| r.kind.eq_ignore_ascii_case(kind) && r.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether this asset references `other`.
+    fn references_asset(&self, other: &Asset) -> bool {
+        match (&other.kind, &other.name) {
+            (Some(kind), Some(name)) => self.references(kind, name),
+            _ => false,
+        }
+    }
+
+    /// Whether both assets are of the same type and their names only differ in case or
+    /// whitespace.
+    fn same_name(&self, other: &Asset) -> bool {
+        match (&self.kind, &self.name, &other.kind, &other.name) {
+            (Some(kind), Some(name), Some(other_kind), Some(other_name)) => {
+                kind.eq_ignore_ascii_case(other_kind)
+                    && normalize_name(name) == normalize_name(other_name)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The files of `assets` whose project-level checks can change with `changed`, whether it
+/// was added, edited or removed: the assets that reference it, those it references and
+/// those with the same name. The checks only look one reference away, so the dependents
+/// stop there too.
+pub fn dependents<'a>(assets: &'a [Asset], changed: &Asset) -> Vec<&'a str> {
+    assets
+        .iter()
+        .filter(|asset| asset.file != changed.file)
+        .filter(|asset| {
+            asset.references_asset(changed)
+                || changed.references_asset(asset)
+                || asset.same_name(changed)
+        })
+        .map(|asset| asset.file.as_str())
+        .collect()
+}
+
+/// Collects every `*Reference` in the document, e.g. `PipelineReference` in
+/// `ExecutePipeline` activities and trigger pipeline lists, or the `LinkedServiceReference`
+/// of a dataset.
+fn collect_references(node: &Value, refs: &mut Vec<Reference>) {
+    match node {
+        Value::Object(map) => {
+            if let Some(kind) = map
+                .get("type")
+                .and_then(|v| v.as_str())
+                .and_then(|t| t.strip_suffix("Reference"))
+                && let Some(name) = map.get("referenceName").and_then(|v| v.as_str())
+            {
+                let mut chars = kind.chars();
+                refs.push(Reference {
+                    kind: chars
+                        .next()
+                        .map(|first| first.to_lowercase().chain(chars).collect())
+                        .unwrap_or_default(),
+                    name: name.to_string(),
+                });
+            }
+            map.values().for_each(|v| collect_references(v, refs));
+        }
+        Value::Array(list) => list.iter().for_each(|v| collect_references(v, refs)),
+        _ => {}
+    }
+}
+
+pub fn check(checks: &ProjectChecks, assets: &[Asset]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if let Some(severity) = checks.unreferenced_pipelines {
+        violations.extend(check_unreferenced_pipelines(assets, severity));
+    }
+    if let Some(severity) = checks.duplicate_names {
+        violations.extend(check_duplicate_names(assets, severity));
+    }
+
+    violations
+}
+
+fn check_unreferenced_pipelines(assets: &[Asset], severity: Severity) -> Vec<Violation> {
+    // A pipeline calling itself doesn't count as a reference.
+    let referenced: HashSet<&str> = assets
+        .iter()
+        .flat_map(|a| {
+            a.references
+                .iter()
+                .filter(|r| r.kind == "pipeline")
+                .filter(move |r| a.name.as_ref() != Some(&r.name))
+                .map(|r| r.name.as_str())
+        })
+        .collect();
+
+    assets
+        .iter()
+        .filter(|a| a.is_pipeline())
+        .filter_map(|a| {
+            let name = a.name.as_deref()?;
+            (!referenced.contains(name)).then(|| Violation {
+                rule_id: UNREFERENCED_PIPELINE_RULE.to_string(),
+                file: a.file.clone(),
+                message: "Pipeline is not referenced by any trigger or other pipeline".to_string(),
+                severity,
+                actual_value: Some(format!("\"{}\"", name)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+fn check_duplicate_names(assets: &[Asset], severity: Severity) -> Vec<Violation> {
+    let mut groups: HashMap<(String, String), Vec<&Asset>> = HashMap::new();
+    for asset in assets {
+        if let (Some(kind), Some(name)) = (&asset.kind, &asset.name) {
+            groups
+                .entry((kind.to_lowercase(), normalize_name(name)))
+                .or_default()
+                .push(asset);
+        }
+    }
+
+    groups
+        .values()
+        .filter(|group| group.len() > 1)
+        .flat_map(|group| {
+            group.iter().map(|asset| {
+                let others: Vec<&str> = group
+                    .iter()
+                    .filter(|other| other.file != asset.file)
+                    .map(|other| other.file.as_str())
+                    .collect();
+                Violation {
+                    rule_id: DUPLICATE_NAME_RULE.to_string(),
+                    file: asset.file.clone(),
+                    message: format!(
+                        "Asset name conflicts with {} (names differ only in case or whitespace)",
+                        others.join(", ")
+                    ),
+                    severity,
+                    actual_value: asset.name.as_ref().map(|n| format!("\"{}\"", n)),
+                    ..Default::default()
+                }
+            })
+        })
+        .collect()
+}
+
+/// Checks the `require` section. A requirement is met by `min` assets of its type, with
+/// its name when one is given; with `per`, it is checked for each asset of that type,
+/// counting only the assets that reference it. Assets missing from the whole project are
+/// reported on `config_file`, the others on the asset that lacks them.
+pub fn check_requirements(
+    requirements: &[Requirement],
+    assets: &[Asset],
+    config_file: &str,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for requirement in requirements {
+        let candidates: Vec<&Asset> = assets
+            .iter()
+            .filter(|a| a.is(&requirement.asset))
+            .filter(|a| {
+                requirement.name.as_deref().is_none_or(|name| {
+                    a.name
+                        .as_deref()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(name))
+                })
+            })
+            .collect();
+        let mut wanted = format!("at least {} {}", requirement.min, requirement.asset);
+        if let Some(name) = &requirement.name {
+            wanted.push_str(&format!(" named '{}'", name));
+        }
+        let violation = |file: &str, found: usize, message: String| Violation {
+            rule_id: requirement.id.clone(),
+            file: file.to_string(),
+            message: requirement.description.clone().unwrap_or(message),
+            severity: requirement.severity,
+            actual_value: Some(format!("{} found", found)),
+            help_url: requirement.help_url.clone(),
+            ..Default::default()
+        };
+
+        let Some(scope) = &requirement.per else {
+            if candidates.len() < requirement.min {
+                violations.push(violation(
+                    config_file,
+                    candidates.len(),
+                    format!("The project requires {}", wanted),
+                ));
+            }
+            continue;
+        };
+        let targets = assets.iter().filter(|a| a.is(&scope.asset)).filter(|a| {
+            scope
+                .annotation
+                .as_ref()
+                .is_none_or(|annotation| a.annotations.contains(annotation))
+        });
+        for target in targets {
+            let Some(name) = target.name.as_deref() else {
+                continue;
+            };
+            let found = candidates
+                .iter()
+                .filter(|c| c.references(&scope.asset, name))
+                .count();
+            if found < requirement.min {
+                violations.push(violation(
+                    &target.file,
+                    found,
+                    format!(
+                        "{} '{}' requires {} referencing it",
+                        scope.asset, name, wanted
+                    ),
+                ));
+            }
+        }
+    }
+    violations
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn asset(path: &str, json: Value) -> Asset {
+        Asset::from_document(Path::new(path), &json)
+    }
+
+    #[test]
+    fn test_unreferenced_pipelines() {
+        let assets = vec![
+            asset(
+                "pipeline/pl_parent.json",
+                json!({ "name": "pl_parent", "properties": { "activities": [{
+                    "type": "ExecutePipeline",
+                    "typeProperties": { "pipeline": { "referenceName": "pl_child", "type": "PipelineReference" } }
+                }] } }),
+            ),
+            asset("pipeline/pl_child.json", json!({ "name": "pl_child" })),
+            asset("pipeline/pl_orphan.json", json!({ "name": "pl_orphan" })),
+            asset(
+                "trigger/tr_daily.json",
+                json!({ "name": "tr_daily", "properties": { "pipelines": [{
+                    "pipelineReference": { "referenceName": "pl_parent", "type": "PipelineReference" }
+                }] } }),
+            ),
+        ];
+
+        let violations = check_unreferenced_pipelines(&assets, Severity::Warning);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].file, "pipeline/pl_orphan.json");
+    }
+
+    #[test]
+    fn test_dependents() {
+        let assets = vec![
+            asset(
+                "pipeline/pl_parent.json",
+                json!({ "name": "pl_parent", "properties": { "activities": [{
+                    "type": "ExecutePipeline",
+                    "typeProperties": { "pipeline": { "referenceName": "PL_Child", "type": "PipelineReference" } }
+                }] } }),
+            ),
+            asset(
+                "pipeline/pl_child.json",
+                json!({ "name": "pl_child", "properties": { "activities": [{
+                    "type": "Copy",
+                    "linkedServiceName": { "referenceName": "ls_sql", "type": "LinkedServiceReference" }
+                }] } }),
+            ),
+            asset("pipeline/pl_child_copy.json", json!({ "name": "PL_Child" })),
+            asset("linkedService/ls_sql.json", json!({ "name": "ls_sql" })),
+            asset("dataset/pl_child.json", json!({ "name": "pl_child" })),
+        ];
+
+        assert_eq!(
+            dependents(&assets, &assets[1]),
+            [
+                "pipeline/pl_parent.json",
+                "pipeline/pl_child_copy.json",
+                "linkedService/ls_sql.json"
+            ]
+        );
+        assert_eq!(dependents(&assets, &assets[3]), ["pipeline/pl_child.json"]);
+    }
+
+    #[test]
+    fn test_duplicate_names() {
+        let assets = vec![
+            asset("pipeline/pl_load.json", json!({ "name": "pl_load" })),
+            asset("pipeline/PL_Load .json", json!({ "name": "PL_Load " })),
+            asset("dataset/pl_load.json", json!({ "name": "pl_load" })),
+        ];
+
+        let violations = check_duplicate_names(&assets, Severity::Error);
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().all(|v| v.file.starts_with("pipeline/")));
+    }
+
+    #[test]
+    fn test_requirements() {
+        let assets = vec![
+            asset(
+                "pipeline/pl_daily.json",
+                json!({ "name": "pl_daily", "properties": { "annotations": ["scheduled"] } }),
+            ),
+            asset(
+                "pipeline/pl_nightly.json",
+                json!({ "name": "pl_nightly", "properties": { "annotations": ["scheduled"] } }),
+            ),
+            asset("pipeline/pl_adhoc.json", json!({ "name": "pl_adhoc" })),
+            asset(
+                "trigger/tr_daily.json",
+                json!({ "name": "tr_daily", "properties": { "pipelines": [{
+                    "pipelineReference": { "referenceName": "PL_Daily", "type": "PipelineReference" }
+                }] } }),
+            ),
+            asset("linkedService/ls_sql.json", json!({ "name": "ls_sql" })),
+        ];
+        let requirements: Vec<Requirement> = serde_yaml::from_str(
+            "
+- id: keyvault
+  asset: linkedService
+  name: ls_keyvault
+- id: scheduled-trigger
+  asset: trigger
+  severity: Warning
+  per: { asset: pipeline, annotation: scheduled }
+- id: linked-service
+  asset: linkedService
+",
+        )
+        .unwrap();
+
+        let violations = check_requirements(&requirements, &assets, "guardian.yaml");
+        let found: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.rule_id.as_str(), v.file.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("keyvault", "guardian.yaml"),
+                ("scheduled-trigger", "pipeline/pl_nightly.json"),
+            ]
+        );
+        assert_eq!(
+            violations[1].message,
+            "pipeline 'pl_nightly' requires at least 1 trigger referencing it"
+        );
+        assert_eq!(violations[1].severity, Severity::Warning);
+    }
+}
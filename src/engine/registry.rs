@@ -0,0 +1,304 @@
+use super::external::{self, ExternalList};
+use super::guards::{self, Regexes};
+#[cfg(feature = "opa")]
+use super::opa;
+use super::schema::{self, ParamSpec};
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A validation primitive, named by the `guard` of a rule. Implement it to add
+/// organization-specific checks and register it in a [`GuardRegistry`].
+pub trait Guard: Send + Sync {
+    /// Checks one node selected by the rule's `target`.
+    fn check(&self, node: &Value, params: &Params) -> GuardResult;
+
+    /// Problems with the params a rule gives this guard, e.g. an unknown or missing key.
+    /// They stop the CLI when the config is loaded (unless `--lenient`); anything is
+    /// accepted by default.
+    fn check_params(&self, _params: &Value) -> Vec<String> {
+        vec![]
+    }
+
+    /// The params this guard takes, offered by `adf-guardian new-rule`; none by default.
+    fn params(&self) -> Vec<ParamSpec> {
+        vec![]
+    }
+}
+
+/// The outcome of a guard on one node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardResult {
+    Pass,
+    /// The violation gets the rule's description as its message.
+    Fail,
+    /// The violation gets this message instead of the rule's description.
+    FailWith(String),
+}
+
+impl From<bool> for GuardResult {
+    fn from(passed: bool) -> Self {
+        if passed {
+            GuardResult::Pass
+        } else {
+            GuardResult::Fail
+        }
+    }
+}
+
+/// The params of a rule (or of the `params_by` case that matched), as given to a guard.
+pub struct Params<'a> {
+    value: &'a Value,
+    regexes: &'a Regexes,
+    document: &'a Value,
+}
+
+impl<'a> Params<'a> {
+    pub(super) fn new(value: &'a Value, regexes: &'a Regexes, document: &'a Value) -> Self {
+        Params {
+            value,
+            regexes,
+            document,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&'a Value> {
+        self.value.get(key)
+    }
+
+    pub fn value(&self) -> &'a Value {
+        self.value
+    }
+
+    /// The whole document the node was selected from (for `scope: project` rules, the
+    /// document of all the assets).
+    pub fn document(&self) -> &'a Value {
+        self.document
+    }
+
+    /// Compiles `pattern`, or reuses it when it's the value of a `*regex` param, which are
+    /// compiled once per rule. `None` if the pattern is invalid.
+    pub fn regex(&self, pattern: &str) -> Option<Cow<'a, Regex>> {
+        self.regexes.get(pattern)
+    }
+}
+
+/// The guards rules can name: the built-in ones, plus any registered by library users.
+pub struct GuardRegistry {
+    guards: HashMap<String, Box<dyn Guard>>,
+}
+
+impl fmt::Debug for GuardRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GuardRegistry")
+            .field("guards", &self.names())
+            .finish()
+    }
+}
+
+impl Default for GuardRegistry {
+    /// The built-in guards. `ExternalList` reads each list once per registry, and `Opa`
+    /// (with the `opa` feature) evaluates each input once.
+    fn default() -> Self {
+        let mut registry = GuardRegistry {
+            guards: HashMap::new(),
+        };
+        for (name, check) in BUILTINS {
+            registry.register(name, Builtin { name, check });
+        }
+        registry.register(external::NAME, ExternalList::default());
+        #[cfg(feature = "opa")]
+        registry.register(opa::NAME, opa::Opa::default());
+        registry
+    }
+}
+
+impl GuardRegistry {
+    /// Makes `guard` available to rules as `guard: <name>`. Registering an existing name,
+    /// including a built-in one, replaces that guard.
+    pub fn register(&mut self, name: impl Into<String>, guard: impl Guard + 'static) -> &mut Self {
+        self.guards.insert(name.into(), Box::new(guard));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Guard> {
+        self.guards.get(name).map(|guard| guard.as_ref())
+    }
+
+    /// The names of the guards, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.guards.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+type BuiltinFn = fn(&Value, &Value, &Regexes) -> bool;
+
+/// The built-in guards, by name.
+const BUILTINS: [(&str, BuiltinFn); 24] = [
+    ("PatternMatch", guards::check_pattern_match),
+    ("AllowedValues", |node, params, _| {
+        guards::check_allowed_values(node, params)
+    }),
+    ("Exists", |node, params, _| {
+        guards::check_exists(node, params)
+    }),
+    ("MutuallyExclusive", |node, params, _| {
+        guards::check_mutually_exclusive(node, params)
+    }),
+    ("RequiredTogether", |node, params, _| {
+        guards::check_required_together(node, params)
+    }),
+    ("Range", |node, params, _| guards::check_range(node, params)),
+    ("Count", |node, params, _| guards::check_count(node, params)),
+    ("Unique", |node, params, _| {
+        guards::check_unique(node, params)
+    }),
+    ("DateTime", |node, params, _| {
+        guards::check_date_time(node, params)
+    }),
+    ("Numeric", |node, params, _| {
+        guards::check_numeric(node, params)
+    }),
+    ("SemVer", |node, params, _| {
+        guards::check_sem_ver(node, params)
+    }),
+    ("Uuid", |node, params, _| guards::check_uuid(node, params)),
+    ("AzureResourceId", guards::check_azure_resource_id),
+    ("SecretLikeness", |node, params, _| {
+        guards::check_secret_likeness(node, params)
+    }),
+    ("DeepMatch", check_deep_match),
+    ("StringLength", |node, params, _| {
+        guards::check_string_length(node, params)
+    }),
+    ("CopyActivity", |node, params, _| {
+        guards::check_copy_activity(node, params)
+    }),
+    ("WebActivity", guards::check_web_activity),
+    ("ComputeCluster", |node, params, _| {
+        guards::check_compute_cluster(node, params)
+    }),
+    ("TriggerPolicy", |node, params, _| {
+        guards::check_trigger_policy(node, params)
+    }),
+    ("CredentialUsage", |node, params, _| {
+        guards::check_credential_usage(node, params)
+    }),
+    ("PrivateEndpoint", guards::check_private_endpoint),
+    ("KeyVaultSecrets", guards::check_key_vault_secrets),
+    ("ConnectorPolicy", |node, params, _| {
+        guards::check_connector_policy(node, params)
+    }),
+];
+
+/// `DeepMatch`, which applies another built-in guard to the subtree of the node.
+fn check_deep_match(node: &Value, params: &Value, regexes: &Regexes) -> bool {
+    let inner = params.get("guard").and_then(|v| v.as_str());
+    let Some(&(_, check)) = BUILTINS
+        .iter()
+        .find(|(name, _)| Some(*name) == inner && *name != "DeepMatch")
+    else {
+        return false;
+    };
+    let inner_params = params.get("params").unwrap_or(&Value::Null);
+    guards::check_deep_match(node, params, |value| check(value, inner_params, regexes))
+}
+
+struct Builtin {
+    name: &'static str,
+    check: BuiltinFn,
+}
+
+impl Guard for Builtin {
+    fn check(&self, node: &Value, params: &Params) -> GuardResult {
+        (self.check)(node, params.value, params.regexes).into()
+    }
+
+    fn check_params(&self, params: &Value) -> Vec<String> {
+        schema::check(self.name, params)
+    }
+
+    fn params(&self) -> Vec<ParamSpec> {
+        schema::specs(self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::engine::{check_guards, check_source};
+    use serde_json::json;
+    use std::path::Path;
+
+    /// Fails pipelines whose `owner` annotation isn't one of the listed teams.
+    struct OwnerAnnotation;
+
+    impl Guard for OwnerAnnotation {
+        fn check(&self, node: &Value, params: &Params) -> GuardResult {
+            let teams = params.get("teams").and_then(|v| v.as_array());
+            let owner = node
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str())
+                .find_map(|a| a.strip_prefix("owner:"));
+            match (owner, teams) {
+                (Some(owner), Some(teams)) if teams.iter().any(|t| t == owner) => GuardResult::Pass,
+                (Some(owner), _) => GuardResult::FailWith(format!("Unknown owner '{}'", owner)),
+                (None, _) => GuardResult::Fail,
+            }
+        }
+
+        fn check_params(&self, params: &Value) -> Vec<String> {
+            match params.get("teams") {
+                Some(Value::Array(_)) => vec![],
+                _ => vec!["param 'teams' must be a list".to_string()],
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_guard() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "owner"
+    asset: "pipeline"
+    description: "Pipelines need an owner annotation"
+    validate: { target: "$.properties.annotations", guard: "OwnerAnnotation", params: { teams: ["data"] } }
+  - id: "owner-typo"
+    asset: "pipeline"
+    validate: { target: "$.properties.annotations", guard: "OwnerAnnotation", params: { team: "data" } }
+"#,
+        )
+        .unwrap();
+        let mut registry = GuardRegistry::default();
+        assert_eq!(check_guards(&config, &registry).len(), 2);
+
+        registry.register("OwnerAnnotation", OwnerAnnotation);
+        assert_eq!(
+            check_guards(&config, &registry),
+            ["Rule 'owner-typo': param 'teams' must be a list"]
+        );
+
+        let check = |annotations: Value| {
+            let source = json!({ "properties": { "annotations": annotations } }).to_string();
+            let result =
+                check_source(&config, &registry, Path::new("pipeline/pl.json"), &source).unwrap();
+            result
+                .violations
+                .into_iter()
+                .filter(|v| v.rule_id == "owner")
+                .map(|v| v.message)
+                .collect::<Vec<_>>()
+        };
+        assert!(check(json!(["owner:data"])).is_empty());
+        assert_eq!(check(json!(["owner:web"])), ["Unknown owner 'web'"]);
+        assert_eq!(check(json!([])), ["Pipelines need an owner annotation"]);
+    }
+}
@@ -0,0 +1,523 @@
+use super::project::{self, Asset};
+use super::taxonomy::Taxonomy;
+use super::{CompiledRule, Diagnostic, FileResult, GuardRegistry, compile, evaluate_document};
+use super::{parse_document, source};
+use crate::config::{Config, param_files};
+use crate::scanner::{self, WalkOptions};
+use anyhow::Result;
+use rayon::prelude::*;
+use self_cell::self_cell;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The rules of a config compiled once (JSONPaths parsed, guards looked up, regexes
+/// compiled), for modes that check document after document with the same rules, like
+/// `serve` and `lsp`. [`RuleSet::check_in_project`] also keeps an index of the assets of
+/// the project on disk, for the project-level checks.
+pub struct RuleSet {
+    compiled: Compiled,
+    diagnostics: Vec<Diagnostic>,
+    /// Shared with the rule sets that replace this one, as it doesn't depend on the rules.
+    index: Arc<Mutex<Index>>,
+}
+
+/// What the compiled rules borrow from.
+struct Owned {
+    config: Config,
+    guards: GuardRegistry,
+}
+
+struct Rules<'a> {
+    rules: Vec<CompiledRule<'a>>,
+    taxonomy: Taxonomy,
+}
+
+self_cell!(
+    struct Compiled {
+        owner: Owned,
+        #[covariant]
+        dependent: Rules,
+    }
+);
+
+/// When a file was last modified, and its size.
+type Stamp = (SystemTime, u64);
+
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// The assets of the files of a project, updated file by file as they change.
+#[derive(Default)]
+struct Index {
+    root: PathBuf,
+    /// The files as they were when read, with their asset (`None` for files that can't be
+    /// read or parsed).
+    files: BTreeMap<PathBuf, (Option<Stamp>, Option<Asset>)>,
+    /// Documents standing in for their file, e.g. unsaved editor buffers.
+    open: BTreeMap<PathBuf, Asset>,
+}
+
+impl Index {
+    /// Reads the files of `root` added or modified since the last refresh, and forgets the
+    /// removed ones.
+    fn refresh(&mut self, root: &Path, walk: &WalkOptions) -> Result<()> {
+        if self.root != root {
+            self.root = root.to_path_buf();
+            self.files.clear();
+        }
+        let listed: HashMap<PathBuf, Option<Stamp>> = scanner::find_json_files(root, walk)?
+            .map(|path| {
+                let stamp = stamp(&path);
+                (path, stamp)
+            })
+            .collect();
+        self.files.retain(|path, _| listed.contains_key(path));
+        let changed: Vec<(PathBuf, Option<Stamp>)> = listed
+            .into_iter()
+            .filter(|(path, stamp)| self.files.get(path).is_none_or(|(old, _)| old != stamp))
+            .collect();
+        if !changed.is_empty() {
+            tracing::debug!("Indexing {} file(s) of {}", changed.len(), root.display());
+        }
+        let read: Vec<_> = changed
+            .into_par_iter()
+            .map(|(path, stamp)| {
+                let asset = read(&path);
+                (path, (stamp, asset))
+            })
+            .collect();
+        self.files.extend(read);
+        Ok(())
+    }
+
+    /// Reads `paths` again, or forgets them if they are gone or the walk leaves them out.
+    /// Only JSON files are read; for a folder, the whole project is walked again.
+    fn refresh_paths(&mut self, paths: &[PathBuf], walk: &WalkOptions) -> Result<()> {
+        if paths.iter().any(|path| path.is_dir()) {
+            return self.refresh(&self.root.clone(), walk);
+        }
+        let overrides = scanner::overrides(&self.root, walk)?;
+        for path in paths {
+            // A removed folder takes its files along.
+            self.files
+                .retain(|file, _| file == path || !file.starts_with(path));
+            let walked = path.starts_with(&self.root)
+                && path.extension().is_some_and(|ext| ext == "json")
+                && !overrides.matched(path, false).is_ignore();
+            match stamp(path).filter(|_| walked) {
+                Some(stamp) => {
+                    self.files.insert(path.clone(), (Some(stamp), read(path)));
+                }
+                None => {
+                    self.files.remove(path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The assets of the project, with the open documents instead of their file.
+    fn assets(&self) -> Vec<Asset> {
+        self.files
+            .iter()
+            .filter(|(path, _)| !self.open.contains_key(*path))
+            .filter_map(|(_, (_, asset))| asset.clone())
+            .chain(self.open.values().cloned())
+            .collect()
+    }
+}
+
+impl RuleSet {
+    pub fn new(config: Config, guards: GuardRegistry) -> Self {
+        RuleSet::with_index(config, guards, Arc::default())
+    }
+
+    fn with_index(config: Config, guards: GuardRegistry, index: Arc<Mutex<Index>>) -> Self {
+        let mut diagnostics = Vec::new();
+        let compiled = Compiled::new(Owned { config, guards }, |owned| {
+            let (mut rules, found) = compile(&owned.config, &owned.guards);
+            diagnostics = found;
+            // `limits.rule_timeout` is a budget for one scan, which a rule set outlives.
+            for rule in &mut rules {
+                rule.budget = None;
+            }
+            Rules {
+                rules,
+                taxonomy: Taxonomy::new(&owned.config, &mut diagnostics),
+            }
+        });
+        RuleSet {
+            compiled,
+            diagnostics,
+            index,
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.compiled.borrow_owner().config
+    }
+
+    pub fn guards(&self) -> &GuardRegistry {
+        &self.compiled.borrow_owner().guards
+    }
+
+    /// What went wrong compiling the rules; rules that can't be compiled are left out.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Like [`super::check_source`], without compiling the rules again.
+    pub fn check_source(&self, file_path: &Path, source: &str) -> serde_json::Result<FileResult> {
+        let json: Value = serde_json::from_str(source)?;
+        let Rules { rules, taxonomy } = self.compiled.borrow_dependent();
+        Ok(evaluate_document(rules, taxonomy, file_path, file_path, source, &json, false).0)
+    }
+
+    /// Checks `source` as the file at `file_path` under the project folder `root`, e.g. the
+    /// buffer of an editor. On top of the rules, the project-level checks of the config
+    /// (`project` and `require`) run with the assets of the other files of the project,
+    /// which are indexed on the first call; files changed on disk since are only read again
+    /// once passed to [`RuleSet::refresh`]. Rules with `scope: project` are not run.
+    pub fn check_in_project(
+        &self,
+        root: &Path,
+        file_path: &Path,
+        source: &str,
+    ) -> serde_json::Result<FileResult> {
+        let json: Value = serde_json::from_str(source)?;
+        let asset_path = file_path.strip_prefix(root).unwrap_or(file_path);
+        let Rules { rules, taxonomy } = self.compiled.borrow_dependent();
+        let (mut result, ..) =
+            evaluate_document(rules, taxonomy, file_path, asset_path, source, &json, false);
+
+        let config = self.config();
+        if !config.project.any_enabled() && config.require.is_empty() {
+            return Ok(result);
+        }
+        let asset = Asset::from_document(file_path, &json);
+        let mut assets = self.index(root).assets();
+        // The document stands in for its file on disk.
+        assets.retain(|other| other.file != asset.file);
+        assets.push(asset);
+        let config_file = config.path.to_string_lossy().to_string();
+        result.violations.extend(
+            project::check(&config.project, &assets)
+                .into_iter()
+                .chain(project::check_requirements(
+                    &config.require,
+                    &assets,
+                    &config_file,
+                ))
+                .filter(|violation| violation.file == result.file),
+        );
+        Ok(result)
+    }
+
+    /// Makes `source` stand in for the file at `file_path` in the project-level checks of
+    /// the other files, e.g. while it is edited, or the file on disk again with `None`.
+    /// Returns the files whose project-level checks can change, before or after (see
+    /// [`project::dependents`]), so only those are checked again.
+    pub fn update(&self, root: &Path, file_path: &Path, source: Option<&str>) -> Vec<PathBuf> {
+        let config = self.config();
+        if !config.project.any_enabled() && config.require.is_empty() {
+            return vec![];
+        }
+        let new = match source.map(serde_json::from_str::<Value>) {
+            Some(Ok(json)) => Some(Asset::from_document(file_path, &json)),
+            // Until it parses again, the last valid document stands.
+            Some(Err(_)) => return vec![],
+            None => None,
+        };
+        let mut index = self.index(root);
+        let old = match new {
+            Some(asset) => index.open.insert(file_path.to_path_buf(), asset),
+            None => index.open.remove(file_path),
+        }
+        .or_else(|| {
+            index
+                .files
+                .get(file_path)
+                .and_then(|(_, asset)| asset.clone())
+        });
+        let assets = index.assets();
+        let file = file_path.to_string_lossy();
+        let current = assets.iter().find(|asset| asset.file == file);
+        let mut files: Vec<PathBuf> = old
+            .iter()
+            .chain(current)
+            .flat_map(|asset| project::dependents(&assets, asset))
+            .map(PathBuf::from)
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Reads the files at `paths` under the project folder `root` from disk again, e.g.
+    /// once saved or changed by another program, for the project-level checks.
+    pub fn refresh(&self, root: &Path, paths: &[PathBuf]) {
+        let walk = self.walk_options();
+        let mut index = self.index(root);
+        if let Err(e) = index.refresh_paths(paths, &walk) {
+            tracing::warn!("Could not index the project {}: {:#}", root.display(), e);
+        }
+    }
+
+    /// The index of `root`, which is walked the first time only.
+    fn index(&self, root: &Path) -> MutexGuard<'_, Index> {
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        if index.root != root
+            && let Err(e) = index.refresh(root, &self.walk_options())
+        {
+            tracing::warn!("Could not index the project {}: {:#}", root.display(), e);
+        }
+        index
+    }
+
+    fn walk_options(&self) -> WalkOptions {
+        let config = self.config();
+        WalkOptions {
+            include: config.include.clone(),
+            exclude: config.exclude.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// The asset of a file, `None` if it can't be read or parsed.
+fn read(path: &Path) -> Option<Asset> {
+    let source = source::decode(fs::read(path).ok()?).ok()?;
+    let json = parse_document(&source).ok()?;
+    Some(Asset::from_document(path, &json))
+}
+
+/// How long a [`SharedRuleSet`] keeps its guards, whose caches only grow: the lists
+/// `ExternalList` fetched from URLs are fetched again after it, and `Opa` results dropped.
+const GUARDS_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// A [`RuleSet`] shared by the threads of a long-running mode, compiled again when the
+/// config file or a file its params name (lists, policies) changes, and with fresh guards
+/// every [`GUARDS_TTL`].
+pub struct SharedRuleSet<'a> {
+    load: Box<dyn Fn() -> Result<Config> + Send + Sync + 'a>,
+    state: Mutex<State>,
+    /// [`GUARDS_TTL`], which tests shorten.
+    guards_ttl: Duration,
+}
+
+struct State {
+    rules: Arc<RuleSet>,
+    /// The files the rules were loaded from, as they were then.
+    sources: Vec<(PathBuf, Option<Stamp>)>,
+    compiled_at: Instant,
+}
+
+impl State {
+    fn new(config: Config, index: Arc<Mutex<Index>>) -> Self {
+        let mut files = param_files(&config.rules);
+        if !config.path.as_os_str().is_empty() {
+            files.push(config.path.clone());
+        }
+        let sources = files
+            .into_iter()
+            .map(|path| {
+                let stamp = stamp(&path);
+                (path, stamp)
+            })
+            .collect();
+        State {
+            rules: Arc::new(RuleSet::with_index(config, GuardRegistry::default(), index)),
+            sources,
+            compiled_at: Instant::now(),
+        }
+    }
+
+    fn changed(&self) -> bool {
+        self.sources.iter().any(|(path, old)| stamp(path) != *old)
+    }
+}
+
+impl<'a> SharedRuleSet<'a> {
+    /// Compiles `config`, which `load` loads again when it changes. The rules use the
+    /// built-in guards.
+    pub fn new(config: Config, load: impl Fn() -> Result<Config> + Send + Sync + 'a) -> Self {
+        SharedRuleSet {
+            load: Box::new(load),
+            state: Mutex::new(State::new(config, Arc::default())),
+            guards_ttl: GUARDS_TTL,
+        }
+    }
+
+    /// The current rules, reloaded first if their files changed. A config that no longer
+    /// loads keeps the previous rules, with a warning, until it changes again.
+    pub fn get(&self) -> Arc<RuleSet> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.changed()
+            && let Err(e) = self.reload_locked(&mut state)
+        {
+            tracing::warn!("Could not reload the configuration: {:#}", e);
+            for (path, old) in &mut state.sources {
+                *old = stamp(path);
+            }
+        } else if state.compiled_at.elapsed() >= self.guards_ttl {
+            let config = state.rules.config().clone();
+            *state = State::new(config, state.rules.index.clone());
+        }
+        state.rules.clone()
+    }
+
+    /// Loads and compiles the config again, even if its files didn't change. On error, the
+    /// previous rules are kept.
+    pub fn reload(&self) -> Result<Arc<RuleSet>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.reload_locked(&mut state)?;
+        Ok(state.rules.clone())
+    }
+
+    fn reload_locked(&self, state: &mut State) -> Result<()> {
+        // The new rules take over the index, with the open documents.
+        *state = State::new((self.load)()?, state.rules.index.clone());
+        tracing::info!("Reloaded the configuration");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_set() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-ruleset-{}", std::process::id()));
+        fs::create_dir_all(dir.join("factory/pipeline")).unwrap();
+        let config_path = dir.join("guards.yaml");
+        let write_config = |regex: &str| {
+            let config = format!(
+                r#"
+project: {{ duplicate_names: Error }}
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    validate: {{ target: "$.name", guard: "PatternMatch", params: {{ regex: "{}" }} }}
+"#,
+                regex
+            );
+            fs::write(&config_path, config).unwrap();
+        };
+        write_config("^pl_");
+        let root = dir.join("factory");
+        fs::write(root.join("pipeline/pl_a.json"), r#"{ "name": "pl_a" }"#).unwrap();
+
+        let load = || Config::load(&config_path);
+        let mut shared = SharedRuleSet::new(load().unwrap(), load);
+        let rules = shared.get();
+        let file = root.join("pipeline/pl_b.json");
+        let rule_ids = |rules: &RuleSet, name: &str| {
+            let source = format!(r#"{{ "name": "{}" }}"#, name);
+            let mut ids: Vec<String> = rules
+                .check_in_project(&root, &file, &source)
+                .unwrap()
+                .violations
+                .into_iter()
+                .map(|v| v.rule_id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert!(rule_ids(&rules, "pl_b").is_empty());
+        assert_eq!(
+            rule_ids(&rules, "pl_a"),
+            [project::DUPLICATE_NAME_RULE.to_string()]
+        );
+        assert_eq!(rule_ids(&rules, "b"), ["naming"]);
+
+        // A new file is indexed once refreshed, and a removed one forgotten.
+        let pl_c = root.join("pipeline/pl_c.json");
+        fs::write(&pl_c, r#"{ "name": "pl_c" }"#).unwrap();
+        assert!(rule_ids(&rules, "pl_c").is_empty());
+        rules.refresh(&root, std::slice::from_ref(&pl_c));
+        assert_eq!(
+            rule_ids(&rules, "pl_c"),
+            [project::DUPLICATE_NAME_RULE.to_string()]
+        );
+        fs::remove_file(&pl_c).unwrap();
+        rules.refresh(&root, &[pl_c]);
+        assert!(rule_ids(&rules, "pl_c").is_empty());
+
+        assert!(Arc::ptr_eq(&rules, &shared.get()));
+        shared.guards_ttl = Duration::ZERO;
+        let renewed = shared.get();
+        shared.guards_ttl = GUARDS_TTL;
+        assert!(!Arc::ptr_eq(&rules, &renewed));
+        assert_eq!(rule_ids(&renewed, "b"), ["naming"]);
+        let rules = renewed;
+
+        write_config("^(pl|pipeline)_");
+        let reloaded = shared.get();
+        assert!(!Arc::ptr_eq(&rules, &reloaded));
+        assert!(rule_ids(&reloaded, "pipeline_b").is_empty());
+
+        // A broken config keeps the previous rules.
+        fs::write(&config_path, "rules: [").unwrap();
+        assert!(Arc::ptr_eq(&reloaded, &shared.get()));
+        assert!(shared.reload().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-update-{}", std::process::id()));
+        let root = dir.join("factory");
+        fs::create_dir_all(root.join("pipeline")).unwrap();
+        fs::create_dir_all(root.join("trigger")).unwrap();
+        let pl_a = root.join("pipeline/pl_a.json");
+        let pl_b = root.join("pipeline/pl_b.json");
+        let trigger = |pipeline: &str| {
+            format!(
+                r#"{{ "name": "tr_daily", "properties": {{ "pipelines": [{{
+                    "pipelineReference": {{ "referenceName": "{}", "type": "PipelineReference" }}
+                }}] }} }}"#,
+                pipeline
+            )
+        };
+        fs::write(&pl_a, r#"{ "name": "pl_a" }"#).unwrap();
+        fs::write(&pl_b, r#"{ "name": "pl_b" }"#).unwrap();
+        fs::write(root.join("trigger/tr_daily.json"), trigger("pl_a")).unwrap();
+        let config = Config::from_yaml(b"project: { unreferenced_pipelines: Warning }").unwrap();
+        let rules = RuleSet::new(config, GuardRegistry::default());
+        let unreferenced = |file: &Path| {
+            let source = fs::read_to_string(file).unwrap();
+            !rules
+                .check_in_project(&root, file, &source)
+                .unwrap()
+                .violations
+                .is_empty()
+        };
+        assert!(!unreferenced(&pl_a));
+        assert!(unreferenced(&pl_b));
+
+        // Editing the trigger affects the pipeline it referenced and the one it references.
+        let tr_daily = root.join("trigger/tr_daily.json");
+        assert_eq!(
+            rules.update(&root, &tr_daily, Some(&trigger("pl_b"))),
+            [pl_a.clone(), pl_b.clone()]
+        );
+        assert!(unreferenced(&pl_a));
+        assert!(!unreferenced(&pl_b));
+        assert!(rules.update(&root, &tr_daily, Some("{")).is_empty());
+
+        // Closed, the trigger on disk counts again.
+        assert_eq!(
+            rules.update(&root, &tr_daily, None),
+            [pl_a.clone(), pl_b.clone()]
+        );
+        assert!(!unreferenced(&pl_a));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
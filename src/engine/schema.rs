@@ -0,0 +1,463 @@
+use super::{guards, interpolate};
+use serde_json::Value;
+
+/// What a guard param accepts.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Bool,
+    Number,
+    /// A non-negative integer.
+    Count,
+    Regex,
+    JsonPath,
+    /// A date and time, or `now` shifted by an amount like `-1d`.
+    DateTimeBound,
+    /// A number, or a string with a number and a unit like `4GB`.
+    Quantity,
+    /// One of the listed strings.
+    OneOf(&'static [&'static str]),
+    /// A list of strings.
+    List,
+    /// A list of strings, or a mapping from a key to such a list.
+    ListOrMap,
+    Text,
+    /// Comparisons with semantic versions, like `>=1.2.0, <2.0.0`.
+    SemVerRange,
+    /// The name of a built-in guard other than `DeepMatch`, `ExternalList` and `Opa`.
+    Guard,
+    Mapping,
+}
+
+/// Guards `DeepMatch` can't apply: itself, and those that aren't plain functions.
+const NOT_NESTED: [&str; 3] = ["DeepMatch", "ExternalList", "Opa"];
+
+/// A param of a built-in guard, for tools that help writing rules.
+#[derive(Debug, Clone)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub required: bool,
+    /// What the param accepts, e.g. `a list of strings`.
+    pub expects: String,
+}
+
+struct Param {
+    name: &'static str,
+    kind: Kind,
+    required: bool,
+}
+
+const fn optional(name: &'static str, kind: Kind) -> Param {
+    Param {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, kind: Kind) -> Param {
+    Param {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+/// The params of each built-in guard, `None` for unknown guards.
+fn params_of(guard: &str) -> Option<&'static [Param]> {
+    use Kind::*;
+    // Inline consts, since `Param`s built by a function call aren't promoted to statics.
+    let params: &'static [Param] = match guard {
+        "PatternMatch" => const { &[required("regex", Regex), optional("negative", Bool)] },
+        "AllowedValues" => {
+            const {
+                &[
+                    required("values", List),
+                    optional("mode", OneOf(&["Allow", "Deny"])),
+                    optional("case_sensitive", Bool),
+                ]
+            }
+        }
+        "ExternalList" => {
+            const {
+                &[
+                    required("source", Text),
+                    optional("column", Text),
+                    optional("mode", OneOf(&["Allow", "Deny"])),
+                    optional("case_sensitive", Bool),
+                ]
+            }
+        }
+        "Opa" => {
+            const {
+                &[
+                    required("policy", Text),
+                    required("query", Text),
+                    optional("input", OneOf(&["node", "document"])),
+                ]
+            }
+        }
+        "Exists" => const { &[optional("should_exist", Bool)] },
+        "Range" => const { &[optional("min", Number), optional("max", Number)] },
+        "Count" | "StringLength" => const { &[optional("min", Count), optional("max", Count)] },
+        "Unique" => const { &[optional("key", JsonPath)] },
+        "Numeric" => {
+            const {
+                &[
+                    optional("min", Quantity),
+                    optional("max", Quantity),
+                    optional(
+                        "unit",
+                        OneOf(&["B", "KB", "MB", "GB", "TB", "ms", "s", "m", "min", "h", "d"]),
+                    ),
+                ]
+            }
+        }
+        "SemVer" => {
+            const {
+                &[
+                    optional("prefix", Text),
+                    optional("range", SemVerRange),
+                    optional("allow_prerelease", Bool),
+                ]
+            }
+        }
+        "MutuallyExclusive" => const { &[required("fields", List), optional("required", Bool)] },
+        "RequiredTogether" => const { &[required("fields", List)] },
+        "DeepMatch" => {
+            const {
+                &[
+                    required("guard", Guard),
+                    optional("params", Mapping),
+                    optional("keys", Bool),
+                    optional("include_keys", List),
+                    optional("exclude_keys", List),
+                ]
+            }
+        }
+        "SecretLikeness" => {
+            const {
+                &[
+                    optional("sensitivity", OneOf(&["low", "medium", "high"])),
+                    optional("min_entropy", Number),
+                    optional("min_length", Count),
+                    optional("keywords", List),
+                    optional("proximity", Count),
+                ]
+            }
+        }
+        "Uuid" => const { &[optional("lowercase", Bool), optional("forbid_nil", Bool)] },
+        "AzureResourceId" => {
+            const {
+                &[
+                    optional("allowed_subscriptions", List),
+                    optional("allowed_resource_groups", List),
+                    optional("allowed_resource_types", List),
+                    optional("name_regex", Regex),
+                ]
+            }
+        }
+        "DateTime" => {
+            const {
+                &[
+                    optional("require_offset", Bool),
+                    optional("not_before", DateTimeBound),
+                    optional("not_after", DateTimeBound),
+                ]
+            }
+        }
+        "CopyActivity" => {
+            const {
+                &[
+                    optional("staging_required_for_types", List),
+                    optional("skip_incompatible_row", Bool),
+                    optional("max_parallel_copies", Count),
+                    optional("max_data_integration_units", Count),
+                    optional("allowed_write_behaviors", ListOrMap),
+                ]
+            }
+        }
+        "WebActivity" => {
+            const {
+                &[
+                    optional("https_only", Bool),
+                    optional("allowed_hosts", List),
+                    optional("allowed_authentication", List),
+                    optional("forbid_inline_password", Bool),
+                    optional("secret_header_names", List),
+                    optional("secret_value_regex", Regex),
+                ]
+            }
+        }
+        "ComputeCluster" => {
+            const {
+                &[
+                    optional("forbid_existing_cluster", Bool),
+                    optional("require_instance_pool", Bool),
+                    optional("allowed_node_types", List),
+                    optional("min_workers", Count),
+                    optional("max_workers", Count),
+                ]
+            }
+        }
+        "TriggerPolicy" => {
+            const {
+                &[
+                    optional("forbid_started", Bool),
+                    optional("require_retry_policy", Bool),
+                    optional("max_concurrency", Count),
+                    optional("allowed_time_zones", List),
+                    optional("allowed_storage_accounts", List),
+                ]
+            }
+        }
+        "CredentialUsage" => {
+            const {
+                &[
+                    optional("forbid_inline_keys", Bool),
+                    optional("require_credential", Bool),
+                    optional("allowed_credentials", List),
+                ]
+            }
+        }
+        "PrivateEndpoint" => {
+            const {
+                &[
+                    optional("allowed_subscriptions", List),
+                    optional("allowed_group_ids", List),
+                    optional("ticket_annotation_regex", Regex),
+                ]
+            }
+        }
+        "KeyVaultSecrets" => {
+            const {
+                &[
+                    optional("allowed_vaults", List),
+                    optional("secret_name_regex", Regex),
+                    optional("secret_properties", List),
+                ]
+            }
+        }
+        "ConnectorPolicy" => {
+            const {
+                &[
+                    optional("allowed_types", List),
+                    optional("denied_types", List),
+                    optional("require_integration_runtime", List),
+                ]
+            }
+        }
+        _ => return None,
+    };
+    Some(params)
+}
+
+/// The params of the built-in `guard`, empty for unknown guards.
+pub fn specs(guard: &str) -> Vec<ParamSpec> {
+    params_of(guard)
+        .unwrap_or_default()
+        .iter()
+        .map(|param| ParamSpec {
+            name: param.name,
+            required: param.required,
+            expects: describe(param.kind),
+        })
+        .collect()
+}
+
+/// What's wrong with `params` for `guard`: unknown or missing params and values of the
+/// wrong type. Empty for unknown guards, which are reported on their own.
+pub fn check(guard: &str, params: &Value) -> Vec<String> {
+    let Some(expected) = params_of(guard) else {
+        return vec![];
+    };
+    let empty = serde_json::Map::new();
+    let given = match params {
+        Value::Object(map) => map,
+        Value::Null => &empty,
+        _ => return vec!["params must be a mapping".to_string()],
+    };
+
+    let mut problems = Vec::new();
+    for name in given.keys() {
+        if !expected.iter().any(|p| p.name == name) {
+            let known: Vec<&str> = expected.iter().map(|p| p.name).collect();
+            problems.push(format!(
+                "unknown param '{}' (expected one of: {})",
+                name,
+                known.join(", ")
+            ));
+        }
+    }
+    for param in expected {
+        match given.get(param.name) {
+            None if param.required => {
+                problems.push(format!("missing required param '{}'", param.name));
+            }
+            None => {}
+            Some(value) => {
+                if let Err(expected) = check_kind(param.kind, value) {
+                    problems.push(format!("param '{}' must be {}", param.name, expected));
+                }
+            }
+        }
+    }
+    if guard == "DeepMatch"
+        && let Some(inner) = given.get("guard").and_then(|v| v.as_str())
+        && inner != "DeepMatch"
+    {
+        let inner_params = given.get("params").unwrap_or(&Value::Null);
+        for problem in check(inner, inner_params) {
+            problems.push(format!("in 'params' of {}: {}", inner, problem));
+        }
+    }
+    problems
+}
+
+fn check_kind(kind: Kind, value: &Value) -> Result<(), String> {
+    // Filled in from the document, so only known when a file is checked.
+    if interpolate::is_placeholder(value) {
+        return Ok(());
+    }
+    let is_list = |value: &Value| {
+        value
+            .as_array()
+            .is_some_and(|list| list.iter().all(Value::is_string))
+    };
+    let ok = match kind {
+        Kind::Bool => value.is_boolean(),
+        Kind::Number => value.is_number(),
+        Kind::Count => value.is_u64(),
+        Kind::Regex => match value.as_str() {
+            Some(pattern) => {
+                return guards::build_regex(&interpolate::without_placeholders(pattern))
+                    .map(|_| ())
+                    .map_err(|e| format!("a valid regex: {}", e));
+            }
+            None => false,
+        },
+        Kind::JsonPath => match value.as_str() {
+            Some(path) => {
+                return serde_json_path::JsonPath::parse(path)
+                    .map(|_| ())
+                    .map_err(|e| format!("a valid JSONPath: {}", e));
+            }
+            None => false,
+        },
+        Kind::DateTimeBound => value
+            .as_str()
+            .is_some_and(|v| guards::parse_bound(v).is_some()),
+        Kind::Quantity => match value {
+            Value::String(text) => {
+                guards::parse_quantity(text, (1.0, guards::Dimension::Count)).is_some()
+            }
+            other => other.is_number(),
+        },
+        Kind::OneOf(values) => value.as_str().is_some_and(|v| values.contains(&v)),
+        Kind::List => is_list(value),
+        Kind::ListOrMap => {
+            is_list(value)
+                || value
+                    .as_object()
+                    .is_some_and(|map| map.values().all(is_list))
+        }
+        Kind::Text => value.is_string(),
+        Kind::SemVerRange => value
+            .as_str()
+            .is_some_and(|v| guards::parse_sem_ver_range(v).is_some()),
+        Kind::Guard => value
+            .as_str()
+            .is_some_and(|v| !NOT_NESTED.contains(&v) && params_of(v).is_some()),
+        Kind::Mapping => value.is_object(),
+    };
+    if ok {
+        return Ok(());
+    }
+    Err(describe(kind))
+}
+
+/// What a param of `kind` accepts, e.g. `a list of strings`.
+fn describe(kind: Kind) -> String {
+    match kind {
+        Kind::Bool => "true or false".to_string(),
+        Kind::Number => "a number".to_string(),
+        Kind::Count => "a non-negative integer".to_string(),
+        Kind::Regex => "a regex string".to_string(),
+        Kind::JsonPath => "a JSONPath string".to_string(),
+        Kind::Quantity => "a number, or a number with a unit like \"4GB\" or \"30s\"".to_string(),
+        Kind::DateTimeBound => {
+            "an ISO-8601 date and time, or `now` with an optional shift like `now-1d`".to_string()
+        }
+        Kind::OneOf(values) => format!("one of: {}", values.join(", ")),
+        Kind::List => "a list of strings".to_string(),
+        Kind::ListOrMap => "a list of strings, or a mapping to lists of strings".to_string(),
+        Kind::Text => "a string".to_string(),
+        Kind::SemVerRange => "comparisons with versions like \">=1.2.0, <2.0.0\"".to_string(),
+        Kind::Guard => {
+            format!(
+                "the name of a built-in guard other than {}",
+                NOT_NESTED.join(", ")
+            )
+        }
+        Kind::Mapping => "a mapping".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check() {
+        assert!(
+            check(
+                "PatternMatch",
+                &json!({ "regex": "^pl_", "negative": true })
+            )
+            .is_empty()
+        );
+        assert!(check("Exists", &Value::Null).is_empty());
+        assert!(check("NoSuchGuard", &json!({ "anything": 1 })).is_empty());
+        assert!(
+            check(
+                "CopyActivity",
+                &json!({ "allowed_write_behaviors": { "AzureSqlSink": ["insert"] } })
+            )
+            .is_empty()
+        );
+
+        assert_eq!(
+            check("PatternMatch", &json!({ "pattern": "^pl_" })),
+            [
+                "unknown param 'pattern' (expected one of: regex, negative)",
+                "missing required param 'regex'"
+            ]
+        );
+        assert_eq!(
+            check("Range", &json!({ "max": "3" })),
+            ["param 'max' must be a number"]
+        );
+        assert_eq!(
+            check(
+                "AllowedValues",
+                &json!({ "values": ["a"], "mode": "Block" })
+            ),
+            ["param 'mode' must be one of: Allow, Deny"]
+        );
+        assert!(check("PatternMatch", &json!({ "regex": "(" }))[0].contains("a valid regex"));
+        assert_eq!(check("Count", &json!([1])), ["params must be a mapping"]);
+        assert_eq!(
+            check(
+                "DeepMatch",
+                &json!({ "guard": "PatternMatch", "params": { "negative": true } })
+            ),
+            ["in 'params' of PatternMatch: missing required param 'regex'"]
+        );
+        assert_eq!(
+            check("DeepMatch", &json!({ "guard": "DeepMatch" })),
+            [
+                "param 'guard' must be the name of a built-in guard other than DeepMatch, ExternalList, Opa"
+            ]
+        );
+    }
+}
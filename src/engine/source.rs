@@ -0,0 +1,114 @@
+/// The text of a file: UTF-8, with or without a byte order mark, or UTF-16 as some
+/// Windows tools write it, told apart by its byte order mark or, without one, by the NUL
+/// bytes around the first ASCII character.
+pub fn decode(bytes: Vec<u8>) -> Result<String, String> {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        if !bytes.len().is_multiple_of(2) {
+            return Err("odd number of bytes for UTF-16".to_string());
+        }
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|e| format!("invalid UTF-16: {}", e))
+    };
+    match bytes.as_slice() {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => {
+            String::from_utf8(rest.to_vec()).map_err(|e| e.to_string())
+        }
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [first, 0, ..] if first.is_ascii() && *first != 0 => utf16(&bytes, u16::from_le_bytes),
+        [0, second, ..] if second.is_ascii() && *second != 0 => utf16(&bytes, u16::from_be_bytes),
+        _ => String::from_utf8(bytes).map_err(|e| e.to_string()),
+    }
+}
+
+/// `source` with the comments and trailing commas of JSONC blanked out. Each removed
+/// character becomes a space and newlines are kept, so lines and columns still point at
+/// the original text.
+pub fn strip_jsonc(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+    // Byte offset in `out` of a comma that only whitespace has followed so far.
+    let mut pending_comma: Option<usize> = None;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    out.push(' ');
+                    chars.next();
+                }
+                out.push(' ');
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                out.push_str("  ");
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    out.push(if next == '\n' { '\n' } else { ' ' });
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ if c.is_whitespace() => out.push(c),
+            _ => {
+                if let Some(comma) = pending_comma.take()
+                    && (c == '}' || c == ']')
+                {
+                    out.replace_range(comma..comma + 1, " ");
+                }
+                if c == ',' {
+                    pending_comma = Some(out.len());
+                }
+                in_string = c == '"';
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_and_strip_jsonc() {
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("{\"a\": \"é\"}".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        assert_eq!(decode(utf16).unwrap(), "{\"a\": \"é\"}");
+        let utf16_be: Vec<u8> = "{}".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(utf16_be).unwrap(), "{}");
+        assert_eq!(decode(b"\xEF\xBB\xBF{}".to_vec()).unwrap(), "{}");
+        assert!(decode(vec![0xC3]).is_err());
+
+        let source =
+            "{\n  // a comment\n  \"url\": \"http://x/*y*/\", /* é\n */ \"b\": [1, 2,],\n}";
+        let stripped = strip_jsonc(source);
+        assert_eq!(stripped.lines().count(), source.lines().count());
+        assert_eq!(stripped.chars().count(), source.chars().count());
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({ "url": "http://x/*y*/", "b": [1, 2] })
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use super::{Diagnostic, FileResult, Violation};
+use crate::config::{Severity, Suppression};
+use chrono::{NaiveDate, Utc};
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Serialize;
+use std::path::Path;
+
+/// Id of the violations reported on the config file for expired suppressions.
+pub const EXPIRED_SUPPRESSION_RULE: &str = "expired-suppression";
+
+/// What a suppression of the config did in a scan.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Hides at least one violation.
+    Active,
+    /// Past its `expires` date; its violations are reported again.
+    Expired,
+    /// Matches no violation any more, so it can be deleted.
+    Unused,
+    /// Has a `file` glob or `expires` date that can't be parsed, so it matches nothing.
+    Invalid,
+}
+
+/// A suppression with what it did in a scan, listed by `adf-guardian suppressions`.
+#[derive(Debug, Serialize)]
+pub struct Audit {
+    #[serde(flatten)]
+    pub suppression: Suppression,
+    pub status: Status,
+    /// Violations it hides, or would hide for an expired suppression.
+    pub violations: usize,
+}
+
+/// What each of `suppressions` did to the `violations` of a scan under `root`, suppressed
+/// and reported ones alike.
+pub fn audit<'v>(
+    suppressions: &[Suppression],
+    root: &Path,
+    violations: impl IntoIterator<Item = &'v Violation>,
+) -> Vec<Audit> {
+    // Invalid entries were already reported by the scan.
+    Suppressions::new(suppressions, Utc::now().date_naive(), &mut vec![]).audit(root, violations)
+}
+
+struct Entry<'a> {
+    suppression: &'a Suppression,
+    file: Option<GlobMatcher>,
+    expires: Option<NaiveDate>,
+    valid: bool,
+}
+
+/// The suppressions of a config, ready to match violations.
+pub(super) struct Suppressions<'a> {
+    entries: Vec<Entry<'a>>,
+    today: NaiveDate,
+}
+
+impl<'a> Suppressions<'a> {
+    /// Invalid entries are reported in `diagnostics` and never match.
+    pub(super) fn new(
+        suppressions: &'a [Suppression],
+        today: NaiveDate,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Self {
+        let entries = suppressions
+            .iter()
+            .map(|suppression| {
+                let mut valid = true;
+                let mut invalid = |what: String| {
+                    valid = false;
+                    diagnostics.push(Diagnostic::for_config(format!(
+                        "Invalid {} of the suppression of '{}', it is skipped",
+                        what, suppression.rule
+                    )));
+                };
+                let file = suppression.file.as_deref().and_then(|glob| {
+                    GlobBuilder::new(glob)
+                        .literal_separator(true)
+                        .build()
+                        .map(|glob| glob.compile_matcher())
+                        .map_err(|e| invalid(format!("file '{}' ({})", glob, e)))
+                        .ok()
+                });
+                let expires = suppression.expires.as_deref().and_then(|date| {
+                    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                        .map_err(|_| {
+                            invalid(format!(
+                                "expires '{}', expected a date like 2026-12-31",
+                                date
+                            ))
+                        })
+                        .ok()
+                });
+                Entry {
+                    suppression,
+                    file,
+                    expires,
+                    valid,
+                }
+            })
+            .collect();
+        Suppressions { entries, today }
+    }
+
+    /// Moves the violations an unexpired suppression matches out of `results`. Files are
+    /// matched relative to `root`.
+    pub(super) fn apply(&self, root: &Path, results: &mut [FileResult]) -> Vec<Violation> {
+        let mut suppressed = Vec::new();
+        for result in results {
+            let (hidden, kept) = std::mem::take(&mut result.violations)
+                .into_iter()
+                .partition(|violation| {
+                    self.entries
+                        .iter()
+                        .any(|entry| !self.expired(entry) && Self::matches(entry, root, violation))
+                });
+            result.violations = kept;
+            suppressed.extend::<Vec<_>>(hidden);
+        }
+        suppressed
+    }
+
+    /// A violation on `config_file` for each expired suppression.
+    pub(super) fn expired_violations(&self, config_file: &str) -> Vec<Violation> {
+        self.entries
+            .iter()
+            .filter(|entry| self.expired(entry))
+            .map(|entry| {
+                let suppression = entry.suppression;
+                let mut message = format!(
+                    "The suppression of '{}' on {} expired on {}",
+                    suppression.rule,
+                    suppression.file.as_deref().unwrap_or("every file"),
+                    suppression.expires.as_deref().unwrap_or_default()
+                );
+                if let Some(added_by) = &suppression.added_by {
+                    message.push_str(&format!(" (added by {})", added_by));
+                }
+                Violation {
+                    rule_id: EXPIRED_SUPPRESSION_RULE.to_string(),
+                    file: config_file.to_string(),
+                    message,
+                    severity: Severity::Error,
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// What each suppression did to `violations`, the suppressed and the reported ones. A
+    /// violation counts for the first suppression that matches it.
+    pub(super) fn audit<'v>(
+        &self,
+        root: &Path,
+        violations: impl IntoIterator<Item = &'v Violation>,
+    ) -> Vec<Audit> {
+        let mut counts = vec![0; self.entries.len()];
+        for violation in violations {
+            if let Some(index) = self
+                .entries
+                .iter()
+                .position(|entry| Self::matches(entry, root, violation))
+            {
+                counts[index] += 1;
+            }
+        }
+        self.entries
+            .iter()
+            .zip(counts)
+            .map(|(entry, violations)| {
+                let status = if !entry.valid {
+                    Status::Invalid
+                } else if self.expired(entry) {
+                    Status::Expired
+                } else if violations == 0 {
+                    Status::Unused
+                } else {
+                    Status::Active
+                };
+                Audit {
+                    suppression: entry.suppression.clone(),
+                    status,
+                    violations,
+                }
+            })
+            .collect()
+    }
+
+    fn matches(entry: &Entry, root: &Path, violation: &Violation) -> bool {
+        let suppression = entry.suppression;
+        let file = Path::new(&violation.file);
+        entry.valid
+            && violation.rule_id == suppression.rule
+            && entry
+                .file
+                .as_ref()
+                .is_none_or(|glob| glob.is_match(file.strip_prefix(root).unwrap_or(file)))
+            && suppression
+                .path
+                .as_ref()
+                .is_none_or(|path| violation.json_path.as_ref() == Some(path))
+    }
+
+    fn expired(&self, entry: &Entry) -> bool {
+        entry.valid && entry.expires.is_some_and(|expires| expires < self.today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suppressions() {
+        let suppressions: Vec<Suppression> = serde_yaml::from_str(
+            r#"
+- rule: "naming"
+  file: "pipeline/legacy_*.json"
+  added_by: "data-platform"
+  expires: "2026-12-31"
+- rule: "retries"
+  path: "$.properties.activities[0].policy"
+  expires: "2026-01-31"
+- rule: "timeouts"
+- rule: "naming"
+  file: "pipeline/[.json"
+"#,
+        )
+        .unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 7, 1).unwrap();
+        let mut diagnostics = vec![];
+        let suppressions = Suppressions::new(&suppressions, today, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+
+        let violation = |rule_id: &str, file: &str, json_path: &str| Violation {
+            rule_id: rule_id.to_string(),
+            file: format!("/factory/{}", file),
+            json_path: Some(json_path.to_string()),
+            ..Default::default()
+        };
+        let mut results = vec![FileResult {
+            file: "/factory/pipeline/legacy_load.json".to_string(),
+            violations: vec![
+                violation("naming", "pipeline/legacy_load.json", "$.name"),
+                violation(
+                    "retries",
+                    "pipeline/legacy_load.json",
+                    "$.properties.activities[0].policy",
+                ),
+                violation(
+                    "concurrency",
+                    "pipeline/legacy_load.json",
+                    "$.properties.concurrency",
+                ),
+            ],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+        let root = Path::new("/factory");
+        let suppressed = suppressions.apply(root, &mut results);
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].rule_id, "naming");
+        let reported: Vec<&str> = results[0]
+            .violations
+            .iter()
+            .map(|v| v.rule_id.as_str())
+            .collect();
+        assert_eq!(reported, ["retries", "concurrency"]);
+
+        let expired = suppressions.expired_violations("adf-guard.yaml");
+        assert_eq!(expired.len(), 1);
+        assert_eq!(
+            expired[0].message,
+            "The suppression of 'retries' on every file expired on 2026-01-31"
+        );
+
+        let audit = suppressions.audit(root, suppressed.iter().chain(&results[0].violations));
+        let statuses: Vec<(Status, usize)> =
+            audit.iter().map(|a| (a.status, a.violations)).collect();
+        assert_eq!(
+            statuses,
+            [
+                (Status::Active, 1),
+                (Status::Expired, 1),
+                (Status::Unused, 0),
+                (Status::Invalid, 0),
+            ]
+        );
+    }
+}
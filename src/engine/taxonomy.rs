@@ -0,0 +1,170 @@
+use super::Diagnostic;
+use crate::config::{AssetMatcher, Config, FolderMatching};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::path::{Component, Path};
+
+/// Tells the asset types of a file: the folder it is in, as Git integration lays out a
+/// factory, and the `asset_mapping` of the config for other layouts.
+#[derive(Default)]
+pub struct Taxonomy {
+    folder_matching: FolderMatching,
+    mappings: Vec<Mapping>,
+}
+
+/// How an asset type is recognised besides its folder.
+struct Mapping {
+    asset: String,
+    globs: GlobSet,
+    discriminators: Vec<JsonPath>,
+}
+
+impl Taxonomy {
+    /// Entries of `asset_mapping` starting with `$` are JSONPaths that select something in
+    /// the documents of the type; the others are globs of their paths, relative to the
+    /// project path. Invalid entries are skipped with a diagnostic.
+    pub fn new(config: &Config, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let mappings = config
+            .asset_mapping
+            .iter()
+            .map(|(asset, entries)| {
+                let mut globs = GlobSetBuilder::new();
+                let mut discriminators = Vec::new();
+                for entry in entries {
+                    let compiled = if entry.starts_with('$') {
+                        JsonPath::parse(entry)
+                            .map(|path| discriminators.push(path))
+                            .map_err(|e| e.to_string())
+                    } else {
+                        GlobBuilder::new(entry)
+                            .case_insensitive(true)
+                            .literal_separator(true)
+                            .build()
+                            .map(|glob| {
+                                globs.add(glob);
+                            })
+                            .map_err(|e| e.to_string())
+                    };
+                    if let Err(e) = compiled {
+                        diagnostics.push(Diagnostic::for_config(format!(
+                            "Invalid asset_mapping entry '{}' of '{}', it is skipped: {}",
+                            entry, asset, e
+                        )));
+                    }
+                }
+                Mapping {
+                    asset: asset.clone(),
+                    globs: globs.build().unwrap_or_else(|_| GlobSet::empty()),
+                    discriminators,
+                }
+            })
+            .collect();
+        Taxonomy {
+            folder_matching: config.folder_matching,
+            mappings,
+        }
+    }
+
+    /// Whether the file at `path`, relative to the project path, is of an asset type of
+    /// `matcher`. Before the file is read, `json` is `None` and a type told by content may
+    /// match.
+    pub fn matches(&self, matcher: &AssetMatcher, path: &Path, json: Option<&Value>) -> bool {
+        matcher
+            .names()
+            .iter()
+            .any(|asset| self.is(asset, path, json))
+    }
+
+    /// Whether the file at `path` is of the asset type `asset`.
+    pub fn is(&self, asset: &str, path: &Path, json: Option<&Value>) -> bool {
+        let in_folder = match self.folder_matching {
+            FolderMatching::Parent => {
+                folder_name(path).is_some_and(|folder| folder.eq_ignore_ascii_case(asset))
+            }
+            FolderMatching::Recursive => {
+                path.parent()
+                    .into_iter()
+                    .flat_map(Path::components)
+                    .any(|folder| match folder {
+                        Component::Normal(name) => name.eq_ignore_ascii_case(asset),
+                        _ => false,
+                    })
+            }
+        };
+        if in_folder {
+            return true;
+        }
+        self.mappings
+            .iter()
+            .filter(|mapping| mapping.asset.eq_ignore_ascii_case(asset))
+            .any(|mapping| {
+                mapping.globs.is_match(path)
+                    || match json {
+                        Some(json) => mapping
+                            .discriminators
+                            .iter()
+                            .any(|discriminator| !discriminator.query(json).is_empty()),
+                        None => !mapping.discriminators.is_empty(),
+                    }
+            })
+    }
+}
+
+/// Name of the folder a file is in, which decides its asset type.
+fn folder_name(file_path: &Path) -> Option<&str> {
+    file_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .and_then(|s| s.to_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_asset_mapping() {
+        let mut config: Config = serde_yaml::from_str("rules: []").unwrap();
+        config.asset_mapping = BTreeMap::from([
+            (
+                "pipeline".to_string(),
+                vec![
+                    "**/pipelines/**".to_string(),
+                    "$.properties.activities".to_string(),
+                ],
+            ),
+            ("dataset".to_string(), vec!["datasets/[".to_string()]),
+        ]);
+        let mut diagnostics = Vec::new();
+        let taxonomy = Taxonomy::new(&config, &mut diagnostics);
+        assert_eq!(diagnostics.len(), 1);
+
+        let pipeline = AssetMatcher::Single("pipeline".to_string());
+        let document = json!({ "properties": { "activities": [] } });
+        let other = json!({ "properties": { "typeProperties": {} } });
+        assert!(taxonomy.matches(&pipeline, Path::new("pipeline/pl_a.json"), Some(&other)));
+        assert!(taxonomy.matches(
+            &pipeline,
+            Path::new("src/Pipelines/ingest/pl_a.json"),
+            Some(&other)
+        ));
+        assert!(taxonomy.matches(&pipeline, Path::new("flows/pl_a.json"), Some(&document)));
+        assert!(!taxonomy.matches(&pipeline, Path::new("flows/pl_a.json"), Some(&other)));
+        assert!(taxonomy.matches(&pipeline, Path::new("flows/pl_a.json"), None));
+        assert!(taxonomy.matches(&pipeline, Path::new("pipeline/ingest/pl_a.json"), None));
+        assert!(!taxonomy.matches(&pipeline, Path::new("pipeline.json"), Some(&other)));
+
+        config.folder_matching = FolderMatching::Parent;
+        config.asset_mapping.clear();
+        let taxonomy = Taxonomy::new(&config, &mut diagnostics);
+        assert!(!taxonomy.matches(&pipeline, Path::new("pipeline/ingest/pl_a.json"), None));
+        assert!(!taxonomy.matches(
+            &AssetMatcher::Single("dataset".to_string()),
+            Path::new("flows/ds_a.json"),
+            None
+        ));
+    }
+}
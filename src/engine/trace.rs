@@ -0,0 +1,265 @@
+use super::taxonomy::Taxonomy;
+use super::{
+    CompiledRule, CompiledValidation, GuardRegistry, GuardResult, check_rule, compile, formatter,
+};
+use crate::config::{Config, OnMissing, Rule, RuleScope};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// How one rule was evaluated on a file, printed by `--explain`.
+#[derive(Debug, Serialize)]
+pub struct RuleTrace {
+    pub rule_id: String,
+    pub asset_matched: bool,
+    /// Why the rule stopped before its `validate`, if it did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub when: Option<ValidationTrace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<ValidationTrace>,
+    /// Violations the rule reports on the file.
+    pub violations: usize,
+}
+
+/// A `when` or `validate` of a rule on a document.
+#[derive(Debug, Serialize)]
+pub struct ValidationTrace {
+    pub target: String,
+    pub guard: String,
+    /// The params given to the guard, after `params_by` and placeholders; `None` when a
+    /// placeholder selects nothing.
+    pub params: Option<Value>,
+    pub nodes: Vec<NodeTrace>,
+    pub passed: bool,
+    /// Why it passed or not.
+    pub reason: String,
+}
+
+/// The guard result on one node selected by a target.
+#[derive(Debug, Serialize)]
+pub struct NodeTrace {
+    /// Normalized path, e.g. `$['properties']['activities'][0]['name']`.
+    pub path: String,
+    /// `None` for rules with `report_value: false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub passed: bool,
+    /// The guard's own message for a failure.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Evaluates every rule of `config` on the document `source`, recording each step. Like
+/// [`super::check_source`], `asset_path` (relative to the project path) decides which
+/// rules apply.
+pub fn trace(
+    config: &Config,
+    guards: &GuardRegistry,
+    file_path: &Path,
+    asset_path: &Path,
+    source: &str,
+) -> serde_json::Result<Vec<RuleTrace>> {
+    let json: Value = serde_json::from_str(source)?;
+    let (rules, mut diagnostics) = compile(config, guards);
+    let taxonomy = Taxonomy::new(config, &mut diagnostics);
+    Ok(config
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(index, rule)| {
+            let compiled = rules.iter().find(|compiled| compiled.index == index);
+            trace_rule(rule, compiled, &taxonomy, file_path, asset_path, &json)
+        })
+        .collect())
+}
+
+fn trace_rule(
+    rule: &Rule,
+    compiled: Option<&CompiledRule>,
+    taxonomy: &Taxonomy,
+    file_path: &Path,
+    asset_path: &Path,
+    json: &Value,
+) -> RuleTrace {
+    let asset_matched = taxonomy.matches(&rule.asset, asset_path, Some(json));
+    let mut trace = RuleTrace {
+        rule_id: rule.id.clone(),
+        asset_matched,
+        skipped: None,
+        when: None,
+        validate: None,
+        violations: 0,
+    };
+    let skipped = if rule.scope == RuleScope::Project {
+        Some("project-scoped, so it is evaluated once on all the assets".to_string())
+    } else if !asset_matched {
+        Some(format!(
+            "the file is not of the asset type {}",
+            rule.asset.names().join(" or ")
+        ))
+    } else if compiled.is_none() {
+        Some("it can't be compiled (see the diagnostics of a scan)".to_string())
+    } else {
+        None
+    };
+    let Some(compiled) = compiled.filter(|_| skipped.is_none()) else {
+        trace.skipped = skipped;
+        return trace;
+    };
+
+    if let Some(when) = &compiled.when {
+        let when = trace_validation(when, rule, json, None);
+        let holds = when.passed;
+        trace.when = Some(when);
+        if !holds {
+            trace.skipped = Some("the `when` condition doesn't hold".to_string());
+            return trace;
+        }
+    }
+    trace.validate = Some(trace_validation(
+        &compiled.validate,
+        rule,
+        json,
+        Some(rule.validate.on_missing),
+    ));
+    trace.violations = check_rule(compiled, json, file_path).len();
+    trace
+}
+
+/// Traces a `validate`, with its `on_missing`, or a `when`, which holds when it selects
+/// nodes that all pass (see `evaluate_condition`).
+fn trace_validation(
+    compiled: &CompiledValidation,
+    rule: &Rule,
+    json: &Value,
+    on_missing: Option<OnMissing>,
+) -> ValidationTrace {
+    let validation = compiled.validation;
+    let located = compiled.path.query_located(json);
+    let params = compiled.resolve_params(json);
+    let nodes: Vec<NodeTrace> = match &params {
+        Some(params) => located
+            .iter()
+            .map(|node| {
+                let (passed, message) = match compiled.check(node.node(), params, json) {
+                    GuardResult::Pass => (true, None),
+                    GuardResult::Fail => (false, None),
+                    GuardResult::FailWith(message) => (false, Some(message)),
+                };
+                NodeTrace {
+                    path: node.location().to_string(),
+                    value: rule.report_value.then(|| {
+                        let formatted =
+                            formatter::format_actual_value(&validation.guard, node.node());
+                        formatter::redact_secrets(&formatted).into_owned()
+                    }),
+                    passed,
+                    message,
+                }
+            })
+            .collect(),
+        None => vec![],
+    };
+    let failed = nodes.iter().filter(|node| !node.passed).count();
+    let (passed, reason) = if located.is_empty() {
+        match on_missing {
+            None => (false, "the target selects nothing".to_string()),
+            Some(OnMissing::Pass) => (
+                true,
+                "the target selects nothing, which passes (on_missing: pass)".to_string(),
+            ),
+            Some(on_missing) => (
+                false,
+                format!(
+                    "the target selects nothing, which is reported (on_missing: {})",
+                    serde_json::to_value(on_missing)
+                        .ok()
+                        .and_then(|v| v.as_str().map(str::to_string))
+                        .unwrap_or_default()
+                ),
+            ),
+        }
+    } else if params.is_none() {
+        (
+            on_missing.is_some(),
+            "a placeholder of the params selects nothing, so no node is checked".to_string(),
+        )
+    } else if failed == 0 {
+        (
+            true,
+            format!("all {} selected node(s) pass the guard", nodes.len()),
+        )
+    } else {
+        (
+            false,
+            format!(
+                "{} of {} selected node(s) fail the guard",
+                failed,
+                nodes.len()
+            ),
+        )
+    };
+    ValidationTrace {
+        target: validation.target.clone(),
+        guard: validation.guard.clone(),
+        params: params.map(|params| params.into_owned()),
+        nodes,
+        passed,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace() {
+        let config = Config::from_yaml(
+            br#"
+rules:
+  - id: "copy-parallelism"
+    asset: "pipeline"
+    when: { target: "$.properties.activities[*].type", guard: "AllowedValues", params: { values: ["Copy"] } }
+    validate: { target: "$..parallelCopies", guard: "Range", params: { max: 32 } }
+  - id: "naming"
+    asset: "pipeline"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+  - id: "dataset-naming"
+    asset: "dataset"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^ds_" } }
+"#,
+        )
+        .unwrap();
+        let source = r#"{ "name": "load", "properties": { "activities": [
+            { "type": "Copy", "typeProperties": { "parallelCopies": 64 } },
+            { "type": "Wait" }
+        ] } }"#;
+        let path = Path::new("pipeline/load.json");
+        let traces = trace(&config, &GuardRegistry::default(), path, path, source).unwrap();
+
+        let copy = &traces[0];
+        let when = copy.when.as_ref().unwrap();
+        assert!(!when.passed);
+        assert_eq!(when.reason, "1 of 2 selected node(s) fail the guard");
+        assert_eq!(
+            copy.skipped.as_deref(),
+            Some("the `when` condition doesn't hold")
+        );
+        assert!(copy.validate.is_none());
+
+        let naming = &traces[1];
+        let validate = naming.validate.as_ref().unwrap();
+        assert_eq!(validate.nodes[0].path, "$['name']");
+        assert!(!validate.nodes[0].passed);
+        assert_eq!(naming.violations, 1);
+
+        assert!(!traces[2].asset_matched);
+        assert_eq!(
+            traces[2].skipped.as_deref(),
+            Some("the file is not of the asset type dataset")
+        );
+    }
+}
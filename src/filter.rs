@@ -0,0 +1,82 @@
+use crate::config::{Config, Severity};
+use crate::engine::project;
+use regex::Regex;
+
+/// Narrows a run to some rules (`--min-severity`, `--only-rules`, `--skip-rules`).
+/// Rules that are filtered out are not evaluated at all.
+#[derive(Debug, Default)]
+pub struct RuleFilter {
+    pub min_severity: Option<Severity>,
+    /// Rule id patterns where `*` matches any sequence of characters.
+    pub only: Vec<String>,
+    pub skip: Vec<String>,
+}
+
+impl RuleFilter {
+    pub fn allows(&self, rule_id: &str, severity: Severity) -> bool {
+        // Error sorts before Warning, so "at least" means "less than or equal".
+        self.min_severity.is_none_or(|min| severity <= min)
+            && (self.only.is_empty() || self.only.iter().any(|p| matches(p, rule_id)))
+            && !self.skip.iter().any(|p| matches(p, rule_id))
+    }
+
+    /// Removes the rules and project checks that the filter excludes.
+    pub fn apply(&self, config: &mut Config) {
+        config
+            .rules
+            .retain(|rule| self.allows(&rule.id, rule.severity));
+        for project in config.projects.values_mut() {
+            project
+                .rules
+                .retain(|rule| self.allows(&rule.id, rule.severity));
+        }
+
+        config
+            .require
+            .retain(|requirement| self.allows(&requirement.id, requirement.severity));
+
+        let checks = &mut config.project;
+        for (rule_id, check) in [
+            (
+                project::UNREFERENCED_PIPELINE_RULE,
+                &mut checks.unreferenced_pipelines,
+            ),
+            (project::DUPLICATE_NAME_RULE, &mut checks.duplicate_names),
+        ] {
+            if check.is_some_and(|severity| !self.allows(rule_id, severity)) {
+                *check = None;
+            }
+        }
+    }
+}
+
+fn matches(pattern: &str, rule_id: &str) -> bool {
+    let regex = format!("^{}$", regex::escape(pattern).replace(r"\*", ".*"));
+    Regex::new(&regex).is_ok_and(|re| re.is_match(rule_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_filter() {
+        let filter = RuleFilter {
+            min_severity: None,
+            only: vec!["pl-*".to_string(), "sec-*".to_string()],
+            skip: vec!["sec-legacy-*".to_string()],
+        };
+        assert!(filter.allows("pl-naming", Severity::Warning));
+        assert!(filter.allows("sec-https", Severity::Error));
+        assert!(!filter.allows("sec-legacy-tls", Severity::Error));
+        assert!(!filter.allows("ds-naming", Severity::Error));
+        assert!(!filter.allows("xpl-naming", Severity::Error));
+
+        let errors_only = RuleFilter {
+            min_severity: Some(Severity::Error),
+            ..Default::default()
+        };
+        assert!(errors_only.allows("any", Severity::Error));
+        assert!(!errors_only.allows("any", Severity::Warning));
+    }
+}
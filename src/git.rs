@@ -0,0 +1,184 @@
+use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Files under `dir` that differ from the merge base of `base` and `HEAD`, including
+/// uncommitted and untracked files. Deleted files are left out. Paths are canonical; one
+/// that can't be resolved is left out with a warning.
+pub fn changed_files(dir: &Path, base: &str) -> Result<HashSet<PathBuf>> {
+    let top_level = top_level(dir)?;
+    let merge_base = git(dir, &["merge-base", base, "HEAD"])
+        .with_context(|| format!("Failed to find the merge base of '{}' and HEAD", base))?;
+
+    let changed = git(
+        dir,
+        &[
+            "diff",
+            "--name-only",
+            "-z",
+            "--diff-filter=ACMR",
+            merge_base.trim(),
+        ],
+    )?;
+    let untracked = git(
+        dir,
+        &[
+            "ls-files",
+            "-z",
+            "--others",
+            "--exclude-standard",
+            "--full-name",
+        ],
+    )?;
+
+    // `-z` lists the names as they are, where git would otherwise quote unusual ones.
+    let mut files = HashSet::new();
+    for path in changed.split('\0').chain(untracked.split('\0')) {
+        if path.is_empty() {
+            continue;
+        }
+        match fs::canonicalize(top_level.join(path)) {
+            Ok(path) => {
+                files.insert(path);
+            }
+            Err(e) => tracing::warn!("Skipping changed file '{}': {}", path, e),
+        }
+    }
+    Ok(files)
+}
+
+/// Root folder of the work tree that contains `dir`.
+pub fn top_level(dir: &Path) -> Result<PathBuf> {
+    Ok(PathBuf::from(
+        git(dir, &["rev-parse", "--show-toplevel"])?.trim(),
+    ))
+}
+
+/// `file` relative to the work tree root `repo_root`, with forward slashes, as hosting
+/// services name files. Files that aren't under it are returned as given, minus a `./`.
+pub fn repo_relative(file: &str, repo_root: &Path) -> String {
+    let relative = fs::canonicalize(file)
+        .ok()
+        .and_then(|path| {
+            let root = fs::canonicalize(repo_root).ok()?;
+            Some(path.strip_prefix(root).ok()?.to_path_buf())
+        })
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file.trim_start_matches("./").to_string());
+    relative
+        .replace('\\', "/")
+        .trim_start_matches('/')
+        .to_string()
+}
+
+/// Commit date of the last commit that changed `file`, in strict ISO 8601; `None` if it
+/// was never committed.
+pub fn last_commit_date(file: &Path) -> Result<Option<String>> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let name = file.file_name().unwrap_or(file.as_os_str());
+    let date = git(
+        dir,
+        &["log", "-1", "--format=%cI", "--", &name.to_string_lossy()],
+    )?;
+    Ok(Some(date.trim().to_string()).filter(|d| !d.is_empty()))
+}
+
+/// Commit checked out in the work tree that contains `dir`.
+pub fn head_commit(dir: &Path) -> Result<String> {
+    Ok(git(dir, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Branch checked out in the work tree that contains `dir`; `None` on a detached HEAD.
+pub fn current_branch(dir: &Path) -> Result<Option<String>> {
+    let branch = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    Ok(Some(branch.trim().to_string()).filter(|b| b != "HEAD"))
+}
+
+/// Folder git runs hooks from; honours `core.hooksPath` and linked work trees.
+pub fn hooks_dir(dir: &Path) -> Result<PathBuf> {
+    let path = PathBuf::from(git(dir, &["rev-parse", "--git-path", "hooks"])?.trim());
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        dir.join(path)
+    })
+}
+
+/// The files under `dir` at `reference` (a branch, tag or commit) as a zip archive, read
+/// from the object database without a checkout.
+pub fn archive(dir: &Path, reference: &str) -> Result<Vec<u8>> {
+    let prefix = git(dir, &["rev-parse", "--show-prefix"])?;
+    let tree = format!("{}:{}", reference, prefix.trim());
+    git_output(&top_level(dir)?, &["archive", "--format=zip", &tree])
+        .with_context(|| format!("Failed to read {} from git", tree))
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    Ok(String::from_utf8_lossy(&git_output(dir, args)?).into_owned())
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git, is it installed?")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_files() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-git-{}", std::process::id()));
+        fs::create_dir_all(dir.join("pipeline")).unwrap();
+        let run = |args: &[&str]| git(&dir, args).unwrap();
+        run(&["init", "-q", "-b", "main"]);
+        run(&["config", "user.email", "test@example.com"]);
+        run(&["config", "user.name", "test"]);
+        fs::write(dir.join("pipeline/unchanged.json"), "{}").unwrap();
+        fs::write(dir.join("pipeline/edited.json"), "{}").unwrap();
+        run(&["add", "."]);
+        run(&["commit", "-q", "-m", "base"]);
+
+        run(&["checkout", "-q", "-b", "feature"]);
+        fs::write(dir.join("pipeline/edited.json"), "{\"name\": \"x\"}").unwrap();
+        fs::write(dir.join("pipeline/new.json"), "{}").unwrap();
+        fs::write(dir.join("pipeline/pl_café v2.json"), "{}").unwrap();
+
+        let changed = changed_files(&dir.join("pipeline"), "main");
+        let committed = last_commit_date(&dir.join("pipeline/unchanged.json")).unwrap();
+        let uncommitted = last_commit_date(&dir.join("pipeline/new.json")).unwrap();
+        let branch = current_branch(&dir).unwrap();
+        let head = head_commit(&dir).unwrap();
+        let archive = archive(&dir.join("pipeline"), "main").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut files: Vec<&str> = archive.file_names().collect();
+        files.sort();
+        assert_eq!(files, ["edited.json", "unchanged.json"]);
+        assert_eq!(branch.as_deref(), Some("feature"));
+        assert_eq!(head.len(), 40);
+        assert!(committed.is_some_and(|date| date.contains('T')));
+        assert_eq!(uncommitted, None);
+        let mut names: Vec<String> = changed
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["edited.json", "new.json", "pl_café v2.json"]);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::config::Severity;
+use crate::engine::Violation;
+use crate::{git, http};
+use anyhow::{Context, Result, anyhow};
+use serde_json::{Value, json};
+use std::env;
+use std::fs;
+use std::path::Path;
+use ureq::typestate::WithBody;
+
+const SERVICE: &str = "GitHub";
+/// Most annotations the API accepts per request; the rest are added by updating the run.
+const ANNOTATIONS_PER_REQUEST: usize = 50;
+
+/// Where the check run is created, read from the GitHub Actions environment.
+pub struct CheckRun {
+    api_url: String,
+    /// `owner/name`.
+    repository: String,
+    head_sha: String,
+    token: String,
+}
+
+impl CheckRun {
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow!("{} is not set; --github-pr runs in GitHub Actions", name))
+        };
+        let token = var("GITHUB_TOKEN").context(
+            "Pass the workflow token to the step, e.g. `env: GITHUB_TOKEN: ${{ github.token }}`",
+        )?;
+        // On pull requests GITHUB_SHA is a merge commit, which the PR's checks don't show.
+        let pr_head = var("GITHUB_EVENT_PATH").ok().and_then(|path| {
+            let event: Value = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+            Some(event["pull_request"]["head"]["sha"].as_str()?.to_string())
+        });
+        Ok(CheckRun {
+            api_url: var("GITHUB_API_URL").unwrap_or_else(|_| "https://api.github.com".to_string()),
+            repository: var("GITHUB_REPOSITORY")?,
+            head_sha: match pr_head {
+                Some(sha) => sha,
+                None => var("GITHUB_SHA")?,
+            },
+            token,
+        })
+    }
+}
+
+/// Creates a completed `adf-guardian` check run on the pull request's head commit, with
+/// one annotation per violation on its file and line. Returns the URL of the run.
+pub fn publish(
+    run: &CheckRun,
+    violations: &[&Violation],
+    failed: bool,
+    repo_root: &Path,
+) -> Result<String> {
+    let agent = http::agent();
+    let authorization = format!("Bearer {}", run.token);
+    let send = |request: ureq::RequestBuilder<WithBody>, body: &Value| -> Result<Value> {
+        let mut response = request
+            .header("Authorization", &authorization)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .send_json(body)?;
+        http::check_status(SERVICE, &mut response)?;
+        Ok(response.body_mut().read_json()?)
+    };
+
+    let annotations: Vec<Value> = violations
+        .iter()
+        .map(|v| annotation(v, repo_root))
+        .collect();
+    let mut batches = annotations.chunks(ANNOTATIONS_PER_REQUEST);
+    let mut output = summary(violations);
+    output["annotations"] = json!(batches.next().unwrap_or_default());
+
+    let created = send(
+        agent.post(format!(
+            "{}/repos/{}/check-runs",
+            run.api_url, run.repository
+        )),
+        &json!({
+            "name": env!("CARGO_PKG_NAME"),
+            "head_sha": run.head_sha,
+            "status": "completed",
+            "conclusion": if failed { "failure" } else { "success" },
+            "output": output,
+        }),
+    )
+    .context("Failed to create the check run")?;
+
+    let url = created["url"].as_str().unwrap_or_default();
+    for batch in batches {
+        output["annotations"] = json!(batch);
+        send(agent.patch(url), &json!({ "output": output }))
+            .context("Failed to add annotations to the check run")?;
+    }
+    Ok(created["html_url"].as_str().unwrap_or(url).to_string())
+}
+
+/// Title and summary of the check run.
+fn summary(violations: &[&Violation]) -> Value {
+    let errors = violations
+        .iter()
+        .filter(|v| v.severity == Severity::Error)
+        .count();
+    let warnings = violations.len() - errors;
+    let title = if violations.is_empty() {
+        "No violations".to_string()
+    } else {
+        format!("{} error(s), {} warning(s)", errors, warnings)
+    };
+    json!({
+        "title": title,
+        "summary": format!(
+            "{} found {} violation(s): {} error(s) and {} warning(s).",
+            env!("CARGO_PKG_NAME"),
+            violations.len(),
+            errors,
+            warnings
+        ),
+    })
+}
+
+fn annotation(violation: &Violation, repo_root: &Path) -> Value {
+    let line = violation.line.unwrap_or(1);
+    let mut annotation = json!({
+        "path": git::repo_relative(&violation.file, repo_root),
+        "start_line": line,
+        "end_line": line,
+        "annotation_level": match violation.severity {
+            Severity::Error => "failure",
+            Severity::Warning => "warning",
+        },
+        "title": violation.rule_id,
+        "message": violation.message,
+    });
+    // Columns are only allowed on single-line annotations.
+    if let Some(column) = violation.column {
+        annotation["start_column"] = json!(column);
+        annotation["end_column"] = json!(column);
+    }
+    let details: Vec<String> = [
+        violation
+            .json_path
+            .as_ref()
+            .map(|path| format!("Path: {}", path)),
+        violation
+            .help_url
+            .as_ref()
+            .map(|url| format!("More information: {}", url)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !details.is_empty() {
+        annotation["raw_details"] = json!(details.join("\n"));
+    }
+    annotation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation() {
+        let root = env::current_dir().unwrap();
+        let violation = Violation {
+            rule_id: "naming".to_string(),
+            file: "./src/main.rs".to_string(),
+            message: "Pipelines start with pl_".to_string(),
+            severity: Severity::Error,
+            line: Some(3),
+            column: Some(12),
+            ..Default::default()
+        };
+        assert_eq!(
+            annotation(&violation, &root),
+            json!({
+                "path": "src/main.rs",
+                "start_line": 3,
+                "end_line": 3,
+                "start_column": 12,
+                "end_column": 12,
+                "annotation_level": "failure",
+                "title": "naming",
+                "message": "Pipelines start with pl_",
+            })
+        );
+
+        let warning = Violation {
+            severity: Severity::Warning,
+            ..violation.clone()
+        };
+        assert_eq!(
+            summary(&[&violation, &warning])["title"],
+            "1 error(s), 1 warning(s)"
+        );
+        assert_eq!(summary(&[])["title"], "No violations");
+    }
+}
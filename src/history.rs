@@ -0,0 +1,327 @@
+use crate::config::Severity;
+use crate::engine::FileResult;
+use crate::git;
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL,
+    project TEXT NOT NULL,
+    commit_sha TEXT,
+    branch TEXT,
+    config_hash TEXT NOT NULL,
+    files_scanned INTEGER NOT NULL,
+    errors INTEGER NOT NULL,
+    warnings INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS violations (
+    run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    rule_id TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    file TEXT NOT NULL,
+    line INTEGER,
+    message TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS violations_by_run ON violations(run_id, rule_id);
+";
+
+/// A SQLite database with the summary and violations of earlier runs.
+pub struct History {
+    connection: Connection,
+}
+
+/// Where and when a recorded run happened.
+pub struct Run<'a> {
+    /// RFC 3339.
+    pub started_at: &'a str,
+    pub project: &'a Path,
+    pub config_hash: &'a str,
+}
+
+/// A recorded run, as listed by [`History::trends`].
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub id: i64,
+    pub started_at: String,
+    pub project: String,
+    pub commit: Option<String>,
+    pub branch: Option<String>,
+    pub errors: u64,
+    pub warnings: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuleTrend {
+    pub rule_id: String,
+    /// Violations in each run, in the order of [`Trends::runs`].
+    pub counts: Vec<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trends {
+    /// Oldest first.
+    pub runs: Vec<RunSummary>,
+    pub rules: Vec<RuleTrend>,
+}
+
+/// Which runs and rules [`History::trends`] looks at.
+pub struct TrendFilter<'a> {
+    pub rule: Option<&'a str>,
+    pub branch: Option<&'a str>,
+    /// Most recent runs to include.
+    pub runs: usize,
+}
+
+impl History {
+    /// Opens the database at `path`, creating it on first use.
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open the history database {:?}", path))?;
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to set up the history database {:?}", path))?;
+        Ok(History { connection })
+    }
+
+    /// Appends a run and its violations, with the commit and branch it ran on. Returns the
+    /// id of the run.
+    pub fn record(&mut self, run: &Run, results: &[FileResult]) -> Result<i64> {
+        let (commit, branch) = revision(run.project);
+        let violations = || results.iter().flat_map(|r| &r.violations);
+        let errors = violations()
+            .filter(|v| v.severity == Severity::Error)
+            .count();
+        let warnings = violations().count() - errors;
+        let project = run
+            .project
+            .canonicalize()
+            .unwrap_or_else(|_| run.project.to_path_buf());
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO runs (started_at, project, commit_sha, branch, config_hash, \
+             files_scanned, errors, warnings) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                run.started_at,
+                project.display().to_string(),
+                commit,
+                branch,
+                run.config_hash,
+                results.len(),
+                errors,
+                warnings
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO violations (run_id, rule_id, severity, file, line, message) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for violation in violations() {
+                insert.execute(params![
+                    id,
+                    violation.rule_id,
+                    violation.severity.to_string(),
+                    violation.file,
+                    violation.line,
+                    violation.message
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    /// Violation counts per rule over the most recent runs. Rules are sorted by their count
+    /// in the last run, most violated first.
+    pub fn trends(&self, filter: &TrendFilter) -> Result<Trends> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, started_at, project, commit_sha, branch, errors, warnings FROM runs \
+             WHERE ?1 IS NULL OR branch = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let mut runs = statement
+            .query_map(params![filter.branch, filter.runs], |row| {
+                Ok(RunSummary {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    project: row.get(2)?,
+                    commit: row.get(3)?,
+                    branch: row.get(4)?,
+                    errors: row.get(5)?,
+                    warnings: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        runs.reverse();
+
+        let columns: HashMap<i64, usize> = runs
+            .iter()
+            .enumerate()
+            .map(|(i, run)| (run.id, i))
+            .collect();
+        let mut counts: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        let first_id = runs.first().map(|run| run.id);
+        let mut statement = self.connection.prepare(
+            "SELECT rule_id, run_id, COUNT(*) FROM violations \
+             WHERE run_id >= ?1 AND (?2 IS NULL OR rule_id = ?2) GROUP BY rule_id, run_id",
+        )?;
+        let rows = statement.query_map(params![first_id, filter.rule], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, u64>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (rule_id, run_id, count) = row?;
+            if let Some(&column) = columns.get(&run_id) {
+                counts.entry(rule_id).or_insert_with(|| vec![0; runs.len()])[column] = count;
+            }
+        }
+
+        let mut rules: Vec<RuleTrend> = counts
+            .into_iter()
+            .map(|(rule_id, counts)| RuleTrend { rule_id, counts })
+            .collect();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.counts.last().copied()));
+        Ok(Trends { runs, rules })
+    }
+}
+
+/// Commit and branch of the run. CI variables come first: pipelines often check out a
+/// detached merge commit, whose branch git can't tell.
+fn revision(project: &Path) -> (Option<String>, Option<String>) {
+    let var = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()))
+    };
+    let commit = var(&["GITHUB_SHA", "BUILD_SOURCEVERSION", "CI_COMMIT_SHA"])
+        .or_else(|| git::head_commit(project).ok());
+    let branch = var(&[
+        "GITHUB_HEAD_REF",
+        "GITHUB_REF_NAME",
+        "SYSTEM_PULLREQUEST_SOURCEBRANCH",
+        "BUILD_SOURCEBRANCH",
+        "CI_COMMIT_REF_NAME",
+    ])
+    .map(|branch| branch.trim_start_matches("refs/heads/").to_string())
+    .or_else(|| git::current_branch(project).ok().flatten());
+    (commit, branch)
+}
+
+pub fn write_human(out: &mut dyn Write, trends: &Trends) -> io::Result<()> {
+    let (Some(first), Some(last)) = (trends.runs.first(), trends.runs.last()) else {
+        return writeln!(out, "No runs recorded yet");
+    };
+    writeln!(
+        out,
+        "Violations per rule over the last {} run(s), from {} to {}:",
+        trends.runs.len(),
+        first.started_at,
+        last.started_at
+    )?;
+    writeln!(
+        out,
+        "  {:<40} {:>6} {:>6} {:>7}  counts",
+        "rule", "first", "last", "change"
+    )?;
+    let mut write_row = |name: &str, counts: &[u64]| {
+        let first = counts.first().copied().unwrap_or_default();
+        let last = counts.last().copied().unwrap_or_default();
+        let change = last as i64 - first as i64;
+        let series: Vec<String> = counts.iter().map(u64::to_string).collect();
+        writeln!(
+            out,
+            "  {:<40} {:>6} {:>6} {:>+7}  {}",
+            name,
+            first,
+            last,
+            change,
+            series.join(" ")
+        )
+    };
+    for rule in &trends.rules {
+        write_row(&rule.rule_id, &rule.counts)?;
+    }
+    let totals: Vec<u64> = trends
+        .runs
+        .iter()
+        .map(|run| run.errors + run.warnings)
+        .collect();
+    write_row("(all rules)", &totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Violation;
+
+    #[test]
+    fn test_trends() {
+        let mut history = History::open(Path::new(":memory:")).unwrap();
+        let result = |rules: &[&str]| FileResult {
+            file: "pipeline/pl_a.json".to_string(),
+            violations: rules
+                .iter()
+                .map(|rule_id| Violation {
+                    rule_id: rule_id.to_string(),
+                    severity: Severity::Error,
+                    ..Default::default()
+                })
+                .collect(),
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        };
+        for (started_at, rules) in [
+            ("2026-01-01T00:00:00Z", &["naming", "naming", "timeout"][..]),
+            ("2026-01-02T00:00:00Z", &["naming", "timeout"][..]),
+            ("2026-01-03T00:00:00Z", &["timeout"][..]),
+        ] {
+            let run = Run {
+                started_at,
+                project: Path::new("."),
+                config_hash: "hash",
+            };
+            history.record(&run, &[result(rules)]).unwrap();
+        }
+
+        let trends = history
+            .trends(&TrendFilter {
+                rule: None,
+                branch: None,
+                runs: 2,
+            })
+            .unwrap();
+        let runs: Vec<(&str, u64)> = trends
+            .runs
+            .iter()
+            .map(|run| (run.started_at.as_str(), run.errors))
+            .collect();
+        assert_eq!(
+            runs,
+            [("2026-01-02T00:00:00Z", 2), ("2026-01-03T00:00:00Z", 1)]
+        );
+        let rules: Vec<(&str, &[u64])> = trends
+            .rules
+            .iter()
+            .map(|rule| (rule.rule_id.as_str(), rule.counts.as_slice()))
+            .collect();
+        assert_eq!(rules, [("timeout", &[1, 1][..]), ("naming", &[1, 0][..])]);
+
+        let mut out = Vec::new();
+        write_human(&mut out, &trends).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("naming"), "{}", text);
+        assert!(text.contains("-1  1 0"), "{}", text);
+    }
+}
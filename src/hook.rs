@@ -0,0 +1,125 @@
+use crate::git;
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// First lines of hooks written by `install-hook`, so they can be replaced without `--force`.
+const HEADER: &str = "#!/bin/sh
+# Installed by adf-guardian install-hook: checks the staged JSON files before each commit.
+# Skip it once with `git commit --no-verify`.";
+
+/// Writes `pre-commit` into the hooks folder of the repository that contains `dir`.
+/// A hook that wasn't written by `install-hook` is only replaced with `force`.
+pub fn install(dir: &Path, config: &Path, force: bool) -> Result<PathBuf> {
+    let config = repo_relative(dir, config)?;
+    let hooks = git::hooks_dir(dir)?;
+    fs::create_dir_all(&hooks)
+        .with_context(|| format!("Failed to create the hooks folder {:?}", hooks))?;
+    let path = fs::canonicalize(&hooks)?.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&path)
+        && !existing.starts_with(HEADER)
+        && !force
+    {
+        bail!(
+            "A pre-commit hook already exists at {:?}; pass --force to replace it",
+            path
+        );
+    }
+
+    fs::write(&path, script(&config))
+        .with_context(|| format!("Failed to write the hook {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .with_context(|| format!("Failed to make {:?} executable", path))?;
+    }
+    Ok(path)
+}
+
+/// Hook entry for the `pre-commit` framework, to paste into a `.pre-commit-hooks.yaml`
+/// (or a `repo: local` block of `.pre-commit-config.yaml`).
+pub fn pre_commit_entry(dir: &Path, config: &Path) -> Result<String> {
+    let config = repo_relative(dir, config)?;
+    Ok(format!(
+        r#"- id: adf-guardian
+  name: adf-guardian
+  description: Check Azure Data Factory JSON against the adf-guardian rules
+  entry: sh -c 'printf "%s\0" "$@" | adf-guardian --config "{}" --files-from -' --
+  language: system
+  files: \.json$
+"#,
+        config.display()
+    ))
+}
+
+/// Hooks run from the root of the work tree, so the config path is made relative to it.
+fn repo_relative(dir: &Path, config: &Path) -> Result<PathBuf> {
+    let top_level = fs::canonicalize(git::top_level(dir)?)?;
+    let config = fs::canonicalize(config)
+        .with_context(|| format!("Config file not found at {:?}", config))?;
+    Ok(config
+        .strip_prefix(&top_level)
+        .map(Path::to_path_buf)
+        .unwrap_or(config))
+}
+
+/// Checks the staged JSON files as staged (not as in the work tree) in a single run over
+/// a copy of the index, so the project checks see the other files as staged too.
+fn script(config: &Path) -> String {
+    let quoted = format!("'{}'", config.display().to_string().replace('\'', r"'\''"));
+    // The scan runs from the copy, so a config inside the repository is given from its root.
+    let config = match config.is_relative() {
+        true => format!(r#""$top"/{quoted}"#),
+        false => quoted,
+    };
+    format!(
+        r#"{HEADER}
+top=$(git rev-parse --show-toplevel) || exit 1
+staged=$(mktemp -d) || exit 1
+list=$(mktemp) || exit 1
+trap 'rm -rf "$staged" "$list"' EXIT
+
+git diff --cached --name-only -z --diff-filter=ACMR -- '*.json' > "$list"
+[ -s "$list" ] || exit 0
+git checkout-index --all --prefix="$staged/" || exit 1
+cd "$staged" && adf-guardian --config {config} --files-from "$list"
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_install() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-hook-{}", std::process::id()));
+        fs::create_dir_all(dir.join("factory")).unwrap();
+        fs::write(dir.join("factory/guards.yaml"), "rules: []").unwrap();
+        Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+
+        let config = dir.join("factory/guards.yaml");
+        let path = install(&dir.join("factory"), &config, false).unwrap();
+        let hook = fs::read_to_string(&path).unwrap();
+        // Reinstalling replaces our own hook, but not someone else's.
+        let reinstalled = install(&dir, &config, false);
+        fs::write(&path, "#!/bin/sh\nmake lint\n").unwrap();
+        let foreign = install(&dir, &config, false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(path.ends_with(".git/hooks/pre-commit"));
+        let expected = Path::new("factory").join("guards.yaml");
+        assert!(hook.contains(&format!(
+            r#"adf-guardian --config "$top"/'{}' --files-from"#,
+            expected.display()
+        )));
+        assert!(reinstalled.is_ok());
+        assert!(foreign.is_err());
+    }
+}
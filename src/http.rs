@@ -0,0 +1,27 @@
+use anyhow::{Result, bail};
+use serde_json::Value;
+use ureq::Agent;
+use ureq::http::Response;
+
+/// An agent that returns error statuses as responses, so their message can be reported.
+pub fn agent() -> Agent {
+    Agent::config_builder()
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+/// Fails with the `message` of the JSON error body (or the whole body) unless `response`
+/// is a success. `service` names the API in the error.
+pub fn check_status(service: &str, response: &mut Response<ureq::Body>) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.body_mut().read_to_string().unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|json| json["message"].as_str().map(str::to_string))
+        .unwrap_or(body);
+    bail!("{} answered {}: {}", service, status, message.trim())
+}
@@ -0,0 +1,89 @@
+//! Checks Azure Data Factory JSON (pipelines, datasets, linked services, triggers, ...)
+//! against rules declared in YAML, the engine behind the `adf-guardian` command line.
+//!
+//! Load a [`Config`] and scan a factory folder with [`run_scan`]:
+//!
+//! ```no_run
+//! use adf_guardian::{Config, Severity, run_scan};
+//! use std::path::Path;
+//!
+//! let config = Config::load("guards.yaml")?;
+//! let report = run_scan(&config, Path::new("factory"))?;
+//! for violation in report.results.iter().flat_map(|r| &r.violations) {
+//!     println!("{}: [{}] {}", violation.file, violation.rule_id, violation.message);
+//! }
+//! let failed = report
+//!     .results
+//!     .iter()
+//!     .flat_map(|r| &r.violations)
+//!     .any(|v| v.severity == Severity::Error);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! [`engine::run`] takes the options the CLI exposes (changed files only, a result cache,
+//! a progress observer), and [`engine::check_source`] checks a document that isn't on
+//! disk, such as an editor buffer.
+//!
+//! Organization-specific checks are written as a [`Guard`] and registered by name in the
+//! [`GuardRegistry`] of [`engine::ScanOptions`]; rules then use them like a built-in guard:
+//!
+//! ```no_run
+//! use adf_guardian::engine::{self, ScanObserver, ScanOptions};
+//! use adf_guardian::{Config, Guard, GuardResult, Params};
+//! use serde_json::Value;
+//! use std::path::Path;
+//!
+//! /// `guard: "CostCenter"`: the node must be a cost center listed in `params.allowed`.
+//! struct CostCenter;
+//!
+//! impl Guard for CostCenter {
+//!     fn check(&self, node: &Value, params: &Params) -> GuardResult {
+//!         let allowed = params.get("allowed").and_then(|v| v.as_array());
+//!         allowed.is_some_and(|list| list.contains(node)).into()
+//!     }
+//! }
+//!
+//! struct Quiet;
+//! impl ScanObserver for Quiet {}
+//!
+//! let mut options = ScanOptions::default();
+//! options.guards.register("CostCenter", CostCenter);
+//! let config = Config::load("guards.yaml")?;
+//! let report = engine::run(&config, Path::new("factory"), &options, &Quiet)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+#[cfg(feature = "scan")]
+mod archive;
+pub mod config;
+pub mod engine;
+pub mod packs;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "scan")]
+pub mod scanner;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use config::{Config, Rule, Severity};
+pub use engine::{
+    Diagnostic, FileResult, Guard, GuardRegistry, GuardResult, Params, ScanReport, Violation,
+};
+
+#[cfg(feature = "scan")]
+use anyhow::Result;
+#[cfg(feature = "scan")]
+use engine::{ScanObserver, ScanOptions};
+#[cfg(feature = "scan")]
+use std::path::Path;
+
+/// Scans every JSON file under `root` with the rules of `config`, the way the CLI does
+/// without options. Unreadable files and invalid rules are listed in
+/// [`ScanReport::diagnostics`] rather than failing the scan.
+#[cfg(feature = "scan")]
+pub fn run_scan(config: &Config, root: &Path) -> Result<ScanReport> {
+    struct Silent;
+    impl ScanObserver for Silent {}
+
+    engine::run(config, root, &ScanOptions::default(), &Silent)
+}
@@ -0,0 +1,389 @@
+use crate::config::{Config, Severity};
+use crate::engine::{RuleSet, SharedRuleSet, locator};
+use anyhow::Result;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+    DidSaveTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{RegisterCapability, Request as _};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, FileSystemWatcher, GlobPattern,
+    InitializeParams, NumberOrString, Position, PublishDiagnosticsParams, Range, Registration,
+    RegistrationParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions, Url,
+};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `adf-guardian lsp`: publishes violations as diagnostics for the JSON documents the
+/// editor has open. The rules are compiled once and again when the config or a file its
+/// params name changes; a document is re-validated on every change, from the editor's
+/// buffer rather than the file on disk. Asset types are told by the path of the document
+/// relative to `project_path`, and the project-level checks use the other files under it,
+/// read again when saved or, if the editor watches files for us, changed on disk. The
+/// config is loaded with `load`, first and on every reload, so it is checked like a scan's.
+pub fn serve(
+    config_path: &Path,
+    project_path: &Path,
+    load: impl Fn() -> Result<Config> + Send + Sync,
+) -> Result<()> {
+    let rules = SharedRuleSet::new(load()?, load);
+    let config_path = fs::canonicalize(config_path)?;
+
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::FULL),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
+        ..Default::default()
+    };
+    let client = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let watches_files = serde_json::from_value::<InitializeParams>(client)
+        .ok()
+        .and_then(|client| client.capabilities.workspace)
+        .and_then(|workspace| workspace.did_change_watched_files)
+        .and_then(|watched| watched.dynamic_registration)
+        .unwrap_or(false);
+    if watches_files {
+        let options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/*.json".to_string()),
+                kind: None,
+            }],
+        };
+        let params = RegistrationParams {
+            registrations: vec![Registration {
+                id: DidChangeWatchedFiles::METHOD.to_string(),
+                method: DidChangeWatchedFiles::METHOD.to_string(),
+                register_options: Some(serde_json::to_value(options)?),
+            }],
+        };
+        let request = Request::new(
+            "watch-files".to_string().into(),
+            RegisterCapability::METHOD.to_string(),
+            params,
+        );
+        connection.sender.send(request.into())?;
+    }
+
+    let mut server = Server {
+        connection: &connection,
+        config_path,
+        project_path: fs::canonicalize(project_path)?,
+        rules: &rules,
+        documents: HashMap::new(),
+    };
+    server.run()?;
+
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    config_path: PathBuf,
+    project_path: PathBuf,
+    rules: &'a SharedRuleSet<'a>,
+    /// Text of the open documents, as the editor last sent it.
+    documents: HashMap<Url, String>,
+}
+
+impl Server<'_> {
+    fn run(&mut self) -> Result<()> {
+        for message in &self.connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = Response::new_err(
+                        request.id,
+                        lsp_server::ErrorCode::MethodNotFound as i32,
+                        format!("Unsupported request '{}'", request.method),
+                    );
+                    self.connection.sender.send(response.into())?;
+                }
+                Message::Notification(notification) => self.handle(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.changed(&uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // Full sync: the last change holds the whole document.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    self.documents.insert(uri.clone(), change.text);
+                    self.changed(&uri)?;
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: DidSaveTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let saved = params.text_document.uri.to_file_path().ok();
+                if saved.and_then(|p| fs::canonicalize(p).ok()).as_ref() == Some(&self.config_path)
+                {
+                    self.reload_config()?;
+                } else if let Some(path) = json_path(&params.text_document.uri) {
+                    self.rules.get().refresh(&self.project_path, &[path]);
+                }
+            }
+            DidChangeWatchedFiles::METHOD => {
+                let params: DidChangeWatchedFilesParams =
+                    serde_json::from_value(notification.params)?;
+                let paths: Vec<PathBuf> = params
+                    .changes
+                    .iter()
+                    .filter_map(|change| change.uri.to_file_path().ok())
+                    .collect();
+                self.rules.get().refresh(&self.project_path, &paths);
+                // Any open document's project-level checks may depend on them.
+                let uris: Vec<Url> = self.documents.keys().cloned().collect();
+                uris.iter().try_for_each(|uri| self.publish(uri))?;
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.send_diagnostics(uri.clone(), vec![])?;
+                self.changed(&uri)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn reload_config(&mut self) -> Result<()> {
+        // Keep the previous rules; the error shows up in the editor's output panel.
+        if let Err(e) = self.rules.reload() {
+            tracing::warn!("Could not reload configuration: {:#}", e);
+            return Ok(());
+        }
+        let uris: Vec<Url> = self.documents.keys().cloned().collect();
+        uris.iter().try_for_each(|uri| self.publish(uri))
+    }
+
+    /// Re-validates the document at `uri`, and the open documents whose project-level
+    /// checks depend on it. A closed document goes back to its file on disk.
+    fn changed(&self, uri: &Url) -> Result<()> {
+        let dependents = match json_path(uri) {
+            Some(path) => self.rules.get().update(
+                &self.project_path,
+                &path,
+                self.documents.get(uri).map(String::as_str),
+            ),
+            None => vec![],
+        };
+        self.publish(uri)?;
+        self.documents
+            .keys()
+            .filter(|other| *other != uri)
+            .filter(|other| json_path(other).is_some_and(|path| dependents.contains(&path)))
+            .try_for_each(|other| self.publish(other))
+    }
+
+    fn publish(&self, uri: &Url) -> Result<()> {
+        let (Some(text), Some(path)) = (self.documents.get(uri), json_path(uri)) else {
+            return Ok(());
+        };
+        let diagnostics = diagnostics(&self.rules.get(), &self.project_path, &path, text);
+        self.send_diagnostics(uri.clone(), diagnostics)
+    }
+
+    fn send_diagnostics(&self, uri: Url, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+}
+
+/// The path of a JSON document, `None` for other documents.
+fn json_path(uri: &Url) -> Option<PathBuf> {
+    let path = uri.to_file_path().ok()?;
+    path.extension()
+        .is_some_and(|ext| ext == "json")
+        .then_some(path)
+}
+
+fn diagnostics(rules: &RuleSet, root: &Path, path: &Path, text: &str) -> Vec<Diagnostic> {
+    let result = match rules.check_in_project(root, path, text) {
+        Ok(result) => result,
+        Err(e) => {
+            let position = Position::new(
+                e.line().saturating_sub(1) as u32,
+                e.column().saturating_sub(1) as u32,
+            );
+            return vec![Diagnostic {
+                range: Range::new(position, position),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("adf-guardian".to_string()),
+                message: format!("Invalid JSON: {}", e),
+                ..Default::default()
+            }];
+        }
+    };
+
+    result
+        .violations
+        .into_iter()
+        .map(|v| {
+            let span = v
+                .pointer
+                .as_deref()
+                .filter(|pointer| !pointer.is_empty())
+                .and_then(|pointer| locator::locate_span(text, pointer));
+            // Whole-document violations are shown on the first line.
+            let range = match span {
+                Some((start, end)) => Range::new(
+                    position(text, start.line, start.column),
+                    position(text, end.line, end.column),
+                ),
+                None => Range::new(Position::new(0, 0), Position::new(1, 0)),
+            };
+            Diagnostic {
+                range,
+                severity: Some(match v.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                code: Some(NumberOrString::String(v.rule_id)),
+                code_description: v
+                    .help_url
+                    .and_then(|url| Url::parse(&url).ok())
+                    .map(|href| lsp_types::CodeDescription { href }),
+                source: Some("adf-guardian".to_string()),
+                message: v.message,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Converts a 1-based line and character column into an LSP position, which counts
+/// UTF-16 code units.
+fn position(text: &str, line: usize, column: usize) -> Position {
+    let line_text = text.lines().nth(line - 1).unwrap_or_default();
+    let character: usize = line_text
+        .chars()
+        .take(column - 1)
+        .map(char::len_utf16)
+        .sum();
+    Position::new((line - 1) as u32, character as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle;
+    use crate::cli::Cli;
+    use crate::engine::GuardRegistry;
+    use clap::Parser;
+
+    #[test]
+    fn test_serve_requires_signed_param_files() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-lsp-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (private_pem, public_pem) = bundle::tests::key_pems();
+        let config_path = dir.join("security.yaml");
+        let list = dir.join("accounts.txt");
+        let key = dir.join("trusted-key.pem");
+        let source = r#"
+rules:
+  - id: "accounts"
+    asset: "linkedService"
+    validate: { target: "$.name", guard: "ExternalList", params: { source: "accounts.txt" } }
+"#;
+        fs::write(&config_path, source).unwrap();
+        fs::write(&list, "ls_sql\n").unwrap();
+        fs::write(&key, &public_pem).unwrap();
+        let signature = bundle::sign(source.as_bytes(), &private_pem).unwrap();
+        fs::write(bundle::signature_path(&config_path), signature).unwrap();
+        bundle::sign_param_files(&Config::load(&config_path).unwrap(), &private_pem).unwrap();
+
+        let args = Cli::parse_from([
+            "adf-guardian".as_ref(),
+            "--config".as_ref(),
+            config_path.as_os_str(),
+            "--project-path".as_ref(),
+            dir.as_os_str(),
+            "--require-signed".as_ref(),
+            "--trusted-key".as_ref(),
+            key.as_os_str(),
+            "lsp".as_ref(),
+        ]);
+        let signed = crate::load_config(&args).is_ok();
+        fs::write(&list, "ls_sql\nls_attacker\n").unwrap();
+        // Refused before the server reads from stdin.
+        let tampered = serve(&config_path, &dir, crate::config_loader(&args));
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(signed);
+        assert!(format!("{:#}", tampered.unwrap_err()).contains("Invalid signature"));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "retries"
+    asset: "pipeline"
+    description: "Too many retries"
+    severity: Warning
+    validate:
+      target: "$.properties.retries"
+      guard: "Range"
+      params: { max: 3 }
+"#,
+        )
+        .unwrap();
+        let rules = RuleSet::new(config, GuardRegistry::default());
+        let root = Path::new("/repo");
+        let text = "{\n  \"name\": \"pl_ü\",\n  \"properties\": { \"retries\": 10 }\n}";
+
+        let found = diagnostics(&rules, root, Path::new("/repo/pipeline/pl_a.json"), text);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            found[0].range,
+            Range::new(Position::new(2, 29), Position::new(2, 31))
+        );
+
+        let broken = diagnostics(
+            &rules,
+            root,
+            Path::new("/repo/pipeline/pl_a.json"),
+            "{ \"name\": ",
+        );
+        assert!(broken[0].message.starts_with("Invalid JSON"));
+    }
+}
@@ -1,46 +1,1368 @@
+mod azdo;
+mod azure;
+mod bundle;
+mod catalog;
 mod cli;
-mod config;
-mod engine;
+mod codeowners;
+mod diff;
+mod docs;
+mod drift;
+mod filter;
+mod git;
+mod github;
+mod history;
+mod hook;
+mod http;
+mod lsp;
+mod notify;
+mod oci;
+mod otel;
+mod policy;
+mod preview;
+mod query;
 mod reporter;
-mod scanner;
+mod server;
+mod wizard;
 
-use anyhow::{Context, Result};
-use clap::Parser;
-use cli::Cli;
-use config::{Config, Severity};
-use std::process::exit;
+use adf_guardian::scanner::{self, WalkOptions};
+use adf_guardian::{config, engine, packs};
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{CommandFactory, Parser, ValueEnum};
+use cli::{
+    Cli, ColorChoice, Command, ImportCommand, LogFormat, LogLevel, OutputFormat, PackCommand,
+    PolicyPreset,
+};
+use config::{Config, Notification, NotifyOn, Severity};
+use engine::{GuardRegistry, ScanOptions, ScanReport, SharedRuleSet, Violation};
+use filter::RuleFilter;
+use reporter::{ReportFormat, ReportOptions, ScanProgress};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::time::Instant;
+use tracing_subscriber::filter::LevelFilter;
+
+/// Violations at or above the failure threshold (errors, or warnings over `--max-warnings`).
+const EXIT_VIOLATIONS: u8 = 1;
+/// The config file is missing or invalid, or the options can't be combined.
+const EXIT_CONFIG_ERROR: u8 = 2;
+/// The project couldn't be scanned or a report couldn't be written.
+const EXIT_SCAN_ERROR: u8 = 3;
+
+/// An error that stops the run, by the exit code it maps to.
+enum Failure {
+    Config(anyhow::Error),
+    Scan(anyhow::Error),
+}
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let start_time = Instant::now();
     let args = Cli::parse();
+    init_logging(&args);
+
+    match run(&args, start_time) {
+        Ok(code) => code,
+        Err(failure) => {
+            let (code, error) = match failure {
+                Failure::Config(e) => (EXIT_CONFIG_ERROR, e),
+                Failure::Scan(e) => (EXIT_SCAN_ERROR, e),
+            };
+            if args.output_format() == OutputFormat::Json {
+                reporter::print_json_error(&format!("{:#}", error));
+            } else {
+                eprintln!("Error: {:?}", error);
+            }
+            ExitCode::from(code)
+        }
+    }
+}
+
+/// Log messages (warnings about files or rules that can't be checked, and more with a
+/// lower `--log-level`) go to stderr, so they never mix with a report on stdout.
+fn init_logging(args: &Cli) {
+    let level = match args.log_level {
+        LogLevel::Off => LevelFilter::OFF,
+        LogLevel::Error => LevelFilter::ERROR,
+        LogLevel::Warn => LevelFilter::WARN,
+        LogLevel::Info => LevelFilter::INFO,
+        LogLevel::Debug => LevelFilter::DEBUG,
+        LogLevel::Trace => LevelFilter::TRACE,
+    };
+    let ansi = match args.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_target(false)
+        .without_time();
+    match args.log_format {
+        LogFormat::Text => builder.with_ansi(ansi).init(),
+        LogFormat::Json => builder.json().with_ansi(false).init(),
+    }
+}
+
+fn run(args: &Cli, start_time: Instant) -> Result<ExitCode, Failure> {
+    let started_at = Utc::now();
+    let format = args.output_format();
+
+    let stdout_is_terminal = io::stdout().is_terminal();
+    let use_color = match args.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            stdout_is_terminal && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+    };
+    colored::control::set_override(use_color);
+
+    if args.man {
+        clap_mangen::Man::new(Cli::command())
+            .render(&mut io::stdout().lock())
+            .context("Failed to write the man page")
+            .map_err(Failure::Scan)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match &args.command {
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(
+                *shell,
+                &mut Cli::command(),
+                env!("CARGO_PKG_NAME"),
+                &mut io::stdout().lock(),
+            );
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Diff { old, new }) => return run_diff(format, old, new, args.max_warnings),
+        Some(Command::Lsp) => {
+            lsp::serve(&args.config, args.project_path(), config_loader(args))
+                .map_err(Failure::Config)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::ListRules) => {
+            let config = load_config(args)?;
+            return write_catalog(format, config.rules.as_slice(), catalog::write_rule_list);
+        }
+        Some(Command::LintConfig) => return run_lint_config(args, format),
+        Some(Command::Preview { rules }) => return run_preview(args, format, rules),
+        Some(Command::CheckSnippet { rule_id, document }) => {
+            return run_check_snippet(args, format, rule_id, document, start_time, started_at);
+        }
+        Some(Command::Query { expression, files }) => {
+            let matches = query::run(expression, files, args.jsonc).map_err(Failure::Config)?;
+            write_catalog(format, matches.as_slice(), query::write_human)?;
+            return Ok(if matches.is_empty() {
+                ExitCode::from(EXIT_VIOLATIONS)
+            } else {
+                ExitCode::SUCCESS
+            });
+        }
+        Some(Command::Import { command }) => return run_import(args, format, command),
+        Some(Command::Suppressions) => return run_suppressions(args, format),
+        Some(Command::NewRule { sample }) => {
+            wizard::run(
+                &args.config,
+                args.project_path(),
+                &walk_options(args),
+                sample.as_deref(),
+            )
+            .map_err(Failure::Config)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Pack { command }) => return run_pack(args, command),
+        Some(Command::Explain { rule_id }) => {
+            let config = load_config(args)?;
+            let rule = catalog::find(&config, rule_id).map_err(Failure::Config)?;
+            return write_catalog(format, rule, catalog::write_rule);
+        }
+        Some(Command::InstallHook { pre_commit, force }) => {
+            return run_install_hook(args, *pre_commit, *force);
+        }
+        Some(Command::Docs { output }) => return run_docs(args, format, output),
+        Some(Command::AnnotateAzdo {
+            org,
+            project,
+            repository,
+            pr,
+            from_report,
+        }) => {
+            let pr = azdo::PullRequest {
+                organization: org.clone(),
+                project: project.clone(),
+                repository: repository.clone().unwrap_or_else(|| project.clone()),
+                id: *pr,
+            };
+            return run_annotate_azdo(args, &pr, from_report.as_deref());
+        }
+        Some(Command::ScanAzure {
+            factory,
+            download_dir,
+        }) => {
+            let factory = azure::Factory::from(factory);
+            let dir = download_dir
+                .clone()
+                .unwrap_or_else(|| Path::new(".adf-guardian").join("azure").join(&factory.name));
+            return run_scan_azure(args, &factory, dir, start_time);
+        }
+        Some(Command::Drift { factory }) => {
+            return run_drift(format, args.project_path(), &factory.into());
+        }
+        Some(Command::Trends { rule, branch, runs }) => {
+            let filter = history::TrendFilter {
+                rule: rule.as_deref(),
+                branch: branch.as_deref(),
+                runs: *runs,
+            };
+            return run_trends(args, format, &filter);
+        }
+        Some(Command::Bench {
+            project,
+            iterations,
+        }) => {
+            let project = project.as_deref().unwrap_or(args.project_path());
+            return run_bench(args, format, project, iterations.get());
+        }
+        Some(Command::Serve { port, host }) => {
+            let rules = SharedRuleSet::new(load_config(args)?, config_loader(args));
+            server::serve(&rules, &format!("{}:{}", host, port)).map_err(Failure::Scan)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {}
+    }
+    if let Some(file) = &args.explain {
+        return run_explain(args, format, file);
+    }
+    if args.compare_to.is_some() {
+        check_diff_format(format).map_err(Failure::Config)?;
+    }
+
+    if !args.stdin
+        && let Some(path) = args.project_paths.iter().find(|path| !path.exists())
+    {
+        return Err(Failure::Scan(anyhow!(
+            "Project path not found at {:?}",
+            path
+        )));
+    }
+
+    let check_run = if args.github_pr {
+        Some(github::CheckRun::from_env().map_err(Failure::Config)?)
+    } else {
+        None
+    };
+    let baseline = match &args.compare_to {
+        Some(path) => Some(diff::load_report(path).map_err(Failure::Scan)?),
+        None => None,
+    };
+
+    let uses_template = format == OutputFormat::Template
+        || args
+            .report
+            .iter()
+            .any(|t| t.format == OutputFormat::Template);
+    if uses_template && args.template.is_none() {
+        return Err(Failure::Config(anyhow!(
+            "The template format needs a template file, given with --template"
+        )));
+    }
+
+    let config = load_config(args)?;
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.get())
+            .build_global()
+            .context("Failed to start the worker threads")
+            .map_err(Failure::Scan)?;
+    }
+
+    let mut options = ReportOptions {
+        start_time,
+        started_at,
+        config_hash: config.hash.clone(),
+        group_by: args.group_by,
+        sort: args.sort,
+        summary_only: args.summary,
+        verbose: args.verbose,
+        unicode: stdout_is_terminal || args.color == ColorChoice::Always,
+        json_compat: args.json_compat,
+        template: args.template.clone(),
+        suppressed: vec![],
+        diagnostics: vec![],
+    };
+
+    let report_on_stdout = args.output.is_none() && !args.quiet;
+    // NDJSON is line-oriented, so it is always streamed when printed from a scan.
+    let streams_ndjson = format == OutputFormat::Ndjson && report_on_stdout && !args.stdin;
+    let stream = if args.stream || streams_ndjson {
+        let stream = reporter::for_stream(format, &options).with_context(|| {
+            let name = format.to_possible_value().map(|v| v.get_name().to_string());
+            format!(
+                "--stream is not supported by the '{}' format",
+                name.unwrap_or_default()
+            )
+        });
+        Some(stream.map_err(Failure::Config)?)
+    } else {
+        None
+    };
+    let mut scan = match &args.stdin_filename {
+        Some(filename) => {
+            check_stdin(&config, filename, args.max_value_length).map_err(Failure::Scan)?
+        }
+        None => {
+            let projects = projects(args, &config)?;
+            let only_files = if args.changed {
+                Some(git::changed_files(args.project_path(), &args.base).map_err(Failure::Scan)?)
+            } else if let Some(list) = &args.files_from {
+                Some(files_from(list).map_err(Failure::Scan)?)
+            } else {
+                None
+            };
+            let cache_dir = args.cache.then(|| {
+                args.cache_dir.clone().unwrap_or_else(|| {
+                    // Next to an archive rather than inside it.
+                    let project_path = args.project_path();
+                    let dir = match project_path.is_file() {
+                        true => project_path.parent().unwrap_or(Path::new(".")),
+                        false => project_path,
+                    };
+                    dir.join(".adf-guardian").join("cache")
+                })
+            });
+            let progress =
+                ScanProgress::new(!args.quiet && io::stderr().is_terminal(), stream.as_deref());
+            progress
+                .start()
+                .context("Failed to write report")
+                .map_err(Failure::Scan)?;
+            let mut scan = ScanReport {
+                results: vec![],
+                suppressed: vec![],
+                rule_stats: vec![],
+                diagnostics: vec![],
+            };
+            for project in &projects {
+                // The cache is keyed by the rules, which differ between projects.
+                let cache = match &cache_dir {
+                    Some(dir) => {
+                        Some(engine::Cache::open(dir, &project.config).map_err(Failure::Scan)?)
+                    }
+                    None => None,
+                };
+                let archive = match &args.git_ref {
+                    Some(reference) => {
+                        Some(git::archive(&project.root, reference).map_err(Failure::Scan)?)
+                    }
+                    None => None,
+                };
+                let scan_options = ScanOptions {
+                    archive,
+                    only_files: only_files.clone(),
+                    max_file_size: args.max_file_size,
+                    cache,
+                    walk: walk_options(args),
+                    jsonc: args.jsonc,
+                    max_value_length: args.max_value_length,
+                    ..Default::default()
+                };
+                let part = engine::run(&project.config, &project.root, &scan_options, &progress)
+                    .map_err(Failure::Scan)?;
+                merge_scan(&mut scan, part, project.name.as_deref());
+            }
+            progress
+                .finish(&scan.results)
+                .context("Failed to write report")
+                .map_err(Failure::Scan)?;
+            scan
+        }
+    };
+    if args.codeowners {
+        let project_path = match &args.stdin_filename {
+            Some(filename) => filename.parent().unwrap_or(Path::new(".")),
+            None => args.project_path(),
+        };
+        match codeowners::CodeOwners::discover(project_path) {
+            Ok(Some(code_owners)) => code_owners.assign(
+                scan.results
+                    .iter_mut()
+                    .flat_map(|r| &mut r.violations)
+                    .chain(&mut scan.suppressed),
+            ),
+            Ok(None) => tracing::warn!("No CODEOWNERS file found in the repository"),
+            Err(e) => tracing::warn!("Could not read the CODEOWNERS file: {:#}", e),
+        }
+    }
+    let results = scan.results;
+    options.suppressed = scan.suppressed;
+    options.diagnostics = scan.diagnostics;
+
+    let report = reporter::for_format(format, &options);
+    let current: Vec<Violation> = match baseline {
+        Some(_) => results
+            .iter()
+            .flat_map(|r| r.violations.iter().cloned())
+            .collect(),
+        None => vec![],
+    };
+    let comparison = baseline.as_deref().map(|old| diff::compare(old, &current));
+    if report_on_stdout && stream.is_none() {
+        match &comparison {
+            Some(comparison) => write_diff(format, comparison),
+            None => report
+                .write(&mut io::stdout().lock(), &results)
+                .context("Failed to write report"),
+        }
+        .map_err(Failure::Scan)?;
+    }
+
+    if args.stats {
+        // Keep machine-readable stdout parseable by sending the table to stderr.
+        let result = if report_on_stdout && format == OutputFormat::Human {
+            reporter::write_stats(&mut io::stdout().lock(), &scan.rule_stats)
+        } else {
+            reporter::write_stats(&mut io::stderr().lock(), &scan.rule_stats)
+        };
+        result
+            .context("Failed to write rule statistics")
+            .map_err(Failure::Scan)?;
+    }
+
+    // Escape codes only make sense on a terminal, never in report files.
+    colored::control::set_override(false);
+    if let Some(path) = &args.output {
+        write_report_file(report.as_ref(), path, &results).map_err(Failure::Scan)?;
+    }
+    for target in &args.report {
+        let report = reporter::for_format(target.format, &options);
+        write_report_file(report.as_ref(), &target.path, &results).map_err(Failure::Scan)?;
+    }
 
+    // Only what the change introduced counts when comparing with an earlier run.
+    let counted: Vec<&Violation> = match comparison {
+        Some(comparison) => comparison.new,
+        None => results.iter().flat_map(|r| &r.violations).collect(),
+    };
+    let failed = fails(counted.iter().copied(), args.max_warnings);
+    if let Some(check_run) = check_run {
+        let repo_root = git::top_level(args.project_path()).unwrap_or_else(|_| PathBuf::from("."));
+        let url =
+            github::publish(&check_run, &counted, failed, &repo_root).map_err(Failure::Scan)?;
+        eprintln!("Published the check run at {}", url);
+    }
+    if let Some(path) = &args.history {
+        let run = history::Run {
+            started_at: &started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            project: args.project_path(),
+            config_hash: &config.hash,
+        };
+        history::History::open(path)
+            .and_then(|mut history| history.record(&run, &results))
+            .context("Failed to record the run in the history")
+            .map_err(Failure::Scan)?;
+    }
+    let mut notifications = config.notifications.clone();
+    notifications.extend(args.notify_webhook.iter().map(|url| Notification {
+        url: url.clone(),
+        kind: None,
+        on: NotifyOn::Errors,
+        template: None,
+    }));
+    notify::send_all(&notifications, &options, &results, args.project_path());
+    if let Some(endpoint) = &args.otel_endpoint {
+        let scan = otel::Scan {
+            project: args.project_path(),
+            started_at,
+            finished_at: Utc::now(),
+            results: &results,
+            rule_stats: &scan.rule_stats,
+        };
+        if let Err(e) = otel::export(&otel::Exporter::new(endpoint), &scan) {
+            tracing::warn!("Could not export telemetry: {:#}", e);
+        }
+    }
+
+    Ok(if failed {
+        ExitCode::from(EXIT_VIOLATIONS)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// [`load_config`] for the modes that load the config again when it changes.
+fn config_loader(args: &Cli) -> impl Fn() -> anyhow::Result<Config> + Send + Sync + '_ {
+    move || match load_config(args) {
+        Ok(config) => Ok(config),
+        Err(Failure::Config(e) | Failure::Scan(e)) => Err(e),
+    }
+}
+
+/// Loads the config and drops the rules excluded by `--min-severity`, `--only-rules` and
+/// `--skip-rules`.
+fn load_config(args: &Cli) -> Result<Config, Failure> {
     if !args.config.exists() {
-        if args.json {
-            reporter::print_json_error("Config file not found");
+        return Err(Failure::Config(anyhow!(
+            "Config file not found at {:?}",
+            args.config
+        )));
+    }
+    if args.require_signed
+        && let Some(key) = &args.trusted_key
+    {
+        bundle::verify_file(&args.config, key).map_err(Failure::Config)?;
+    }
+    let trusted_key = args.trusted_key.as_deref().filter(|_| args.require_signed);
+    oci::fetch_missing(&args.config, trusted_key).map_err(Failure::Config)?;
+    let mut config = Config::load(&args.config)
+        .with_context(|| format!("Failed to load configuration from {:?}", args.config))
+        .map_err(Failure::Config)?;
+    if let Some(key) = trusted_key {
+        bundle::verify_param_files(&config, key).map_err(Failure::Config)?;
+    }
+    RuleFilter {
+        min_severity: args.min_severity,
+        only: args.only_rules.clone(),
+        skip: args.skip_rules.clone(),
+    }
+    .apply(&mut config);
+    if !args.lenient {
+        let mut problems = engine::check_guards(&config, &GuardRegistry::default());
+        for project in config.projects.values() {
+            for problem in
+                engine::check_guards(&config.for_project(project), &GuardRegistry::default())
+            {
+                if !problems.contains(&problem) {
+                    problems.push(problem);
+                }
+            }
+        }
+        if !problems.is_empty() {
+            return Err(Failure::Config(anyhow!(
+                "Invalid rules in {:?} (pass --lenient to only warn about them):\n  {}",
+                args.config,
+                problems.join("\n  ")
+            )));
+        }
+    }
+    if args.strict {
+        config
+            .strict
+            .unreadable_files
+            .get_or_insert(Severity::Error);
+        config.strict.invalid_rules.get_or_insert(Severity::Error);
+    }
+    Ok(config)
+}
+
+/// A factory scanned by `run`, with the config it's checked with.
+struct Project<'a> {
+    /// `None` when a single factory is scanned.
+    name: Option<String>,
+    root: PathBuf,
+    config: Cow<'a, Config>,
+}
+
+/// The factories to scan: the `projects` of the config, under the project path; each
+/// `--project-path` when several are given, named by its path; or the project path.
+fn projects<'a>(args: &Cli, config: &'a Config) -> Result<Vec<Project<'a>>, Failure> {
+    if config.projects.is_empty() {
+        let roots = if args.auto_detect {
+            detect_roots(&args.project_paths)
         } else {
-            eprintln!("Error: Config file not found at {:?}", args.config);
+            args.project_paths.clone()
+        };
+        let several = roots.len() > 1;
+        return Ok(roots
+            .into_iter()
+            .map(|root| Project {
+                name: several.then(|| reporter::display_path(&root.to_string_lossy())),
+                root,
+                config: Cow::Borrowed(config),
+            })
+            .collect());
+    }
+    if args.project_paths.len() > 1 || args.auto_detect {
+        return Err(Failure::Config(anyhow!(
+            "Several --project-path and --auto-detect can't be combined with the `projects` of \
+             the config"
+        )));
+    }
+    config
+        .projects
+        .iter()
+        .map(|(name, project)| {
+            let root = args.project_path().join(&project.path);
+            if !root.is_dir() {
+                return Err(Failure::Scan(anyhow!(
+                    "Folder of project '{}' not found at {:?}",
+                    name,
+                    root
+                )));
+            }
+            Ok(Project {
+                name: Some(name.clone()),
+                root,
+                config: Cow::Owned(config.for_project(project)),
+            })
+        })
+        .collect()
+}
+
+fn walk_options(args: &Cli) -> WalkOptions {
+    WalkOptions {
+        include: args.include.clone(),
+        exclude: args.exclude.clone(),
+        no_gitignore: args.no_gitignore,
+        hidden: args.hidden,
+        follow_links: args.follow_links,
+        max_depth: args.max_depth,
+    }
+}
+
+/// The files listed in `list` (`-` for stdin), one per line and relative to the current
+/// folder. Blank lines and `#` comments are skipped, and so are files that don't exist,
+/// such as those a diff lists as deleted.
+fn files_from(list: &Path) -> Result<HashSet<PathBuf>> {
+    let text = if list == Path::new("-") {
+        io::read_to_string(io::stdin()).context("Failed to read the file list from stdin")?
+    } else {
+        fs::read_to_string(list)
+            .with_context(|| format!("Failed to read the file list {:?}", list))?
+    };
+    // NUL-separated names are taken as they are, since they may hold any other character.
+    let lines: Vec<&str> = match text.contains('\0') {
+        true => text.split('\0').collect(),
+        false => text.lines().map(str::trim).collect(),
+    };
+    let mut files = HashSet::new();
+    for line in lines {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match fs::canonicalize(line) {
+            Ok(path) => {
+                files.insert(path);
+            }
+            Err(_) => tracing::debug!("Skipping {:?} of the file list, which doesn't exist", line),
         }
-        exit(1);
     }
+    Ok(files)
+}
 
-    let config = Config::load(&args.config)
-        .with_context(|| format!("Failed to load configuration from {:?}", args.config))?;
+/// The factory roots under `paths`. A path without any is scanned as is; several roots
+/// under one path are all scanned, with a warning since the path may be too broad.
+fn detect_roots(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    for path in paths {
+        let found = scanner::find_factory_roots(path);
+        match found.len() {
+            0 => {
+                tracing::warn!("No factory root found under {:?}, scanning it as is", path);
+                roots.push(path.clone());
+            }
+            1 => tracing::info!("Found the factory root {:?}", found[0]),
+            _ => tracing::warn!(
+                "Found {} factory roots under {:?}, scanning all of them: {}",
+                found.len(),
+                path,
+                found
+                    .iter()
+                    .map(|root| root.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+        roots.extend(found);
+    }
+    roots
+}
+
+/// Adds the results of one project's scan to `scan`, labelled with its name.
+fn merge_scan(scan: &mut ScanReport, part: ScanReport, project: Option<&str>) {
+    for mut result in part.results {
+        result.project = project.map(str::to_string);
+        for violation in &mut result.violations {
+            violation.project = result.project.clone();
+        }
+        scan.results.push(result);
+    }
+    for mut violation in part.suppressed {
+        violation.project = project.map(str::to_string);
+        scan.suppressed.push(violation);
+    }
+    for stats in part.rule_stats {
+        match scan
+            .rule_stats
+            .iter_mut()
+            .find(|s| s.rule_id == stats.rule_id)
+        {
+            Some(existing) => {
+                existing.files_checked += stats.files_checked;
+                existing.violations += stats.violations;
+                existing.elapsed += stats.elapsed;
+            }
+            None => scan.rule_stats.push(stats),
+        }
+    }
+    scan.diagnostics.extend(part.diagnostics);
+}
 
-    let results = engine::run(&config, &args.project_path)?;
+/// Prints rule metadata for `list-rules` and `explain`, as text or as JSON.
+fn write_catalog<T: Serialize + ?Sized>(
+    format: OutputFormat,
+    value: &T,
+    write_human: fn(&mut dyn Write, &T) -> io::Result<()>,
+) -> Result<ExitCode, Failure> {
+    let mut out = io::stdout().lock();
+    let result = match format {
+        OutputFormat::Human => write_human(&mut out, value).map_err(anyhow::Error::from),
+        OutputFormat::Json => serde_json::to_writer_pretty(&mut out, value)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| writeln!(out).map_err(anyhow::Error::from)),
+        _ => {
+            return Err(Failure::Config(anyhow!(
+                "Rules can only be printed in the human and json formats"
+            )));
+        }
+    };
+    result
+        .context("Failed to write rules")
+        .map_err(Failure::Scan)?;
+    Ok(ExitCode::SUCCESS)
+}
 
-    if args.json {
-        reporter::print_json_report(&results);
+fn run_lint_config(args: &Cli, format: OutputFormat) -> Result<ExitCode, Failure> {
+    // Invalid params are findings here, not a reason to stop.
+    let args = Cli {
+        lenient: true,
+        ..args.clone()
+    };
+    let config = load_config(&args)?;
+    let mut findings = engine::lint::lint(
+        &config,
+        &GuardRegistry::default(),
+        args.project_path(),
+        &walk_options(&args),
+    )
+    .context("Failed to read the project")
+    .map_err(Failure::Scan)?;
+    // Findings of a rule are listed together, in the order of the rules.
+    findings.sort_by_key(|finding| {
+        config
+            .rules
+            .iter()
+            .position(|rule| rule.id == finding.rule_id)
+    });
+    write_catalog(format, findings.as_slice(), catalog::write_findings)?;
+    Ok(if findings.is_empty() {
+        ExitCode::SUCCESS
     } else {
-        reporter::print_human_report(&results, start_time);
+        ExitCode::from(EXIT_VIOLATIONS)
+    })
+}
+
+fn run_preview(args: &Cli, format: OutputFormat, specs: &[String]) -> Result<ExitCode, Failure> {
+    let config = load_config(args)?;
+    let rules = preview::select_rules(&config, specs).map_err(Failure::Config)?;
+    let options = ScanOptions {
+        max_file_size: args.max_file_size,
+        walk: walk_options(args),
+        jsonc: args.jsonc,
+        ..Default::default()
+    };
+    let scan = engine::run(
+        &preview::config_with(&config, rules.clone()),
+        args.project_path(),
+        &options,
+        &ScanProgress::new(false, None),
+    )
+    .map_err(Failure::Scan)?;
+    let impacts = preview::summarize(&rules, &scan);
+    write_catalog(format, impacts.as_slice(), preview::write_human)
+}
+
+fn run_pack(args: &Cli, command: &PackCommand) -> Result<ExitCode, Failure> {
+    match command {
+        PackCommand::Build {
+            dir,
+            output,
+            sign,
+            oci,
+            pack_version,
+        } => run_pack_build(dir, output, sign.as_deref(), *oci, pack_version.as_deref()),
+        PackCommand::Publish { layout, reference } => {
+            let reference = parse_oci_reference(reference)?;
+            oci::publish(layout, &reference)
+                .with_context(|| format!("Failed to publish {}", reference))
+                .map_err(Failure::Scan)?;
+            eprintln!("Published {}", reference);
+            Ok(ExitCode::SUCCESS)
+        }
+        PackCommand::Add { reference } => {
+            let reference = parse_oci_reference(reference)?;
+            let config_dir = args.config.parent().unwrap_or(Path::new(""));
+            let path = oci::fetch(&reference, config_dir)
+                .with_context(|| format!("Failed to pull rule pack {}", reference))
+                .map_err(Failure::Scan)?;
+            if args.require_signed
+                && let Some(key) = &args.trusted_key
+            {
+                bundle::verify_file(&path, key).map_err(Failure::Config)?;
+            }
+            let source = fs::read_to_string(&args.config).unwrap_or_default();
+            let edited = oci::add_extends(&source, &reference.to_string())
+                .with_context(|| format!("Failed to edit {:?}", args.config))
+                .map_err(Failure::Config)?;
+            fs::write(&args.config, edited)
+                .with_context(|| format!("Failed to write {:?}", args.config))
+                .map_err(Failure::Scan)?;
+            eprintln!(
+                "Pulled {} into {:?} and added it to {:?}",
+                reference, path, args.config
+            );
+            Ok(ExitCode::SUCCESS)
+        }
     }
+}
 
-    let has_errors = results
+fn run_import(
+    args: &Cli,
+    format: OutputFormat,
+    command: &ImportCommand,
+) -> Result<ExitCode, Failure> {
+    let ImportCommand::AzurePolicy {
+        policy,
+        preset,
+        append,
+    } = command;
+    let mut documents = Vec::new();
+    if let Some(PolicyPreset::CafNaming) = preset {
+        documents.push(
+            serde_json::from_str(policy::CAF_NAMING)
+                .context("Invalid built-in preset")
+                .map_err(Failure::Config)?,
+        );
+    }
+    if let Some(path) = policy {
+        let source = fs::read(path)
+            .with_context(|| format!("Failed to read {:?}", path))
+            .map_err(Failure::Config)?;
+        documents.push(
+            serde_json::from_slice(&source)
+                .with_context(|| format!("Failed to parse {:?}", path))
+                .map_err(Failure::Config)?,
+        );
+    }
+    let import = policy::convert(&documents).map_err(Failure::Config)?;
+    if import.rules.is_empty() || *append {
+        for skipped in &import.skipped {
+            eprintln!("Skipped {}", skipped);
+        }
+    }
+    if import.rules.is_empty() {
+        return Err(Failure::Config(anyhow!(
+            "None of the policy definitions could be converted"
+        )));
+    }
+    if !*append {
+        return write_catalog(format, &import, policy::write_human);
+    }
+
+    let source = fs::read_to_string(&args.config).unwrap_or_default();
+    let existing = serde_yaml::from_str::<Config>(&source)
+        .map(|config| config.rules)
+        .unwrap_or_default();
+    if let Some(rule) = import
+        .rules
         .iter()
-        .any(|r| r.violations.iter().any(|v| v.severity == Severity::Error));
-    if has_errors {
-        exit(1);
+        .find(|rule| existing.iter().any(|r| r.id == rule.id))
+    {
+        return Err(Failure::Config(anyhow!(
+            "{:?} already has a rule '{}'",
+            args.config,
+            rule.id
+        )));
+    }
+    let edited = wizard::append_rules(&source, &import.rules)
+        .with_context(|| format!("Failed to add the rules to {:?}", args.config))
+        .map_err(Failure::Config)?;
+    fs::write(&args.config, edited)
+        .with_context(|| format!("Failed to write {:?}", args.config))
+        .map_err(Failure::Scan)?;
+    eprintln!("Added {} rule(s) to {:?}", import.rules.len(), args.config);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn parse_oci_reference(reference: &str) -> Result<packs::OciReference, Failure> {
+    packs::OciReference::parse(reference).ok_or_else(|| {
+        Failure::Config(anyhow!(
+            "Invalid pack reference '{}', expected oci://registry/repository:tag",
+            reference
+        ))
+    })
+}
+
+fn run_pack_build(
+    dir: &Path,
+    output: &Path,
+    key: Option<&Path>,
+    oci: bool,
+    version: Option<&str>,
+) -> Result<ExitCode, Failure> {
+    let bundle = bundle::build(dir).map_err(Failure::Config)?;
+    let signature = key
+        .map(|key| {
+            let key_pem = fs::read_to_string(key)
+                .with_context(|| format!("Failed to read the signing key {:?}", key))?;
+            // The bundle only names the lists and policies its rules read.
+            for file in bundle::sign_param_files(&Config::from_yaml(bundle.as_bytes())?, &key_pem)?
+            {
+                eprintln!("Signed {:?}", file);
+            }
+            bundle::sign(bundle.as_bytes(), &key_pem)
+        })
+        .transpose()
+        .map_err(Failure::Config)?;
+    if oci {
+        let mut metadata = oci::read_metadata(dir).map_err(Failure::Config)?;
+        if let Some(version) = version {
+            metadata.version = version.to_string();
+        }
+        if metadata.version.is_empty() {
+            return Err(Failure::Config(anyhow!(
+                "The pack has no version: set `version` in {:?} or pass --version",
+                dir.join(oci::PACK_FILE)
+            )));
+        }
+        if metadata.name.is_empty() {
+            metadata.name = dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        oci::write_layout(output, &metadata, bundle.as_bytes(), signature.as_deref())
+            .with_context(|| format!("Failed to write the pack layout {:?}", output))
+            .map_err(Failure::Scan)?;
+        eprintln!(
+            "Wrote {} {} to {:?}",
+            metadata.name, metadata.version, output
+        );
+        return Ok(ExitCode::SUCCESS);
     }
+    fs::write(output, &bundle)
+        .with_context(|| format!("Failed to write {:?}", output))
+        .map_err(Failure::Scan)?;
+    if let Some(signature) = signature {
+        let signature_path = bundle::signature_path(output);
+        fs::write(&signature_path, signature + "\n")
+            .with_context(|| format!("Failed to write {:?}", signature_path))
+            .map_err(Failure::Scan)?;
+        eprintln!("Wrote {:?} and its signature {:?}", output, signature_path);
+    } else {
+        eprintln!("Wrote {:?}", output);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Checks the rule `rule_id` on `document`, named like a file of the rule's first
+/// asset type so the rule applies.
+fn run_check_snippet(
+    args: &Cli,
+    format: OutputFormat,
+    rule_id: &str,
+    document: &str,
+    start_time: Instant,
+    started_at: DateTime<Utc>,
+) -> Result<ExitCode, Failure> {
+    let config = load_config(args)?;
+    let rule = catalog::find(&config, rule_id)
+        .map_err(Failure::Config)?
+        .clone();
+    let source = if document == "-" {
+        io::read_to_string(io::stdin())
+            .context("Failed to read stdin")
+            .map_err(Failure::Scan)?
+    } else {
+        document.to_string()
+    };
+    let asset = rule.asset.names().first().cloned().unwrap_or_default();
+    let filename = Path::new(&asset).join("snippet.json");
+    let config = Config {
+        rules: vec![rule],
+        ..config
+    };
+    let mut result = engine::check_source(&config, &GuardRegistry::default(), &filename, &source)
+        .context("The snippet is not valid JSON")
+        .map_err(Failure::Config)?;
+    engine::limit_values(&mut result.violations, args.max_value_length);
+
+    let options = ReportOptions {
+        start_time,
+        started_at,
+        config_hash: config.hash.clone(),
+        group_by: args.group_by,
+        sort: args.sort,
+        summary_only: args.summary,
+        verbose: args.verbose,
+        unicode: io::stdout().is_terminal() || args.color == ColorChoice::Always,
+        json_compat: args.json_compat,
+        template: args.template.clone(),
+        suppressed: vec![],
+        diagnostics: vec![],
+    };
+    reporter::for_format(format, &options)
+        .write(&mut io::stdout().lock(), std::slice::from_ref(&result))
+        .context("Failed to write report")
+        .map_err(Failure::Scan)?;
+    Ok(exit_code_for(result.violations.iter(), args.max_warnings))
+}
+
+/// Traces the rules on `file` with the config of the project it is in.
+fn run_explain(args: &Cli, format: OutputFormat, file: &Path) -> Result<ExitCode, Failure> {
+    let config = load_config(args)?;
+    let projects = projects(args, &config)?;
+    let project = projects
+        .iter()
+        .find(|project| file.starts_with(&project.root))
+        .or(projects.first());
+    let (config, root) = match project {
+        Some(project) => (project.config.as_ref(), project.root.as_path()),
+        None => (&config, args.project_path()),
+    };
+    let bytes = fs::read(file)
+        .with_context(|| format!("Failed to read {:?}", file))
+        .map_err(Failure::Scan)?;
+    let source = engine::decode(bytes)
+        .map_err(|e| Failure::Scan(anyhow!("Failed to read {:?}: {}", file, e)))?;
+    let source = if args.jsonc {
+        engine::strip_jsonc(&source)
+    } else {
+        source
+    };
+    let traces = engine::trace::trace(
+        config,
+        &GuardRegistry::default(),
+        file,
+        file.strip_prefix(root).unwrap_or(file),
+        &source,
+    )
+    .with_context(|| format!("Could not parse JSON from {:?}", file))
+    .map_err(Failure::Scan)?;
+    write_catalog(format, traces.as_slice(), catalog::write_trace)
+}
+
+fn run_suppressions(args: &Cli, format: OutputFormat) -> Result<ExitCode, Failure> {
+    let config = load_config(args)?;
+    let options = ScanOptions {
+        max_file_size: args.max_file_size,
+        walk: walk_options(args),
+        jsonc: args.jsonc,
+        ..Default::default()
+    };
+    let root = args.project_path();
+    let scan = engine::run(&config, root, &options, &ScanProgress::new(false, None))
+        .map_err(Failure::Scan)?;
+    let reported = scan.results.iter().flat_map(|r| &r.violations);
+    let audit = engine::suppress::audit(
+        &config.suppressions,
+        root,
+        scan.suppressed.iter().chain(reported),
+    );
+    write_catalog(format, audit.as_slice(), catalog::write_suppressions)?;
+    Ok(
+        if audit
+            .iter()
+            .any(|entry| entry.status == engine::suppress::Status::Expired)
+        {
+            ExitCode::from(EXIT_VIOLATIONS)
+        } else {
+            ExitCode::SUCCESS
+        },
+    )
+}
 
+fn run_install_hook(args: &Cli, pre_commit: bool, force: bool) -> Result<ExitCode, Failure> {
+    // Catch a broken config now rather than on the next commit.
+    load_config(args)?;
+    if pre_commit {
+        let entry = hook::pre_commit_entry(args.project_path(), &args.config);
+        print!("{}", entry.map_err(Failure::Scan)?);
+    } else {
+        let path =
+            hook::install(args.project_path(), &args.config, force).map_err(Failure::Scan)?;
+        println!("Installed the pre-commit hook at {:?}", path);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_docs(args: &Cli, format: OutputFormat, dir: &Path) -> Result<ExitCode, Failure> {
+    let page_format = match format {
+        OutputFormat::Human | OutputFormat::Markdown => docs::PageFormat::Markdown,
+        OutputFormat::Html => docs::PageFormat::Html,
+        _ => {
+            return Err(Failure::Config(anyhow!(
+                "Documentation can only be generated in the markdown and html formats"
+            )));
+        }
+    };
+    let config = load_config(args)?;
+    let pages = docs::generate(&config, dir, page_format).map_err(Failure::Scan)?;
+    println!("Wrote {} rule page(s) to {:?}", pages, dir);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_annotate_azdo(
+    args: &Cli,
+    pr: &azdo::PullRequest,
+    from_report: Option<&Path>,
+) -> Result<ExitCode, Failure> {
+    let auth = azdo::Auth::from_env().map_err(Failure::Config)?;
+    let violations = match from_report {
+        Some(path) => diff::load_report(path).map_err(Failure::Scan)?,
+        None => scan_violations(args)?,
+    };
+    let repo_root = git::top_level(args.project_path()).unwrap_or_else(|_| PathBuf::from("."));
+    let outcome = azdo::annotate(pr, &auth, &violations, &repo_root).map_err(Failure::Scan)?;
+    println!(
+        "Posted {} thread(s) on pull request {} ({} already there)",
+        outcome.posted, pr.id, outcome.existing
+    );
+    Ok(exit_code_for(violations.iter(), args.max_warnings))
+}
+
+/// Downloads the factory to `dir`, then scans it as the project, with the usual reports.
+fn run_scan_azure(
+    args: &Cli,
+    factory: &azure::Factory,
+    dir: PathBuf,
+    start_time: Instant,
+) -> Result<ExitCode, Failure> {
+    // Catch a broken config before talking to Azure.
+    load_config(args)?;
+    let token = azure::access_token().map_err(Failure::Config)?;
+    let assets = azure::download(factory, &token, &dir)
+        .with_context(|| format!("Failed to download the factory '{}'", factory.name))
+        .map_err(Failure::Scan)?;
+    tracing::info!(
+        "Downloaded {} asset(s) of the factory '{}' to {:?}",
+        assets,
+        factory.name,
+        dir
+    );
+
+    let mut args = args.clone();
+    args.command = None;
+    args.project_paths = vec![dir];
+    run(&args, start_time)
+}
+
+fn run_bench(
+    args: &Cli,
+    format: OutputFormat,
+    project: &Path,
+    iterations: usize,
+) -> Result<ExitCode, Failure> {
+    let config = load_config(args)?;
+    let options = ScanOptions {
+        max_file_size: args.max_file_size,
+        walk: walk_options(args),
+        jsonc: args.jsonc,
+        ..Default::default()
+    };
+    let benchmark = engine::bench::run(&config, project, &options, iterations)
+        .with_context(|| format!("Failed to benchmark {:?}", project))
+        .map_err(Failure::Scan)?;
+    write_catalog(format, &benchmark, reporter::write_benchmark)
+}
+
+fn run_drift(
+    format: OutputFormat,
+    project_path: &Path,
+    factory: &azure::Factory,
+) -> Result<ExitCode, Failure> {
+    if !matches!(format, OutputFormat::Human | OutputFormat::Json) {
+        return Err(Failure::Config(anyhow!(
+            "Drift can only be printed in the human and json formats"
+        )));
+    }
+    let token = azure::access_token().map_err(Failure::Config)?;
+    let drifts = drift::detect(project_path, factory, &token)
+        .with_context(|| format!("Failed to compare with the factory '{}'", factory.name))
+        .map_err(Failure::Scan)?;
+
+    let mut out = io::stdout().lock();
+    match format {
+        OutputFormat::Json => serde_json::to_writer_pretty(&mut out, &drifts)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| writeln!(out).map_err(anyhow::Error::from)),
+        _ => drift::write_human(&mut out, &factory.name, &drifts).map_err(anyhow::Error::from),
+    }
+    .context("Failed to write the drift report")
+    .map_err(Failure::Scan)?;
+    Ok(if drifts.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_VIOLATIONS)
+    })
+}
+
+fn run_trends(
+    args: &Cli,
+    format: OutputFormat,
+    filter: &history::TrendFilter,
+) -> Result<ExitCode, Failure> {
+    let Some(path) = &args.history else {
+        return Err(Failure::Config(anyhow!(
+            "trends reads the database given with --history sqlite://PATH"
+        )));
+    };
+    if !matches!(format, OutputFormat::Human | OutputFormat::Json) {
+        return Err(Failure::Config(anyhow!(
+            "Trends can only be printed in the human and json formats"
+        )));
+    }
+    if !path.exists() {
+        return Err(Failure::Config(anyhow!(
+            "No history database at {:?}; record runs with --history first",
+            path
+        )));
+    }
+    let trends = history::History::open(path)
+        .and_then(|history| history.trends(filter))
+        .map_err(Failure::Scan)?;
+
+    let mut out = io::stdout().lock();
+    match format {
+        OutputFormat::Json => serde_json::to_writer_pretty(&mut out, &trends)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| writeln!(out).map_err(anyhow::Error::from)),
+        _ => history::write_human(&mut out, &trends).map_err(anyhow::Error::from),
+    }
+    .context("Failed to write the trends")
+    .map_err(Failure::Scan)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Scans the project without printing a report, for subcommands that publish the
+/// violations elsewhere.
+fn scan_violations(args: &Cli) -> Result<Vec<Violation>, Failure> {
+    let config = load_config(args)?;
+    let options = ScanOptions {
+        max_file_size: args.max_file_size,
+        walk: walk_options(args),
+        jsonc: args.jsonc,
+        max_value_length: args.max_value_length,
+        ..Default::default()
+    };
+    let scan = engine::run(
+        &config,
+        args.project_path(),
+        &options,
+        &ScanProgress::new(false, None),
+    )
+    .map_err(Failure::Scan)?;
+    Ok(scan
+        .results
+        .into_iter()
+        .flat_map(|r| r.violations)
+        .collect())
+}
+
+/// Checks the document piped on stdin as if it were the file at `filename`.
+fn check_stdin(
+    config: &Config,
+    filename: &Path,
+    max_value_length: Option<usize>,
+) -> Result<engine::ScanReport> {
+    let source = io::read_to_string(io::stdin()).context("Failed to read stdin")?;
+    let mut result = engine::check_source(config, &GuardRegistry::default(), filename, &source)
+        .with_context(|| format!("Could not parse JSON from stdin ({:?})", filename))?;
+    engine::limit_values(&mut result.violations, max_value_length);
+    // Snippets come from the piped document, not the file of the same name on disk.
+    result.source = Some(source.into());
+    Ok(engine::ScanReport {
+        results: vec![result],
+        suppressed: vec![],
+        rule_stats: vec![],
+        diagnostics: vec![],
+    })
+}
+
+/// Fails on any error, or on more warnings than `max_warnings` allows.
+fn exit_code_for<'a>(
+    violations: impl Iterator<Item = &'a Violation>,
+    max_warnings: Option<usize>,
+) -> ExitCode {
+    if fails(violations, max_warnings) {
+        ExitCode::from(EXIT_VIOLATIONS)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn fails<'a>(violations: impl Iterator<Item = &'a Violation>, max_warnings: Option<usize>) -> bool {
+    let (errors, warnings) = violations.fold((0, 0), |(errors, warnings), v| match v.severity {
+        Severity::Error => (errors + 1, warnings),
+        Severity::Warning => (errors, warnings + 1),
+    });
+    errors > 0 || max_warnings.is_some_and(|max| warnings > max)
+}
+
+fn run_diff(
+    format: OutputFormat,
+    old: &Path,
+    new: &Path,
+    max_warnings: Option<usize>,
+) -> Result<ExitCode, Failure> {
+    check_diff_format(format).map_err(Failure::Config)?;
+    let old = diff::load_report(old).map_err(Failure::Scan)?;
+    let new = diff::load_report(new).map_err(Failure::Scan)?;
+
+    let comparison = diff::compare(&old, &new);
+    write_diff(format, &comparison).map_err(Failure::Scan)?;
+    Ok(exit_code_for(comparison.new.into_iter(), max_warnings))
+}
+
+fn check_diff_format(format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Human | OutputFormat::Json => Ok(()),
+        _ => bail!("Comparisons can only be printed in the human and json formats"),
+    }
+}
+
+fn write_diff(format: OutputFormat, comparison: &diff::Diff) -> Result<()> {
+    let mut out = io::stdout().lock();
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, comparison)?;
+            writeln!(out)?;
+        }
+        _ => diff::write_human(&mut out, comparison)?,
+    }
     Ok(())
 }
+
+fn write_report_file(
+    report: &dyn ReportFormat,
+    path: &Path,
+    results: &[engine::FileResult],
+) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Failed to create report file {:?}", path))?;
+    let mut writer = BufWriter::new(file);
+    report
+        .write(&mut writer, results)
+        .and_then(|_| writer.flush())
+        .with_context(|| format!("Failed to write report to {:?}", path))
+}
@@ -0,0 +1,196 @@
+use crate::config::{Notification, NotificationKind, NotifyOn, Severity};
+use crate::engine::FileResult;
+use crate::http;
+use crate::reporter::{self, ReportOptions};
+use anyhow::{Context, Result, anyhow};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+use tera::Tera;
+
+/// Message sent when a notification has no `template`.
+const DEFAULT_TEMPLATE: &str = "\
+adf-guardian found {{ summary.errors }} error(s) and {{ summary.warnings }} warning(s) \
+in {{ summary.files_with_violations }} of {{ summary.files_scanned }} file(s) of {{ project }}.{% for rule in rules %}
+- {{ rule.rule_id }}: {{ rule.count }}{% endfor %}";
+
+#[derive(Serialize)]
+struct RuleCount<'a> {
+    rule_id: &'a str,
+    count: usize,
+}
+
+/// Posts a summary of the run to each webhook whose condition it meets. A webhook that
+/// can't be reached is logged rather than failing the run, whose result still stands.
+pub fn send_all(
+    notifications: &[Notification],
+    options: &ReportOptions,
+    results: &[FileResult],
+    project: &Path,
+) {
+    let violations = || results.iter().flat_map(|r| &r.violations);
+    let errors = violations()
+        .filter(|v| v.severity == Severity::Error)
+        .count();
+    let warnings = violations().count() - errors;
+    for notification in notifications {
+        let send = match notification.on {
+            NotifyOn::Errors => errors > 0,
+            NotifyOn::Violations => errors + warnings > 0,
+            NotifyOn::Always => true,
+        };
+        if !send {
+            continue;
+        }
+        if let Err(e) = send_one(notification, options, results, project) {
+            tracing::warn!("Could not send a notification: {:#}", e);
+        }
+    }
+}
+
+fn send_one(
+    notification: &Notification,
+    options: &ReportOptions,
+    results: &[FileResult],
+    project: &Path,
+) -> Result<()> {
+    let url = expand_env(&notification.url)?;
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for violation in results.iter().flat_map(|r| &r.violations) {
+        *counts.entry(&violation.rule_id).or_default() += 1;
+    }
+    let mut rules: Vec<RuleCount> = counts
+        .into_iter()
+        .map(|(rule_id, count)| RuleCount { rule_id, count })
+        .collect();
+    rules.sort_by_key(|rule| Reverse(rule.count));
+
+    let mut context = reporter::template_context(options, results)?;
+    let project = project
+        .canonicalize()
+        .unwrap_or_else(|_| project.to_path_buf());
+    context.insert("project", &project.display().to_string());
+    context.insert("rules", &rules);
+    let template = notification.template.as_deref().unwrap_or(DEFAULT_TEMPLATE);
+    let message = Tera::one_off(template, &context, false)
+        .map_err(reporter::template_error)
+        .context("Failed to render the notification template")?;
+
+    let kind = notification.kind.unwrap_or_else(|| kind_for(&url));
+    let summary = context.get("summary").cloned().unwrap_or_default();
+    let mut response = http::agent()
+        .post(&url)
+        .send_json(payload(kind, message.trim(), summary))
+        .with_context(|| format!("Failed to post to {}", redact(&url)))?;
+    http::check_status("The webhook", &mut response)
+        .with_context(|| format!("Failed to post to {}", redact(&url)))
+}
+
+/// The shape of the webhook: Slack and Teams by their hosts, anything else generic.
+fn kind_for(url: &str) -> NotificationKind {
+    let host = url
+        .split("://")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+        .unwrap_or_default();
+    if host == "hooks.slack.com" {
+        NotificationKind::Slack
+    } else if host.ends_with(".webhook.office.com") || host.ends_with(".logic.azure.com") {
+        NotificationKind::Teams
+    } else {
+        NotificationKind::Generic
+    }
+}
+
+fn payload(kind: NotificationKind, message: &str, summary: Value) -> Value {
+    match kind {
+        NotificationKind::Slack => json!({ "text": message }),
+        // An Adaptive Card, which both Teams workflows and Office 365 connectors accept.
+        NotificationKind::Teams => json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "type": "AdaptiveCard",
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "version": "1.4",
+                    "body": [{ "type": "TextBlock", "text": message, "wrap": true }],
+                },
+            }],
+        }),
+        NotificationKind::Generic => json!({
+            "tool": env!("CARGO_PKG_NAME"),
+            "message": message,
+            "summary": summary,
+        }),
+    }
+}
+
+/// Replaces each `${NAME}` with the environment variable `NAME`.
+fn expand_env(value: &str) -> Result<String> {
+    let pattern = Regex::new(r"\$\{(\w+)\}").expect("valid regex");
+    let mut missing = None;
+    let expanded = pattern.replace_all(value, |captures: &Captures| {
+        env::var(&captures[1]).unwrap_or_else(|_| {
+            missing.get_or_insert(captures[1].to_string());
+            String::new()
+        })
+    });
+    match missing {
+        Some(name) => Err(anyhow!("The environment variable {} is not set", name)),
+        None => Ok(expanded.into_owned()),
+    }
+}
+
+/// The URL without its path, which for most webhooks is the secret.
+fn redact(url: &str) -> String {
+    match url.match_indices('/').nth(2) {
+        Some((end, _)) => format!("{}/...", &url[..end]),
+        None => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        assert_eq!(
+            kind_for("https://hooks.slack.com/services/T0/B0/x"),
+            NotificationKind::Slack
+        );
+        assert_eq!(
+            kind_for("https://contoso.webhook.office.com/webhookb2/x"),
+            NotificationKind::Teams
+        );
+        assert_eq!(
+            kind_for("https://alerts.example.com/hooks.slack.com"),
+            NotificationKind::Generic
+        );
+
+        assert_eq!(
+            payload(NotificationKind::Slack, "2 errors", Value::Null),
+            json!({ "text": "2 errors" })
+        );
+        assert_eq!(
+            payload(NotificationKind::Teams, "2 errors", Value::Null)["attachments"][0]["content"]
+                ["body"][0]["text"],
+            "2 errors"
+        );
+
+        assert_eq!(
+            expand_env("https://hooks.slack.com/${CARGO_PKG_NAME}").unwrap(),
+            "https://hooks.slack.com/adf-guardian"
+        );
+        assert!(expand_env("${ADF_GUARDIAN_UNSET_VARIABLE}").is_err());
+        assert_eq!(
+            redact("https://hooks.slack.com/services/T0/B0/secret"),
+            "https://hooks.slack.com/..."
+        );
+    }
+}
@@ -0,0 +1,514 @@
+use crate::{bundle, http};
+use adf_guardian::packs::OciReference;
+use anyhow::{Context, Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ureq::Agent;
+
+const MANIFEST_TYPE: &str = "application/vnd.oci.image.manifest.v1+json";
+const CONFIG_TYPE: &str = "application/vnd.adf-guardian.pack.config.v1+json";
+const RULES_TYPE: &str = "application/vnd.adf-guardian.rules.v1+yaml";
+const SIGNATURE_TYPE: &str = "application/vnd.adf-guardian.rules.signature.v1+base64";
+const SERVICE: &str = "The registry";
+
+/// Metadata file of a folder of rule files, skipped by `bundle::build`.
+pub const PACK_FILE: &str = "pack.yaml";
+
+/// Name, version and description of a pack, from the `pack.yaml` of its folder; stored as
+/// the config of the OCI artifact.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PackMetadata {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A pack pulled from a registry: its rules and their signature, if it was signed.
+pub struct Pulled {
+    pub rules: Vec<u8>,
+    pub signature: Option<String>,
+}
+
+fn digest(bytes: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(bytes))
+}
+
+fn descriptor(media_type: &str, bytes: &[u8]) -> Value {
+    json!({ "mediaType": media_type, "digest": digest(bytes), "size": bytes.len() })
+}
+
+/// The blobs of a pack artifact, manifest last: (media type, contents).
+fn blobs(
+    metadata: &PackMetadata,
+    rules: &[u8],
+    signature: Option<&str>,
+) -> Result<Vec<(&'static str, Vec<u8>)>> {
+    let config = serde_json::to_vec(metadata)?;
+    let mut layers = vec![(RULES_TYPE, rules.to_vec())];
+    if let Some(signature) = signature {
+        layers.push((SIGNATURE_TYPE, signature.as_bytes().to_vec()));
+    }
+    let manifest = json!({
+        "schemaVersion": 2,
+        "mediaType": MANIFEST_TYPE,
+        "artifactType": CONFIG_TYPE,
+        "config": descriptor(CONFIG_TYPE, &config),
+        "layers": layers.iter().map(|(media_type, bytes)| descriptor(media_type, bytes)).collect::<Vec<_>>(),
+        "annotations": {
+            "org.opencontainers.image.title": metadata.name,
+            "org.opencontainers.image.version": metadata.version,
+        },
+    });
+    let mut blobs = vec![(CONFIG_TYPE, config)];
+    blobs.extend(layers);
+    blobs.push((MANIFEST_TYPE, serde_json::to_vec_pretty(&manifest)?));
+    Ok(blobs)
+}
+
+/// Writes the pack as an OCI image layout in `dir`, tagged with its version.
+pub fn write_layout(
+    dir: &Path,
+    metadata: &PackMetadata,
+    rules: &[u8],
+    signature: Option<&str>,
+) -> Result<()> {
+    let blob_dir = dir.join("blobs").join("sha256");
+    fs::create_dir_all(&blob_dir).with_context(|| format!("Failed to create {:?}", blob_dir))?;
+    let blobs = blobs(metadata, rules, signature)?;
+    for (_, bytes) in &blobs {
+        let name = digest(bytes);
+        fs::write(blob_dir.join(name.trim_start_matches("sha256:")), bytes)?;
+    }
+    let (_, manifest) = blobs.last().expect("the manifest is always written");
+    let mut manifest = descriptor(MANIFEST_TYPE, manifest);
+    manifest["annotations"] = json!({ "org.opencontainers.image.ref.name": metadata.version });
+    let index = json!({ "schemaVersion": 2, "manifests": [manifest] });
+    fs::write(dir.join("index.json"), serde_json::to_vec_pretty(&index)?)?;
+    fs::write(dir.join("oci-layout"), r#"{"imageLayoutVersion":"1.0.0"}"#)?;
+    Ok(())
+}
+
+/// Pushes the pack of the OCI image layout `dir` to the registry of `reference`.
+pub fn publish(dir: &Path, reference: &OciReference) -> Result<()> {
+    let read = |path: &Path| fs::read(path).with_context(|| format!("Failed to read {:?}", path));
+    let index: Value = serde_json::from_slice(&read(&dir.join("index.json"))?)
+        .context("Invalid index.json in the pack layout")?;
+    let blob = |digest: &str| {
+        read(
+            &dir.join("blobs")
+                .join("sha256")
+                .join(digest.trim_start_matches("sha256:")),
+        )
+    };
+    let manifest_digest = index["manifests"][0]["digest"]
+        .as_str()
+        .ok_or_else(|| anyhow!("The pack layout has no manifest"))?;
+    let manifest_bytes = blob(manifest_digest)?;
+    let manifest: Value = serde_json::from_slice(&manifest_bytes)?;
+
+    let registry = Registry::connect(reference, "pull,push")?;
+    let layers = manifest["layers"].as_array().into_iter().flatten();
+    for descriptor in std::iter::once(&manifest["config"]).chain(layers) {
+        let digest = descriptor["digest"]
+            .as_str()
+            .ok_or_else(|| anyhow!("A blob of the manifest has no digest"))?;
+        registry
+            .upload(reference, digest, blob(digest)?)
+            .with_context(|| format!("Failed to upload {}", digest))?;
+    }
+    let mut response = registry
+        .agent
+        .put(registry.url(reference, &format!("manifests/{}", reference.tag)))
+        .header("Authorization", registry.authorization())
+        .content_type(MANIFEST_TYPE)
+        .send(&manifest_bytes[..])?;
+    http::check_status(SERVICE, &mut response).context("Failed to push the manifest")
+}
+
+/// Downloads the rules of the pack `reference` and their signature, checking their digests.
+pub fn pull(reference: &OciReference) -> Result<Pulled> {
+    let registry = Registry::connect(reference, "pull")?;
+    let mut response = registry
+        .agent
+        .get(registry.url(reference, &format!("manifests/{}", reference.tag)))
+        .header("Authorization", registry.authorization())
+        .header("Accept", MANIFEST_TYPE)
+        .call()?;
+    http::check_status(SERVICE, &mut response)
+        .with_context(|| format!("Failed to get the manifest of {}", reference))?;
+    let manifest: Value = response.body_mut().read_json()?;
+    let layer = |media_type: &str| {
+        manifest["layers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|layer| layer["mediaType"] == media_type)
+            .and_then(|layer| layer["digest"].as_str())
+            .map(|digest| registry.download(reference, digest))
+            .transpose()
+    };
+    let rules = layer(RULES_TYPE)?
+        .ok_or_else(|| anyhow!("{} is not an adf-guardian rule pack", reference))?;
+    let signature = layer(SIGNATURE_TYPE)?
+        .map(String::from_utf8)
+        .transpose()
+        .context("The signature of the pack is not text")?;
+    Ok(Pulled { rules, signature })
+}
+
+/// A registry of the OCI distribution API, with the token it handed out if it asked for
+/// one. Credentials are read from `ADF_GUARDIAN_REGISTRY_TOKEN`, or
+/// `ADF_GUARDIAN_REGISTRY_USERNAME` and `ADF_GUARDIAN_REGISTRY_PASSWORD`.
+struct Registry {
+    agent: Agent,
+    base: String,
+    authorization: Option<String>,
+}
+
+impl Registry {
+    /// Asks the registry for a token allowing `actions` (e.g. `pull,push`) on the repository.
+    fn connect(reference: &OciReference, actions: &str) -> Result<Self> {
+        let agent = http::agent();
+        // Local registries usually don't have a certificate.
+        let scheme = match reference.registry.split(':').next() {
+            Some("localhost" | "127.0.0.1") => "http",
+            _ => "https",
+        };
+        let base = format!("{}://{}/v2", scheme, reference.registry);
+        let basic = match (
+            env::var("ADF_GUARDIAN_REGISTRY_USERNAME"),
+            env::var("ADF_GUARDIAN_REGISTRY_PASSWORD"),
+        ) {
+            (Ok(username), Ok(password)) => Some(format!(
+                "Basic {}",
+                BASE64.encode(format!("{}:{}", username, password))
+            )),
+            _ => None,
+        };
+        if let Ok(token) = env::var("ADF_GUARDIAN_REGISTRY_TOKEN") {
+            return Ok(Registry {
+                agent,
+                base,
+                authorization: Some(format!("Bearer {}", token)),
+            });
+        }
+
+        let response = agent
+            .get(format!("{}/", base))
+            .call()
+            .with_context(|| format!("Failed to reach {}", reference.registry))?;
+        let challenge = response
+            .headers()
+            .get("www-authenticate")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let authorization = match challenge {
+            None => None,
+            Some(challenge) if challenge.starts_with("Basic") => basic,
+            Some(challenge) => {
+                let param = |name: &str| bearer_param(&challenge, name);
+                let realm = param("realm")
+                    .ok_or_else(|| anyhow!("The registry asked for an unknown authentication"))?;
+                let mut request = agent.get(&realm).query(
+                    "scope",
+                    format!("repository:{}:{}", reference.repository, actions),
+                );
+                if let Some(service) = param("service") {
+                    request = request.query("service", service);
+                }
+                if let Some(basic) = &basic {
+                    request = request.header("Authorization", basic);
+                }
+                let mut response = request.call()?;
+                http::check_status("The registry's token service", &mut response)
+                    .context("Failed to get a registry token")?;
+                let body: Value = response.body_mut().read_json()?;
+                let token = body["token"]
+                    .as_str()
+                    .or(body["access_token"].as_str())
+                    .ok_or_else(|| anyhow!("The registry answered without a token"))?;
+                Some(format!("Bearer {}", token))
+            }
+        };
+        Ok(Registry {
+            agent,
+            base,
+            authorization,
+        })
+    }
+
+    fn url(&self, reference: &OciReference, path: &str) -> String {
+        format!("{}/{}/{}", self.base, reference.repository, path)
+    }
+
+    fn authorization(&self) -> &str {
+        self.authorization.as_deref().unwrap_or_default()
+    }
+
+    fn upload(&self, reference: &OciReference, digest: &str, bytes: Vec<u8>) -> Result<()> {
+        let mut response = self
+            .agent
+            .post(self.url(reference, "blobs/uploads/"))
+            .header("Authorization", self.authorization())
+            .send_empty()?;
+        http::check_status(SERVICE, &mut response)?;
+        let location = response
+            .headers()
+            .get("location")
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| anyhow!("The registry didn't say where to upload"))?;
+        let location = if location.starts_with('/') {
+            let origin = self.base.trim_end_matches("/v2");
+            format!("{}{}", origin, location)
+        } else {
+            location.to_string()
+        };
+        let separator = if location.contains('?') { '&' } else { '?' };
+        let mut response = self
+            .agent
+            .put(format!("{}{}digest={}", location, separator, digest))
+            .header("Authorization", self.authorization())
+            .content_type("application/octet-stream")
+            .send(&bytes[..])?;
+        http::check_status(SERVICE, &mut response)
+    }
+
+    fn download(&self, reference: &OciReference, digest: &str) -> Result<Vec<u8>> {
+        let mut response = self
+            .agent
+            .get(self.url(reference, &format!("blobs/{}", digest)))
+            .header("Authorization", self.authorization())
+            .call()?;
+        http::check_status(SERVICE, &mut response)?;
+        let bytes = response.body_mut().read_to_vec()?;
+        if self::digest(&bytes) != digest {
+            bail!(
+                "The blob {} of {} doesn't match its digest",
+                digest,
+                reference
+            );
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reads the `pack.yaml` of a folder of rule files, if it has one.
+pub fn read_metadata(dir: &Path) -> Result<PackMetadata> {
+    let path = dir.join(PACK_FILE);
+    if !path.exists() {
+        return Ok(PackMetadata::default());
+    }
+    let source = fs::read(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    serde_yaml::from_slice(&source).with_context(|| format!("Invalid pack metadata in {:?}", path))
+}
+
+/// Pulls `reference` into the pack cache of the configs in `config_dir`, with its signature
+/// when it has one, and returns where its rules were written.
+pub fn fetch(reference: &OciReference, config_dir: &Path) -> Result<PathBuf> {
+    let pulled = pull(reference)?;
+    let path = reference.cache_path(config_dir);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    fs::write(&path, &pulled.rules).with_context(|| format!("Failed to write {:?}", path))?;
+    let signature_path = bundle::signature_path(&path);
+    match pulled.signature {
+        Some(signature) => fs::write(&signature_path, signature)
+            .with_context(|| format!("Failed to write {:?}", signature_path))?,
+        None if signature_path.exists() => fs::remove_file(&signature_path)?,
+        None => {}
+    }
+    Ok(path)
+}
+
+/// Pulls the `oci://` packs the config at `path` extends that aren't in the pack cache yet.
+/// With a `trusted_key`, every one of them must carry a valid signature.
+pub fn fetch_missing(path: &Path, trusted_key: Option<&Path>) -> Result<()> {
+    let source =
+        fs::read(path).with_context(|| format!("Failed to open config file: {:?}", path))?;
+    let Ok(document) = serde_yaml::from_slice::<Value>(&source) else {
+        // Config::load reports it.
+        return Ok(());
+    };
+    let extends = match &document["extends"] {
+        Value::String(name) => vec![name.as_str()],
+        Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    let config_dir = path.parent().unwrap_or(Path::new(""));
+    for reference in extends.into_iter().filter_map(OciReference::parse) {
+        let mut cached = reference.cache_path(config_dir);
+        if !cached.exists() {
+            tracing::info!("Pulling rule pack {}", reference);
+            cached = fetch(&reference, config_dir)
+                .with_context(|| format!("Failed to pull rule pack {}", reference))?;
+        }
+        if let Some(key) = trusted_key {
+            bundle::verify_file(&cached, key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds `reference` to the `extends` of the config `source`, editing the text so its
+/// comments and layout are kept. A config already extending it is returned as is.
+pub fn add_extends(source: &str, reference: &str) -> Result<String> {
+    let document: Value = serde_yaml::from_str(source).context("Failed to parse the config")?;
+    let extends = &document["extends"];
+    if extends == reference
+        || extends
+            .as_array()
+            .is_some_and(|e| e.iter().any(|p| p == reference))
+    {
+        return Ok(source.to_string());
+    }
+    let quoted = serde_json::to_string(reference)?;
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    let Some(index) = lines.iter().position(|line| line.starts_with("extends:")) else {
+        // Keep a leading comment header on top.
+        let index = lines
+            .iter()
+            .position(|line| !line.trim_start().starts_with('#') && !line.trim().is_empty())
+            .unwrap_or(lines.len());
+        lines.insert(index, format!("extends: [{}]", quoted));
+        return Ok(lines.join("\n") + "\n");
+    };
+    let value = lines[index]["extends:".len()..].trim().to_string();
+    if value.is_empty() || value.starts_with('#') {
+        // A block list: add an item after the last one, with the same indent.
+        let items = lines[index + 1..]
+            .iter()
+            .take_while(|line| {
+                let line = line.trim_start();
+                line.starts_with('-') || line.starts_with('#') || line.is_empty()
+            })
+            .count();
+        let last = lines[index + 1..index + 1 + items]
+            .iter()
+            .rposition(|line| line.trim_start().starts_with('-'))
+            .map(|i| index + 1 + i);
+        let (at, indent) = match last {
+            Some(last) => {
+                let line = &lines[last];
+                (
+                    last + 1,
+                    line[..line.len() - line.trim_start().len()].to_string(),
+                )
+            }
+            None => (index + 1, "  ".to_string()),
+        };
+        lines.insert(at, format!("{}- {}", indent, quoted));
+    } else if let Some(list) = value.strip_prefix('[') {
+        let end = list
+            .rfind(']')
+            .ok_or_else(|| anyhow!("Can't edit the `extends` list of the config"))?;
+        let items = list[..end].trim();
+        let separator = if items.is_empty() { "" } else { ", " };
+        lines[index] = format!(
+            "extends: [{}{}{}]{}",
+            items,
+            separator,
+            quoted,
+            &list[end + 1..]
+        );
+    } else {
+        let (value, comment) = match value.split_once(" #") {
+            Some((value, comment)) => (value.trim(), format!(" #{}", comment)),
+            None => (value.as_str(), String::new()),
+        };
+        lines[index] = format!("extends: [{}, {}]{}", value, quoted, comment);
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+/// A parameter of a `WWW-Authenticate: Bearer realm="...",service="..."` challenge.
+fn bearer_param(challenge: &str, name: &str) -> Option<String> {
+    let params = challenge.strip_prefix("Bearer")?;
+    params.split(',').find_map(|param| {
+        let (key, value) = param.trim().split_once('=')?;
+        (key == name).then(|| value.trim_matches('"').to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_layout() {
+        let dir = env::temp_dir().join(format!("adf-guardian-oci-{}", std::process::id()));
+        let metadata = PackMetadata {
+            name: "adf-rules".to_string(),
+            version: "1.2.0".to_string(),
+            description: None,
+        };
+        let rules = b"rules: []\n";
+        write_layout(&dir, &metadata, rules, Some("c2ln")).unwrap();
+
+        let index: Value =
+            serde_json::from_slice(&fs::read(dir.join("index.json")).unwrap()).unwrap();
+        let entry = &index["manifests"][0];
+        assert_eq!(
+            entry["annotations"]["org.opencontainers.image.ref.name"],
+            "1.2.0"
+        );
+        let blob = |digest: &str| {
+            fs::read(
+                dir.join("blobs/sha256")
+                    .join(digest.trim_start_matches("sha256:")),
+            )
+            .unwrap()
+        };
+        let manifest: Value =
+            serde_json::from_slice(&blob(entry["digest"].as_str().unwrap())).unwrap();
+        assert_eq!(manifest["layers"][0]["mediaType"], RULES_TYPE);
+        assert_eq!(
+            blob(manifest["layers"][0]["digest"].as_str().unwrap()),
+            rules
+        );
+        assert_eq!(manifest["layers"][1]["mediaType"], SIGNATURE_TYPE);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            bearer_param(
+                r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:a/b:pull""#,
+                "service"
+            )
+            .as_deref(),
+            Some("ghcr.io")
+        );
+    }
+
+    #[test]
+    fn test_add_extends() {
+        let reference = "oci://ghcr.io/contoso/adf-rules:1.2.0";
+        let add = |source: &str| add_extends(source, reference).unwrap();
+        assert_eq!(
+            add("# Rules\nrules: []\n"),
+            "# Rules\nextends: [\"oci://ghcr.io/contoso/adf-rules:1.2.0\"]\nrules: []\n"
+        );
+        assert_eq!(
+            add("extends: [\"adf:copy\"] # packs\nrules: []\n"),
+            "extends: [\"adf:copy\", \"oci://ghcr.io/contoso/adf-rules:1.2.0\"] # packs\nrules: []\n"
+        );
+        assert_eq!(
+            add("extends:\n  - adf:copy\n  # more\nrules: []\n"),
+            "extends:\n  - adf:copy\n  - \"oci://ghcr.io/contoso/adf-rules:1.2.0\"\n  # more\nrules: []\n"
+        );
+        assert_eq!(
+            add("extends: adf:copy\n"),
+            "extends: [adf:copy, \"oci://ghcr.io/contoso/adf-rules:1.2.0\"]\n"
+        );
+        let extended = add("rules: []\n");
+        assert_eq!(add(&extended), extended);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::config::Severity;
+use crate::engine::{FileResult, RuleStats};
+use crate::http;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::env;
+use std::hash::{BuildHasher, RandomState};
+use std::path::Path;
+
+const SERVICE: &str = "The OpenTelemetry collector";
+/// Bucket bounds of the scan duration histogram, in seconds.
+const DURATION_BOUNDS: [f64; 8] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// An OTLP/HTTP receiver, e.g. an OpenTelemetry collector on `http://localhost:4318`.
+/// Telemetry is sent in the JSON encoding, which every receiver accepts.
+pub struct Exporter {
+    endpoint: String,
+    /// From `OTEL_EXPORTER_OTLP_HEADERS`, e.g. `api-key=secret,team=data`.
+    headers: Vec<(String, String)>,
+    service_name: String,
+}
+
+impl Exporter {
+    pub fn new(endpoint: &str) -> Self {
+        let headers = env::var("OTEL_EXPORTER_OTLP_HEADERS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Exporter {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            headers,
+            service_name: env::var("OTEL_SERVICE_NAME")
+                .ok()
+                .filter(|v| !v.is_empty())
+                .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string()),
+        }
+    }
+
+    fn post(&self, signal: &str, body: &Value) -> Result<()> {
+        let url = format!("{}/v1/{}", self.endpoint, signal);
+        let mut request = http::agent().post(&url);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let mut response = request
+            .send_json(body)
+            .with_context(|| format!("Failed to export {} to {}", signal, url))?;
+        http::check_status(SERVICE, &mut response)
+            .with_context(|| format!("Failed to export {} to {}", signal, url))
+    }
+
+    fn resource(&self) -> Value {
+        json!({
+            "attributes": [
+                attribute("service.name", &self.service_name),
+                attribute("service.version", env!("CARGO_PKG_VERSION")),
+            ]
+        })
+    }
+}
+
+/// What a finished scan reports.
+pub struct Scan<'a> {
+    pub project: &'a Path,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub results: &'a [FileResult],
+    pub rule_stats: &'a [RuleStats],
+}
+
+/// Sends the metrics and the trace of `scan`: its duration, the files scanned, the
+/// violations by rule and severity, and the time spent in each rule.
+pub fn export(exporter: &Exporter, scan: &Scan) -> Result<()> {
+    let scope = json!({ "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") });
+    exporter.post(
+        "metrics",
+        &json!({
+            "resourceMetrics": [{
+                "resource": exporter.resource(),
+                "scopeMetrics": [{ "scope": scope, "metrics": metrics(scan) }],
+            }]
+        }),
+    )?;
+    exporter.post(
+        "traces",
+        &json!({
+            "resourceSpans": [{
+                "resource": exporter.resource(),
+                "scopeSpans": [{ "scope": scope, "spans": spans(scan) }],
+            }]
+        }),
+    )
+}
+
+fn metrics(scan: &Scan) -> Vec<Value> {
+    let start = nanos(scan.started_at);
+    let end = nanos(scan.finished_at);
+    // Each run is exported once, so its counts are deltas over the run.
+    let sum = |points: Vec<Value>| {
+        json!({
+            "dataPoints": points,
+            "aggregationTemporality": 1,
+            "isMonotonic": true,
+        })
+    };
+    let point = |attributes: Vec<Value>, value: Value| {
+        let mut point = json!({
+            "attributes": attributes,
+            "startTimeUnixNano": start,
+            "timeUnixNano": end,
+        });
+        match value {
+            Value::String(_) => point["asInt"] = value,
+            _ => point["asDouble"] = value,
+        }
+        point
+    };
+
+    let mut violations: BTreeMap<(&str, Severity), u64> = BTreeMap::new();
+    for violation in scan.results.iter().flat_map(|r| &r.violations) {
+        *violations
+            .entry((&violation.rule_id, violation.severity))
+            .or_default() += 1;
+    }
+
+    let seconds = duration_seconds(scan);
+    let bucket = DURATION_BOUNDS
+        .iter()
+        .position(|bound| seconds <= *bound)
+        .unwrap_or(DURATION_BOUNDS.len());
+    let mut bucket_counts = vec!["0"; DURATION_BOUNDS.len() + 1];
+    bucket_counts[bucket] = "1";
+
+    vec![
+        json!({
+            "name": "adf_guardian.scan.duration",
+            "description": "Duration of the scan",
+            "unit": "s",
+            "histogram": {
+                "dataPoints": [{
+                    "startTimeUnixNano": start,
+                    "timeUnixNano": end,
+                    "count": "1",
+                    "sum": seconds,
+                    "bucketCounts": bucket_counts,
+                    "explicitBounds": DURATION_BOUNDS,
+                }],
+                "aggregationTemporality": 1,
+            },
+        }),
+        json!({
+            "name": "adf_guardian.files.scanned",
+            "description": "Files scanned",
+            "unit": "{file}",
+            "sum": sum(vec![point(vec![], json!(scan.results.len().to_string()))]),
+        }),
+        json!({
+            "name": "adf_guardian.violations",
+            "description": "Violations found, by rule and severity",
+            "unit": "{violation}",
+            "sum": sum(violations
+                .into_iter()
+                .map(|((rule_id, severity), count)| {
+                    point(
+                        vec![
+                            attribute("rule.id", rule_id),
+                            attribute("severity", &severity.to_string()),
+                        ],
+                        json!(count.to_string()),
+                    )
+                })
+                .collect()),
+        }),
+        json!({
+            "name": "adf_guardian.rule.duration",
+            "description": "Time spent evaluating each rule, summed over the files",
+            "unit": "s",
+            "sum": sum(scan
+                .rule_stats
+                .iter()
+                .map(|stats| {
+                    point(
+                        vec![attribute("rule.id", &stats.rule_id)],
+                        json!(stats.elapsed.as_secs_f64()),
+                    )
+                })
+                .collect()),
+        }),
+    ]
+}
+
+/// A `scan` span for the run, with a child span per rule. Rules run interleaved across
+/// files and threads, so each child starts with the scan and lasts the summed time spent
+/// in that rule.
+fn spans(scan: &Scan) -> Vec<Value> {
+    let trace_id = random_hex(2);
+    let scan_span_id = random_hex(1);
+    let errors = scan
+        .results
+        .iter()
+        .flat_map(|r| &r.violations)
+        .filter(|v| v.severity == Severity::Error)
+        .count();
+    let violations: usize = scan.results.iter().map(|r| r.violations.len()).sum();
+
+    let mut spans = vec![json!({
+        "traceId": trace_id,
+        "spanId": scan_span_id,
+        "name": "scan",
+        "kind": 1,
+        "startTimeUnixNano": nanos(scan.started_at),
+        "endTimeUnixNano": nanos(scan.finished_at),
+        "attributes": [
+            attribute("adf_guardian.project", &scan.project.display().to_string()),
+            int_attribute("adf_guardian.files_scanned", scan.results.len()),
+            int_attribute("adf_guardian.errors", errors),
+            int_attribute("adf_guardian.warnings", violations - errors),
+        ],
+    })];
+    for stats in scan.rule_stats {
+        let start = scan.started_at.timestamp_nanos_opt().unwrap_or_default();
+        let end = start.saturating_add(stats.elapsed.as_nanos().try_into().unwrap_or(i64::MAX));
+        spans.push(json!({
+            "traceId": trace_id,
+            "spanId": random_hex(1),
+            "parentSpanId": scan_span_id,
+            "name": format!("rule {}", stats.rule_id),
+            "kind": 1,
+            "startTimeUnixNano": start.to_string(),
+            "endTimeUnixNano": end.to_string(),
+            "attributes": [
+                attribute("rule.id", &stats.rule_id),
+                int_attribute("adf_guardian.files_checked", stats.files_checked),
+                int_attribute("adf_guardian.violations", stats.violations),
+            ],
+        }));
+    }
+    spans
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// 64-bit integers are strings in the JSON encoding of OTLP.
+fn int_attribute(key: &str, value: usize) -> Value {
+    json!({ "key": key, "value": { "intValue": value.to_string() } })
+}
+
+fn nanos(time: DateTime<Utc>) -> String {
+    time.timestamp_nanos_opt().unwrap_or_default().to_string()
+}
+
+fn duration_seconds(scan: &Scan) -> f64 {
+    (scan.finished_at - scan.started_at)
+        .to_std()
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// `words` random 64-bit words in hex: 2 for a trace id, 1 for a span id.
+fn random_hex(words: usize) -> String {
+    (0..words)
+        .map(|_| format!("{:016x}", RandomState::new().hash_one(Utc::now())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Violation;
+    use std::time::Duration;
+
+    #[test]
+    fn test_metrics() {
+        let violation = |rule_id: &str, severity| Violation {
+            rule_id: rule_id.to_string(),
+            severity,
+            ..Default::default()
+        };
+        let results = [FileResult {
+            file: "pipeline/pl_a.json".to_string(),
+            violations: vec![
+                violation("naming", Severity::Error),
+                violation("naming", Severity::Error),
+                violation("timeout", Severity::Warning),
+            ],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+        let rule_stats = [RuleStats {
+            rule_id: "naming".to_string(),
+            files_checked: 1,
+            violations: 2,
+            elapsed: Duration::from_millis(5),
+        }];
+        let started_at = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let scan = Scan {
+            project: Path::new("factory"),
+            started_at,
+            finished_at: started_at + Duration::from_secs(3),
+            results: &results,
+            rule_stats: &rule_stats,
+        };
+
+        let metrics = metrics(&scan);
+        let duration = &metrics[0]["histogram"]["dataPoints"][0];
+        assert_eq!(duration["sum"], 3.0);
+        assert_eq!(
+            duration["bucketCounts"],
+            json!(["0", "0", "0", "1", "0", "0", "0", "0", "0"])
+        );
+        assert_eq!(
+            metrics[2]["sum"]["dataPoints"][0],
+            json!({
+                "attributes": [attribute("rule.id", "naming"), attribute("severity", "Error")],
+                "startTimeUnixNano": "1700000000000000000",
+                "timeUnixNano": "1700000003000000000",
+                "asInt": "2",
+            })
+        );
+
+        let spans = spans(&scan);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[1]["parentSpanId"], spans[0]["spanId"]);
+        assert_eq!(spans[1]["endTimeUnixNano"], "1700000000005000000");
+        assert_eq!(spans[0]["traceId"].as_str().unwrap().len(), 32);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::config::{Config, Rule};
+use anyhow::{Context, Result, anyhow, bail};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Built-in rule packs, referenced from the config through `extends`.
+const BUILTIN_PACKS: &[(&str, &str)] = &[
+    ("adf:copy", include_str!("packs/copy.yaml")),
+    ("adf:web-security", include_str!("packs/web_security.yaml")),
+    ("adf:compute", include_str!("packs/compute.yaml")),
+    ("adf:triggers", include_str!("packs/triggers.yaml")),
+    ("adf:key-vault", include_str!("packs/key_vault.yaml")),
+];
+
+/// Folder of the packs pulled from registries, next to the config file.
+pub const PACK_CACHE: &str = ".adf-guardian/packs";
+
+/// A rule pack published to an OCI registry, e.g. `oci://ghcr.io/contoso/adf-rules:1.2.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OciReference {
+    /// Host, with its port if any.
+    pub registry: String,
+    pub repository: String,
+    pub tag: String,
+}
+
+impl OciReference {
+    /// `None` unless `text` is an `oci://` reference; the tag defaults to `latest`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (registry, path) = text.strip_prefix("oci://")?.split_once('/')?;
+        let (repository, tag) = match path.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+            _ => (path, "latest"),
+        };
+        (!registry.is_empty() && !repository.is_empty() && !tag.is_empty()).then(|| OciReference {
+            registry: registry.to_string(),
+            repository: repository.to_string(),
+            tag: tag.to_string(),
+        })
+    }
+
+    /// Where the pack is kept for the configs in `config_dir`.
+    pub fn cache_path(&self, config_dir: &Path) -> PathBuf {
+        config_dir
+            .join(PACK_CACHE)
+            .join(self.registry.replace(':', "_"))
+            .join(&self.repository)
+            .join(format!("{}.yaml", self.tag))
+    }
+}
+
+impl fmt::Display for OciReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "oci://{}/{}:{}",
+            self.registry, self.repository, self.tag
+        )
+    }
+}
+
+/// The rules of the pack `name`: a built-in pack, or an `oci://` pack pulled into the pack
+/// cache of `config_dir` (by `adf-guardian pack add`, or when the CLI loads the config).
+pub fn load(name: &str, config_dir: Option<&Path>) -> Result<Vec<Rule>> {
+    if let Some(reference) = OciReference::parse(name) {
+        let config_dir =
+            config_dir.ok_or_else(|| anyhow!("Pack '{}' needs a config file on disk", name))?;
+        let path = reference.cache_path(config_dir);
+        let source = fs::read(&path).with_context(|| {
+            format!(
+                "Pack '{}' isn't downloaded yet (run `adf-guardian pack add {}`)",
+                name, name
+            )
+        })?;
+        let pack = Config::from_yaml(&source)
+            .with_context(|| format!("Failed to parse rule pack '{}'", name))?;
+        return Ok(pack.rules);
+    }
+    let Some((_, source)) = BUILTIN_PACKS.iter().find(|(n, _)| *n == name) else {
+        bail!("Unknown rule pack '{}'", name);
+    };
+
+    let pack: Config = serde_yaml::from_str(source)
+        .with_context(|| format!("Failed to parse built-in rule pack '{}'", name))?;
+
+    Ok(pack.rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_packs_parse() {
+        for (name, _) in BUILTIN_PACKS {
+            let rules = load(name, None).unwrap();
+            assert!(!rules.is_empty(), "pack '{}' has no rules", name);
+        }
+    }
+
+    #[test]
+    fn test_unknown_pack() {
+        assert!(load("adf:does-not-exist", None).is_err());
+    }
+
+    #[test]
+    fn test_oci_reference() {
+        let reference =
+            OciReference::parse("oci://localhost:5000/contoso/adf-rules:1.2.0").unwrap();
+        assert_eq!(reference.registry, "localhost:5000");
+        assert_eq!(reference.repository, "contoso/adf-rules");
+        assert_eq!(reference.tag, "1.2.0");
+        assert_eq!(
+            reference.cache_path(Path::new("ci")),
+            Path::new("ci/.adf-guardian/packs/localhost_5000/contoso/adf-rules/1.2.0.yaml")
+        );
+        let latest = OciReference::parse("oci://ghcr.io/contoso/adf-rules").unwrap();
+        assert_eq!(latest.to_string(), "oci://ghcr.io/contoso/adf-rules:latest");
+        assert!(OciReference::parse("adf:copy").is_none());
+        assert!(load("oci://ghcr.io/contoso/adf-rules:1.2.0", None).is_err());
+    }
+}
@@ -0,0 +1,575 @@
+use crate::azure;
+use adf_guardian::config::{AssetMatcher, OnMissing, Rule, RuleScope, Severity, Validation};
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::io::{self, Write};
+
+/// Azure Policy definitions of a naming standard for factory assets, after the Cloud
+/// Adoption Framework: its abbreviations where it has one (`adf`, `pep`), and the usual
+/// `pl_`, `ds_`, ... prefixes for the other assets.
+pub const CAF_NAMING: &str = include_str!("presets/caf-naming.json");
+
+/// The rules converted from policy definitions, and the definitions that couldn't be.
+#[derive(Debug, Default, Serialize)]
+pub struct Import {
+    pub rules: Vec<Rule>,
+    /// Each definition without a rule, with the reason.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+/// Converts the Azure Policy definitions of `documents`, each one definition, a list of
+/// them, or the output of `az policy definition list` (`{ "value": [...] }`). A definition
+/// becomes rules when it denies (`Error`) or audits (`Warning`) resources of Data Factory
+/// types by their name or tags; tags are read from annotations like `CostCenter:4100`.
+pub fn convert(documents: &[Value]) -> Result<Import> {
+    let definitions: Vec<&Value> = documents
+        .iter()
+        .flat_map(|document| match document {
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(map) if map.get("value").is_some_and(Value::is_array) => {
+                map["value"].as_array().into_iter().flatten().collect()
+            }
+            _ => vec![document],
+        })
+        .collect();
+    if definitions.is_empty() {
+        bail!("No policy definitions found");
+    }
+    let mut import = Import::default();
+    for definition in definitions {
+        let properties = definition.get("properties").unwrap_or(definition);
+        let name = definition["name"]
+            .as_str()
+            .or_else(|| properties["displayName"].as_str())
+            .unwrap_or("policy");
+        let rules = match convert_definition(name, properties) {
+            Ok(rules) => rules,
+            Err(e) => {
+                import.skipped.push(format!("'{}': {:#}", name, e));
+                continue;
+            }
+        };
+        for mut rule in rules {
+            let id = rule.id.clone();
+            let mut suffix = 1;
+            while import.rules.iter().any(|r| r.id == rule.id) {
+                suffix += 1;
+                rule.id = format!("{}-{}", id, suffix);
+            }
+            import.rules.push(rule);
+        }
+    }
+    Ok(import)
+}
+
+/// The rules of one definition: one per way a resource can be non-compliant.
+fn convert_definition(name: &str, properties: &Value) -> Result<Vec<Rule>> {
+    if properties.get("policyDefinitions").is_some() {
+        bail!("policy sets refer to their definitions by id; import the definitions instead");
+    }
+    let policy_rule = properties
+        .get("policyRule")
+        .ok_or_else(|| anyhow!("no policyRule"))?;
+    let parameters = Parameters(properties.get("parameters"));
+    let effect = parameters.resolve(&policy_rule["then"]["effect"])?;
+    let severity = match effect.as_str().map(str::to_ascii_lowercase).as_deref() {
+        Some("deny") => Severity::Error,
+        Some("audit") => Severity::Warning,
+        Some("disabled") => bail!("the effect is Disabled"),
+        Some(other) => bail!("the {} effect can't be checked on a file", other),
+        None => bail!("no effect"),
+    };
+
+    let mut assets: Option<Vec<String>> = None;
+    let mut conditions = Vec::new();
+    let parts: Vec<&Value> = match policy_rule.get("if") {
+        Some(Value::Object(condition)) => match condition.get("allOf") {
+            Some(Value::Array(all)) => all.iter().collect(),
+            _ => vec![&policy_rule["if"]],
+        },
+        _ => bail!("no if condition"),
+    };
+    for part in parts {
+        match asset_types(&parameters, part)? {
+            Some(_) if assets.is_some() => bail!("it has several conditions on the type"),
+            Some(types) => assets = Some(types),
+            None => conditions.push(part),
+        }
+    }
+    let [condition] = conditions[..] else {
+        bail!("only a condition on the type and one on the name or a tag can be converted");
+    };
+    let alternatives: Vec<&Value> = match condition.get("anyOf") {
+        Some(Value::Array(any)) => any.iter().collect(),
+        _ => vec![condition],
+    };
+    let validations = alternatives
+        .into_iter()
+        .map(|alternative| requirement(&parameters, alternative))
+        .collect::<Result<Vec<_>>>()?;
+
+    let asset = match assets {
+        Some(mut types) if types.len() == 1 => AssetMatcher::Single(types.remove(0)),
+        Some(types) => AssetMatcher::List(types),
+        None => AssetMatcher::List(azure::asset_folders().map(str::to_string).collect()),
+    };
+    let id = slug(name);
+    let count = validations.len();
+    Ok(validations
+        .into_iter()
+        .enumerate()
+        .map(|(index, validation)| Rule {
+            id: if count == 1 {
+                id.clone()
+            } else {
+                format!("{}-{}", id, index + 1)
+            },
+            asset: asset.clone(),
+            description: properties["displayName"]
+                .as_str()
+                .or_else(|| properties["description"].as_str())
+                .map(str::to_string),
+            help_url: None,
+            severity,
+            promote: None,
+            tags: vec!["azure-policy".to_string()],
+            scope: RuleScope::File,
+            report_value: true,
+            when: None,
+            validate: validation,
+        })
+        .collect())
+}
+
+/// The asset folders a condition on the resource `type` limits a definition to, or `None`
+/// for a condition on another field.
+fn asset_types(parameters: &Parameters, condition: &Value) -> Result<Option<Vec<String>>> {
+    let field = parameters.resolve(&condition["field"])?;
+    if !field
+        .as_str()
+        .is_some_and(|f| f.eq_ignore_ascii_case("type"))
+    {
+        return Ok(None);
+    }
+    let types = if let Some(value) = condition.get("equals") {
+        vec![parameters.resolve(value)?]
+    } else if let Some(value) = condition.get("in") {
+        match parameters.resolve(value)? {
+            Value::Array(types) => types,
+            _ => bail!("the types of `in` are not a list"),
+        }
+    } else if let Some(value) = condition.get("like") {
+        let pattern = parameters.resolve(value)?;
+        if !pattern
+            .as_str()
+            .is_some_and(|p| p.eq_ignore_ascii_case("Microsoft.DataFactory/factories/*"))
+        {
+            bail!(
+                "the type pattern {} is not the Data Factory assets",
+                pattern
+            );
+        }
+        return Ok(Some(azure::asset_folders().map(str::to_string).collect()));
+    } else {
+        bail!("only `equals`, `in` and `like` conditions on the type can be converted");
+    };
+    types
+        .iter()
+        .map(|kind| {
+            let kind = kind.as_str().unwrap_or_default();
+            asset_folder(kind)
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("the type {} is not a Data Factory asset", kind))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// The folder Git integration stores resources of the ARM `kind` in.
+fn asset_folder(kind: &str) -> Option<&'static str> {
+    let kind = kind.to_ascii_lowercase();
+    let child = kind.strip_prefix("microsoft.datafactory/factories")?;
+    match child {
+        "" => Some("factory"),
+        "/managedvirtualnetworks/managedprivateendpoints" => Some("managedPrivateEndpoint"),
+        "/managedvirtualnetworks" => Some("managedVirtualNetwork"),
+        child => azure::COLLECTIONS
+            .iter()
+            .find(|(collection, _)| {
+                Some(collection.to_ascii_lowercase().as_str()) == child.strip_prefix('/')
+            })
+            .map(|(_, folder)| *folder),
+    }
+}
+
+/// What a compliant resource satisfies, given a `condition` that makes it non-compliant.
+fn requirement(parameters: &Parameters, condition: &Value) -> Result<Validation> {
+    let mut leaf = condition;
+    let mut negated = false;
+    while let Some(inner) = leaf.get("not") {
+        negated = !negated;
+        leaf = inner;
+    }
+    let field = parameters.resolve(&leaf["field"])?;
+    let Some(field) = field.as_str() else {
+        bail!("only conditions on a field can be converted");
+    };
+    let Some((operator, mut holds, value)) = OPERATORS
+        .iter()
+        .find_map(|&(key, operator, holds)| leaf.get(key).map(|value| (operator, holds, value)))
+    else {
+        bail!(
+            "the condition on {} uses an operator that can't be converted",
+            field
+        );
+    };
+    let value = parameters.resolve(value)?;
+    if operator == "exists" && !truthy(&value) {
+        holds = !holds;
+    }
+    // A non-compliant resource satisfies `condition`, so a compliant one doesn't.
+    let expected = !(holds ^ negated);
+
+    if field.eq_ignore_ascii_case("name") {
+        return name_requirement(operator, &value, expected);
+    }
+    let Some(tag) = tag_name(field) else {
+        bail!("the field {} has no equivalent in the factory JSON", field);
+    };
+    tag_requirement(tag, operator, &value, expected)
+}
+
+/// The operators of conditions that can be converted, with the operator they negate, or
+/// themselves, and whether the condition holds when that one does.
+const OPERATORS: [(&str, &str, bool); 11] = [
+    ("notEquals", "equals", false),
+    ("notLike", "like", false),
+    ("notMatchInsensitively", "matchInsensitively", false),
+    ("notMatch", "match", false),
+    ("notIn", "in", false),
+    ("equals", "equals", true),
+    ("like", "like", true),
+    ("matchInsensitively", "matchInsensitively", true),
+    ("match", "match", true),
+    ("in", "in", true),
+    ("exists", "exists", true),
+];
+
+fn name_requirement(operator: &str, value: &Value, expected: bool) -> Result<Validation> {
+    let (guard, params) = match operator {
+        "equals" | "in" => {
+            let values = strings(value)?;
+            let mode = if expected { "Allow" } else { "Deny" };
+            (
+                "AllowedValues",
+                json!({ "values": values, "mode": mode, "case_sensitive": false }),
+            )
+        }
+        "exists" => bail!("names always exist"),
+        pattern => pattern_match(pattern_regex(pattern, value, "")?, expected),
+    };
+    Ok(Validation {
+        target: "$.name".to_string(),
+        guard: guard.to_string(),
+        params,
+        params_by: None,
+        on_missing: OnMissing::Pass,
+    })
+}
+
+/// A tag is an annotation `<tag>:<value>`. As in Azure Policy, a missing tag fails a
+/// requirement on its value.
+fn tag_requirement(tag: &str, operator: &str, value: &Value, expected: bool) -> Result<Validation> {
+    // A character class per character, which the I-Regexp of JSONPath filters accepts.
+    let literal: String = tag
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c.to_string()
+            } else if c == '\'' {
+                "\\'".to_string()
+            } else {
+                format!("[{}]", c)
+            }
+        })
+        .collect();
+    let target = format!("$.properties.annotations[?match(@, '{}:.*')]", literal);
+    let prefix = format!("{}:", regex::escape(tag));
+    let (guard, params) = match operator {
+        "exists" => ("Exists", json!({ "should_exist": expected })),
+        "equals" | "in" => {
+            let values: Vec<String> = strings(value)?
+                .iter()
+                .map(|value| regex::escape(value))
+                .collect();
+            let regex = format!("(?i)^{}(?:{})$", prefix, values.join("|"));
+            pattern_match(regex, expected)
+        }
+        pattern => pattern_match(pattern_regex(pattern, value, &prefix)?, expected),
+    };
+    Ok(Validation {
+        target,
+        guard: guard.to_string(),
+        params,
+        params_by: None,
+        on_missing: if expected {
+            OnMissing::Fail
+        } else {
+            OnMissing::Pass
+        },
+    })
+}
+
+/// The tag of a field like `tags['CostCenter']`, `tags[CostCenter]` or `tags.CostCenter`.
+fn tag_name(field: &str) -> Option<&str> {
+    if let Some(rest) = field.strip_prefix("tags.") {
+        return Some(rest);
+    }
+    let inner = field.strip_prefix("tags[")?.strip_suffix(']')?;
+    Some(inner.trim_matches('\''))
+}
+
+/// The regex of a `like` pattern (`*` for any text, ignoring case), or of a `match` pattern
+/// (`#` for a digit, `?` for a letter, `.` for any character), after the regex `prefix`.
+fn pattern_regex(operator: &str, value: &Value, prefix: &str) -> Result<String> {
+    let Some(pattern) = value.as_str() else {
+        bail!("the pattern of `{}` is not a string", operator);
+    };
+    let body = match operator {
+        "like" => {
+            let parts: Vec<String> = pattern.split('*').map(regex::escape).collect();
+            parts.join(".*")
+        }
+        "match" | "matchInsensitively" => pattern
+            .chars()
+            .map(|c| match c {
+                '#' => "[0-9]".to_string(),
+                '?' => "[a-zA-Z]".to_string(),
+                '.' => ".".to_string(),
+                c => regex::escape(&c.to_string()),
+            })
+            .collect(),
+        other => bail!("the `{}` operator can't be converted", other),
+    };
+    let flags = if operator == "match" { "" } else { "(?i)" };
+    Ok(format!("{}^{}{}$", flags, prefix, body))
+}
+
+/// `PatternMatch` params for values that must match `regex`, or must not.
+fn pattern_match(regex: String, expected: bool) -> (&'static str, Value) {
+    let params = if expected {
+        json!({ "regex": regex })
+    } else {
+        json!({ "regex": regex, "negative": true })
+    };
+    ("PatternMatch", params)
+}
+
+fn strings(value: &Value) -> Result<Vec<String>> {
+    let values = match value {
+        Value::Array(values) => values.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(text) => Ok(text.clone()),
+            Value::Number(number) => Ok(number.to_string()),
+            other => Err(anyhow!("expected strings, found {}", other)),
+        })
+        .collect()
+}
+
+/// `true` and `"true"`, as `exists` takes both.
+fn truthy(value: &Value) -> bool {
+    value.as_bool().unwrap_or_else(|| {
+        value
+            .as_str()
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+    })
+}
+
+/// A rule id from the name of a definition, e.g. `require-costcenter-tag`.
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// The `parameters` of a definition, whose default values stand for them.
+struct Parameters<'a>(Option<&'a Value>);
+
+impl Parameters<'_> {
+    /// `value`, with an ARM expression like `[parameters('tagName')]` or
+    /// `[concat('tags[', parameters('tagName'), ']')]` evaluated.
+    fn resolve(&self, value: &Value) -> Result<Value> {
+        let Some(text) = value.as_str() else {
+            return Ok(value.clone());
+        };
+        if let Some(escaped) = text.strip_prefix("[[") {
+            return Ok(Value::String(format!("[{}", escaped)));
+        }
+        let Some(expression) = text.strip_prefix('[').and_then(|e| e.strip_suffix(']')) else {
+            return Ok(value.clone());
+        };
+        let mut rest = expression.trim();
+        let value = self.evaluate(&mut rest)?;
+        if !rest.trim().is_empty() {
+            bail!("the expression {} can't be evaluated", text);
+        }
+        Ok(value)
+    }
+
+    /// Evaluates the call or string literal at the start of `rest`, then skips it.
+    fn evaluate(&self, rest: &mut &str) -> Result<Value> {
+        *rest = rest.trim_start();
+        if let Some(literal) = rest.strip_prefix('\'') {
+            let mut text = String::new();
+            let mut chars = literal.char_indices().peekable();
+            while let Some((index, c)) = chars.next() {
+                if c != '\'' {
+                    text.push(c);
+                } else if chars.peek().is_some_and(|(_, next)| *next == '\'') {
+                    chars.next();
+                    text.push('\'');
+                } else {
+                    *rest = &literal[index + 1..];
+                    return Ok(Value::String(text));
+                }
+            }
+            bail!("unterminated string in an expression");
+        }
+        let open = rest
+            .find('(')
+            .ok_or_else(|| anyhow!("the expression {} can't be evaluated", rest))?;
+        let function = rest[..open].trim().to_string();
+        *rest = &rest[open + 1..];
+        let mut args = Vec::new();
+        loop {
+            *rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(')') {
+                *rest = after;
+                break;
+            }
+            args.push(self.evaluate(rest)?);
+            *rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                *rest = after;
+            }
+        }
+        match (function.as_str(), args.as_slice()) {
+            ("parameters", [Value::String(name)]) => self
+                .0
+                .and_then(|parameters| parameters.get(name))
+                .and_then(|parameter| parameter.get("defaultValue"))
+                .cloned()
+                .ok_or_else(|| anyhow!("the parameter '{}' has no default value", name)),
+            ("concat", args) => Ok(Value::String(
+                args.iter()
+                    .map(|arg| match arg {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect(),
+            )),
+            (function, _) => bail!("the {}() function can't be evaluated", function),
+        }
+    }
+}
+
+pub fn write_human(out: &mut dyn Write, import: &Import) -> io::Result<()> {
+    #[derive(Serialize)]
+    struct Rules<'a> {
+        rules: &'a [Rule],
+    }
+
+    for skipped in &import.skipped {
+        writeln!(out, "# Skipped {}", skipped)?;
+    }
+    let yaml = serde_yaml::to_string(&Rules {
+        rules: &import.rules,
+    })
+    .map_err(io::Error::other)?;
+    write!(out, "{}", yaml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let document = json!({ "value": [
+            {
+                "name": "Pipeline naming",
+                "properties": {
+                    "displayName": "Pipelines start with pl_",
+                    "parameters": { "effect": { "type": "String", "defaultValue": "Audit" } },
+                    "policyRule": {
+                        "if": { "allOf": [
+                            { "field": "type", "equals": "Microsoft.DataFactory/factories/pipelines" },
+                            { "not": { "field": "name", "like": "pl_*" } }
+                        ] },
+                        "then": { "effect": "[parameters('effect')]" }
+                    }
+                }
+            },
+            {
+                "name": "cost-center",
+                "properties": {
+                    "parameters": { "tagName": { "type": "String", "defaultValue": "CostCenter" } },
+                    "policyRule": {
+                        "if": { "anyOf": [
+                            { "field": "[concat('tags[', parameters('tagName'), ']')]", "exists": "false" },
+                            { "field": "tags['env']", "in": ["sandbox"] }
+                        ] },
+                        "then": { "effect": "deny" }
+                    }
+                }
+            },
+            {
+                "name": "storage",
+                "properties": { "policyRule": {
+                    "if": { "field": "type", "equals": "Microsoft.Storage/storageAccounts" },
+                    "then": { "effect": "deny" }
+                } }
+            }
+        ] });
+        let import = convert(&[document]).unwrap();
+        let ids: Vec<&str> = import.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["pipeline-naming", "cost-center-1", "cost-center-2"]);
+        assert_eq!(import.skipped.len(), 1);
+
+        let naming = &import.rules[0];
+        assert_eq!(naming.severity, Severity::Warning);
+        assert_eq!(naming.asset.names(), ["pipeline"]);
+        assert_eq!(naming.validate.params, json!({ "regex": "(?i)^pl_.*$" }));
+
+        let tag = &import.rules[1].validate;
+        assert_eq!(
+            tag.target,
+            "$.properties.annotations[?match(@, 'CostCenter:.*')]"
+        );
+        assert_eq!(tag.params, json!({ "should_exist": true }));
+        assert_eq!(tag.on_missing, OnMissing::Fail);
+        let env = &import.rules[2].validate;
+        assert_eq!(
+            env.params,
+            json!({ "regex": "(?i)^env:(?:sandbox)$", "negative": true })
+        );
+        assert_eq!(env.on_missing, OnMissing::Pass);
+
+        assert_eq!(
+            pattern_regex("match", &json!("st###.?"), "").unwrap(),
+            "^st[0-9][0-9][0-9].[a-zA-Z]$"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::config::{Config, Rule, Severity};
+use crate::engine::ScanReport;
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// What one rule would report on the project, for `preview`.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct Impact {
+    pub rule_id: String,
+    pub severity: Severity,
+    pub files_checked: usize,
+    pub files_failed: usize,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+/// The rules named by `specs`: the rules of a YAML file in the config format when the spec
+/// is a path to one, otherwise the configured rule with that id. Later rules replace
+/// earlier ones with the same id.
+pub fn select_rules(config: &Config, specs: &[String]) -> Result<Vec<Rule>> {
+    let mut rules: Vec<Rule> = Vec::new();
+    for spec in specs {
+        let found = if Path::new(spec).is_file() {
+            Config::load(spec)
+                .with_context(|| format!("Failed to load the rules in {:?}", spec))?
+                .rules
+        } else {
+            let rule = config
+                .rules
+                .iter()
+                .find(|rule| &rule.id == spec)
+                .ok_or_else(|| anyhow!("'{}' is neither a rule id nor a rules file", spec))?;
+            vec![rule.clone()]
+        };
+        for rule in found {
+            rules.retain(|r| r.id != rule.id);
+            rules.push(rule);
+        }
+    }
+    Ok(rules)
+}
+
+/// `config` with only `rules`: no project checks, required assets or strict checks, so the
+/// scan reports nothing else.
+pub fn config_with(config: &Config, rules: Vec<Rule>) -> Config {
+    Config {
+        rules,
+        project: Default::default(),
+        require: vec![],
+        strict: Default::default(),
+        projects: Default::default(),
+        ..config.clone()
+    }
+}
+
+/// The impact of each rule of `rules`, in their order.
+pub fn summarize(rules: &[Rule], report: &ScanReport) -> Vec<Impact> {
+    rules
+        .iter()
+        .map(|rule| {
+            let files_checked = report
+                .rule_stats
+                .iter()
+                .find(|stats| stats.rule_id == rule.id)
+                .map_or(0, |stats| stats.files_checked);
+            let mut impact = Impact {
+                rule_id: rule.id.clone(),
+                severity: rule.severity,
+                files_checked,
+                files_failed: 0,
+                errors: 0,
+                warnings: 0,
+            };
+            let mut failed = HashSet::new();
+            for violation in report
+                .results
+                .iter()
+                .flat_map(|result| &result.violations)
+                .filter(|violation| violation.rule_id == rule.id)
+            {
+                failed.insert(&violation.file);
+                match violation.severity {
+                    Severity::Error => impact.errors += 1,
+                    Severity::Warning => impact.warnings += 1,
+                }
+            }
+            impact.files_failed = failed.len();
+            impact
+        })
+        .collect()
+}
+
+pub fn write_human(out: &mut dyn Write, impacts: &[Impact]) -> io::Result<()> {
+    for impact in impacts {
+        writeln!(out, "{} ({})", impact.rule_id.bold(), impact.severity)?;
+        writeln!(
+            out,
+            "  {} of {} file(s) would fail: {} error(s), {} warning(s)",
+            impact.files_failed, impact.files_checked, impact.errors, impact.warnings
+        )?;
+    }
+    let (errors, warnings) = impacts
+        .iter()
+        .fold((0, 0), |(e, w), i| (e + i.errors, w + i.warnings));
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{} rule(s) would report {} error(s) and {} warning(s)",
+        impacts.len(),
+        errors,
+        warnings
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, RuleStats, Violation};
+
+    #[test]
+    fn test_preview() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    severity: Error
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+  - id: "retries"
+    asset: "pipeline"
+    validate: { target: "$.properties.retries", guard: "Range", params: { max: 3 } }
+"#,
+        )
+        .unwrap();
+        let rules = select_rules(&config, &["naming".to_string()]).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert!(select_rules(&config, &["missing".to_string()]).is_err());
+        let preview = config_with(&config, rules.clone());
+        assert_eq!(preview.rules.len(), 1);
+
+        let violation = |file: &str| Violation {
+            rule_id: "naming".to_string(),
+            file: file.to_string(),
+            severity: Severity::Error,
+            ..Default::default()
+        };
+        let report = ScanReport {
+            results: vec![FileResult {
+                file: "pipeline/a.json".to_string(),
+                violations: vec![violation("pipeline/a.json"), violation("pipeline/a.json")],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            }],
+            suppressed: vec![],
+            rule_stats: vec![RuleStats {
+                rule_id: "naming".to_string(),
+                files_checked: 4,
+                ..Default::default()
+            }],
+            diagnostics: vec![],
+        };
+        let impacts = summarize(&rules, &report);
+        assert_eq!(
+            impacts,
+            [Impact {
+                rule_id: "naming".to_string(),
+                severity: Severity::Error,
+                files_checked: 4,
+                files_failed: 1,
+                errors: 2,
+                warnings: 0,
+            }]
+        );
+
+        let mut out = Vec::new();
+        write_human(&mut out, &impacts).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("1 of 4 file(s) would fail: 2 error(s), 0 warning(s)"));
+    }
+}
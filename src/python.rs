@@ -0,0 +1,128 @@
+//! The `pyadf_guardian` Python module, so governance scripts can scan a factory without
+//! running the CLI and parsing its output. Built with `maturin build --release` (see
+//! `pyproject.toml`).
+
+use crate::config::Config;
+use crate::engine::{self, GuardRegistry};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyList, PyString};
+use std::path::Path;
+
+/// A broken rule, as returned by `scan` and `validate_json`.
+#[pyclass(name = "Violation", module = "pyadf_guardian", frozen, get_all)]
+struct PyViolation {
+    rule_id: String,
+    file: String,
+    message: String,
+    /// `"Error"` or `"Warning"`.
+    severity: String,
+    actual_value: Option<String>,
+    help_url: Option<String>,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+#[pymethods]
+impl PyViolation {
+    fn __repr__(&self) -> String {
+        format!(
+            "Violation(rule_id={:?}, file={:?}, severity={:?}, message={:?})",
+            self.rule_id, self.file, self.severity, self.message
+        )
+    }
+}
+
+impl From<engine::Violation> for PyViolation {
+    fn from(v: engine::Violation) -> Self {
+        PyViolation {
+            rule_id: v.rule_id,
+            file: v.file,
+            message: v.message,
+            severity: v.severity.to_string(),
+            actual_value: v.actual_value,
+            help_url: v.help_url,
+            line: v.line,
+            column: v.column,
+        }
+    }
+}
+
+/// Raises `ValueError` for rules with an unknown guard or invalid params, like the CLI
+/// does without `--lenient`.
+fn check_rules(config: &Config) -> PyResult<()> {
+    let problems = engine::check_guards(config, &GuardRegistry::default());
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(PyValueError::new_err(format!(
+        "Invalid rules:\n  {}",
+        problems.join("\n  ")
+    )))
+}
+
+/// Scans every JSON file under `project_path` with the rules of the config file at
+/// `config_path`, and returns the violations sorted by file.
+#[pyfunction]
+fn scan(py: Python<'_>, project_path: &str, config_path: &str) -> PyResult<Vec<PyViolation>> {
+    let config =
+        Config::load(config_path).map_err(|e| PyValueError::new_err(format!("{:#}", e)))?;
+    check_rules(&config)?;
+
+    let report = py
+        .detach(|| crate::run_scan(&config, Path::new(project_path)))
+        .map_err(|e| PyRuntimeError::new_err(format!("{:#}", e)))?;
+    Ok(report
+        .results
+        .into_iter()
+        .flat_map(|r| r.violations)
+        .map(PyViolation::from)
+        .collect())
+}
+
+/// Checks one document (a dict, or a JSON string) as if it were a file in the `asset`
+/// folder. `rules` is a config: its YAML text, a dict, or just the list of rules.
+#[pyfunction]
+#[pyo3(signature = (obj, rules, *, asset = "pipeline"))]
+fn validate_json(
+    py: Python<'_>,
+    obj: &Bound<'_, PyAny>,
+    rules: &Bound<'_, PyAny>,
+    asset: &str,
+) -> PyResult<Vec<PyViolation>> {
+    let json = py.import("json")?;
+    let to_json = |value: &Bound<'_, PyAny>| -> PyResult<String> {
+        if let Ok(text) = value.cast::<PyString>() {
+            return Ok(text.to_string());
+        }
+        json.call_method1("dumps", (value,))?.extract()
+    };
+
+    // JSON is valid YAML, so dicts and lists go through the same parser as a config file.
+    let config = if rules.cast::<PyList>().is_ok() {
+        format!("{{\"rules\": {}}}", to_json(rules)?)
+    } else {
+        to_json(rules)?
+    };
+    let config = Config::from_yaml(config.as_bytes())
+        .map_err(|e| PyValueError::new_err(format!("{:#}", e)))?;
+    check_rules(&config)?;
+
+    let document = to_json(obj)?;
+    let path = Path::new(asset).join("document.json");
+    let result = engine::check_source(&config, &GuardRegistry::default(), &path, &document)
+        .map_err(|e| PyValueError::new_err(format!("Invalid JSON document: {}", e)))?;
+    Ok(result
+        .violations
+        .into_iter()
+        .map(PyViolation::from)
+        .collect())
+}
+
+#[pymodule]
+fn pyadf_guardian(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyViolation>()?;
+    m.add_function(wrap_pyfunction!(scan, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_json, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use adf_guardian::engine::{self, locator};
+use anyhow::{Context, Result, anyhow};
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Values longer than this are cut in the human output.
+const MAX_VALUE_WIDTH: usize = 120;
+
+/// A node a JSONPath selects, printed by `query`.
+#[derive(Debug, Serialize)]
+pub struct Match {
+    pub file: String,
+    /// Normalized path of the node, e.g. `$['properties']['activities'][0]['name']`.
+    pub path: String,
+    /// JSON pointer of the node, as in the `pointer` of violations.
+    pub pointer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    pub value: Value,
+}
+
+/// The nodes `expression` selects in each of `files`, which are read the way a scan reads
+/// them (any byte order mark, UTF-16, and comments with `jsonc`).
+pub fn run(expression: &str, files: &[PathBuf], jsonc: bool) -> Result<Vec<Match>> {
+    let path = JsonPath::parse(expression)
+        .map_err(|e| anyhow!("Invalid JSONPath '{}': {}", expression, e))?;
+    let mut matches = Vec::new();
+    for file in files {
+        let bytes = fs::read(file).with_context(|| format!("Failed to read {:?}", file))?;
+        let source =
+            engine::decode(bytes).map_err(|e| anyhow!("Failed to read {:?}: {}", file, e))?;
+        let source = if jsonc {
+            engine::strip_jsonc(&source)
+        } else {
+            source
+        };
+        let json: Value =
+            serde_json::from_str(&source).with_context(|| format!("Failed to parse {:?}", file))?;
+        matches.extend(select(&path, &file.to_string_lossy(), &source, &json));
+    }
+    Ok(matches)
+}
+
+/// The nodes `path` selects in `json`, parsed from the `source` of `file`.
+pub fn select(path: &JsonPath, file: &str, source: &str, json: &Value) -> Vec<Match> {
+    path.query_located(json)
+        .iter()
+        .map(|node| {
+            let pointer = node.location().to_json_pointer();
+            let location = locator::locate(source, &pointer).filter(|_| !pointer.is_empty());
+            Match {
+                file: file.to_string(),
+                path: node.location().to_string(),
+                pointer,
+                line: location.as_ref().map(|l| l.line),
+                column: location.as_ref().map(|l| l.column),
+                value: node.node().clone(),
+            }
+        })
+        .collect()
+}
+
+/// `value` as one line of JSON, cut to `width` characters.
+pub fn short_value(value: &Value, width: usize) -> String {
+    let text = value.to_string();
+    if text.chars().count() <= width {
+        return text;
+    }
+    text.chars().take(width - 3).collect::<String>() + "..."
+}
+
+pub fn write_human(out: &mut dyn Write, matches: &[Match]) -> io::Result<()> {
+    for m in matches {
+        let location = match (m.line, m.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", m.file, line, column),
+            _ => m.file.clone(),
+        };
+        writeln!(out, "{} {}", location.dimmed(), m.path.bold())?;
+        writeln!(out, "  {}", short_value(&m.value, MAX_VALUE_WIDTH))?;
+    }
+    let mut files: Vec<&str> = matches.iter().map(|m| m.file.as_str()).collect();
+    files.dedup();
+    writeln!(
+        out,
+        "{} node(s) selected in {} file(s)",
+        matches.len(),
+        files.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let source = r#"{
+  "name": "pl_copy",
+  "properties": {
+    "activities": [
+      { "name": "Wait", "type": "Wait" },
+      { "name": "Copy data", "type": "Copy" }
+    ]
+  }
+}"#;
+        let json: Value = serde_json::from_str(source).unwrap();
+        let path = JsonPath::parse(r#"$..activities[?(@.type=="Copy")].name"#).unwrap();
+        let matches = select(&path, "pipeline/pl_copy.json", source, &json);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "$['properties']['activities'][1]['name']");
+        assert_eq!(matches[0].pointer, "/properties/activities/1/name");
+        assert_eq!((matches[0].line, matches[0].column), (Some(6), Some(17)));
+        assert_eq!(matches[0].value, "Copy data");
+
+        assert_eq!(short_value(&json["name"], 6), "\"pl...");
+    }
+}
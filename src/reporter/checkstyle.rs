@@ -0,0 +1,83 @@
+use super::{ReportFormat, display_path, message_with_path};
+use crate::{config::Severity, engine};
+use std::io::{self, Write};
+
+/// Checkstyle XML, understood by most CI servers and code review tools.
+pub struct CheckstyleReport;
+
+impl ReportFormat for CheckstyleReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(out, r#"<checkstyle version="4.3">"#)?;
+
+        for result in results.iter().filter(|r| !r.violations.is_empty()) {
+            writeln!(
+                out,
+                r#"  <file name="{}">"#,
+                escape_xml(&display_path(&result.file))
+            )?;
+            for v in &result.violations {
+                let severity = match v.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                };
+                let column = v
+                    .column
+                    .map(|c| format!(r#" column="{}""#, c))
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    r#"    <error line="{}"{} severity="{}" message="{}" source="adf-guardian.{}"/>"#,
+                    v.line.unwrap_or(1),
+                    column,
+                    severity,
+                    escape_xml(&message_with_path(v)),
+                    escape_xml(&v.rule_id)
+                )?;
+            }
+            writeln!(out, "  </file>")?;
+        }
+
+        writeln!(out, "</checkstyle>")
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_checkstyle_report() {
+        let results = vec![FileResult {
+            file: "./pipeline/pl_a.json".to_string(),
+            violations: vec![Violation {
+                rule_id: "naming".to_string(),
+                file: "./pipeline/pl_a.json".to_string(),
+                message: "Names must match '^pl_' & <rules>".to_string(),
+                severity: Severity::Warning,
+                ..Default::default()
+            }],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+
+        let mut out = Vec::new();
+        CheckstyleReport.write(&mut out, &results).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+
+        assert!(xml.contains(r#"<file name="pipeline/pl_a.json">"#));
+        assert!(xml.contains(r#"severity="warning""#));
+        assert!(xml.contains("Names must match &apos;^pl_&apos; &amp; &lt;rules&gt;"));
+        assert!(xml.contains(r#"source="adf-guardian.naming""#));
+    }
+}
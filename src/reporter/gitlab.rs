@@ -0,0 +1,104 @@
+use super::{ReportFormat, display_path, message_with_path};
+use crate::{config::Severity, engine};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// GitLab Code Quality report (a subset of the Code Climate JSON format).
+pub struct GitlabReport;
+
+#[derive(Serialize)]
+struct Issue<'a> {
+    description: Cow<'a, str>,
+    check_name: &'a str,
+    fingerprint: String,
+    severity: &'static str,
+    location: Location,
+}
+
+#[derive(Serialize)]
+struct Location {
+    path: String,
+    lines: Lines,
+}
+
+#[derive(Serialize)]
+struct Lines {
+    begin: usize,
+}
+
+impl ReportFormat for GitlabReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let issues: Vec<Issue> = results
+            .iter()
+            .flat_map(|r| &r.violations)
+            .map(|v| {
+                let path = display_path(&v.file);
+                Issue {
+                    description: message_with_path(v),
+                    check_name: &v.rule_id,
+                    fingerprint: fingerprint(v, &path),
+                    severity: match v.severity {
+                        Severity::Error => "major",
+                        Severity::Warning => "minor",
+                    },
+                    location: Location {
+                        path,
+                        lines: Lines {
+                            begin: v.line.unwrap_or(1),
+                        },
+                    },
+                }
+            })
+            .collect();
+
+        serde_json::to_writer_pretty(&mut *out, &issues)?;
+        writeln!(out)
+    }
+}
+
+/// GitLab tracks issues across pipelines by fingerprint, so it must be stable between runs.
+fn fingerprint(v: &engine::Violation, path: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(v.rule_id.as_bytes());
+    hasher.update(path.as_bytes());
+    if let Some(value) = &v.actual_value {
+        hasher.update(value.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_gitlab_report() {
+        let violation = |value: &str| Violation {
+            rule_id: "naming".to_string(),
+            file: "./pipeline/pl_a.json".to_string(),
+            message: "Names must start with 'pl_'".to_string(),
+            severity: Severity::Error,
+            actual_value: Some(value.to_string()),
+            ..Default::default()
+        };
+        let results = vec![FileResult {
+            file: "./pipeline/pl_a.json".to_string(),
+            violations: vec![violation("\"a\""), violation("\"b\"")],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+
+        let mut out = Vec::new();
+        GitlabReport.write(&mut out, &results).unwrap();
+        let issues: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+        assert_eq!(issues[0]["check_name"], "naming");
+        assert_eq!(issues[0]["severity"], "major");
+        assert_eq!(issues[0]["location"]["path"], "pipeline/pl_a.json");
+        assert_ne!(issues[0]["fingerprint"], issues[1]["fingerprint"]);
+    }
+}
@@ -0,0 +1,178 @@
+use super::{ReportFormat, display_path};
+use crate::{config::Severity, engine};
+use std::io::{self, Write};
+
+/// Standalone HTML page with a summary dashboard, one collapsible section per file
+/// and severity filters. No external assets, so it can be attached as a CI artifact.
+pub struct HtmlReport;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Roboto, sans-serif; margin: 2rem; color: #1f2328; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.15rem; margin-top: 1.5rem; }
+.summary { display: flex; gap: 1rem; margin: 1rem 0 1.5rem; }
+.card { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.75rem 1.25rem; min-width: 8rem; }
+.card .count { font-size: 1.75rem; font-weight: 600; }
+.card.error .count { color: #cf222e; }
+.card.warning .count { color: #9a6700; }
+.filters { margin-bottom: 1rem; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin-bottom: 0.5rem; }
+summary { cursor: pointer; padding: 0.5rem 0.75rem; font-family: monospace; }
+table { border-collapse: collapse; width: 100%; }
+td, th { text-align: left; padding: 0.4rem 0.75rem; border-top: 1px solid #d0d7de; vertical-align: top; }
+.badge { border-radius: 1rem; padding: 0.1rem 0.5rem; font-size: 0.8rem; color: #fff; }
+.badge.error { background: #cf222e; }
+.badge.warning { background: #9a6700; }
+code { font-size: 0.85rem; word-break: break-all; }
+body.hide-error tr.error, body.hide-warning tr.warning { display: none; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll('.filters input').forEach(function (input) {
+  input.addEventListener('change', function () {
+    document.body.classList.toggle('hide-' + input.value, !input.checked);
+  });
+});
+"#;
+
+impl ReportFormat for HtmlReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let count = |severity: Severity| {
+            results
+                .iter()
+                .flat_map(|r| &r.violations)
+                .filter(|v| v.severity == severity)
+                .count()
+        };
+        let failed_files = results.iter().filter(|r| !r.violations.is_empty()).count();
+
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, r#"<html lang="en"><head><meta charset="utf-8">"#)?;
+        writeln!(out, "<title>adf-guardian report</title>")?;
+        writeln!(out, "<style>{}</style></head><body>", STYLE)?;
+        writeln!(
+            out,
+            "<h1>⛊ adf-guardian v{}</h1>",
+            env!("CARGO_PKG_VERSION")
+        )?;
+
+        writeln!(out, r#"<div class="summary">"#)?;
+        for (class, label, value) in [
+            ("", "Files scanned", results.len()),
+            ("", "Files with findings", failed_files),
+            ("error", "Errors", count(Severity::Error)),
+            ("warning", "Warnings", count(Severity::Warning)),
+        ] {
+            writeln!(
+                out,
+                r#"<div class="card {}"><div class="count">{}</div>{}</div>"#,
+                class, value, label
+            )?;
+        }
+        writeln!(out, "</div>")?;
+
+        writeln!(
+            out,
+            r#"<div class="filters">Show: <label><input type="checkbox" value="error" checked> Errors</label> <label><input type="checkbox" value="warning" checked> Warnings</label></div>"#
+        )?;
+
+        for (project, results) in super::by_project(results) {
+            if let Some(name) = project {
+                writeln!(out, "<h2>{}</h2>", escape_html(name))?;
+            }
+            for result in results.iter().filter(|r| !r.violations.is_empty()) {
+                writeln!(
+                    out,
+                    "<details open><summary>{} ({})</summary><table>",
+                    escape_html(&display_path(&result.file)),
+                    result.violations.len()
+                )?;
+                writeln!(
+                    out,
+                    "<tr><th>Severity</th><th>Rule</th><th>Message</th><th>Path</th><th>Actual value</th></tr>"
+                )?;
+                for v in &result.violations {
+                    let class = match v.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    };
+                    let rule = match &v.help_url {
+                        Some(url) => format!(
+                            r#"<a href="{}">{}</a>"#,
+                            escape_html(url),
+                            escape_html(&v.rule_id)
+                        ),
+                        None => escape_html(&v.rule_id),
+                    };
+                    writeln!(
+                        out,
+                        r#"<tr class="{0}"><td><span class="badge {0}">{1}</span></td><td>{2}</td><td>{3}</td><td><code>{4}</code></td><td><code>{5}</code></td></tr>"#,
+                        class,
+                        v.severity,
+                        rule,
+                        escape_html(&v.message),
+                        escape_html(v.json_path.as_deref().unwrap_or_default()),
+                        escape_html(v.actual_value.as_deref().unwrap_or_default())
+                    )?;
+                }
+                writeln!(out, "</table></details>")?;
+            }
+        }
+
+        if failed_files == 0 {
+            writeln!(out, "<p>No violations found.</p>")?;
+        }
+
+        writeln!(out, "<script>{}</script></body></html>", SCRIPT)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_html_report() {
+        let results = vec![
+            FileResult {
+                file: "./pipeline/pl_a.json".to_string(),
+                violations: vec![Violation {
+                    rule_id: "naming".to_string(),
+                    file: "./pipeline/pl_a.json".to_string(),
+                    message: "Names must match <pl_>".to_string(),
+                    severity: Severity::Error,
+                    help_url: Some("https://wiki/naming".to_string()),
+                    ..Default::default()
+                }],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            },
+            FileResult {
+                file: "./pipeline/pl_b.json".to_string(),
+                violations: vec![],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            },
+        ];
+
+        let mut out = Vec::new();
+        HtmlReport.write(&mut out, &results).unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(html.contains("<summary>pipeline/pl_a.json (1)</summary>"));
+        assert!(!html.contains("pl_b.json"));
+        assert!(html.contains(r#"<a href="https://wiki/naming">naming</a>"#));
+        assert!(html.contains("Names must match &lt;pl_&gt;"));
+    }
+}
@@ -0,0 +1,632 @@
+use super::snippet::{self, Snippet};
+use super::{ReportFormat, ReportOptions, StreamFormat};
+use crate::cli::{GroupBy, SortBy};
+use crate::{
+    config::Severity,
+    engine::{self, Violation},
+};
+use colored::*;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::Arc;
+
+/// The text of the files the scan kept it for, such as a Git revision or stdin, which
+/// differs from what is on disk. Snippets of the other files are read from disk.
+fn scanned_sources<'a>(
+    results: impl IntoIterator<Item = &'a engine::FileResult>,
+) -> HashMap<&'a str, Option<Arc<str>>> {
+    results
+        .into_iter()
+        .filter_map(|r| Some((r.file.as_str(), Some(r.source.clone()?))))
+        .collect()
+}
+
+/// Header of the violations of files no team owns.
+const NO_OWNER: &str = "(no owner)";
+
+pub struct HumanReport {
+    pub options: ReportOptions,
+}
+
+struct Symbols {
+    logo: &'static str,
+    file: &'static str,
+    error: &'static str,
+    warning: &'static str,
+    pass: &'static str,
+    separator: &'static str,
+}
+
+const UNICODE_SYMBOLS: Symbols = Symbols {
+    logo: "⛊ ",
+    file: "›",
+    error: "×",
+    warning: "•",
+    pass: "✓",
+    separator: "·",
+};
+
+// Used when stdout is not a terminal, e.g. CI logs that don't render Unicode well.
+const ASCII_SYMBOLS: Symbols = Symbols {
+    logo: "",
+    file: ">",
+    error: "x",
+    warning: "!",
+    pass: "+",
+    separator: "|",
+};
+
+impl ReportFormat for HumanReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        self.write_start(out)?;
+        for (project, results) in super::by_project(results) {
+            if let Some(name) = project {
+                writeln!(out, "{} {}", "Project".bold(), name.bold())?;
+                writeln!(out)?;
+            }
+            if self.options.summary_only {
+                self.write_rule_counts(out, results)?;
+            } else {
+                self.write_violations(out, results)?;
+            }
+        }
+        self.write_end(out, results)
+    }
+}
+
+impl StreamFormat for HumanReport {
+    fn write_start(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{}{} v{}",
+            self.symbols().logo.bold(),
+            "adf-guardian".bold(),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        writeln!(out)
+    }
+
+    fn write_file(&self, out: &mut dyn Write, result: &engine::FileResult) -> io::Result<()> {
+        self.write_violations(out, std::slice::from_ref(result))
+    }
+
+    fn write_end(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let symbols = self.symbols();
+        let count = |results: &[engine::FileResult], severity: Severity| {
+            results
+                .iter()
+                .flat_map(|r| &r.violations)
+                .filter(|v| v.severity == severity)
+                .count()
+        };
+        let total_errors_count = count(results, Severity::Error);
+        let total_warnings_count = count(results, Severity::Warning);
+
+        for (project, results) in super::by_project(results) {
+            if let Some(name) = project {
+                writeln!(
+                    out,
+                    "{}: {} scanned {} {} failed {} {} warning(s)",
+                    name.bold(),
+                    results.len(),
+                    symbols.separator.dimmed(),
+                    count(results, Severity::Error),
+                    symbols.separator.dimmed(),
+                    count(results, Severity::Warning)
+                )?;
+            }
+        }
+
+        let owners = super::by_owner(results);
+        if owners.iter().any(|(owner, _)| owner.is_some()) {
+            for (owner, violations) in &owners {
+                let count = |severity: Severity| {
+                    violations.iter().filter(|v| v.severity == severity).count()
+                };
+                writeln!(
+                    out,
+                    "{}: {} failed {} {} warning(s)",
+                    owner.unwrap_or(NO_OWNER).bold(),
+                    count(Severity::Error),
+                    symbols.separator.dimmed(),
+                    count(Severity::Warning)
+                )?;
+            }
+        }
+
+        let elapsed = self.options.start_time.elapsed().as_secs_f64();
+
+        let mut summary_parts = vec![format!("{} scanned", results.len())];
+
+        if total_errors_count > 0 {
+            summary_parts.push(format!("{} failed", total_errors_count).red().to_string());
+        } else {
+            summary_parts.push("0 failed".to_string().green().to_string());
+        }
+
+        if total_warnings_count > 0 {
+            summary_parts.push(
+                format!("{} warning(s)", total_warnings_count)
+                    .yellow()
+                    .to_string(),
+            );
+        } else {
+            summary_parts.push("0 warning(s)".green().to_string());
+        }
+
+        summary_parts.push(format!("{:.2}s", elapsed));
+
+        writeln!(
+            out,
+            "Done: {}",
+            summary_parts.join(&format!(" {} ", symbols.separator.dimmed()))
+        )
+    }
+}
+
+impl HumanReport {
+    fn symbols(&self) -> &'static Symbols {
+        if self.options.unicode {
+            &UNICODE_SYMBOLS
+        } else {
+            &ASCII_SYMBOLS
+        }
+    }
+
+    fn write_violations(
+        &self,
+        out: &mut dyn Write,
+        results: &[engine::FileResult],
+    ) -> io::Result<()> {
+        if self.options.verbose {
+            return self.write_verbose(out, results);
+        }
+
+        let symbols = self.symbols();
+        let mut sources = scanned_sources(results);
+        for (header, violations) in self.groups(results) {
+            writeln!(out, "{} {}", symbols.file.bold(), header.bold())?;
+            for v in violations {
+                self.write_violation(out, v, &mut sources)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every scanned file with its violations, followed by a pass marker for each rule
+    /// that was evaluated against it and found nothing. Files are listed by path with `--sort`.
+    fn write_verbose(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let symbols = self.symbols();
+        let mut results: Vec<&engine::FileResult> = results.iter().collect();
+        if self.options.sort.is_some() {
+            results.sort_by(|a, b| a.file.cmp(&b.file));
+        }
+
+        let mut sources = scanned_sources(results.iter().copied());
+        for result in results {
+            writeln!(out, "{} {}", symbols.file.bold(), result.file.bold())?;
+            for v in &result.violations {
+                self.write_violation(out, v, &mut sources)?;
+            }
+
+            let passed: Vec<&String> = result
+                .rules_checked
+                .iter()
+                .filter(|id| !result.violations.iter().any(|v| &v.rule_id == *id))
+                .collect();
+            for rule_id in &passed {
+                writeln!(out, "  {} [{}] passed", symbols.pass.green(), rule_id)?;
+            }
+            if result.rules_checked.is_empty() && result.violations.is_empty() {
+                writeln!(out, "  {}", "No rules apply to this file".dimmed())?;
+            }
+            // Each violation already ends with a blank line.
+            if !passed.is_empty() || result.violations.is_empty() {
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_violation<'a>(
+        &self,
+        out: &mut dyn Write,
+        v: &'a Violation,
+        sources: &mut HashMap<&'a str, Option<Arc<str>>>,
+    ) -> io::Result<()> {
+        let group_by = self.options.group_by;
+        let symbols = self.symbols();
+        let (rule_symbol, rule_id, message) = match v.severity {
+            Severity::Error => (
+                symbols.error.bright_red(),
+                v.rule_id.bright_red(),
+                v.message.bright_red(),
+            ),
+            Severity::Warning => (
+                symbols.warning.yellow(),
+                v.rule_id.yellow(),
+                v.message.yellow(),
+            ),
+        };
+
+        match group_by {
+            GroupBy::Rule => writeln!(out, "  {} {}", rule_symbol, v.file)?,
+            GroupBy::File | GroupBy::Severity | GroupBy::Owner => {
+                writeln!(out, "  {} [{}] {}", rule_symbol, rule_id, message)?
+            }
+        }
+
+        if matches!(group_by, GroupBy::Severity | GroupBy::Owner) {
+            writeln!(out, "    {} {}", "File:".dimmed(), v.file.dimmed())?;
+        }
+
+        if let Some(val) = &v.actual_value {
+            writeln!(
+                out,
+                "    {} {}",
+                "Actual value:".dimmed(),
+                val.to_string().dimmed()
+            )?;
+        }
+
+        if let Some(path) = &v.json_path {
+            writeln!(out, "    {} {}", "Path:".dimmed(), path.dimmed())?;
+        }
+
+        if let (Some(line), Some(column)) = (v.line, v.column) {
+            let source = sources.entry(&v.file).or_insert_with(|| {
+                let source = fs::read(&v.file).ok().and_then(|b| engine::decode(b).ok());
+                source.map(Arc::from)
+            });
+            if let Some(snippet) = source
+                .as_deref()
+                .and_then(|s| snippet::extract(s, line, column))
+            {
+                write_snippet(out, &v.file, column, &snippet, v.severity)?;
+            }
+        }
+        writeln!(out)
+    }
+
+    /// One line per failing rule with its violation and file counts, most frequent first.
+    fn write_rule_counts(
+        &self,
+        out: &mut dyn Write,
+        results: &[engine::FileResult],
+    ) -> io::Result<()> {
+        let mut counts: Vec<(&Violation, usize, usize)> = Vec::new();
+        for result in results {
+            let mut rules_in_file: Vec<&str> = Vec::new();
+            for v in &result.violations {
+                let first_in_file = !rules_in_file.contains(&v.rule_id.as_str());
+                if first_in_file {
+                    rules_in_file.push(&v.rule_id);
+                }
+                match counts
+                    .iter_mut()
+                    .find(|(first, _, _)| first.rule_id == v.rule_id)
+                {
+                    Some((_, violations, files)) => {
+                        *violations += 1;
+                        *files += usize::from(first_in_file);
+                    }
+                    None => counts.push((v, 1, 1)),
+                }
+            }
+        }
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.rule_id.cmp(&b.0.rule_id)));
+
+        let symbols = self.symbols();
+        for (v, violations, files) in &counts {
+            let (rule_symbol, rule_id) = match v.severity {
+                Severity::Error => (symbols.error.bright_red(), v.rule_id.bright_red()),
+                Severity::Warning => (symbols.warning.yellow(), v.rule_id.yellow()),
+            };
+            writeln!(
+                out,
+                "  {} [{}] {} violation(s) in {} file(s)",
+                rule_symbol, rule_id, violations, files
+            )?;
+        }
+        if !counts.is_empty() {
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Groups violations under a header according to `--group-by`. Groups keep the order in
+    /// which they were first seen unless `--sort` is given, which orders groups and violations.
+    fn groups<'a>(&self, results: &'a [engine::FileResult]) -> Vec<(String, Vec<&'a Violation>)> {
+        let mut groups: Vec<(String, Vec<&Violation>)> = Vec::new();
+
+        for v in results.iter().flat_map(|r| &r.violations) {
+            let header = match self.options.group_by {
+                GroupBy::File => v.file.clone(),
+                GroupBy::Rule => format!("[{}] {}", v.rule_id, v.message),
+                GroupBy::Severity => v.severity.to_string(),
+                GroupBy::Owner => v.owner.clone().unwrap_or_else(|| NO_OWNER.to_string()),
+            };
+            match groups.iter_mut().find(|(h, _)| *h == header) {
+                Some((_, violations)) => violations.push(v),
+                None => groups.push((header, vec![v])),
+            }
+        }
+
+        if let Some(sort) = self.options.sort {
+            let key = |v: &Violation| match sort {
+                SortBy::File => (v.file.clone(), v.rule_id.clone(), v.severity),
+                SortBy::Rule => (v.rule_id.clone(), v.file.clone(), v.severity),
+                SortBy::Severity => (String::new(), String::new(), v.severity),
+            };
+            for (_, violations) in groups.iter_mut() {
+                violations.sort_by_key(|v| key(v));
+            }
+            // Groups are ordered by their own key: errors before warnings, otherwise by name.
+            match self.options.group_by {
+                GroupBy::Severity => groups.sort_by_key(|(_, vs)| vs.first().map(|v| v.severity)),
+                GroupBy::File | GroupBy::Rule | GroupBy::Owner => {
+                    groups.sort_by(|(a, _), (b, _)| a.cmp(b))
+                }
+            }
+        }
+
+        groups
+    }
+}
+
+/// Renders the offending lines in the style of compiler diagnostics:
+///
+/// ```text
+///     --> pipeline/pl_a.json:3:13
+///    2 |   "name": "pl_a",
+///    3 |   "retries": 10,
+///      |              ^^
+/// ```
+fn write_snippet(
+    out: &mut dyn Write,
+    file: &str,
+    column: usize,
+    snippet: &Snippet,
+    severity: Severity,
+) -> io::Result<()> {
+    let width = snippet.lines.last().map_or(1, |(n, _)| n.to_string().len());
+    writeln!(
+        out,
+        "    {} {}:{}:{}",
+        "-->".dimmed(),
+        file,
+        snippet.line,
+        column
+    )?;
+
+    for (n, text) in &snippet.lines {
+        writeln!(
+            out,
+            "    {} {}",
+            format!("{:>width$} |", n, width = width).dimmed(),
+            text
+        )?;
+        if *n == snippet.line {
+            let carets = "^".repeat(snippet.caret_len);
+            let carets = match severity {
+                Severity::Error => carets.bright_red(),
+                Severity::Warning => carets.yellow(),
+            };
+            writeln!(
+                out,
+                "    {} {}{}",
+                format!("{:>width$} |", "", width = width).dimmed(),
+                " ".repeat(snippet.caret_offset),
+                carets
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::FileResult;
+    use chrono::Utc;
+    use std::time::Instant;
+
+    fn report(group_by: GroupBy, sort: Option<SortBy>) -> HumanReport {
+        HumanReport {
+            options: ReportOptions {
+                start_time: Instant::now(),
+                started_at: Utc::now(),
+                config_hash: String::new(),
+                group_by,
+                sort,
+                summary_only: false,
+                verbose: false,
+                unicode: true,
+                json_compat: false,
+                template: None,
+                suppressed: vec![],
+                diagnostics: vec![],
+            },
+        }
+    }
+
+    fn results() -> Vec<FileResult> {
+        let violation = |rule_id: &str, file: &str, severity| Violation {
+            rule_id: rule_id.to_string(),
+            file: file.to_string(),
+            message: format!("{} message", rule_id),
+            severity,
+            ..Default::default()
+        };
+        vec![
+            FileResult {
+                file: "b.json".to_string(),
+                violations: vec![
+                    violation("naming", "b.json", Severity::Warning),
+                    violation("security", "b.json", Severity::Error),
+                ],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            },
+            FileResult {
+                file: "a.json".to_string(),
+                violations: vec![violation("naming", "a.json", Severity::Warning)],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_group_by_rule() {
+        let results = results();
+        let groups = report(GroupBy::Rule, None).groups(&results);
+        let headers: Vec<&str> = groups.iter().map(|(h, _)| h.as_str()).collect();
+        assert_eq!(
+            headers,
+            vec!["[naming] naming message", "[security] security message"]
+        );
+        assert_eq!(groups[0].1.len(), 2);
+    }
+
+    #[test]
+    fn test_group_by_file_sorted() {
+        let results = results();
+        let groups = report(GroupBy::File, Some(SortBy::File)).groups(&results);
+        let headers: Vec<&str> = groups.iter().map(|(h, _)| h.as_str()).collect();
+        assert_eq!(headers, vec!["a.json", "b.json"]);
+    }
+
+    #[test]
+    fn test_group_by_severity_sorted() {
+        let results = results();
+        let groups = report(GroupBy::Severity, Some(SortBy::Severity)).groups(&results);
+        assert_eq!(groups[0].0, "Error");
+        assert_eq!(groups[1].1.len(), 2);
+    }
+
+    #[test]
+    fn test_group_by_owner() {
+        let mut results = results();
+        results[0].violations[1].owner = Some("security-team".to_string());
+        let groups = report(GroupBy::Owner, None).groups(&results);
+        let headers: Vec<&str> = groups.iter().map(|(h, _)| h.as_str()).collect();
+        assert_eq!(headers, vec!["(no owner)", "security-team"]);
+
+        let mut out = Vec::new();
+        report(GroupBy::Owner, None)
+            .write_end(&mut out, &results)
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("security-team: 1 failed · 0 warning(s)"));
+        assert!(text.contains("(no owner): 0 failed · 2 warning(s)"));
+    }
+
+    #[test]
+    fn test_summary_only() {
+        let results = results();
+        let mut report = report(GroupBy::File, None);
+        report.options.summary_only = true;
+
+        let mut out = Vec::new();
+        report.write(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("2 violation(s) in 2 file(s)"));
+        assert!(text.contains("1 violation(s) in 1 file(s)"));
+        assert!(!text.contains("naming message"));
+    }
+
+    #[test]
+    fn test_verbose_lists_passed_rules() {
+        let mut results = results();
+        results[0].rules_checked = vec!["naming".to_string(), "security".to_string()];
+        results[1].rules_checked = vec!["naming".to_string(), "description".to_string()];
+        results.push(FileResult {
+            file: "c.json".to_string(),
+            violations: vec![],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        });
+        let mut report = report(GroupBy::File, Some(SortBy::File));
+        report.options.verbose = true;
+
+        let mut out = Vec::new();
+        report.write(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("[description] passed"));
+        assert!(!text.contains("[naming] passed"));
+        assert!(text.contains("c.json\n  No rules apply to this file"));
+        assert!(text.find("a.json").unwrap() < text.find("b.json").unwrap());
+    }
+
+    #[test]
+    fn test_projects() {
+        let mut results = results();
+        results[0].project = Some("sales".to_string());
+        results[1].project = Some("sales".to_string());
+        results.push(FileResult {
+            file: "c.json".to_string(),
+            violations: vec![],
+            rules_checked: vec![],
+            project: Some("hr".to_string()),
+            source: None,
+        });
+
+        let mut out = Vec::new();
+        report(GroupBy::File, None)
+            .write(&mut out, &results)
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(text.matches("Project sales").count(), 1);
+        assert!(text.contains("sales: 2 scanned · 1 failed · 2 warning(s)"));
+        assert!(text.contains("hr: 1 scanned · 0 failed · 0 warning(s)"));
+    }
+
+    #[test]
+    fn test_snippet_from_scanned_source() {
+        // Not the file on disk: a Git revision or the document piped on stdin.
+        let results = vec![FileResult {
+            file: "pipeline/not-on-disk.json".to_string(),
+            violations: vec![Violation {
+                rule_id: "retries".to_string(),
+                file: "pipeline/not-on-disk.json".to_string(),
+                message: "retries message".to_string(),
+                line: Some(3),
+                column: Some(14),
+                ..Default::default()
+            }],
+            rules_checked: vec![],
+            project: None,
+            source: Some("{\n  \"name\": \"pl_a\",\n  \"retries\": 10\n}".into()),
+        }];
+
+        let mut out = Vec::new();
+        report(GroupBy::File, None)
+            .write(&mut out, &results)
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("3 |   \"retries\": 10"));
+        assert!(text.contains("|              ^^"));
+    }
+
+    #[test]
+    fn test_ascii_symbols() {
+        let results = results();
+        let mut report = report(GroupBy::File, None);
+        report.options.unicode = false;
+
+        let mut out = Vec::new();
+        report.write(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.is_ascii());
+        assert!(text.contains("x [security] security message"));
+    }
+}
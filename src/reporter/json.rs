@@ -0,0 +1,244 @@
+use super::{ReportFormat, ReportOptions};
+use crate::{config::Severity, engine};
+use chrono::SecondsFormat;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// Version of the JSON envelope. Bump it whenever a field is removed or changes meaning.
+const SCHEMA_VERSION: u32 = 2;
+
+pub struct JsonReport {
+    pub options: ReportOptions,
+}
+
+/// The results model, also exposed to custom templates.
+#[derive(Serialize)]
+pub(super) struct Envelope<'a> {
+    schema_version: u32,
+    tool: Tool,
+    config_hash: &'a str,
+    scanned_at: String,
+    summary: Summary,
+    /// The summary of each factory of a multi-project scan.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    projects: Vec<ProjectSummary<'a>>,
+    /// Violation counts per team, when the config has `owners`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    owners: Vec<OwnerSummary<'a>>,
+    files: &'a [engine::FileResult],
+    /// Violations hidden by the suppressions of the config.
+    suppressed: &'a [engine::Violation],
+    /// Files and rules that couldn't be checked, e.g. unparseable JSON or a bad JSONPath.
+    diagnostics: &'a [engine::Diagnostic],
+}
+
+#[derive(Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Serialize)]
+struct ProjectSummary<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    summary: Summary,
+}
+
+#[derive(Serialize)]
+struct OwnerSummary<'a> {
+    /// `None` for the files no team owns.
+    team: Option<&'a str>,
+    errors: usize,
+    warnings: usize,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    files_scanned: usize,
+    files_with_violations: usize,
+    errors: usize,
+    warnings: usize,
+}
+
+impl ReportFormat for JsonReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let serialized = if self.options.json_compat {
+            let all_violations: Vec<&engine::Violation> =
+                results.iter().flat_map(|r| &r.violations).collect();
+            serde_json::to_string_pretty(&all_violations)
+        } else {
+            serde_json::to_string_pretty(&envelope(&self.options, results))
+        };
+        match serialized {
+            Ok(json_output) => writeln!(out, "{}", json_output),
+            Err(e) => write_json_error(out, &format!("Failed to serialize results to JSON: {}", e)),
+        }
+    }
+}
+
+pub(super) fn envelope<'a>(
+    options: &'a ReportOptions,
+    results: &'a [engine::FileResult],
+) -> Envelope<'a> {
+    Envelope {
+        schema_version: SCHEMA_VERSION,
+        tool: Tool {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+        },
+        config_hash: &options.config_hash,
+        scanned_at: options
+            .started_at
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        summary: summary(results),
+        projects: super::by_project(results)
+            .filter_map(|(project, results)| {
+                Some(ProjectSummary {
+                    name: project?,
+                    summary: summary(results),
+                })
+            })
+            .collect(),
+        owners: owner_summaries(results),
+        files: results,
+        suppressed: &options.suppressed,
+        diagnostics: &options.diagnostics,
+    }
+}
+
+fn owner_summaries(results: &[engine::FileResult]) -> Vec<OwnerSummary<'_>> {
+    let owners = super::by_owner(results);
+    if owners.iter().all(|(owner, _)| owner.is_none()) {
+        return vec![];
+    }
+    owners
+        .into_iter()
+        .map(|(team, violations)| {
+            let count = |severity| violations.iter().filter(|v| v.severity == severity).count();
+            OwnerSummary {
+                team,
+                errors: count(Severity::Error),
+                warnings: count(Severity::Warning),
+            }
+        })
+        .collect()
+}
+
+fn summary(results: &[engine::FileResult]) -> Summary {
+    let count = |severity: Severity| {
+        results
+            .iter()
+            .flat_map(|r| &r.violations)
+            .filter(|v| v.severity == severity)
+            .count()
+    };
+    Summary {
+        files_scanned: results.len(),
+        files_with_violations: results.iter().filter(|r| !r.violations.is_empty()).count(),
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+    }
+}
+
+pub fn write_json_error(out: &mut dyn Write, msg: &str) -> io::Result<()> {
+    let error_json = serde_json::json!({
+        "error": msg
+    });
+    writeln!(
+        out,
+        "{}",
+        serde_json::to_string_pretty(&error_json).unwrap()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::GroupBy;
+    use crate::engine::{FileResult, Violation};
+    use chrono::{TimeZone, Utc};
+    use serde_json::Value;
+    use std::time::Instant;
+
+    fn write(json_compat: bool, project: Option<&str>) -> Value {
+        let report = JsonReport {
+            options: ReportOptions {
+                start_time: Instant::now(),
+                started_at: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+                config_hash: "abc123".to_string(),
+                group_by: GroupBy::File,
+                sort: None,
+                summary_only: false,
+                verbose: false,
+                unicode: true,
+                json_compat,
+                template: None,
+                suppressed: vec![],
+                diagnostics: vec![engine::Diagnostic {
+                    file: Some("pipeline/broken.json".to_string()),
+                    rule_id: None,
+                    message: "Could not parse JSON".to_string(),
+                }],
+            },
+        };
+        let results = vec![
+            FileResult {
+                file: "pipeline/pl_a.json".to_string(),
+                violations: vec![Violation {
+                    rule_id: "naming".to_string(),
+                    file: "pipeline/pl_a.json".to_string(),
+                    severity: Severity::Warning,
+                    ..Default::default()
+                }],
+                rules_checked: vec![],
+                project: project.map(str::to_string),
+                source: None,
+            },
+            FileResult {
+                file: "pipeline/pl_b.json".to_string(),
+                violations: vec![],
+                rules_checked: vec![],
+                project: project.map(str::to_string),
+                source: None,
+            },
+        ];
+
+        let mut out = Vec::new();
+        report.write(&mut out, &results).unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn test_json_report() {
+        let json = write(false, None);
+        assert_eq!(json["schema_version"], 2);
+        assert_eq!(json["tool"]["name"], "adf-guardian");
+        assert_eq!(json["config_hash"], "abc123");
+        assert_eq!(json["scanned_at"], "2026-01-02T03:04:05Z");
+        assert_eq!(json["summary"]["files_scanned"], 2);
+        assert_eq!(json["summary"]["warnings"], 1);
+        assert_eq!(json["files"][0]["violations"][0]["rule_id"], "naming");
+        assert_eq!(json["suppressed"], Value::Array(vec![]));
+        assert_eq!(json["diagnostics"][0]["file"], "pipeline/broken.json");
+        assert!(json["diagnostics"][0].get("rule_id").is_none());
+        assert!(json.get("projects").is_none());
+
+        let json = write(false, Some("sales"));
+        assert_eq!(
+            json["projects"],
+            serde_json::json!([{
+                "name": "sales",
+                "files_scanned": 2,
+                "files_with_violations": 1,
+                "errors": 0,
+                "warnings": 1
+            }])
+        );
+        assert_eq!(json["files"][1]["project"], "sales");
+
+        let compat = write(true, None);
+        assert_eq!(compat.as_array().unwrap().len(), 1);
+        assert_eq!(compat[0]["rule_id"], "naming");
+    }
+}
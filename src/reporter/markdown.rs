@@ -0,0 +1,138 @@
+use super::{ReportFormat, display_path};
+use crate::{config::Severity, engine};
+use std::io::{self, Write};
+
+/// Compact Markdown meant to be posted as a pull request comment.
+pub struct MarkdownReport;
+
+impl ReportFormat for MarkdownReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let count = |severity: Severity| {
+            results
+                .iter()
+                .flat_map(|r| &r.violations)
+                .filter(|v| v.severity == severity)
+                .count()
+        };
+        let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+
+        writeln!(out, "### ⛊ adf-guardian v{}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "**{} scanned** · 🔴 {} error(s) · 🟡 {} warning(s)",
+            results.len(),
+            errors,
+            warnings
+        )?;
+
+        if errors + warnings == 0 {
+            writeln!(out)?;
+            return writeln!(out, "✅ No violations found.");
+        }
+
+        for (project, results) in super::by_project(results) {
+            // Files go one level below their project.
+            let file_heading = match project {
+                Some(name) => {
+                    writeln!(out)?;
+                    writeln!(out, "#### 📁 {}", name)?;
+                    if results.iter().all(|r| r.violations.is_empty()) {
+                        writeln!(out)?;
+                        writeln!(out, "✅ No violations found.")?;
+                    }
+                    "#####"
+                }
+                None => "####",
+            };
+            self.write_files(out, results, file_heading)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MarkdownReport {
+    fn write_files(
+        &self,
+        out: &mut dyn Write,
+        results: &[engine::FileResult],
+        heading: &str,
+    ) -> io::Result<()> {
+        for result in results.iter().filter(|r| !r.violations.is_empty()) {
+            writeln!(out)?;
+            writeln!(out, "{} `{}`", heading, display_path(&result.file))?;
+            writeln!(out)?;
+            writeln!(out, "| | Rule | Message | Path | Actual value |")?;
+            writeln!(out, "|---|---|---|---|---|")?;
+            for v in &result.violations {
+                let emoji = match v.severity {
+                    Severity::Error => "🔴",
+                    Severity::Warning => "🟡",
+                };
+                let rule = match &v.help_url {
+                    Some(url) => format!("[`{}`]({})", v.rule_id, url),
+                    None => format!("`{}`", v.rule_id),
+                };
+                let value = v
+                    .actual_value
+                    .as_deref()
+                    .map(|val| format!("`{}`", escape_cell(val)))
+                    .unwrap_or_default();
+                let path = v
+                    .json_path
+                    .as_deref()
+                    .map(|path| format!("`{}`", escape_cell(path)))
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} |",
+                    emoji,
+                    rule,
+                    escape_cell(&v.message),
+                    path,
+                    value
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_markdown_report() {
+        let results = vec![FileResult {
+            file: "./pipeline/pl_a.json".to_string(),
+            violations: vec![Violation {
+                rule_id: "naming".to_string(),
+                file: "./pipeline/pl_a.json".to_string(),
+                message: "Use pl_ | ds_ prefixes".to_string(),
+                severity: Severity::Warning,
+                actual_value: Some("\"a\"".to_string()),
+                json_path: Some("$.name".to_string()),
+                ..Default::default()
+            }],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+
+        let mut out = Vec::new();
+        MarkdownReport.write(&mut out, &results).unwrap();
+        let md = String::from_utf8(out).unwrap();
+
+        assert!(md.contains("**1 scanned** · 🔴 0 error(s) · 🟡 1 warning(s)"));
+        assert!(md.contains("#### `pipeline/pl_a.json`"));
+        assert!(md.contains("| 🟡 | `naming` | Use pl_ \\| ds_ prefixes | `$.name` | `\"a\"` |"));
+    }
+}
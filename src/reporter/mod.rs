@@ -0,0 +1,132 @@
+mod checkstyle;
+mod gitlab;
+mod html;
+mod human;
+mod json;
+mod markdown;
+mod ndjson;
+mod progress;
+mod snippet;
+mod stats;
+mod tap;
+mod teamcity;
+mod template;
+
+pub use progress::ScanProgress;
+pub use stats::{write_benchmark, write_stats};
+pub use template::{context as template_context, template_error};
+
+use crate::cli::{GroupBy, OutputFormat, SortBy};
+use crate::engine::{self, Diagnostic, FileResult, Violation};
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// An output format for scan results. Each format lives in its own module.
+pub trait ReportFormat {
+    fn write(&self, out: &mut dyn Write, results: &[FileResult]) -> io::Result<()>;
+}
+
+/// A format that can print each file's results as soon as it has been checked (`--stream`).
+pub trait StreamFormat: Sync {
+    fn write_start(&self, out: &mut dyn Write) -> io::Result<()>;
+    fn write_file(&self, out: &mut dyn Write, result: &FileResult) -> io::Result<()>;
+    fn write_end(&self, out: &mut dyn Write, results: &[FileResult]) -> io::Result<()>;
+}
+
+/// Presentation settings shared by the report formats.
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    pub start_time: Instant,
+    /// Wall-clock start of the scan, reported in JSON output.
+    pub started_at: DateTime<Utc>,
+    /// SHA-256 of the config file (see `Config::hash`).
+    pub config_hash: String,
+    pub group_by: GroupBy,
+    pub sort: Option<SortBy>,
+    pub summary_only: bool,
+    /// List every file and the rules that passed on it, not just the violations.
+    pub verbose: bool,
+    /// Use Unicode symbols (`⛊ › × •`) instead of ASCII in the human report.
+    pub unicode: bool,
+    /// Write the pre-v2 JSON report, a flat array of violations.
+    pub json_compat: bool,
+    /// Tera template rendered by the `template` format.
+    pub template: Option<PathBuf>,
+    /// Violations hidden by the suppressions of the config, listed by the JSON report.
+    pub suppressed: Vec<Violation>,
+    /// Files and rules the scan couldn't check, listed by the JSON report.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub fn for_format(format: OutputFormat, options: &ReportOptions) -> Box<dyn ReportFormat> {
+    let options = options.clone();
+    match format {
+        OutputFormat::Human => Box::new(human::HumanReport { options }),
+        OutputFormat::Json => Box::new(json::JsonReport { options }),
+        OutputFormat::Checkstyle => Box::new(checkstyle::CheckstyleReport),
+        OutputFormat::Gitlab => Box::new(gitlab::GitlabReport),
+        OutputFormat::Html => Box::new(html::HtmlReport),
+        OutputFormat::Markdown => Box::new(markdown::MarkdownReport),
+        OutputFormat::Ndjson => Box::new(ndjson::NdjsonReport),
+        OutputFormat::Teamcity => Box::new(teamcity::TeamcityReport),
+        OutputFormat::Tap => Box::new(tap::TapReport),
+        OutputFormat::Template => Box::new(template::TemplateReport { options }),
+    }
+}
+
+/// Returns the streaming variant of `format`, if it has one.
+pub fn for_stream(format: OutputFormat, options: &ReportOptions) -> Option<Box<dyn StreamFormat>> {
+    match format {
+        OutputFormat::Human => Some(Box::new(human::HumanReport {
+            options: options.clone(),
+        })),
+        OutputFormat::Ndjson => Some(Box::new(ndjson::NdjsonReport)),
+        _ => None,
+    }
+}
+
+pub fn print_json_error(msg: &str) {
+    // Nothing sensible is left to do if stdout itself is gone.
+    let _ = json::write_json_error(&mut io::stdout(), msg);
+}
+
+/// Consecutive results of the same project, with its name. Results of a multi-project
+/// scan are ordered by project, so each project comes up once.
+pub fn by_project(results: &[FileResult]) -> impl Iterator<Item = (Option<&str>, &[FileResult])> {
+    results
+        .chunk_by(|a, b| a.project == b.project)
+        .map(|group| (group[0].project.as_deref(), group))
+}
+
+/// The violations of `results` by the team owning their file, in team order; violations
+/// without an owner come last, under `None`.
+pub fn by_owner(results: &[FileResult]) -> Vec<(Option<&str>, Vec<&Violation>)> {
+    let mut owners: Vec<(Option<&str>, Vec<&Violation>)> = Vec::new();
+    for v in results.iter().flat_map(|r| &r.violations) {
+        let owner = v.owner.as_deref();
+        match owners.iter_mut().find(|(o, _)| *o == owner) {
+            Some((_, violations)) => violations.push(v),
+            None => owners.push((owner, vec![v])),
+        }
+    }
+    owners.sort_by_key(|(owner, _)| (owner.is_none(), *owner));
+    owners
+}
+
+/// The message of a violation followed by the JSONPath of its node, for formats that have
+/// no place of their own for it.
+pub fn message_with_path(v: &engine::Violation) -> Cow<'_, str> {
+    match &v.json_path {
+        Some(path) => Cow::Owned(format!("{} ({})", v.message, path)),
+        None => Cow::Borrowed(&v.message),
+    }
+}
+
+/// Paths relative to the scan root with forward slashes, as CI tools expect.
+pub fn display_path(file: &str) -> String {
+    let path = file.replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
@@ -0,0 +1,66 @@
+use super::{ReportFormat, StreamFormat};
+use crate::engine;
+use std::io::{self, Write};
+
+/// One compact JSON object per violation and line, for jq, Logstash or Log Analytics.
+/// On stdout it is streamed, so each file's violations are written as soon as it is checked.
+pub struct NdjsonReport;
+
+impl ReportFormat for NdjsonReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        results.iter().try_for_each(|r| self.write_file(out, r))
+    }
+}
+
+impl StreamFormat for NdjsonReport {
+    fn write_start(&self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_file(&self, out: &mut dyn Write, result: &engine::FileResult) -> io::Result<()> {
+        for v in &result.violations {
+            serde_json::to_writer(&mut *out, v)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn write_end(&self, _out: &mut dyn Write, _results: &[engine::FileResult]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Severity;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_ndjson_report() {
+        let violation = |rule_id: &str| Violation {
+            rule_id: rule_id.to_string(),
+            file: "pipeline/pl_a.json".to_string(),
+            message: "Line\nbreak".to_string(),
+            severity: Severity::Error,
+            ..Default::default()
+        };
+        let results = vec![FileResult {
+            file: "pipeline/pl_a.json".to_string(),
+            violations: vec![violation("naming"), violation("retries")],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+
+        let mut out = Vec::new();
+        NdjsonReport.write(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["rule_id"], "naming");
+        assert_eq!(first["message"], "Line\nbreak");
+    }
+}
@@ -0,0 +1,71 @@
+use super::StreamFormat;
+use crate::engine::{FileResult, ScanObserver};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::{self, Write};
+
+/// Drives the progress bar and, with `--stream`, prints each file's violations as it finishes.
+pub struct ScanProgress<'a> {
+    bar: ProgressBar,
+    stream: Option<&'a dyn StreamFormat>,
+}
+
+impl<'a> ScanProgress<'a> {
+    /// The bar is drawn on stderr only when `show_bar` is set and stderr is a terminal.
+    pub fn new(show_bar: bool, stream: Option<&'a dyn StreamFormat>) -> Self {
+        let bar = if show_bar {
+            let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
+            bar.set_style(
+                ProgressStyle::with_template("{spinner} [{bar:30}] {pos}/{len} files ({elapsed})")
+                    .expect("valid progress template")
+                    .progress_chars("=> "),
+            );
+            bar
+        } else {
+            ProgressBar::hidden()
+        };
+        ScanProgress { bar, stream }
+    }
+
+    pub fn start(&self) -> io::Result<()> {
+        match self.stream {
+            Some(stream) => stream.write_start(&mut io::stdout().lock()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn finish(&self, results: &[FileResult]) -> io::Result<()> {
+        self.bar.finish_and_clear();
+        match self.stream {
+            Some(stream) => stream.write_end(&mut io::stdout().lock(), results),
+            None => Ok(()),
+        }
+    }
+
+    fn print(&self, result: &FileResult) {
+        let Some(stream) = self.stream else { return };
+        self.bar.suspend(|| {
+            let mut out = io::stdout().lock();
+            // A closed stdout surfaces again when the summary is written.
+            let _ = stream
+                .write_file(&mut out, result)
+                .and_then(|_| out.flush());
+        });
+    }
+}
+
+impl ScanObserver for ScanProgress<'_> {
+    fn started(&self, total_files: usize) {
+        // Called once per factory when several are scanned.
+        self.bar
+            .set_length(self.bar.length().unwrap_or(0) + total_files as u64);
+    }
+
+    fn file_finished(&self, result: &FileResult) {
+        self.print(result);
+        self.bar.inc(1);
+    }
+
+    fn project_checked(&self, results: &[FileResult]) {
+        results.iter().for_each(|result| self.print(result));
+    }
+}
@@ -0,0 +1,78 @@
+/// A few source lines around a violation, with the span of the offending value.
+pub struct Snippet<'a> {
+    /// Line number and text of each line, including context lines.
+    pub lines: Vec<(usize, &'a str)>,
+    pub line: usize,
+    /// Character offset and length of the caret under `line`.
+    pub caret_offset: usize,
+    pub caret_len: usize,
+}
+
+const CONTEXT_LINES: usize = 1;
+
+pub fn extract(source: &str, line: usize, column: usize) -> Option<Snippet<'_>> {
+    let all_lines: Vec<&str> = source.lines().collect();
+    let text = *all_lines.get(line.checked_sub(1)?)?;
+    let caret_offset = column.checked_sub(1)?;
+    let value: String = text.chars().skip(caret_offset).collect();
+
+    let first = line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (line + CONTEXT_LINES).min(all_lines.len());
+    let lines = (first..=last).map(|n| (n, all_lines[n - 1])).collect();
+
+    Some(Snippet {
+        lines,
+        line,
+        caret_offset,
+        caret_len: value_len(&value).max(1),
+    })
+}
+
+/// Length in characters of the scalar value at the start of `text`. Objects and arrays
+/// usually span several lines, so only their opening bracket is underlined.
+fn value_len(text: &str) -> usize {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some('"') => {
+            let mut escaped = false;
+            for (i, c) in chars.enumerate() {
+                match c {
+                    '\\' if !escaped => escaped = true,
+                    '"' if !escaped => return i + 2,
+                    _ => escaped = false,
+                }
+            }
+            text.chars().count()
+        }
+        Some('{') | Some('[') => 1,
+        _ => text
+            .chars()
+            .take_while(|c| !matches!(c, ',' | '}' | ']') && !c.is_whitespace())
+            .count(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let source = "{\n  \"name\": \"pl_\\\"a\",\n  \"retries\": 10,\n  \"policy\": {}\n}";
+
+        let snippet = extract(source, 2, 11).unwrap();
+        assert_eq!(
+            snippet.lines.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!((snippet.caret_offset, snippet.caret_len), (10, 8));
+
+        let number = extract(source, 3, 14).unwrap();
+        assert_eq!(number.caret_len, 2);
+
+        let object = extract(source, 4, 13).unwrap();
+        assert_eq!(object.caret_len, 1);
+
+        assert!(extract(source, 9, 1).is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::engine::RuleStats;
+use crate::engine::bench::Benchmark;
+use colored::*;
+use std::io::{self, Write};
+
+/// Table of per-rule evaluation counts and timings for `--stats`, slowest rule first.
+pub fn write_stats(out: &mut dyn Write, stats: &[RuleStats]) -> io::Result<()> {
+    let mut stats: Vec<&RuleStats> = stats.iter().collect();
+    stats.sort_by(|a, b| {
+        b.elapsed
+            .cmp(&a.elapsed)
+            .then_with(|| a.rule_id.cmp(&b.rule_id))
+    });
+
+    let width = stats
+        .iter()
+        .map(|s| s.rule_id.len())
+        .chain(["Rule".len()])
+        .max()
+        .unwrap_or_default();
+
+    writeln!(out)?;
+    writeln!(out, "{}", "Rule statistics".bold())?;
+    writeln!(
+        out,
+        "  {:<width$}  {:>6}  {:>10}  {:>10}",
+        "Rule",
+        "Files",
+        "Violations",
+        "Time",
+        width = width
+    )?;
+    for s in stats {
+        writeln!(
+            out,
+            "  {:<width$}  {:>6}  {:>10}  {:>8.2}ms",
+            s.rule_id,
+            s.files_checked,
+            s.violations,
+            s.elapsed.as_secs_f64() * 1000.0,
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+/// Per-phase timings and per-rule costs for `bench`, costliest rule first.
+pub fn write_benchmark(out: &mut dyn Write, benchmark: &Benchmark) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} file(s), {:.1} MB, {} iteration(s)",
+        benchmark.files,
+        benchmark.bytes as f64 / (1024.0 * 1024.0),
+        benchmark.iterations
+    )?;
+    writeln!(out)?;
+    writeln!(out, "{}", "Phases".bold())?;
+    writeln!(
+        out,
+        "  {:<6}  {:>10}  {:>10}  {:>10}",
+        "Phase", "Mean", "Min", "Max"
+    )?;
+    let phases = benchmark
+        .phases
+        .iter()
+        .map(|p| (p.phase.as_str(), p.timing));
+    for (phase, timing) in phases.chain([("total", benchmark.total)]) {
+        writeln!(
+            out,
+            "  {:<6}  {:>8.2}ms  {:>8.2}ms  {:>8.2}ms",
+            phase, timing.mean_ms, timing.min_ms, timing.max_ms
+        )?;
+    }
+
+    let mut rules: Vec<_> = benchmark.rules.iter().collect();
+    rules.sort_by(|a, b| {
+        b.total_ms()
+            .total_cmp(&a.total_ms())
+            .then_with(|| a.rule_id.cmp(&b.rule_id))
+    });
+    let width = rules
+        .iter()
+        .map(|r| r.rule_id.len())
+        .chain(["Rule".len()])
+        .max()
+        .unwrap_or_default();
+    writeln!(out)?;
+    writeln!(out, "{}", "Rule costs (mean per iteration)".bold())?;
+    writeln!(
+        out,
+        "  {:<width$}  {:>6}  {:>7}  {:>10}  {:>10}  {:>10}",
+        "Rule",
+        "Files",
+        "Nodes",
+        "Violations",
+        "Match",
+        "Guard",
+        width = width
+    )?;
+    for r in rules {
+        writeln!(
+            out,
+            "  {:<width$}  {:>6}  {:>7}  {:>10}  {:>8.2}ms  {:>8.2}ms",
+            r.rule_id,
+            r.files,
+            r.nodes,
+            r.violations,
+            r.match_ms,
+            r.guard_ms,
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_write_stats() {
+        let stats = vec![
+            RuleStats {
+                rule_id: "fast-rule".to_string(),
+                files_checked: 10,
+                violations: 0,
+                elapsed: Duration::from_micros(50),
+            },
+            RuleStats {
+                rule_id: "slow-rule".to_string(),
+                files_checked: 4,
+                violations: 3,
+                elapsed: Duration::from_millis(12),
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_stats(&mut out, &stats).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[3].starts_with("  slow-rule"));
+        assert!(lines[3].ends_with("4           3     12.00ms"));
+        assert!(lines[4].starts_with("  fast-rule"));
+    }
+}
@@ -0,0 +1,104 @@
+use super::{ReportFormat, display_path};
+use crate::{config::Severity, engine};
+use std::io::{self, Write};
+
+/// TAP version 13 with one test point per file. A file fails when it has errors; every
+/// violation is listed in the YAML diagnostics block that follows its test point.
+pub struct TapReport;
+
+impl ReportFormat for TapReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        writeln!(out, "TAP version 13")?;
+        writeln!(out, "1..{}", results.len())?;
+
+        for (index, result) in results.iter().enumerate() {
+            if let Some(name) = &result.project
+                && (index == 0 || results[index - 1].project != result.project)
+            {
+                writeln!(out, "# {}", name)?;
+            }
+            let failed = result
+                .violations
+                .iter()
+                .any(|v| v.severity == Severity::Error);
+            writeln!(
+                out,
+                "{} {} - {}",
+                if failed { "not ok" } else { "ok" },
+                index + 1,
+                display_path(&result.file)
+            )?;
+
+            if result.violations.is_empty() {
+                continue;
+            }
+            writeln!(out, "  ---")?;
+            writeln!(out, "  violations:")?;
+            for v in &result.violations {
+                // JSON strings are valid YAML scalars, which takes care of quoting.
+                writeln!(out, "    - rule: {}", quote(&v.rule_id))?;
+                writeln!(out, "      severity: {}", v.severity)?;
+                writeln!(out, "      message: {}", quote(&v.message))?;
+                if let Some(value) = &v.actual_value {
+                    writeln!(out, "      actual: {}", quote(value))?;
+                }
+                if let Some(path) = &v.json_path {
+                    writeln!(out, "      path: {}", quote(path))?;
+                }
+                if let Some(line) = v.line {
+                    writeln!(out, "      line: {}", line)?;
+                }
+            }
+            writeln!(out, "  ...")?;
+        }
+        Ok(())
+    }
+}
+
+fn quote(text: &str) -> String {
+    serde_json::Value::from(text).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_tap_report() {
+        let results = vec![
+            FileResult {
+                file: "./pipeline/pl_a.json".to_string(),
+                violations: vec![Violation {
+                    rule_id: "naming".to_string(),
+                    file: "./pipeline/pl_a.json".to_string(),
+                    message: "Names must match: ^pl_".to_string(),
+                    severity: Severity::Error,
+                    actual_value: Some("\"a\"".to_string()),
+                    json_path: Some("$.name".to_string()),
+                    ..Default::default()
+                }],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            },
+            FileResult {
+                file: "./pipeline/pl_b.json".to_string(),
+                violations: vec![],
+                rules_checked: vec![],
+                project: None,
+                source: None,
+            },
+        ];
+
+        let mut out = Vec::new();
+        TapReport.write(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.starts_with("TAP version 13\n1..2\n"));
+        assert!(text.contains("not ok 1 - pipeline/pl_a.json\n  ---\n"));
+        assert!(text.contains("      message: \"Names must match: ^pl_\"\n"));
+        assert!(text.contains("      actual: \"\\\"a\\\"\"\n      path: \"$.name\"\n"));
+        assert!(text.contains("ok 2 - pipeline/pl_b.json\n"));
+    }
+}
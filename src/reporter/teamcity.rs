@@ -0,0 +1,114 @@
+use super::{ReportFormat, display_path, message_with_path};
+use crate::{config::Severity, engine};
+use std::io::{self, Write};
+
+/// TeamCity service messages. Each failing rule is declared as an inspection type and
+/// each violation reported as an inspection, so they show up on the build's Inspections tab.
+/// The messages of each factory of a multi-project scan are wrapped in a block.
+pub struct TeamcityReport;
+
+impl ReportFormat for TeamcityReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let mut declared: Vec<&str> = Vec::new();
+        for (project, results) in super::by_project(results) {
+            if let Some(name) = project {
+                writeln!(out, "##teamcity[blockOpened name='{}']", escape(name))?;
+            }
+            self.write_inspections(out, results, &mut declared)?;
+            if let Some(name) = project {
+                writeln!(out, "##teamcity[blockClosed name='{}']", escape(name))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TeamcityReport {
+    fn write_inspections<'a>(
+        &self,
+        out: &mut dyn Write,
+        results: &'a [engine::FileResult],
+        declared: &mut Vec<&'a str>,
+    ) -> io::Result<()> {
+        for v in results.iter().flat_map(|r| &r.violations) {
+            if !declared.contains(&v.rule_id.as_str()) {
+                declared.push(&v.rule_id);
+                writeln!(
+                    out,
+                    "##teamcity[inspectionType id='{0}' name='{0}' description='{1}' category='adf-guardian']",
+                    escape(&v.rule_id),
+                    escape(&v.message)
+                )?;
+            }
+
+            let severity = match v.severity {
+                Severity::Error => "ERROR",
+                Severity::Warning => "WARNING",
+            };
+            writeln!(
+                out,
+                "##teamcity[inspection typeId='{}' message='{}' file='{}' line='{}' SEVERITY='{}']",
+                escape(&v.rule_id),
+                escape(&message_with_path(v)),
+                escape(&display_path(&v.file)),
+                v.line.unwrap_or(1),
+                severity
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes a service message attribute value as TeamCity expects.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '|' => escaped.push_str("||"),
+            '\'' => escaped.push_str("|'"),
+            '[' => escaped.push_str("|["),
+            ']' => escaped.push_str("|]"),
+            '\n' => escaped.push_str("|n"),
+            '\r' => escaped.push_str("|r"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{FileResult, Violation};
+
+    #[test]
+    fn test_teamcity_report() {
+        let violation = |file: &str| Violation {
+            rule_id: "naming".to_string(),
+            file: file.to_string(),
+            message: "Names must match '[pl_]'".to_string(),
+            severity: Severity::Warning,
+            line: Some(2),
+            ..Default::default()
+        };
+        let results = vec![FileResult {
+            file: "./pipeline/pl_a.json".to_string(),
+            violations: vec![
+                violation("./pipeline/pl_a.json"),
+                violation("./pipeline/pl_b.json"),
+            ],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+
+        let mut out = Vec::new();
+        TeamcityReport.write(&mut out, &results).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert_eq!(text.matches("##teamcity[inspectionType ").count(), 1);
+        assert!(text.contains(
+            "##teamcity[inspection typeId='naming' message='Names must match |'|[pl_|]|'' file='pipeline/pl_a.json' line='2' SEVERITY='WARNING']"
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use super::{ReportFormat, ReportOptions, json};
+use crate::engine;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use tera::{Context, Tera};
+
+/// Renders a user-supplied Tera template (`--template`). The template sees the same model as
+/// the JSON report, plus a flat `violations` list.
+pub struct TemplateReport {
+    pub options: ReportOptions,
+}
+
+impl ReportFormat for TemplateReport {
+    fn write(&self, out: &mut dyn Write, results: &[engine::FileResult]) -> io::Result<()> {
+        let path = self.options.template.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the template format needs --template",
+            )
+        })?;
+        let source = fs::read_to_string(path)?;
+
+        let context = context(&self.options, results)?;
+        let rendered = Tera::one_off(&source, &context, false).map_err(template_error)?;
+        out.write_all(rendered.as_bytes())
+    }
+}
+
+/// The model of the JSON report, plus a flat `violations` list.
+pub fn context(options: &ReportOptions, results: &[engine::FileResult]) -> io::Result<Context> {
+    let mut context =
+        Context::from_serialize(json::envelope(options, results)).map_err(template_error)?;
+    let violations: Vec<&engine::Violation> = results.iter().flat_map(|r| &r.violations).collect();
+    context.insert("violations", &violations);
+    Ok(context)
+}
+
+/// Tera keeps the useful part (line, missing variable, ...) in the error's sources.
+pub fn template_error(error: tera::Error) -> io::Error {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::GroupBy;
+    use crate::config::Severity;
+    use crate::engine::{FileResult, Violation};
+    use chrono::Utc;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    #[test]
+    fn test_template_report() {
+        let template =
+            std::env::temp_dir().join(format!("adf-guardian-template-{}.tera", std::process::id()));
+        fs::write(
+            &template,
+            "{{ summary.errors }} error(s)\n{% for v in violations %}- {{ v.rule_id }} in {{ v.file }}\n{% endfor %}",
+        )
+        .unwrap();
+
+        let report = TemplateReport {
+            options: ReportOptions {
+                start_time: Instant::now(),
+                started_at: Utc::now(),
+                config_hash: String::new(),
+                group_by: GroupBy::File,
+                sort: None,
+                summary_only: false,
+                verbose: false,
+                unicode: true,
+                json_compat: false,
+                template: Some(PathBuf::from(&template)),
+                suppressed: vec![],
+                diagnostics: vec![],
+            },
+        };
+        let results = vec![FileResult {
+            file: "pipeline/pl_a.json".to_string(),
+            violations: vec![Violation {
+                rule_id: "naming".to_string(),
+                file: "pipeline/pl_a.json".to_string(),
+                severity: Severity::Error,
+                ..Default::default()
+            }],
+            rules_checked: vec![],
+            project: None,
+            source: None,
+        }];
+
+        let mut out = Vec::new();
+        let written = report.write(&mut out, &results);
+        fs::remove_file(&template).unwrap();
+        written.unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1 error(s)\n- naming in pipeline/pl_a.json\n"
+        );
+    }
+}
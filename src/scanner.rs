@@ -1,11 +1,203 @@
+use anyhow::{Context, Result};
 use ignore::WalkBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
+use std::fs;
 use std::path::{Path, PathBuf};
 
-pub fn find_json_files<P: AsRef<Path>>(root: P) -> impl Iterator<Item = PathBuf> {
-    WalkBuilder::new(root)
-        .follow_links(false)
+/// Folders the Git integration of ADF stores assets in, at the root of a factory.
+const FACTORY_FOLDERS: [&str; 9] = [
+    "pipeline",
+    "dataset",
+    "linkedservice",
+    "trigger",
+    "dataflow",
+    "integrationruntime",
+    "credential",
+    "managedvirtualnetwork",
+    "factory",
+];
+/// How deep [`find_factory_roots`] looks below the folder it is given.
+const MAX_ROOT_DEPTH: usize = 8;
+
+/// Which files [`find_json_files`] visits, besides those `.gitignore` excludes.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    /// Globs relative to the root; when any is given, only the files matching one are visited.
+    pub include: Vec<String>,
+    /// Globs relative to the root of the files and folders to skip. They win over `include`.
+    pub exclude: Vec<String>,
+    /// Also visit the files `.gitignore`, `.git/info/exclude` and the global git excludes
+    /// leave out, e.g. JSON generated into a build folder.
+    pub no_gitignore: bool,
+    /// Also visit hidden files and folders.
+    pub hidden: bool,
+    /// Follow symbolic links, e.g. to asset folders shared between factories.
+    pub follow_links: bool,
+    /// Levels below the root to descend; 1 visits only the files directly in it.
+    pub max_depth: Option<usize>,
+}
+
+pub fn find_json_files<P: AsRef<Path>>(
+    root: P,
+    options: &WalkOptions,
+) -> Result<impl Iterator<Item = PathBuf>> {
+    let root = root.as_ref();
+    let overrides = overrides(root, options)?;
+
+    Ok(WalkBuilder::new(root)
+        .git_ignore(!options.no_gitignore)
+        .git_exclude(!options.no_gitignore)
+        .git_global(!options.no_gitignore)
+        .hidden(!options.hidden)
+        .follow_links(options.follow_links)
+        .max_depth(options.max_depth)
+        .overrides(overrides)
         .build()
         .filter_map(|e| e.ok())
         .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
-        .map(|e| e.path().to_owned())
+        .map(|e| e.path().to_owned()))
+}
+
+/// The `include` and `exclude` globs of `options`, relative to `root`.
+pub(crate) fn overrides(root: &Path, options: &WalkOptions) -> Result<Override> {
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in &options.include {
+        overrides
+            .add(glob)
+            .with_context(|| format!("Invalid include glob {:?}", glob))?;
+    }
+    for glob in &options.exclude {
+        overrides
+            .add(&format!("!{}", glob))
+            .with_context(|| format!("Invalid exclude glob {:?}", glob))?;
+    }
+    overrides
+        .build()
+        .context("Invalid include or exclude globs")
+}
+
+/// Whether `dir` looks like the root of a factory: it has the `publish_config.json` ADF
+/// writes there, or at least two of the asset folders (e.g. `pipeline` and `dataset`).
+pub fn is_factory_root(dir: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    let mut folders = 0;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if name == "publish_config.json" {
+            return true;
+        }
+        if entry.path().is_dir() && FACTORY_FOLDERS.contains(&name.as_str()) {
+            folders += 1;
+        }
+    }
+    folders >= 2
+}
+
+/// The factory roots at or below `dir`, sorted. Hidden folders and the folders of a
+/// factory already found aren't searched.
+pub fn find_factory_roots(dir: &Path) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    collect_factory_roots(dir, 0, &mut roots);
+    roots.sort();
+    roots
+}
+
+fn collect_factory_roots(dir: &Path, depth: usize, roots: &mut Vec<PathBuf>) {
+    if is_factory_root(dir) {
+        roots.push(dir.to_path_buf());
+        return;
+    }
+    if depth == MAX_ROOT_DEPTH {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_factory_roots(&entry.path(), depth + 1, roots);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_json_files() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-walk-{}", std::process::id()));
+        for file in [
+            "pipeline/pl_a.json",
+            "pipeline/notes.txt",
+            "dataset/ds_a.json",
+            "ARMTemplateForFactory/ARMTemplateParametersForFactory.json",
+            "samples/pipeline/pl_sample.json",
+            ".generated/pipeline/pl_generated.json",
+        ] {
+            fs::create_dir_all(dir.join(file).parent().unwrap()).unwrap();
+            fs::write(dir.join(file), "{}").unwrap();
+        }
+        let find = |include: &[&str], exclude: &[&str]| {
+            let options = WalkOptions {
+                include: include.iter().map(|g| g.to_string()).collect(),
+                exclude: exclude.iter().map(|g| g.to_string()).collect(),
+                ..Default::default()
+            };
+            let mut files: Vec<String> = find_json_files(&dir, &options)
+                .unwrap()
+                .map(|path| {
+                    let path = path.strip_prefix(&dir).unwrap().to_string_lossy();
+                    path.replace('\\', "/")
+                })
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(find(&[], &[]).len(), 4);
+        assert_eq!(
+            find(&[], &["**/ARMTemplate*/**", "samples/"]),
+            ["dataset/ds_a.json", "pipeline/pl_a.json"]
+        );
+        assert_eq!(
+            find(&["**/pipeline/**"], &["samples/"]),
+            ["pipeline/pl_a.json"]
+        );
+        assert!(
+            find_json_files(
+                &dir,
+                &WalkOptions {
+                    include: vec!["pipeline/[".to_string()],
+                    ..Default::default()
+                }
+            )
+            .is_err()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_factory_roots() {
+        let dir = std::env::temp_dir().join(format!("adf-guardian-roots-{}", std::process::id()));
+        for folder in [
+            "infra/adf/pipeline",
+            "infra/adf/Dataset",
+            "infra/adf/pipeline/nested/dataset",
+            "legacy/factory",
+            "docs/pipeline",
+            ".git/pipeline",
+            ".git/dataset",
+        ] {
+            fs::create_dir_all(dir.join(folder)).unwrap();
+        }
+        fs::write(dir.join("legacy/publish_config.json"), "{}").unwrap();
+
+        let roots = find_factory_roots(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(roots, [dir.join("infra/adf"), dir.join("legacy")]);
+    }
 }
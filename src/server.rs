@@ -0,0 +1,278 @@
+use crate::cli::{GroupBy, OutputFormat};
+use crate::config::Config;
+use crate::engine::{self, RuleSet, ScanObserver, ScanOptions, SharedRuleSet};
+use crate::reporter::{self, ReportOptions};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest request body accepted, so one upload can't exhaust the service's memory.
+const MAX_BODY_SIZE: u64 = 100 * 1024 * 1024;
+/// Largest total size of the files in an uploaded archive, as declared by the archive.
+const MAX_EXTRACTED_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// `adf-guardian serve`: validates assets submitted over HTTP with the rules of one config,
+/// compiled at startup and again when the config changes, so repositories don't each need
+/// the binary.
+///
+/// - `POST /validate` with `{"asset": "pipeline", "document": {...}}` checks one document
+///   as if it were a file in the `asset` folder and returns `{"violations": [...]}`.
+/// - `POST /scan` with a zip archive of a factory folder scans it and returns the JSON report.
+/// - `GET /health` answers `{"status": "ok"}`.
+pub fn serve(rules: &SharedRuleSet, address: &str) -> Result<()> {
+    let server =
+        Server::http(address).map_err(|e| anyhow!("Failed to listen on {}: {}", address, e))?;
+    println!("Listening on http://{}", server.server_addr());
+
+    let workers = thread::available_parallelism().map_or(4, |n| n.get());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                for request in server.incoming_requests() {
+                    handle(&rules.get(), request);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle(rules: &RuleSet, mut request: Request) {
+    let started = Instant::now();
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or_default();
+
+    let too_large = request
+        .body_length()
+        .is_some_and(|length| length as u64 > MAX_BODY_SIZE);
+    let (status, body) = if too_large {
+        error(413, "The request body is too large".to_string())
+    } else {
+        route(rules, &method, path, request.as_reader())
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        tracing::warn!("Could not answer {} {}: {}", method, url, e);
+    }
+    tracing::info!("{} {} {} in {:?}", method, url, status, started.elapsed());
+}
+
+/// The status code and JSON body answering a request.
+fn route(rules: &RuleSet, method: &Method, path: &str, body: &mut dyn Read) -> (u16, Vec<u8>) {
+    let result = match (method, path) {
+        (Method::Post, "/validate") => validate(rules, body),
+        (Method::Post, "/scan") => scan(rules.config(), body),
+        (Method::Get, "/health") => Ok(json!({ "status": "ok" }).to_string().into_bytes()),
+        (_, "/validate" | "/scan") => Err((405, format!("{} only accepts POST", path))),
+        _ => Err((404, format!("No endpoint at {}", path))),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err((status, message)) => error(status, message),
+    }
+}
+
+fn error(status: u16, message: String) -> (u16, Vec<u8>) {
+    (status, json!({ "error": message }).to_string().into_bytes())
+}
+
+fn read_body(body: &mut dyn Read) -> Result<Vec<u8>, (u16, String)> {
+    let mut bytes = Vec::new();
+    body.take(MAX_BODY_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| (400, format!("Failed to read the request body: {}", e)))?;
+    if bytes.len() as u64 > MAX_BODY_SIZE {
+        return Err((413, "The request body is too large".to_string()));
+    }
+    Ok(bytes)
+}
+
+#[derive(Deserialize)]
+struct ValidateRequest {
+    /// Asset folder the document belongs to, e.g. `pipeline`; it decides which rules apply.
+    asset: String,
+    document: Value,
+}
+
+fn validate(rules: &RuleSet, body: &mut dyn Read) -> Result<Vec<u8>, (u16, String)> {
+    let body = read_body(body)?;
+    let request: ValidateRequest =
+        serde_json::from_slice(&body).map_err(|e| (400, format!("Invalid request body: {}", e)))?;
+    if request.asset.is_empty() || request.asset.contains(['/', '\\']) {
+        return Err((400, format!("Invalid asset type '{}'", request.asset)));
+    }
+
+    let path = Path::new(&request.asset).join("document.json");
+    let source = serde_json::to_string_pretty(&request.document).unwrap_or_default();
+    let result = rules
+        .check_source(&path, &source)
+        .map_err(|e| (400, format!("Invalid document: {}", e)))?;
+    Ok(json!({ "violations": result.violations })
+        .to_string()
+        .into_bytes())
+}
+
+fn scan(config: &Config, body: &mut dyn Read) -> Result<Vec<u8>, (u16, String)> {
+    let start_time = Instant::now();
+    let started_at = Utc::now();
+    let body = read_body(body)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(body))
+        .map_err(|e| (400, format!("Invalid zip archive: {}", e)))?;
+    let declared_size = (0..archive.len())
+        .filter_map(|i| archive.by_index_raw(i).ok().map(|file| file.size()))
+        .sum::<u64>();
+    if declared_size > MAX_EXTRACTED_SIZE {
+        return Err((413, "The archive is too large once extracted".to_string()));
+    }
+
+    let dir = TempDir::new().map_err(|e| (500, format!("{:#}", e)))?;
+    archive
+        .extract(&dir.0)
+        .map_err(|e| (400, format!("Failed to extract the archive: {}", e)))?;
+
+    struct Quiet;
+    impl ScanObserver for Quiet {}
+    let mut scan = engine::run(config, &dir.0, &ScanOptions::default(), &Quiet)
+        .map_err(|e| (500, format!("{:#}", e)))?;
+
+    // Report paths inside the archive, not in the temporary folder.
+    let relative = |file: &mut String| {
+        if let Ok(path) = Path::new(file.as_str()).strip_prefix(&dir.0) {
+            *file = path.to_string_lossy().replace('\\', "/");
+        }
+    };
+    for result in &mut scan.results {
+        relative(&mut result.file);
+        result
+            .violations
+            .iter_mut()
+            .for_each(|v| relative(&mut v.file));
+    }
+    scan.suppressed
+        .iter_mut()
+        .for_each(|v| relative(&mut v.file));
+    for file in scan.diagnostics.iter_mut().filter_map(|d| d.file.as_mut()) {
+        relative(file);
+    }
+
+    let options = ReportOptions {
+        start_time,
+        started_at,
+        config_hash: config.hash.clone(),
+        group_by: GroupBy::File,
+        sort: None,
+        summary_only: false,
+        verbose: false,
+        unicode: false,
+        json_compat: false,
+        template: None,
+        suppressed: scan.suppressed,
+        diagnostics: scan.diagnostics,
+    };
+    let mut report = Vec::new();
+    reporter::for_format(OutputFormat::Json, &options)
+        .write(&mut report, &scan.results)
+        .map_err(|e| (500, format!("Failed to write the report: {}", e)))?;
+    Ok(report)
+}
+
+/// A folder of its own under the system temp folder, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "adf-guardian-serve-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        fs::create_dir_all(&path)
+            .map_err(|e| anyhow!("Failed to create a temporary folder {:?}: {}", path, e))?;
+        Ok(TempDir(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::GuardRegistry;
+    use std::io::Write;
+
+    #[test]
+    fn test_route() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+rules:
+  - id: "naming"
+    asset: "pipeline"
+    description: "Pipelines start with pl_"
+    validate: { target: "$.name", guard: "PatternMatch", params: { regex: "^pl_" } }
+"#,
+        )
+        .unwrap();
+        let rules = RuleSet::new(config, GuardRegistry::default());
+        let call = |method: Method, path: &str, body: &[u8]| {
+            let (status, body) = route(&rules, &method, path, &mut &body[..]);
+            (status, serde_json::from_slice::<Value>(&body).unwrap())
+        };
+
+        let (status, body) = call(
+            Method::Post,
+            "/validate",
+            br#"{ "asset": "pipeline", "document": { "name": "load_sales" } }"#,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["violations"][0]["rule_id"], "naming");
+        assert_eq!(
+            call(Method::Post, "/validate", br#"{ "document": {} }"#).0,
+            400
+        );
+
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        archive
+            .start_file("factory/pipeline/pl_a.json", options)
+            .unwrap();
+        archive.write_all(br#"{ "name": "pl_a" }"#).unwrap();
+        archive
+            .start_file("factory/pipeline/b.json", options)
+            .unwrap();
+        archive.write_all(br#"{ "name": "b" }"#).unwrap();
+        let archive = archive.finish().unwrap().into_inner();
+
+        let (status, body) = call(Method::Post, "/scan", &archive);
+        assert_eq!(status, 200);
+        assert_eq!(body["summary"]["files_scanned"], 2);
+        assert_eq!(
+            body["files"][0]["violations"][0]["file"],
+            "factory/pipeline/b.json"
+        );
+        assert_eq!(call(Method::Post, "/scan", b"not a zip").0, 400);
+
+        assert_eq!(call(Method::Get, "/scan", b"").0, 405);
+        assert_eq!(call(Method::Get, "/health", b"").1["status"], "ok");
+        assert_eq!(call(Method::Get, "/", b"").0, 404);
+    }
+}
@@ -0,0 +1,27 @@
+//! JavaScript bindings for checking one document in the browser, e.g. a playground where
+//! a rule and a pipeline JSON are pasted side by side.
+
+use crate::config::Config;
+use crate::engine::{self, GuardRegistry};
+use std::path::Path;
+use wasm_bindgen::prelude::*;
+
+/// Checks `document` (an asset's JSON) against the rules in `config` (a config file's
+/// YAML; built-in packs can be extended) as if it were a file in the `asset` folder, e.g.
+/// `pipeline`. Returns the violations as a JSON array, like the `files[].violations` of
+/// the JSON report. Throws on invalid YAML or JSON, and on rules with an unknown guard or
+/// invalid params.
+#[wasm_bindgen(js_name = validateDocument)]
+pub fn validate_document(document: &str, config: &str, asset: &str) -> Result<String, JsError> {
+    let config =
+        Config::from_yaml(config.as_bytes()).map_err(|e| JsError::new(&format!("{:#}", e)))?;
+    let guards = GuardRegistry::default();
+    let problems = engine::check_guards(&config, &guards);
+    if !problems.is_empty() {
+        return Err(JsError::new(&problems.join("\n")));
+    }
+
+    let path = Path::new(asset).join("document.json");
+    let result = engine::check_source(&config, &guards, &path, document)?;
+    Ok(serde_json::to_string(&result.violations)?)
+}
@@ -0,0 +1,378 @@
+use crate::query;
+use adf_guardian::config::{
+    AssetMatcher, Config, OnMissing, Rule, RuleScope, Severity, Validation,
+};
+use adf_guardian::engine::{self, Guard, GuardRegistry};
+use adf_guardian::scanner::{self, WalkOptions};
+use anyhow::{Context, Result, bail};
+use dialoguer::{Confirm, Input, Select};
+use serde_json::{Value, json};
+use serde_json_path::JsonPath;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+/// Matched nodes and violations listed in a preview; the rest are only counted.
+const PREVIEW_LIMIT: usize = 10;
+
+/// Asks for the asset type, target, guard and params of a rule, previewing the target and
+/// then the rule on a file of the project (`sample`, or the first file of the asset type),
+/// and appends the rule to the config at `config_path`, which is created if missing.
+pub fn run(
+    config_path: &Path,
+    root: &Path,
+    walk: &WalkOptions,
+    sample: Option<&Path>,
+) -> Result<()> {
+    if !io::stdin().is_terminal() {
+        bail!("new-rule asks questions, so it must run in a terminal");
+    }
+    let config = if config_path.exists() {
+        Config::load(config_path)?
+    } else {
+        Config::from_yaml(b"rules: []")?
+    };
+    let files = files_by_asset(root, walk)?;
+
+    let mut items: Vec<String> = files
+        .iter()
+        .map(|(asset, files)| format!("{} ({} files)", asset, files.len()))
+        .collect();
+    items.push("another asset type".to_string());
+    let choice = Select::new()
+        .with_prompt("Asset type")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    let asset = match files.keys().nth(choice) {
+        Some(asset) => asset.clone(),
+        None => Input::<String>::new()
+            .with_prompt("Asset folder, e.g. pipeline")
+            .interact_text()?,
+    };
+    let asset_files = files.get(&asset).map(Vec::as_slice).unwrap_or_default();
+
+    let sample_path = sample
+        .map(Path::to_path_buf)
+        .or_else(|| asset_files.first().cloned());
+    let sample = match &sample_path {
+        Some(path) => {
+            let source =
+                fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+            let json: Value = serde_json::from_str(&source)
+                .with_context(|| format!("Failed to parse {:?}", path))?;
+            eprintln!("Previewing on {}", relative(path, root).display());
+            Some((source, json))
+        }
+        None => {
+            eprintln!("No {} file in the project to preview on", asset);
+            None
+        }
+    };
+
+    let target = loop {
+        let target: String = Input::new()
+            .with_prompt("Target JSONPath")
+            .default("$.name".to_string())
+            .validate_with(|target: &String| {
+                JsonPath::parse(target)
+                    .map(|_| ())
+                    .map_err(|e| e.to_string())
+            })
+            .interact_text()?;
+        let Some((source, json)) = &sample else {
+            break target;
+        };
+        let matches = query::select(&JsonPath::parse(&target)?, "", source, json);
+        eprintln!("{} node(s) selected", matches.len());
+        for m in matches.iter().take(PREVIEW_LIMIT) {
+            eprintln!("  {} = {}", m.path, query::short_value(&m.value, 60));
+        }
+        if Confirm::new()
+            .with_prompt("Use this target?")
+            .default(!matches.is_empty())
+            .interact()?
+        {
+            break target;
+        }
+    };
+
+    let registry = GuardRegistry::default();
+    let names = registry.names();
+    let guard_name = names[Select::new()
+        .with_prompt("Guard")
+        .items(&names)
+        .default(names.iter().position(|n| *n == "PatternMatch").unwrap_or(0))
+        .interact()?];
+    let guard = registry.get(guard_name).expect("the guard was listed");
+    let params = ask_params(guard)?;
+
+    let id: String = Input::new()
+        .with_prompt("Rule id")
+        .validate_with(|id: &String| {
+            if id.trim().is_empty() {
+                Err("The id can't be empty".to_string())
+            } else if config.rules.iter().any(|rule| rule.id == *id) {
+                Err(format!("The config already has a rule '{}'", id))
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+    let description: String = Input::new()
+        .with_prompt("Description, shown in violations")
+        .allow_empty(true)
+        .interact_text()?;
+    let severity = match Select::new()
+        .with_prompt("Severity")
+        .items(["Error", "Warning"])
+        .default(0)
+        .interact()?
+    {
+        0 => Severity::Error,
+        _ => Severity::Warning,
+    };
+    let rule = Rule {
+        id,
+        asset: AssetMatcher::Single(asset.clone()),
+        description: (!description.is_empty()).then_some(description),
+        help_url: None,
+        severity,
+        promote: None,
+        tags: vec![],
+        scope: RuleScope::File,
+        report_value: true,
+        when: None,
+        validate: Validation {
+            target,
+            guard: guard_name.to_string(),
+            params,
+            params_by: None,
+            on_missing: OnMissing::Pass,
+        },
+    };
+
+    let mut preview = config.clone();
+    preview.rules = vec![rule.clone()];
+    if let (Some(path), Some((source, _))) = (&sample_path, &sample) {
+        let result = engine::check_source(&preview, &registry, &relative(path, root), source)?;
+        if result.violations.is_empty() {
+            eprintln!("The rule passes on {}", relative(path, root).display());
+        }
+        for violation in result.violations.iter().take(PREVIEW_LIMIT) {
+            eprintln!(
+                "  {}: {}",
+                violation.json_path.as_deref().unwrap_or("$"),
+                violation.message
+            );
+        }
+    }
+    let failing = asset_files
+        .iter()
+        .filter(|path| {
+            fs::read_to_string(path).is_ok_and(|source| {
+                engine::check_source(&preview, &registry, &relative(path, root), &source)
+                    .is_ok_and(|result| !result.violations.is_empty())
+            })
+        })
+        .count();
+    if !asset_files.is_empty() {
+        eprintln!(
+            "It would fail on {} of the {} {} files",
+            failing,
+            asset_files.len(),
+            asset
+        );
+    }
+
+    if !Confirm::new()
+        .with_prompt(format!("Add rule '{}' to {:?}?", rule.id, config_path))
+        .default(true)
+        .interact()?
+    {
+        return Ok(());
+    }
+    let source = fs::read_to_string(config_path).unwrap_or_default();
+    let edited = append_rules(&source, std::slice::from_ref(&rule))?;
+    fs::write(config_path, edited).with_context(|| format!("Failed to write {:?}", config_path))?;
+    eprintln!("Added rule '{}' to {:?}", rule.id, config_path);
+    Ok(())
+}
+
+/// The JSON files of the project by asset type, i.e. the folder they are in.
+fn files_by_asset(root: &Path, walk: &WalkOptions) -> Result<BTreeMap<String, Vec<PathBuf>>> {
+    let mut files: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for path in scanner::find_json_files(root, walk)? {
+        let folder = relative(&path, root)
+            .parent()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        if let Some(folder) = folder {
+            files.entry(folder).or_default().push(path);
+        }
+    }
+    for paths in files.values_mut() {
+        paths.sort();
+    }
+    Ok(files)
+}
+
+fn relative(path: &Path, root: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+/// Asks for each param of `guard` until they are all valid. Values are read as YAML, so
+/// `true`, `5` and `[a, b]` work, and kept as text when the guard expects a string.
+fn ask_params(guard: &dyn Guard) -> Result<Value> {
+    let specs = guard.params();
+    if specs.is_empty() {
+        let params: String = Input::new()
+            .with_prompt("Params, as YAML like {key: value}")
+            .allow_empty(true)
+            .interact_text()?;
+        return Ok(serde_yaml::from_str::<Option<Value>>(&params)?.unwrap_or(Value::Null));
+    }
+    loop {
+        let mut params = serde_json::Map::new();
+        for spec in &specs {
+            let value: String = Input::new()
+                .with_prompt(format!(
+                    "{} ({}{})",
+                    spec.name,
+                    spec.expects,
+                    if spec.required { "" } else { ", optional" }
+                ))
+                .allow_empty(!spec.required)
+                .validate_with(|text: &String| {
+                    if text.trim().is_empty() {
+                        return Ok(());
+                    }
+                    param_value(guard, spec.name, text)
+                        .map(|_| ())
+                        .map_err(|problem| problem.to_string())
+                })
+                .interact_text()?;
+            if !value.trim().is_empty() {
+                let value = param_value(guard, spec.name, &value).map_err(anyhow::Error::msg)?;
+                params.insert(spec.name.to_string(), value);
+            }
+        }
+        let params = Value::Object(params);
+        let problems = guard.check_params(&params);
+        if problems.is_empty() {
+            return Ok(params);
+        }
+        for problem in problems {
+            eprintln!("  {}", problem);
+        }
+    }
+}
+
+/// The value of the param `name` typed as `text`: the YAML it reads as, or else the text
+/// itself, whichever the guard accepts.
+fn param_value(guard: &dyn Guard, name: &str, text: &str) -> Result<Value, String> {
+    let quoted = format!("'{}'", name);
+    let mut problem = None;
+    let parsed = serde_yaml::from_str::<Value>(text).ok();
+    for value in parsed.into_iter().chain([Value::String(text.to_string())]) {
+        match guard
+            .check_params(&json!({ name: value }))
+            .into_iter()
+            .find(|problem| problem.contains(&quoted))
+        {
+            None => return Ok(value),
+            Some(found) => problem = problem.or(Some(found)),
+        }
+    }
+    Err(problem.unwrap_or_default())
+}
+
+/// Appends `rules` to the `rules` list of the config `source`, editing the text so its
+/// comments and layout are kept.
+pub fn append_rules(source: &str, rules: &[Rule]) -> Result<String> {
+    let item = serde_yaml::to_string(rules)?;
+    let mut lines: Vec<String> = source.lines().map(str::to_string).collect();
+    match lines.iter().position(|line| line.starts_with("rules:")) {
+        None => {
+            lines.push("rules:".to_string());
+            lines.extend(item.lines().map(str::to_string));
+        }
+        Some(index) => {
+            let value = lines[index]["rules:".len()..].trim().to_string();
+            if let Some(comment) = value.strip_prefix("[]") {
+                lines[index] = format!("rules:{}", comment);
+            } else if !value.is_empty() && !value.starts_with('#') {
+                bail!("The `rules` of the config are not a block list, add the rules by hand");
+            }
+            // The list ends at the next top-level key, without the blank lines and comments
+            // before it.
+            let end = lines[index + 1..]
+                .iter()
+                .position(|line| !line.is_empty() && !line.starts_with([' ', '-', '#']))
+                .map_or(lines.len(), |position| index + 1 + position);
+            let mut at = end;
+            while at > index + 1
+                && (lines[at - 1].trim().is_empty() || lines[at - 1].starts_with('#'))
+            {
+                at -= 1;
+            }
+            let indent = lines[index + 1..at]
+                .iter()
+                .find(|line| line.trim_start().starts_with('-'))
+                .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+                .unwrap_or_default();
+            let item = item.lines().map(|line| format!("{}{}", indent, line));
+            lines.splice(at..at, item);
+        }
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rules() {
+        let rule: Rule = serde_yaml::from_str(
+            r#"
+id: "pipeline-prefix"
+asset: "pipeline"
+validate:
+  target: "$.name"
+  guard: "PatternMatch"
+  params: { regex: "^pl_" }
+"#,
+        )
+        .unwrap();
+        let ids = |source: &str| -> Vec<String> {
+            let config: serde_yaml::Value = serde_yaml::from_str(source).unwrap();
+            config["rules"]
+                .as_sequence()
+                .unwrap()
+                .iter()
+                .map(|rule| rule["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let source =
+            "rules:\n  - id: naming # existing\n    asset: dataset\n\n# Checks\nproject: {}\n";
+        let edited = append_rules(source, std::slice::from_ref(&rule)).unwrap();
+        assert_eq!(ids(&edited), ["naming", "pipeline-prefix"]);
+        assert!(edited.starts_with(
+            "rules:\n  - id: naming # existing\n    asset: dataset\n  - id: pipeline-prefix\n"
+        ));
+        assert!(edited.ends_with("\n\n# Checks\nproject: {}\n"));
+
+        assert_eq!(
+            ids(&append_rules("rules: [] # none yet\n", std::slice::from_ref(&rule)).unwrap()),
+            ["pipeline-prefix"]
+        );
+        assert_eq!(
+            ids(&append_rules("extends: [adf:copy]\n", std::slice::from_ref(&rule)).unwrap()),
+            ["pipeline-prefix"]
+        );
+        assert!(append_rules("rules: [{ id: a }]\n", std::slice::from_ref(&rule)).is_err());
+    }
+}